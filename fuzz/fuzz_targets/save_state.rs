@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/main.rs"]
+mod hexagon;
+
+fuzz_target!(|the_data: &[u8]| {
+    hexagon::fuzzing::parse_save_state(the_data);
+});
@@ -0,0 +1,10 @@
+#version 100
+precision mediump float;
+uniform float time;
+varying vec2 field_pos;
+varying vec3 v_color;
+void main() {
+    // bands of light travelling outwards along the slots
+    float wave = 0.5 + 0.5 * sin(field_pos.y * 20. - time * 6.);
+    gl_FragColor = vec4(v_color * (0.75 + 0.25 * wave), 1.);
+}
@@ -0,0 +1,221 @@
+use super::model::{linear_to_srgb, srgb_to_linear, Color, Style};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Color vision deficiencies for which the level's palette can be corrected
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum PaletteMode {
+  Normal,
+  Deuteranopia,
+  Protanopia,
+  Tritanopia,
+}
+
+impl PaletteMode {
+  pub fn next(self) -> PaletteMode {
+    match self {
+      PaletteMode::Normal => PaletteMode::Deuteranopia,
+      PaletteMode::Deuteranopia => PaletteMode::Protanopia,
+      PaletteMode::Protanopia => PaletteMode::Tritanopia,
+      PaletteMode::Tritanopia => PaletteMode::Normal,
+    }
+  }
+  /// Simulation matrices (in linear RGB) by Machado et al. 2009, severity 1
+  fn simulation_matrix(self) -> [[f32; 3]; 3] {
+    match self {
+      PaletteMode::Normal => [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+      PaletteMode::Deuteranopia => [
+        [0.367322, 0.860646, -0.227968],
+        [0.280085, 0.672501, 0.047413],
+        [-0.011820, 0.042940, 0.968881],
+      ],
+      PaletteMode::Protanopia => [
+        [0.152286, 1.052583, -0.204868],
+        [0.114503, 0.786281, 0.099216],
+        [-0.003882, -0.048116, 1.051998],
+      ],
+      PaletteMode::Tritanopia => [
+        [1.255528, -0.076749, -0.178779],
+        [-0.078411, 0.930809, 0.147602],
+        [0.004733, 0.691367, 0.303900],
+      ],
+    }
+  }
+  /// Redistributes the information lost to the deficiency onto the channels
+  /// that can still be told apart (daltonization, Fidaner et al.)
+  fn correction_matrix(self) -> [[f32; 3]; 3] {
+    match self {
+      PaletteMode::Normal => [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]],
+      PaletteMode::Deuteranopia | PaletteMode::Protanopia => {
+        [[0., 0., 0.], [0.7, 1., 0.], [0.7, 0., 1.]]
+      }
+      PaletteMode::Tritanopia => [[1., 0., 0.7], [0., 1., 0.7], [0., 0., 0.]],
+    }
+  }
+}
+
+/// Minimum contrast ratio (WCAG definition) between obstacles and the slots
+/// behind them
+const MIN_OBSTACLE_CONTRAST: f32 = 3.;
+
+fn mul(the_mat: &[[f32; 3]; 3], the_vec: &[f32; 3]) -> [f32; 3] {
+  let mut a_result = [0.; 3];
+  for i in 0..3 {
+    a_result[i] = (0..3).fold(0., |the_acc, j| the_acc + the_mat[i][j] * the_vec[j]);
+  }
+  a_result
+}
+fn to_linear(the_color: &Color) -> [f32; 3] {
+  [
+    srgb_to_linear(the_color.its_r),
+    srgb_to_linear(the_color.its_g),
+    srgb_to_linear(the_color.its_b),
+  ]
+}
+
+/// How `the_color` is perceived by someone with the given deficiency
+pub fn simulate(the_mode: PaletteMode, the_color: &Color) -> Color {
+  let a_sim = mul(&the_mode.simulation_matrix(), &to_linear(the_color));
+  Color::rgba(
+    linear_to_srgb(a_sim[0].clamp(0., 1.)),
+    linear_to_srgb(a_sim[1].clamp(0., 1.)),
+    linear_to_srgb(a_sim[2].clamp(0., 1.)),
+    the_color.its_a,
+  )
+}
+
+pub fn daltonize(the_mode: PaletteMode, the_color: &Color) -> Color {
+  let a_lin = to_linear(the_color);
+  let a_sim = mul(&the_mode.simulation_matrix(), &a_lin);
+  let a_err = [
+    a_lin[0] - a_sim[0],
+    a_lin[1] - a_sim[1],
+    a_lin[2] - a_sim[2],
+  ];
+  let a_shift = mul(&the_mode.correction_matrix(), &a_err);
+  Color::rgba(
+    linear_to_srgb((a_lin[0] + a_shift[0]).clamp(0., 1.)),
+    linear_to_srgb((a_lin[1] + a_shift[1]).clamp(0., 1.)),
+    linear_to_srgb((a_lin[2] + a_shift[2]).clamp(0., 1.)),
+    the_color.its_a,
+  )
+}
+
+fn contrast_ratio(the_a: &Color, the_b: &Color) -> f32 {
+  let a_la = the_a.luminance();
+  let a_lb = the_b.luminance();
+  (a_la.max(a_lb) + 0.05) / (a_la.min(a_lb) + 0.05)
+}
+fn min_contrast(the_mode: PaletteMode, the_color: &Color, the_others: &[Color]) -> f32 {
+  let a_seen = simulate(the_mode, the_color);
+  the_others
+    .iter()
+    .map(|the_other| contrast_ratio(&a_seen, &simulate(the_mode, the_other)))
+    .fold(f32::INFINITY, f32::min)
+}
+
+/// Transform all colors of `the_style` for the given deficiency and make
+/// sure obstacles stand out against every slot color as perceived by the
+/// player
+pub fn apply_palette_mode(the_mode: PaletteMode, the_style: &mut Style) -> () {
+  if the_mode == PaletteMode::Normal {
+    return;
+  }
+  the_style.map_colors(|the_color| daltonize(the_mode, the_color));
+  let a_slot_colors = the_style.get_slot_colors().clone();
+  let a_obstacle_colors = the_style
+    .get_obstacle_colors()
+    .iter()
+    .map(|the_obstacle| {
+      if min_contrast(the_mode, the_obstacle, &a_slot_colors) >= MIN_OBSTACLE_CONTRAST {
+        return the_obstacle.clone();
+      }
+      // Fall back to whichever extreme is easiest to tell apart from the slots
+      let a_black = Color::rgba(0., 0., 0., the_obstacle.its_a);
+      let a_white = Color::rgba(1., 1., 1., the_obstacle.its_a);
+      if min_contrast(the_mode, &a_black, &a_slot_colors)
+        >= min_contrast(the_mode, &a_white, &a_slot_colors)
+      {
+        a_black
+      } else {
+        a_white
+      }
+    })
+    .collect();
+  the_style.set_obstacle_colors(a_obstacle_colors);
+}
+
+/// Replace the level's colors by a fixed scheme in which slots, cursor and
+/// obstacles fall into clearly separated luminance bands: slots are close to
+/// black, the cursor sits in the middle and obstacles are white
+pub fn apply_high_contrast(the_style: &mut Style) -> () {
+  let a_slot_colors = vec![
+    Color::rgba(0., 0., 0., 1.),
+    Color::rgba(0.12, 0.12, 0.12, 1.),
+  ];
+  the_style.set_slot_colors(a_slot_colors);
+  the_style.set_obstacle_colors(vec![Color::rgba(1., 1., 1., 1.)]);
+  the_style.set_cursor_color(Color::rgba(1., 0.6, 0., 1.));
+  the_style.set_cursor_shadow_color(Color::rgba(0., 0., 0., 0.));
+  the_style.set_inner_hexagon_color(Color::rgba(0., 0., 0., 1.));
+  the_style.set_outer_hexagon_color(Color::rgba(0.5, 0.5, 0.5, 1.));
+  // rays and stars would blur the bands
+  the_style.set_background(None);
+}
+
+/// Upper bounds for everything that moves the whole scene, derived from the
+/// user's motion intensity setting
+pub struct MotionLimits {
+  /// In turns per second
+  pub max_rotation_speed: f32,
+  pub max_wobble: f32,
+  pub max_zoom_pulse: f32,
+}
+
+impl MotionLimits {
+  /// An intensity of 1 imposes no limits, lower values scale down the
+  /// strongest motion that is still allowed
+  pub fn from_intensity(the_intensity: f32) -> MotionLimits {
+    if the_intensity >= 1. {
+      return MotionLimits {
+        max_rotation_speed: f32::INFINITY,
+        max_wobble: f32::INFINITY,
+        max_zoom_pulse: f32::INFINITY,
+      };
+    }
+    MotionLimits {
+      max_rotation_speed: 0.5 * the_intensity,
+      max_wobble: 0.02 * the_intensity,
+      max_zoom_pulse: 0.5 * the_intensity,
+    }
+  }
+}
+
+/// Time constant with which the displayed colors follow the style's colors
+/// in reduced-flash mode
+const COLOR_SMOOTHING: Duration = Duration::from_millis(250);
+
+/// Replaces flashes and rapid palette changes by gentler alternatives: colors
+/// only ever fade towards their targets and the death flash desaturates the
+/// scene instead of turning it white
+pub struct FlashLimiter {
+  its_previous: Option<Style>,
+}
+
+impl FlashLimiter {
+  pub fn new() -> FlashLimiter {
+    FlashLimiter { its_previous: None }
+  }
+  pub fn filter(&mut self, the_style: &Style, the_delta: Duration) -> Style {
+    let mut a_style = the_style.clone();
+    if the_style.get_flash_time().as_millis() > 0 {
+      a_style.map_colors(|the_color| the_color.desaturated());
+    }
+    if let Some(a_previous) = &self.its_previous {
+      let a_t = 1. - (-the_delta.as_secs_f32() / COLOR_SMOOTHING.as_secs_f32()).exp();
+      a_style.blend_colors_from(a_previous, a_t);
+    }
+    self.its_previous = Some(a_style.clone());
+    a_style
+  }
+}
@@ -0,0 +1,79 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+  static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts the heap allocations of every thread, so that tests can check
+/// that code does not allocate without being disturbed by tests running in
+/// parallel
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, the_layout: Layout) -> *mut u8 {
+    // the counter may already be gone while the thread shuts down
+    let _ = ALLOCATIONS.try_with(|the_count| the_count.set(the_count.get() + 1));
+    System.alloc(the_layout)
+  }
+  unsafe fn dealloc(&self, the_ptr: *mut u8, the_layout: Layout) -> () {
+    System.dealloc(the_ptr, the_layout)
+  }
+  unsafe fn realloc(&self, the_ptr: *mut u8, the_layout: Layout, the_size: usize) -> *mut u8 {
+    let _ = ALLOCATIONS.try_with(|the_count| the_count.set(the_count.get() + 1));
+    System.realloc(the_ptr, the_layout, the_size)
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations `the_fn` makes on the current thread
+pub fn count_allocations<F: FnOnce() -> ()>(the_fn: F) -> usize {
+  let a_before = ALLOCATIONS.with(|the_count| the_count.get());
+  the_fn();
+  ALLOCATIONS.with(|the_count| the_count.get()) - a_before
+}
+
+#[cfg(test)]
+mod tests {
+  use super::count_allocations;
+  use crate::model::{GameEvent, GameState};
+  use crate::spawner::{SpawnStrategy, Spawner};
+
+  /// Spawn and play the default patterns like the app does, carrying on
+  /// after every death
+  fn play(
+    the_spawner: &mut Spawner,
+    the_game: &mut GameState,
+    the_events: &mut Vec<GameEvent>,
+    the_ticks: usize,
+  ) -> () {
+    for _ in 0..the_ticks {
+      let a_delta = the_game.get_tick_duration();
+      the_spawner.tick(the_game, a_delta);
+      the_game.tick();
+      the_game.swap_events(the_events);
+      the_events.clear();
+      if !the_game.is_running() {
+        the_game.set_running(true);
+      }
+    }
+  }
+
+  #[test]
+  fn steady_play_does_not_allocate() {
+    for a_strategy in [SpawnStrategy::Weighted, SpawnStrategy::ShuffleBag].iter() {
+      let mut a_spawner = Spawner::new(Vec::new(), 10., *a_strategy);
+      let mut a_game = GameState::new();
+      a_game.restart(7);
+      a_spawner.reset(7);
+      let mut a_events = Vec::new();
+      // let every buffer grow to its working size first
+      play(&mut a_spawner, &mut a_game, &mut a_events, 20000);
+      let a_allocations =
+        count_allocations(|| play(&mut a_spawner, &mut a_game, &mut a_events, 5000));
+      assert_eq!(a_allocations, 0);
+    }
+  }
+}
@@ -1,50 +1,280 @@
+use super::accessibility::{self, MotionLimits};
+use super::bot::Bot;
+use super::checkpoint::Checkpoint;
+use super::clipboard;
+use super::console::{self, Command, Console, Speed};
+use super::constants;
 use super::controls;
+use super::daily::{self, DailyResults};
+use super::devices::DeviceChange;
+use super::editor::PatternEditor;
+use super::heatmap::DeathLog;
+use super::history::RunHistory;
+use super::i18n;
+use super::level::{CursorDef, GoalDef, LevelDef};
+use super::loader;
 use super::model;
+use super::offscreen::PostEffects;
+use super::pacing::FpsMeter;
+use super::pack;
+use super::race::{OpponentStatus, Race, RaceEvent};
+use super::records::Records;
 use super::renderer;
+use super::replay::Replay;
+use super::rng::Rng;
+use super::session_log::{LoggedEvent, SessionLog};
+use super::settings::{self, Settings};
+use super::spawner::{SpawnStrategy, Spawner, UpcomingWave};
+use super::stats::LifetimeStats;
+use super::summary;
+use super::theme::ThemeEditor;
+use super::timeline::Timeline;
+use super::tutorial::Prompt;
+use super::window::{self, WindowGeometry};
 use glutin::window::Window;
 use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 pub trait TweenAPI {
-  fn get_window(&self) -> &Window;
-  fn get_renderer(&self) -> &dyn renderer::Renderer;
+  fn get_renderer_mut(&mut self) -> &mut dyn renderer::Renderer;
   fn get_game_state_mut(&mut self) -> &mut model::GameState;
+  fn get_settings(&self) -> &Settings;
+  /// In turns per second
+  fn get_rotation_speed(&self) -> f32;
+  /// Takes effect at the start of the next tick
+  fn set_rotation_speed(&mut self, the_speed: f32) -> ();
 }
 
 pub trait Tween {
   fn run(&mut self, the_progress: f32, the_app: &mut dyn TweenAPI) -> ();
 }
 
-struct FPSTween {}
-impl FPSTween {
-  pub fn new() -> FPSTween {
-    FPSTween {}
+struct ZoomTween {
+  its_depth: f32,
+}
+impl ZoomTween {
+  /// `the_depth` is how far the zoom dips below 1 at the peak of a pulse
+  pub fn new(the_depth: f32) -> ZoomTween {
+    ZoomTween {
+      its_depth: the_depth,
+    }
   }
 }
-impl Tween for FPSTween {
-  fn run(&mut self, _the_progress: f32, the_app: &mut dyn TweenAPI) -> () {
-    let a_title = format!(
-      "FPS: {}",
-      (1000. / the_app.get_renderer().get_frame_time()) as u32
-    );
-    the_app.get_window().set_title(&a_title);
+impl Tween for ZoomTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    let a_limits = MotionLimits::from_intensity(the_api.get_settings().get_motion_intensity());
+    let a_depth = self.its_depth.min(a_limits.max_zoom_pulse);
+    let a_zoom = 1. - a_depth + (std::f32::consts::PI * the_progress).sin() * a_depth;
+    the_api
+      .get_game_state_mut()
+      .submit_style_command(model::StyleCommand::PulseZoom(a_zoom));
   }
 }
 
-struct ZoomTween {}
-impl ZoomTween {
-  pub fn new() -> ZoomTween {
-    ZoomTween {}
+/// Sways the camera around the center in a small circle
+struct WobbleTween {
+  its_amplitude: f32,
+}
+impl WobbleTween {
+  pub fn new(the_amplitude: f32) -> WobbleTween {
+    WobbleTween {
+      its_amplitude: the_amplitude,
+    }
   }
 }
-impl Tween for ZoomTween {
+impl Tween for WobbleTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    let a_limits = MotionLimits::from_intensity(the_api.get_settings().get_motion_intensity());
+    let a_amplitude = self.its_amplitude.min(a_limits.max_wobble);
+    let a_angle = 2. * std::f32::consts::PI * the_progress;
+    the_api
+      .get_game_state_mut()
+      .get_style_mut()
+      .set_eye(a_angle.cos() * a_amplitude, a_angle.sin() * a_amplitude);
+  }
+}
+
+/// Moves the camera in from `its_from` to the normal zoom, from far out at
+/// the start of a level's intro
+struct IntroZoomTween {
+  its_from: f32,
+}
+impl IntroZoomTween {
+  pub fn new(the_from: f32) -> IntroZoomTween {
+    IntroZoomTween { its_from: the_from }
+  }
+}
+impl Tween for IntroZoomTween {
   fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    // with reduced motion the camera starts closer
+    let a_intensity = the_api.get_settings().get_motion_intensity();
+    let a_from = 1. - (1. - self.its_from) * a_intensity;
+    let a_eased = 1. - (1. - the_progress).powi(3);
     the_api
       .get_game_state_mut()
       .get_style_mut()
-      .set_zoom(0.5 + (std::f32::consts::PI * the_progress).sin() * 0.5);
+      .set_zoom(a_from + (1. - a_from) * a_eased);
+  }
+}
+
+/// Slides the level's title in from the right, or out to the left
+struct IntroTitleTween {
+  its_title: String,
+  its_leaving: bool,
+}
+impl IntroTitleTween {
+  pub fn new(the_title: String, the_leaving: bool) -> IntroTitleTween {
+    IntroTitleTween {
+      its_title: the_title,
+      its_leaving: the_leaving,
+    }
+  }
+}
+impl Tween for IntroTitleTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    let (a_title, a_offset) = if !self.its_leaving {
+      (Some(self.its_title.clone()), (1. - the_progress).powi(2))
+    } else if the_progress < 1. {
+      (Some(self.its_title.clone()), -the_progress.powi(2))
+    } else {
+      (None, 0.)
+    };
+    the_api
+      .get_renderer_mut()
+      .set_intro_title(a_title, a_offset);
+  }
+}
+
+/// Spins the playfield up from standing still to the level's rotation
+/// speed. The simulation waits during the intro, so this turns the
+/// playfield itself.
+struct SpinUpTween {
+  its_duration: Duration,
+  /// Rotation before the spin-up, captured on the first run
+  its_start: Option<f32>,
+}
+impl SpinUpTween {
+  pub fn new(the_duration: Duration) -> SpinUpTween {
+    SpinUpTween {
+      its_duration: the_duration,
+      its_start: None,
+    }
+  }
+}
+impl Tween for SpinUpTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    let a_game = the_api.get_game_state_mut();
+    let a_start = *self
+      .its_start
+      .get_or_insert(a_game.get_style().get_rotation());
+    // the speed rises linearly, so the angle grows with the square of time
+    let a_turned = a_game.get_effective_rotation_speed()
+      * self.its_duration.as_secs_f32()
+      * the_progress.powi(2)
+      / 2.;
+    a_game
+      .get_style_mut()
+      .set_rotation((a_start + a_turned).rem_euclid(1.));
+  }
+}
+
+/// Zooms the camera in on the cursor where the run ended and back out
+struct DeathZoomTween {
+  its_zoom: f32,
+  /// Ticks of the run that ended and the camera before the zoom, captured
+  /// on the first run since the zoom only changes at tick boundaries
+  its_start: Option<DeathZoomStart>,
+}
+#[derive(Clone, Copy)]
+struct DeathZoomStart {
+  ticks: u64,
+  zoom: f32,
+  eye: (f32, f32),
+  look_at: (f32, f32),
+}
+impl DeathZoomTween {
+  /// `the_zoom` is how far the camera zooms in at the peak
+  pub fn new(the_zoom: f32) -> DeathZoomTween {
+    DeathZoomTween {
+      its_zoom: the_zoom,
+      its_start: None,
+    }
   }
 }
+impl Tween for DeathZoomTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    let a_intensity = the_api.get_settings().get_motion_intensity();
+    let a_game = the_api.get_game_state_mut();
+    let a_ticks = a_game.get_run().get_ticks();
+    let a_start = *self.its_start.get_or_insert_with(|| {
+      let a_style = a_game.get_style();
+      DeathZoomStart {
+        ticks: a_ticks,
+        zoom: a_style.get_zoom(),
+        eye: (a_style.get_eye().x, a_style.get_eye().y),
+        look_at: (a_style.get_look_at().x, a_style.get_look_at().y),
+      }
+    });
+    // a new run may have started, and ended, in the meantime
+    if a_game.is_running() || a_start.ticks != a_ticks {
+      return;
+    }
+    let a_amount = (std::f32::consts::PI * the_progress).sin() * a_intensity;
+    let a_zoom = a_start.zoom * (1. + (self.its_zoom - 1.) * a_amount);
+    // moves the cursor towards the middle of the screen
+    let a_angle =
+      2. * std::f32::consts::PI * (a_game.get_position() + a_game.get_style().get_rotation());
+    let a_radius = std::f32::consts::SQRT_2 * constants::CURSOR_Y * a_zoom * a_amount;
+    let a_shift = (a_angle.sin() * a_radius, a_angle.cos() * a_radius);
+    a_game.submit_style_command(model::StyleCommand::PulseZoom(a_zoom));
+    let a_style = a_game.get_style_mut();
+    let (a_eye, a_look_at) = (a_start.eye, a_start.look_at);
+    a_style.set_eye(a_eye.0 + a_shift.0, a_eye.1 + a_shift.1);
+    a_style.set_look_at(a_look_at.0 + a_shift.0, a_look_at.1 + a_shift.1);
+  }
+}
+
+/// Mixed into the run's seed for the spawner of a hyper level so that it does
+/// not repeat the patterns of the normal level
+const HYPER_SEED_SALT: u64 = 0x48_5950_4552;
+/// Most simulation time to catch up on in a single frame, whatever the tick
+/// rate
+const MAX_CATCH_UP: Duration = Duration::from_millis(166);
+/// Idle time on the game over screen after which a demo run starts
+const ATTRACT_DELAY: Duration = Duration::from_secs(30);
+/// Demo runs always show the same patterns
+const DEMO_SEED: u64 = 0x4445_4d4f;
+/// How often the run in progress is checkpointed, i.e. how much of it a
+/// crash can cost at most
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+/// Degrees per second the palette cycles through the hues in zen mode
+const ZEN_HUE_SPEED: f32 = 20.;
+/// Longest time the console can skip at once, since it is simulated
+const MAX_SKIP: Duration = Duration::from_secs(600);
+/// How long everything slows down after a death before the game over
+/// screen is shown
+const DEATH_EFFECT_TIME: Duration = Duration::from_millis(1000);
+/// Speed of the simulation right after a death, it returns to normal over
+/// `DEATH_EFFECT_TIME`
+const DEATH_TIME_SCALE: f32 = 0.25;
+/// How far the camera zooms in on the cursor after a death
+const DEATH_ZOOM: f32 = 1.8;
+/// How long the run waits after a pause before it goes on, counted down
+/// in whole seconds
+const RESUME_COUNTDOWN: Duration = Duration::from_secs(3);
+/// How long a notice stays on screen
+const NOTICE_TIME: Duration = Duration::from_secs(2);
+/// Most entries of the session log the debug overlay lists
+const DEBUG_LOG_LINES: usize = 8;
+/// Most upcoming waves the debug overlay lists
+const DEBUG_UPCOMING_WAVES: usize = 5;
+/// Zoom the camera starts the intro of a level at, i.e. far out
+const INTRO_ZOOM: f32 = 0.1;
+const INTRO_ZOOM_TIME: Duration = Duration::from_millis(1200);
+/// How long the level's title takes to slide in, and out again
+const INTRO_TITLE_TIME: Duration = Duration::from_millis(400);
+const INTRO_SPIN_UP_TIME: Duration = Duration::from_millis(1000);
 
 struct TweenInfo {
   its_duration: Duration,
@@ -62,7 +292,7 @@ impl TweenInfo {
     }
   }
 }
-struct TweenEngine {
+pub struct TweenEngine {
   its_tweens: Vec<(RefCell<TweenInfo>, RefCell<Box<dyn Tween>>)>,
 }
 impl TweenEngine {
@@ -89,7 +319,7 @@ impl TweenEngine {
       let mut a_state = a_state_cell.borrow_mut();
       let mut a_action = a_action_cell.borrow_mut();
       if a_state.its_repetitions == 0 {
-        return;
+        continue;
       }
       a_state.its_progress += the_delta;
       if a_state.its_progress <= a_state.its_duration + the_delta {
@@ -107,37 +337,120 @@ impl TweenEngine {
       }
     }
   }
-  pub fn cleanup(&mut self) -> () {}
+  /// Drop tweens that have run all their repetitions
+  pub fn cleanup(&mut self) -> () {
+    self
+      .its_tweens
+      .retain(|(the_state, _)| the_state.borrow().its_repetitions != 0);
+  }
+}
+
+/// Tweens that run one after another, each over a duration of its own,
+/// e.g. the steps of a level's intro
+pub struct TweenSequence {
+  its_steps: Vec<(Duration, Box<dyn Tween>)>,
+  /// Index of the step that is running
+  its_step: usize,
+  its_progress: Duration,
+}
+impl TweenSequence {
+  pub fn new() -> TweenSequence {
+    TweenSequence {
+      its_steps: Vec::new(),
+      its_step: 0,
+      its_progress: Duration::from_secs(0),
+    }
+  }
+  /// Append `the_tween`, which runs over `the_duration` once all steps
+  /// before it are done
+  pub fn then(mut self, the_tween: Box<dyn Tween>, the_duration: Duration) -> TweenSequence {
+    self.its_steps.push((the_duration, the_tween));
+    self
+  }
+  pub fn is_done(&self) -> bool {
+    self.its_step >= self.its_steps.len()
+  }
+  /// Time left over from a step that ends goes to the next one
+  pub fn tick(&mut self, the_api: &mut dyn TweenAPI, the_delta: Duration) -> () {
+    self.its_progress += the_delta;
+    while let Some((a_duration, a_tween)) = self.its_steps.get_mut(self.its_step) {
+      if self.its_progress < *a_duration {
+        a_tween.run(
+          self.its_progress.as_secs_f32() / a_duration.as_secs_f32(),
+          the_api,
+        );
+        return;
+      }
+      a_tween.run(1., the_api);
+      self.its_progress -= *a_duration;
+      self.its_step += 1;
+    }
+  }
+  /// Run every step that is not done yet to its end right away
+  pub fn finish(&mut self, the_api: &mut dyn TweenAPI) -> () {
+    for (_, a_tween) in self.its_steps.iter_mut().skip(self.its_step) {
+      a_tween.run(1., the_api);
+    }
+    self.its_step = self.its_steps.len();
+  }
 }
 
-struct AppTweenAPI<'g, 'r, 'w> {
+struct AppTweenAPI<'g, 'r, 's> {
   its_game_state: &'g mut model::GameState,
-  its_renderer: &'r dyn renderer::Renderer,
-  its_window: &'w Window,
+  its_renderer: &'r mut dyn renderer::Renderer,
+  its_settings: &'s Settings,
 }
-impl<'g, 'r, 'w> AppTweenAPI<'g, 'r, 'w> {
+impl<'g, 'r, 's> AppTweenAPI<'g, 'r, 's> {
   pub fn new(
     the_game: &'g mut model::GameState,
-    the_renderer: &'r dyn renderer::Renderer,
-    the_window: &'w Window,
-  ) -> AppTweenAPI<'g, 'r, 'w> {
+    the_renderer: &'r mut dyn renderer::Renderer,
+    the_settings: &'s Settings,
+  ) -> AppTweenAPI<'g, 'r, 's> {
     AppTweenAPI {
       its_game_state: the_game,
       its_renderer: the_renderer,
-      its_window: the_window,
+      its_settings: the_settings,
     }
   }
 }
-impl<'g, 'a, 'w> TweenAPI for AppTweenAPI<'g, 'a, 'w> {
-  fn get_window(&self) -> &Window {
-    self.its_window
-  }
-  fn get_renderer(&self) -> &dyn renderer::Renderer {
+impl<'g, 'a, 's> TweenAPI for AppTweenAPI<'g, 'a, 's> {
+  fn get_renderer_mut(&mut self) -> &mut dyn renderer::Renderer {
     self.its_renderer
   }
   fn get_game_state_mut(&mut self) -> &mut model::GameState {
     self.its_game_state
   }
+  fn get_settings(&self) -> &Settings {
+    self.its_settings
+  }
+  fn get_rotation_speed(&self) -> f32 {
+    self.its_game_state.get_rotation_speed()
+  }
+  fn set_rotation_speed(&mut self, the_speed: f32) -> () {
+    self
+      .its_game_state
+      .submit_style_command(model::StyleCommand::SetRotationSpeed(the_speed));
+  }
+}
+
+/// Spawner and timeline for runs of `the_level`
+fn create_level_logic(the_level: &LevelDef) -> (Spawner, Timeline) {
+  let a_spawner = Spawner::new(
+    the_level.patterns.clone(),
+    the_level.difficulty_ramp,
+    the_level.spawn_strategy,
+  );
+  (a_spawner, Timeline::new(&the_level.events, the_level.bpm))
+}
+
+/// Everything that determines how a run goes on from a point in time, so
+/// that practice mode can rewind to it, or that the opponent's run of a
+/// lockstep race can be simulated alongside the own
+struct Snapshot {
+  its_game: model::GameState,
+  its_spawner: Spawner,
+  its_timeline: Timeline,
+  its_hyper: bool,
 }
 
 pub struct App<Renderer: renderer::Renderer> {
@@ -145,6 +458,106 @@ pub struct App<Renderer: renderer::Renderer> {
   its_controls: controls::Controls,
   its_renderer: Renderer,
   its_tweens: TweenEngine,
+  its_spawner: Spawner,
+  its_timeline: Timeline,
+  /// The run waits until the player follows this prompt of the level
+  its_prompt: Option<Prompt>,
+  /// Played when a level starts, the run waits until it is over
+  its_intro: Option<TweenSequence>,
+  /// What is left of the slow motion after a death, the game over screen
+  /// waits for it
+  its_death_effect: Duration,
+  /// What is left of the countdown after a pause, the run waits for it
+  its_countdown: Duration,
+  /// What is left of the time the current notice is shown
+  its_notice_left: Duration,
+  /// The level being played and its style, restored at the start of a run
+  its_level: Option<LevelDef>,
+  its_level_style: model::Style,
+  /// Whether the level has turned into its hyper level during this run
+  its_hyper: bool,
+  its_editor: Option<PatternEditor>,
+  its_theme_editor: Option<ThemeEditor>,
+  /// The game is frozen while the developer console is open
+  its_console: Option<Console>,
+  its_loader: Option<loader::PackLoader>,
+  its_pack: Option<pack::Pack>,
+  its_settings: Settings,
+  its_settings_path: PathBuf,
+  /// Texts in the language of the settings
+  its_catalog: i18n::Catalog,
+  its_records: Records,
+  its_records_path: PathBuf,
+  its_stats: LifetimeStats,
+  its_stats_path: PathBuf,
+  its_history: RunHistory,
+  its_history_path: PathBuf,
+  /// How the current run is being played, saved once it ends so that it
+  /// can be rendered offline
+  its_recording: Option<Replay>,
+  its_replay_path: PathBuf,
+  /// A recorded run is being played back, which counts for nothing
+  its_playback: bool,
+  its_checkpoint_path: PathBuf,
+  its_since_checkpoint: Duration,
+  /// The run that was interrupted by a crash, offered to be resumed on the
+  /// game over screen at launch
+  its_resumable: Option<Checkpoint>,
+  /// The run to resume once its pack has been loaded
+  its_pending_resume: Option<Checkpoint>,
+  /// Where runs ended, shown as a heatmap in practice mode
+  its_deaths: DeathLog,
+  its_deaths_path: PathBuf,
+  /// The game is frozen while the statistics screen is shown
+  its_show_stats: bool,
+  its_debug_overlay: bool,
+  /// The debug overlay is shown in a window of its own, see `debug_view`
+  its_debug_view: bool,
+  /// Spawns, stages and ends of the runs played since the game started
+  its_session_log: SessionLog,
+  /// The spawner's next waves, along with the wave count they were
+  /// predicted at and whether the game was running, since predicting them
+  /// means playing ahead
+  its_upcoming: Option<((usize, bool), Vec<UpcomingWave>)>,
+  its_focused: bool,
+  /// The simulation is frozen while the window is unfocused, if the
+  /// settings ask for it
+  its_paused: bool,
+  /// The simulation is frozen while the input device in use is unplugged
+  its_device_lost: bool,
+  /// Simulation time that has passed but not been ticked yet
+  its_sim_backlog: Duration,
+  /// Tick of the run before which the cursor was at the given position,
+  /// the cursor is drawn in between there and where it is now
+  its_previous_position: Option<(u64, f32)>,
+  /// Allows saving and restoring the state of a run, which then no longer
+  /// counts as a record
+  its_practice: bool,
+  its_snapshot: Option<Snapshot>,
+  its_daily_results: DailyResults,
+  its_daily_results_path: PathBuf,
+  /// Date of the daily challenge being played, if the run is one. Daily runs
+  /// are played with the settings that affect fairness at their defaults.
+  its_daily: Option<String>,
+  /// No obstacles and no death, just the moving playfield, e.g. as a
+  /// screensaver
+  its_zen: bool,
+  /// A bot plays a demo run, which ends on any key press
+  its_demo: bool,
+  /// How long nothing has happened on the game over screen
+  its_idle_time: Duration,
+  /// Race against another instance, whose rounds are played with the same
+  /// settings locked as daily challenges
+  its_race: Option<Race>,
+  /// The opponent's world in lockstep rounds of the race, simulated here
+  /// from their inputs as on their side
+  its_rival: Option<Snapshot>,
+  /// Events of the current tick, kept to reuse its storage
+  its_events: Vec<model::GameEvent>,
+  /// What the window title was last set to, it is only set on changes
+  its_title: String,
+  /// Frame times of the last second for the frame rate overlay
+  its_fps_meter: FpsMeter,
 }
 
 impl<Renderer: renderer::Renderer> App<Renderer> {
@@ -152,22 +565,114 @@ impl<Renderer: renderer::Renderer> App<Renderer> {
     the_game: model::GameState,
     the_controls: controls::Controls,
     the_renderer: Renderer,
+    the_settings: Settings,
+    the_settings_path: &Path,
   ) -> App<Renderer> {
+    let a_level_style = the_game.get_style().clone();
+    let a_records_path = the_settings_path.with_file_name("records.json");
+    let a_daily_results_path = the_settings_path.with_file_name("daily.json");
+    let a_stats_path = the_settings_path.with_file_name("stats.json");
+    let a_deaths_path = the_settings_path.with_file_name("deaths.json");
+    let a_history_path = the_settings_path.with_file_name("history.json");
+    let a_replay_path = the_settings_path.with_file_name("replay.json");
+    let a_checkpoint_path = the_settings_path.with_file_name("checkpoint.json");
+    let a_catalog = i18n::Catalog::load(the_settings.language.as_deref());
     let mut a_app = App {
       its_game: the_game,
       its_controls: the_controls,
       its_renderer: the_renderer,
       its_tweens: TweenEngine::new(),
+      its_spawner: Spawner::new(Vec::new(), 20., SpawnStrategy::Weighted),
+      its_timeline: Timeline::new(&[], None),
+      its_prompt: None,
+      its_intro: None,
+      its_death_effect: Duration::from_secs(0),
+      its_countdown: Duration::from_secs(0),
+      its_notice_left: Duration::from_secs(0),
+      its_level: None,
+      its_level_style: a_level_style,
+      its_hyper: false,
+      its_editor: None,
+      its_theme_editor: None,
+      its_console: None,
+      its_loader: None,
+      its_pack: None,
+      its_settings: the_settings,
+      its_settings_path: the_settings_path.to_path_buf(),
+      its_catalog: a_catalog,
+      its_records: Records::load(&a_records_path),
+      its_records_path: a_records_path,
+      its_stats: LifetimeStats::load(&a_stats_path),
+      its_stats_path: a_stats_path,
+      its_history: RunHistory::load(&a_history_path),
+      its_history_path: a_history_path,
+      its_recording: None,
+      its_replay_path: a_replay_path,
+      its_playback: false,
+      its_resumable: None,
+      its_checkpoint_path: a_checkpoint_path,
+      its_since_checkpoint: Duration::from_secs(0),
+      its_pending_resume: None,
+      its_deaths: DeathLog::load(&a_deaths_path),
+      its_deaths_path: a_deaths_path,
+      its_show_stats: false,
+      its_debug_overlay: false,
+      its_debug_view: false,
+      its_session_log: SessionLog::default(),
+      its_upcoming: None,
+      its_focused: true,
+      its_paused: false,
+      its_device_lost: false,
+      its_sim_backlog: Duration::from_secs(0),
+      its_previous_position: None,
+      its_practice: false,
+      its_snapshot: None,
+      its_daily_results: DailyResults::load(&a_daily_results_path),
+      its_daily_results_path: a_daily_results_path,
+      its_daily: None,
+      its_zen: false,
+      its_demo: false,
+      its_idle_time: Duration::from_secs(0),
+      its_race: None,
+      its_rival: None,
+      its_events: Vec::new(),
+      its_title: String::new(),
+      its_fps_meter: FpsMeter::new(),
     };
+    let a_reduced_flash = a_app.its_settings.reduced_flash;
+    a_app.its_renderer.set_reduced_flash(a_reduced_flash);
+    let a_motion_trail = a_app.its_settings.motion_trail;
+    a_app.its_renderer.set_motion_trail(a_motion_trail);
+    let a_proximity_shading = a_app.its_settings.proximity_shading;
+    a_app
+      .its_renderer
+      .set_proximity_shading(a_proximity_shading);
+    let a_forced_aspect = a_app.its_settings.forced_aspect;
+    a_app.its_renderer.set_forced_aspect(a_forced_aspect);
+    let a_chroma_key = a_app.its_settings.get_chroma_key();
+    a_app.its_renderer.set_chroma_key(a_chroma_key);
+    let a_cursor_shape = a_app
+      .get_cursor_skin()
+      .map(|the_skin| the_skin.shape.clone());
+    a_app.its_renderer.set_cursor_shape(a_cursor_shape);
+    let a_catalog = a_app.its_catalog.clone();
+    a_app.its_renderer.set_catalog(a_catalog);
+    let a_scheme = a_app.its_settings.control_scheme;
+    a_app.its_controls.set_scheme(a_scheme);
+    let a_tick_rate = a_app.its_settings.get_tick_rate();
+    a_app.its_game.set_tick_rate(a_tick_rate);
+    a_app.start_run(Rng::time_seed());
+    a_app.its_resumable = Checkpoint::load(&a_app.its_checkpoint_path);
+    a_app.offer_resume();
     a_app.its_tweens.register(
-      Box::new(FPSTween::new()),
+      Box::new(ZoomTween::new(constants::ZOOM_PULSE_DEPTH)),
+      Duration::from_secs(2),
       Duration::from_secs(0),
-      Duration::from_secs(1),
       -1,
     );
     a_app.its_tweens.register(
-      Box::new(ZoomTween::new()),
-      Duration::from_secs(2),
+      Box::new(WobbleTween::new(constants::CAMERA_WOBBLE)),
+      Duration::from_secs(4),
       Duration::from_secs(0),
       -1,
     );
@@ -184,13 +689,1923 @@ impl<Renderer: renderer::Renderer> App<Renderer> {
     &mut self.its_renderer
   }
 
+  pub fn get_settings(&self) -> &Settings {
+    &self.its_settings
+  }
+  fn save_settings(&self) -> () {
+    if let Err(a_err) = self.its_settings.save(&self.its_settings_path) {
+      eprintln!(
+        "Failed to save settings to {}: {}",
+        self.its_settings_path.display(),
+        a_err
+      );
+    }
+  }
+  /// The game's style with all accessibility options applied. This is what
+  /// gets rendered.
+  fn get_display_style(&self) -> model::Style {
+    let mut a_style = self.its_game.get_style().clone();
+    if let Some(a_skin) = self.get_cursor_skin() {
+      if let Some(a_color) = &a_skin.color {
+        a_style.set_cursor_color(a_color.0.clone());
+      }
+      if let Some(a_color) = &a_skin.shadow_color {
+        a_style.set_cursor_shadow_color(a_color.0.clone());
+      }
+    }
+    if self.its_settings.high_contrast {
+      // The high-contrast scheme already works without relying on hue
+      accessibility::apply_high_contrast(&mut a_style);
+    } else {
+      accessibility::apply_palette_mode(self.its_settings.palette_mode, &mut a_style);
+    }
+    a_style
+  }
+  /// The effects requested by the level plus the ones chosen by the user
+  fn get_post_effects(&self) -> PostEffects {
+    match self.get_current_level() {
+      Some(a_level) => a_level.post_effects.union(&self.its_settings.post_effects),
+      None => self.its_settings.post_effects,
+    }
+  }
+  pub fn focus_changed(&mut self, the_focused: bool) -> () {
+    self.its_focused = the_focused;
+    if self.its_settings.pause_when_unfocused {
+      self.its_paused = !the_focused;
+    }
+  }
+  pub fn device_changed(&mut self, the_change: DeviceChange) -> () {
+    // the notice of the lost device stays until one is back
+    let (a_key, a_time) = match the_change {
+      DeviceChange::Lost => ("notice.device_lost", Duration::MAX),
+      DeviceChange::Rebound => ("notice.device_rebound", NOTICE_TIME),
+    };
+    let a_text = self.its_catalog.get(a_key).to_string();
+    self.notify(a_text, a_time);
+    self.its_device_lost = the_change == DeviceChange::Lost;
+    if self.its_device_lost {
+      // the device will not report what was held when it went away
+      self.its_controls.release_all();
+    }
+  }
+  /// Most frames to draw per second, lowered while the window is in the
+  /// background if the settings ask for saving power
+  pub fn get_fps_limit(&self) -> Option<u32> {
+    let a_limit = self.its_settings.get_fps_limit();
+    if self.its_focused || !self.its_settings.power_saver {
+      return a_limit;
+    }
+    Some(a_limit.map_or(constants::POWER_SAVER_FPS, |the_limit| {
+      the_limit.min(constants::POWER_SAVER_FPS)
+    }))
+  }
+  /// Remember where `the_window` is so that it opens there next time
+  pub fn remember_window(&mut self, the_window: &Window) -> () {
+    let a_geometry = WindowGeometry::capture(the_window, self.its_settings.window.as_ref());
+    if a_geometry.is_some() {
+      self.its_settings.window = a_geometry;
+      self.save_settings();
+    }
+  }
+  /// Put the window on the selected monitor, in fullscreen with the
+  /// selected video mode if `the_fullscreen` is set
+  pub fn apply_display_settings(&self, the_window: &Window, the_fullscreen: bool) -> () {
+    let a_name = self
+      .its_settings
+      .monitor
+      .as_ref()
+      .or_else(|| self.its_settings.window.as_ref()?.monitor.as_ref());
+    let a_monitor = a_name.and_then(|the_name| window::find_monitor(the_window, the_name));
+    if the_fullscreen {
+      let a_mode = self.its_settings.video_mode.as_ref();
+      window::set_fullscreen(the_window, a_monitor, a_mode, true);
+    } else {
+      window::set_fullscreen(the_window, None, None, false);
+      if let Some(a_monitor) = &a_monitor {
+        window::move_to_monitor(the_window, a_monitor);
+      }
+    }
+  }
+  fn cycle_monitor(&mut self, the_window: &Window) -> () {
+    let a_monitors = window::list_monitors(the_window);
+    if a_monitors.is_empty() {
+      return;
+    }
+    let a_current = window::select_monitor(the_window, self.its_settings.monitor.as_deref())
+      .and_then(|the_monitor| the_monitor.name());
+    let a_next = match a_monitors
+      .iter()
+      .position(|the_name| Some(the_name) == a_current.as_ref())
+    {
+      Some(a_idx) => (a_idx + 1) % a_monitors.len(),
+      None => 0,
+    };
+    let a_text = self
+      .its_catalog
+      .format("notice.monitor", &[("name", &a_monitors[a_next])]);
+    self.notify(a_text, NOTICE_TIME);
+    self.its_settings.monitor = Some(a_monitors[a_next].clone());
+    // modes differ between monitors
+    self.its_settings.video_mode = None;
+  }
+  fn print_fps_limit(&self) -> () {
+    match self.its_settings.get_fps_limit() {
+      Some(a_limit) => println!("Limiting the frame rate to {} FPS", a_limit),
+      None => println!("Uncapped frame rate"),
+    }
+  }
+  /// Step through the video modes of the selected monitor, ending with its
+  /// native one
+  fn cycle_video_mode(&mut self, the_window: &Window) -> () {
+    let a_monitor = match window::select_monitor(the_window, self.its_settings.monitor.as_deref()) {
+      Some(a_monitor) => a_monitor,
+      None => return,
+    };
+    let a_modes = window::list_video_modes(&a_monitor);
+    let a_next = match &self.its_settings.video_mode {
+      None => 0,
+      Some(a_mode) => match a_modes.iter().position(|the_mode| the_mode == a_mode) {
+        Some(a_idx) => a_idx + 1,
+        None => 0,
+      },
+    };
+    self.its_settings.video_mode = a_modes.get(a_next).cloned();
+    let a_text = match &self.its_settings.video_mode {
+      Some(a_mode) => self
+        .its_catalog
+        .format("notice.video_mode", &[("mode", a_mode)]),
+      None => self.its_catalog.get("notice.native_video_mode").to_string(),
+    };
+    self.notify(a_text, NOTICE_TIME);
+  }
+  /// React to what happened in the game during the last tick
+  fn handle_game_event(&mut self, the_event: &model::GameEvent) -> () {
+    self.its_session_log.record_game_event(
+      self.its_game.get_run().get_ticks(),
+      the_event,
+      self.its_game.get_current_slot_idx(),
+    );
+    let a_ended = matches!(
+      the_event,
+      model::GameEvent::Died | model::GameEvent::Completed
+    );
+    if a_ended {
+      let a_medal = self
+        .get_goal()
+        .and_then(|the_goal| the_goal.get_medal(self.its_game.get_run().get_time()));
+      self.its_renderer.set_medal(a_medal);
+      // the opponent of a race would not wait
+      let a_died = matches!(the_event, model::GameEvent::Died);
+      if a_died && !self.its_demo && self.its_race.is_none() {
+        self.start_death_effect();
+      }
+      if self.its_demo {
+        // demo runs neither count nor end the demo
+        self.start_run(DEMO_SEED);
+        return;
+      }
+      if self.its_playback {
+        return;
+      }
+      if let Some(a_race) = &mut self.its_race {
+        // the opponent of a lockstep round simulates the death by itself
+        if !a_race.is_lockstep() {
+          a_race.report_death(self.its_game.get_run().get_time());
+        }
+      }
+      self.submit_stats();
+      self.submit_run();
+      self.save_replay();
+      Checkpoint::remove(&self.its_checkpoint_path);
+    }
+  }
+  /// Keep the inputs of the run that just ended, replacing the previous
+  /// ones
+  fn save_replay(&mut self) -> () {
+    let a_replay = match self.its_recording.take() {
+      Some(a_replay) => a_replay,
+      None => return,
+    };
+    if let Err(a_err) = a_replay.save(&self.its_replay_path) {
+      eprintln!(
+        "Failed to save replay to {}: {}",
+        self.its_replay_path.display(),
+        a_err
+      );
+    }
+  }
+  /// Play `the_replay` back instead of letting the player steer. Its pack
+  /// has to be loaded already.
+  pub fn start_playback(&mut self, the_replay: Replay) -> Result<(), String> {
+    if let Some(a_level) = &the_replay.level {
+      self.select_level(a_level)?;
+    }
+    self.its_game.set_tick_rate(the_replay.tick_rate);
+    self.its_playback = true;
+    self.start_run(the_replay.seed);
+    self.its_controls.set_playback(Some(the_replay));
+    Ok(())
+  }
+  pub fn is_running(&self) -> bool {
+    self.its_game.is_running()
+  }
+  /// Identifies the level being played in the records
+  fn get_record_key(&self) -> String {
+    format!(
+      "{}/{}",
+      self
+        .its_pack
+        .as_ref()
+        .map_or("built-in", |the_pack| the_pack.get_name()),
+      self
+        .its_level
+        .as_ref()
+        .map_or("built-in", |the_level| the_level.name.as_str())
+    )
+  }
+  /// Count the run that just ended in the lifetime statistics, the death
+  /// log and the run history
+  fn submit_stats(&mut self) -> () {
+    let a_key = self.get_record_key();
+    self.its_stats.submit(&a_key, self.its_game.get_run());
+    if let Err(a_err) = self.its_stats.save(&self.its_stats_path) {
+      eprintln!(
+        "Failed to save statistics to {}: {}",
+        self.its_stats_path.display(),
+        a_err
+      );
+    }
+    self.its_deaths.record(&a_key, &self.its_game);
+    if let Err(a_err) = self.its_deaths.save(&self.its_deaths_path) {
+      eprintln!(
+        "Failed to save death log to {}: {}",
+        self.its_deaths_path.display(),
+        a_err
+      );
+    }
+    self.update_heatmap();
+    self.its_history.add(&a_key, &self.its_game);
+    if let Err(a_err) = self.its_history.save(&self.its_history_path) {
+      eprintln!(
+        "Failed to save run history to {}: {}",
+        self.its_history_path.display(),
+        a_err
+      );
+    }
+  }
+  /// Show the deaths in the current level in practice mode
+  fn update_heatmap(&mut self) -> () {
+    let a_heatmap = if self.its_practice {
+      Some(self.its_deaths.get_heatmap(&self.get_record_key()))
+    } else {
+      None
+    };
+    self.its_renderer.set_heatmap(a_heatmap);
+  }
+  fn submit_run(&mut self) -> () {
+    let a_key = self.get_record_key();
+    if let Some(a_date) = self.its_daily.take() {
+      let a_run = self.its_game.get_run();
+      self.its_daily_results.finish(&a_key, &a_date, a_run);
+      println!(
+        "Daily challenge {}: {}s",
+        a_date,
+        summary::format_time(a_run.get_time())
+      );
+      self.save_daily_results();
+      return;
+    }
+    let a_run = self.its_game.get_run();
+    let a_medal = self
+      .get_goal()
+      .and_then(|the_goal| the_goal.get_medal(a_run.get_time()));
+    let a_best = self.its_records.submit(&a_key, a_run);
+    if a_best {
+      println!("New best time: {}s", summary::format_time(a_run.get_time()));
+    }
+    let a_goal = self.get_goal().is_some() && self.its_records.submit_goal(&a_key, a_run, a_medal);
+    if !a_best && !a_goal {
+      return;
+    }
+    if let Err(a_err) = self.its_records.save(&self.its_records_path) {
+      eprintln!(
+        "Failed to save records to {}: {}",
+        self.its_records_path.display(),
+        a_err
+      );
+    }
+  }
+  /// Start today's challenge of the current level, unless it has already
+  /// been attempted
+  fn start_daily(&mut self) -> () {
+    let a_key = self.get_record_key();
+    let a_date = daily::get_today();
+    if let Some(a_result) = self.its_daily_results.get(&a_key, &a_date) {
+      println!(
+        "Daily challenge {} already played: {}s",
+        a_date,
+        summary::format_time(Duration::from_secs_f32(a_result.time))
+      );
+      return;
+    }
+    let a_seed = daily::get_seed(&a_date, &a_key);
+    self.its_practice = false;
+    self.its_snapshot = None;
+    self.its_zen = false;
+    self.start_run(a_seed);
+    if let Some(a_recording) = &mut self.its_recording {
+      a_recording.speed_curve = None;
+    }
+    self.its_daily_results.begin(&a_key, &a_date, a_seed);
+    self.save_daily_results();
+    println!("Daily challenge {} started", a_date);
+    self.its_daily = Some(a_date);
+  }
+  /// Switch zen mode on or off, which starts a new run either way
+  pub fn set_zen(&mut self, the_zen: bool) -> () {
+    if the_zen && self.is_fairness_locked() {
+      println!("Zen mode is not available in daily challenges and races");
+      return;
+    }
+    if the_zen == self.its_zen {
+      return;
+    }
+    self.its_zen = the_zen;
+    self.start_run(Rng::time_seed());
+  }
+  /// Let the bot play a demo run once the game over screen has been left
+  /// alone for long enough
+  fn tick_attract(&mut self, the_delta: Duration) -> () {
+    // the offer to resume a run must not time out
+    if self.its_game.is_running() || self.its_race.is_some() || self.its_resumable.is_some() {
+      self.its_idle_time = Duration::from_secs(0);
+      return;
+    }
+    self.its_idle_time += the_delta;
+    if self.its_idle_time >= ATTRACT_DELAY {
+      self.its_idle_time = Duration::from_secs(0);
+      self.its_demo = true;
+      self.its_controls.set_bot(Some(Bot::new()));
+      self.start_run(DEMO_SEED);
+    }
+  }
+  /// Back to the game over screen, with a fresh run waiting to be started
+  fn stop_demo(&mut self) -> () {
+    self.its_demo = false;
+    self.its_controls.set_bot(None);
+    self.start_run(Rng::time_seed());
+    self.its_game.set_running(false);
+  }
+  /// Whether the settings that affect fairness are locked at their defaults
+  fn is_fairness_locked(&self) -> bool {
+    self.its_daily.is_some() || self.its_race.is_some()
+  }
+  fn save_daily_results(&self) -> () {
+    if let Err(a_err) = self.its_daily_results.save(&self.its_daily_results_path) {
+      eprintln!(
+        "Failed to save daily results to {}: {}",
+        self.its_daily_results_path.display(),
+        a_err
+      );
+    }
+  }
+  /// Put a description of the last run on the clipboard for sharing it
+  fn copy_run_summary(&self) -> () {
+    let a_summary = summary::format_run_summary(
+      &self.its_catalog,
+      self.its_pack.as_ref().map(|the_pack| the_pack.get_name()),
+      self
+        .get_current_level()
+        .map(|the_level| the_level.name.as_str()),
+      self.its_game.get_run(),
+    );
+    match clipboard::copy(&a_summary) {
+      Ok(()) => println!("Copied run summary to the clipboard"),
+      Err(a_err) => eprintln!("Failed to copy run summary: {}", a_err),
+    }
+  }
+  fn handle_hotkey(&mut self, the_scancode: u32, the_window: &Window) -> () {
+    match the_scancode {
+      controls::PALETTE_KEY => {
+        self.its_settings.palette_mode = self.its_settings.palette_mode.next();
+        self.save_settings();
+      }
+      controls::HIGH_CONTRAST_KEY => {
+        self.its_settings.high_contrast = !self.its_settings.high_contrast;
+        self.save_settings();
+      }
+      controls::GAME_SPEED_KEY => {
+        self.its_settings.cycle_game_speed();
+        self.save_settings();
+      }
+      controls::EDITOR_KEY => {
+        self.its_theme_editor = None;
+        self.its_editor = match self.its_editor {
+          Some(_) => None,
+          None => Some(PatternEditor::new(&self.its_game)),
+        };
+      }
+      controls::THEME_EDITOR_KEY => {
+        self.its_editor = None;
+        self.its_theme_editor = match self.its_theme_editor {
+          Some(_) => None,
+          None => Some(ThemeEditor::new(self.its_game.get_style())),
+        };
+      }
+      controls::MOTION_TRAIL_KEY => {
+        self.its_settings.motion_trail = !self.its_settings.motion_trail;
+        self
+          .its_renderer
+          .set_motion_trail(self.its_settings.motion_trail);
+        self.save_settings();
+      }
+      controls::PROXIMITY_SHADING_KEY => {
+        self.its_settings.proximity_shading = !self.its_settings.proximity_shading;
+        self
+          .its_renderer
+          .set_proximity_shading(self.its_settings.proximity_shading);
+        self.save_settings();
+      }
+      controls::SCREEN_RELATIVE_KEY => {
+        // takes effect with the next run, which records it
+        self.its_settings.screen_relative_steering = !self.its_settings.screen_relative_steering;
+        self.save_settings();
+      }
+      controls::FULLSCREEN_KEY => {
+        // keep the windowed geometry from before going fullscreen
+        self.remember_window(the_window);
+        self.apply_display_settings(the_window, !window::is_fullscreen(the_window));
+      }
+      controls::DISPLAY_KEY => {
+        if self.its_controls.is_shift_pressed() {
+          self.cycle_video_mode(the_window);
+        } else {
+          self.cycle_monitor(the_window);
+        }
+        self.save_settings();
+        self.apply_display_settings(the_window, window::is_fullscreen(the_window));
+      }
+      controls::FORCED_ASPECT_KEY => {
+        self.its_settings.cycle_forced_aspect();
+        self
+          .its_renderer
+          .set_forced_aspect(self.its_settings.forced_aspect);
+        self.save_settings();
+      }
+      controls::DEBUG_OVERLAY_KEY => {
+        if self.its_controls.is_shift_pressed() {
+          self.its_debug_view = !self.its_debug_view;
+        } else {
+          self.its_debug_overlay = !self.its_debug_overlay;
+          self.its_renderer.set_debug_overlay(self.its_debug_overlay);
+        }
+      }
+      controls::COPY_SUMMARY_KEY if !self.its_game.is_running() => self.copy_run_summary(),
+      controls::RESUME_KEY if !self.its_game.is_running() && !self.is_loading() => {
+        self.accept_resume()
+      }
+      controls::SKIP_INTRO_KEY => self.skip_intro(),
+      controls::RESTART_KEY if !self.its_game.is_running() && !self.is_loading() => {
+        match &self.its_race {
+          Some(_) => self.propose_race_round(),
+          None => self.start_run(Rng::time_seed()),
+        }
+      }
+      controls::ZEN_KEY if !self.is_loading() => self.set_zen(!self.its_zen),
+      controls::SEED_KEY if !self.its_game.is_running() && !self.is_loading() => {
+        self.toggle_console();
+        if let Some(a_console) = &mut self.its_console {
+          a_console.type_text("seed ");
+        }
+      }
+      controls::DAILY_KEY
+        if !self.its_game.is_running() && !self.is_loading() && self.its_race.is_none() =>
+      {
+        self.start_daily()
+      }
+      controls::CONTROL_SCHEME_KEY => {
+        self.its_settings.control_scheme = self.its_settings.control_scheme.next();
+        self
+          .its_controls
+          .set_scheme(self.its_settings.control_scheme);
+        self.save_settings();
+      }
+      controls::MOTION_KEY => {
+        self.its_settings.cycle_motion_intensity();
+        self.save_settings();
+      }
+      controls::FPS_LIMIT_KEY => {
+        self.its_settings.cycle_fps_limit();
+        self.print_fps_limit();
+        self.save_settings();
+      }
+      controls::FPS_OVERLAY_KEY => {
+        self.its_settings.fps_overlay = !self.its_settings.fps_overlay;
+        self.save_settings();
+      }
+      controls::STATS_KEY => {
+        self.its_show_stats = !self.its_show_stats;
+        let a_stats = if self.its_show_stats {
+          Some(self.its_stats.clone())
+        } else {
+          None
+        };
+        self.its_renderer.set_stats(a_stats);
+      }
+      controls::INPUT_DISPLAY_KEY => {
+        self.its_settings.input_display = !self.its_settings.input_display;
+        self.save_settings();
+      }
+      controls::PRACTICE_KEY => {
+        if self.is_fairness_locked() {
+          let a_text = self.its_catalog.get("notice.practice_unavailable");
+          self.notify(a_text.to_string(), NOTICE_TIME);
+          return;
+        }
+        self.its_practice = !self.its_practice;
+        let a_key = if self.its_practice {
+          self.its_game.get_run_mut().mark_practice();
+          "notice.practice_on"
+        } else {
+          self.its_snapshot = None;
+          "notice.practice_off"
+        };
+        let a_text = self.its_catalog.get(a_key).to_string();
+        self.notify(a_text, NOTICE_TIME);
+        self.update_heatmap();
+      }
+      controls::SAVE_STATE_KEY if self.its_practice && self.its_game.is_running() => {
+        self.save_snapshot()
+      }
+      controls::LOAD_STATE_KEY if self.its_practice => self.load_snapshot(),
+      controls::REDUCED_FLASH_KEY => {
+        self.its_settings.reduced_flash = !self.its_settings.reduced_flash;
+        self
+          .its_renderer
+          .set_reduced_flash(self.its_settings.reduced_flash);
+        self.save_settings();
+      }
+      _ => (),
+    }
+  }
+
+  /// Race `the_race`, whose rounds replace the normal runs
+  pub fn set_race(&mut self, the_race: Race) -> () {
+    self.its_practice = false;
+    self.its_resumable = None;
+    self.its_renderer.set_prompt(None);
+    self.its_snapshot = None;
+    self.its_zen = false;
+    self.update_heatmap();
+    if !the_race.is_host() {
+      // the host decides when the first round starts
+      self.its_game.set_running(false);
+    }
+    self.its_race = Some(the_race);
+  }
+  /// Let the guest know which round to set up next, unless the opponent is
+  /// still playing the current one
+  fn propose_race_round(&mut self) -> () {
+    let a_key = self.get_record_key();
+    let a_opponent = self.get_opponent();
+    let a_tick_rate = self.its_settings.get_tick_rate();
+    let a_race = match &mut self.its_race {
+      Some(a_race) => a_race,
+      None => return,
+    };
+    if !a_race.is_host() {
+      println!("Waiting for the host to start the next round");
+    } else if !a_race.is_connected() {
+      println!("Waiting for an opponent to join");
+    } else if a_opponent.is_some_and(|the_status| the_status.alive) {
+      println!("Waiting for the opponent to finish the round");
+    } else {
+      a_race.propose(&a_key, Rng::time_seed(), a_tick_rate);
+      self.its_game.set_running(false);
+    }
+  }
+  fn tick_race(&mut self, the_delta: Duration) -> () {
+    let a_race = match &mut self.its_race {
+      Some(a_race) => a_race,
+      None => return,
+    };
+    // lockstep opponents simulate the own run rather than being told
+    if self.its_game.is_running() && !a_race.is_lockstep() {
+      a_race.tick(the_delta, self.its_game.get_run().get_time());
+    }
+    while let Some(a_event) = self.its_race.as_mut().and_then(|the_race| the_race.poll()) {
+      match a_event {
+        RaceEvent::Joined => {
+          println!("Opponent joined");
+          self.propose_race_round();
+        }
+        RaceEvent::Proposed { level, tick_rate } => {
+          let a_key = self.get_record_key();
+          if level != a_key {
+            eprintln!(
+              "Cannot race: the host plays {}, but {} is loaded",
+              level, a_key
+            );
+            self.its_race = None;
+          } else if let Some(a_race) = &mut self.its_race {
+            if let Some(a_tick_rate) = tick_rate {
+              self.its_game.set_tick_rate(a_tick_rate);
+            }
+            self.its_game.set_running(false);
+            a_race.ready();
+          }
+        }
+        RaceEvent::Start { seed } => {
+          self.start_run(seed);
+          let a_lockstep = self
+            .its_race
+            .as_ref()
+            .is_some_and(|the_race| the_race.is_lockstep());
+          self.its_rival = if a_lockstep {
+            // both runs start out alike, only the inputs tell them apart
+            Some(Snapshot {
+              its_game: self.its_game.clone(),
+              its_spawner: self.its_spawner.clone(),
+              its_timeline: self.its_timeline.clone(),
+              its_hyper: self.its_hyper,
+            })
+          } else {
+            None
+          };
+        }
+        RaceEvent::Disconnected(a_reason) => {
+          eprintln!("Race ended: {}", a_reason);
+          self.its_race = None;
+          self.its_rival = None;
+          let a_tick_rate = self.its_settings.get_tick_rate();
+          self.its_game.set_tick_rate(a_tick_rate);
+        }
+      }
+    }
+    let a_opponent = self.get_opponent();
+    self.its_renderer.set_opponent(a_opponent);
+  }
+  /// How the opponent of the race is doing, as simulated here in lockstep
+  /// rounds and as reported otherwise
+  fn get_opponent(&self) -> Option<OpponentStatus> {
+    if let Some(a_rival) = &self.its_rival {
+      return Some(OpponentStatus {
+        time: a_rival.its_game.get_run().get_time(),
+        alive: a_rival.its_game.is_running(),
+      });
+    }
+    self
+      .its_race
+      .as_ref()
+      .and_then(|the_race| the_race.get_opponent())
+  }
+  /// Whether the next tick of a lockstep round has to wait for the
+  /// opponent's input
+  fn is_waiting_for_opponent(&self) -> bool {
+    self
+      .its_race
+      .as_ref()
+      .is_some_and(|the_race| the_race.is_stepping() && !the_race.can_step())
+  }
+  /// Advance the opponent's world of a lockstep round by one tick in which
+  /// they steered in `the_direction`, just like `tick_simulation` does with
+  /// the own
+  fn tick_rival(&mut self, the_direction: i8) -> () {
+    let mut a_rival = match self.its_rival.take() {
+      Some(a_rival) => a_rival,
+      None => return,
+    };
+    let a_delta = a_rival.its_game.get_tick_duration();
+    a_rival
+      .its_game
+      .set_strict_math(self.its_game.is_strict_math());
+    controls::steer(&mut a_rival.its_game, the_direction, a_delta);
+    a_rival.its_spawner.tick(&mut a_rival.its_game, a_delta);
+    // races lock the motion settings at their defaults
+    let a_limits = MotionLimits::from_intensity(1.);
+    a_rival
+      .its_game
+      .set_max_rotation_speed(a_limits.max_rotation_speed);
+    a_rival.its_game.tick();
+    // nothing here reacts to what happens over there
+    let mut a_events = std::mem::take(&mut self.its_events);
+    a_rival.its_game.swap_events(&mut a_events);
+    a_events.clear();
+    self.its_events = a_events;
+    if !a_rival.its_hyper {
+      if let Some(a_hyper) = self.get_due_hyper(&a_rival.its_game) {
+        let a_run_time = a_rival.its_game.get_run().get_time().as_secs_f32();
+        a_rival.its_hyper = true;
+        a_rival.its_game.apply_level(&a_hyper);
+        let (a_spawner, a_timeline) = create_level_logic(&a_hyper);
+        a_rival.its_spawner = a_spawner;
+        a_rival.its_timeline = a_timeline;
+        let a_seed = a_rival.its_game.get_run().get_seed();
+        a_rival.its_spawner.reset(a_seed ^ HYPER_SEED_SALT);
+        a_rival.its_timeline.reset_at(a_run_time);
+      }
+    }
+    let mut a_api = AppTweenAPI::new(
+      &mut a_rival.its_game,
+      &mut self.its_renderer,
+      &self.its_settings,
+    );
+    a_rival.its_timeline.tick(&mut a_api, a_delta);
+    a_rival.its_timeline.take_prompt();
+    self.its_rival = Some(a_rival);
+  }
+
+  fn save_snapshot(&mut self) -> () {
+    self.its_snapshot = Some(Snapshot {
+      its_game: self.its_game.clone(),
+      its_spawner: self.its_spawner.clone(),
+      its_timeline: self.its_timeline.clone(),
+      its_hyper: self.its_hyper,
+    });
+    println!(
+      "Saved state at {}s",
+      summary::format_time(self.its_game.get_run().get_time())
+    );
+  }
+  /// Rewind to the saved state, also after the run has ended
+  fn load_snapshot(&mut self) -> () {
+    let a_snapshot = match &self.its_snapshot {
+      Some(a_snapshot) => a_snapshot,
+      None => return,
+    };
+    if a_snapshot.its_hyper != self.its_hyper {
+      // switches the shader back, the spawner and timeline are replaced
+      // below anyway
+      self.its_hyper = a_snapshot.its_hyper;
+      if let Some(a_level) = self.get_current_level().cloned() {
+        self.setup_level(&a_level);
+      }
+    }
+    let a_snapshot = self.its_snapshot.as_ref().unwrap();
+    self.its_game = a_snapshot.its_game.clone();
+    self.its_spawner = a_snapshot.its_spawner.clone();
+    self.its_timeline = a_snapshot.its_timeline.clone();
+    self.its_sim_backlog = Duration::from_secs(0);
+    self.set_prompt(None);
+    // the replay goes on from the saved state
+    let a_tick = self.its_game.get_run().get_ticks();
+    if let Some(a_recording) = &mut self.its_recording {
+      a_recording.truncate(a_tick);
+    }
+  }
+
+  /// Start loading a pack in the background. The game is halted until the
+  /// pack has been loaded and its first level applied.
+  pub fn load_pack(&mut self, the_dir: PathBuf) -> () {
+    self.its_game.set_running(false);
+    self.its_loader = Some(loader::PackLoader::spawn(the_dir));
+  }
+  pub fn is_loading(&self) -> bool {
+    self.its_loader.is_some()
+  }
+
+  fn tick_loader(&mut self, the_window: &Window) -> () {
+    let a_result = match &mut self.its_loader {
+      Some(a_loader) => match a_loader.poll() {
+        Some(a_result) => a_result,
+        None => {
+          let a_title = self.its_catalog.format(
+            "title.loading",
+            &[
+              ("pack", &a_loader.get_dir().display()),
+              ("percent", &((a_loader.get_progress() * 100.) as u32)),
+              ("file", &a_loader.get_current_file()),
+            ],
+          );
+          the_window.set_title(&a_title);
+          // set again once the pack has been loaded
+          self.its_title.clear();
+          return;
+        }
+      },
+      None => return,
+    };
+    self.its_loader = None;
+    // a savestate of another level makes no sense
+    self.its_snapshot = None;
+    match a_result {
+      Ok(a_pack) => {
+        // the level's assets are looked up in the pack
+        let a_level = a_pack.get_levels().first().cloned();
+        self.its_pack = Some(a_pack);
+        if let Some(a_level) = a_level {
+          self.setup_level(&a_level);
+          self.its_level = Some(a_level);
+          self.its_hyper = false;
+        }
+        if let Some(a_checkpoint) = self.its_pending_resume.take() {
+          self.resume(a_checkpoint);
+          return;
+        }
+        // the offer still stands after loading the pack of the command line
+        let a_resumable = self.its_resumable.take();
+        self.start_run(Rng::time_seed());
+        self.start_intro();
+        self.its_resumable = a_resumable;
+      }
+      Err(a_err) => eprintln!("Failed to load pack: {}", a_err),
+    }
+    // a guest waits for the host to start the round
+    let a_waiting = self
+      .its_race
+      .as_ref()
+      .is_some_and(|the_race| !the_race.is_host());
+    self.its_game.set_running(!a_waiting);
+    self.offer_resume();
+  }
+  /// Show the offer to resume the interrupted run, if there is one, on the
+  /// game over screen
+  fn offer_resume(&mut self) -> () {
+    if self.its_resumable.is_none() {
+      return;
+    }
+    self.its_game.set_running(false);
+    let a_text = self.its_catalog.get("resume.offer").to_string();
+    self.its_renderer.set_prompt(Some(a_text));
+  }
+  /// Take up the offer to resume the interrupted run, loading its pack
+  /// first if need be
+  fn accept_resume(&mut self) -> () {
+    let a_checkpoint = match self.its_resumable.take() {
+      Some(a_checkpoint) => a_checkpoint,
+      None => return,
+    };
+    self.its_renderer.set_prompt(None);
+    let a_loaded = self
+      .its_pack
+      .as_ref()
+      .map(|the_pack| the_pack.get_dir().to_path_buf());
+    if a_checkpoint.replay.pack == a_loaded {
+      self.resume(a_checkpoint);
+      return;
+    }
+    match a_checkpoint.replay.pack.clone() {
+      Some(a_dir) => {
+        self.its_pending_resume = Some(a_checkpoint);
+        self.load_pack(a_dir);
+      }
+      None => eprintln!("Cannot resume the run: it was played without a pack"),
+    }
+  }
+  /// Continue the run of `the_checkpoint` where it was interrupted. Its
+  /// pack has to be loaded.
+  fn resume(&mut self, the_checkpoint: Checkpoint) -> () {
+    let Checkpoint {
+      replay: a_replay,
+      state: a_state,
+    } = the_checkpoint;
+    if let Some(a_level) = &a_replay.level {
+      if let Err(a_err) = self.select_level(a_level) {
+        eprintln!("Cannot resume the run: {}", a_err);
+        return;
+      }
+    }
+    self.its_game.set_tick_rate(a_replay.tick_rate);
+    self.start_run(a_replay.seed);
+    let a_tick_rate = self.its_settings.get_tick_rate();
+    self.its_game.set_tick_rate(a_tick_rate);
+    // the spawner and the timeline are not saved, playing the inputs again
+    // brings them to where they were
+    self.its_controls.set_playback(Some(a_replay.clone()));
+    self.its_game.set_invincible(true);
+    for _ in 0..a_state.get_run().get_ticks() {
+      self.tick_simulation();
+    }
+    self.its_controls.set_playback(None);
+    self.set_prompt(None);
+    self.its_game = a_state;
+    self.its_recording = Some(a_replay);
+    // killing the game must not take back mistakes
+    self.its_game.get_run_mut().mark_practice();
+    println!(
+      "Resumed the run at {}s",
+      summary::format_time(self.its_game.get_run().get_time())
+    );
+  }
+  /// Checkpoint the run in progress every now and then
+  fn tick_checkpoint(&mut self, the_delta: Duration) -> () {
+    if !self.its_game.is_running() || self.its_zen || self.its_race.is_some() {
+      return;
+    }
+    // nothing is recorded in demo runs and playback
+    let a_recording = match &self.its_recording {
+      Some(a_recording) => a_recording,
+      None => return,
+    };
+    self.its_since_checkpoint += the_delta;
+    if self.its_since_checkpoint < CHECKPOINT_INTERVAL {
+      return;
+    }
+    self.its_since_checkpoint = Duration::from_secs(0);
+    let a_checkpoint = Checkpoint::new(a_recording.clone(), self.its_game.clone());
+    if let Err(a_err) = a_checkpoint.save(&self.its_checkpoint_path) {
+      eprintln!(
+        "Failed to save checkpoint to {}: {}",
+        self.its_checkpoint_path.display(),
+        a_err
+      );
+    }
+  }
+
+  /// The level being played, i.e. the hyper level once it has been reached
+  fn get_current_level(&self) -> Option<&LevelDef> {
+    let a_level = self.its_level.as_ref()?;
+    if self.its_hyper {
+      a_level.hyper.as_deref()
+    } else {
+      Some(a_level)
+    }
+  }
+  /// Goal of the level's time attack, if it is one. Zen mode never ends.
+  fn get_goal(&self) -> Option<&GoalDef> {
+    if self.its_zen {
+      return None;
+    }
+    self.its_level.as_ref()?.goal.as_ref()
+  }
+  /// Look of the cursor, the player's choice over the level's
+  fn get_cursor_skin(&self) -> Option<&CursorDef> {
+    self
+      .its_settings
+      .cursor
+      .as_ref()
+      .or_else(|| self.get_current_level()?.cursor.as_ref())
+  }
+  fn setup_level(&mut self, the_level: &LevelDef) -> () {
+    let (a_spawner, a_timeline) = create_level_logic(the_level);
+    self.its_spawner = a_spawner;
+    self.its_timeline = a_timeline;
+    let a_assets = pack::AssetResolver::new(self.its_pack.as_ref());
+    let a_font = a_assets.get_font(the_level.font.as_deref());
+    self.its_renderer.set_font(a_font);
+    let a_cursor_shape = self
+      .its_settings
+      .cursor
+      .as_ref()
+      .or(the_level.cursor.as_ref())
+      .map(|the_skin| the_skin.shape.clone());
+    self.its_renderer.set_cursor_shape(a_cursor_shape);
+    let a_shader = the_level.fragment_shader_source.as_deref();
+    if let Err(a_err) = self.its_renderer.set_level_shader(a_shader) {
+      eprintln!(
+        "Falling back to the built-in shader for level {}: {}",
+        the_level.name, a_err
+      );
+    }
+  }
+  /// Switch to the level `the_name` of the loaded pack, the run has to be
+  /// started afterwards
+  fn select_level(&mut self, the_name: &str) -> Result<(), String> {
+    let a_pack = self.its_pack.as_ref().ok_or("no pack loaded")?;
+    let a_level = a_pack
+      .get_levels()
+      .iter()
+      .find(|the_level| the_level.name == the_name)
+      .cloned()
+      .ok_or_else(|| {
+        let a_names: Vec<&str> = a_pack
+          .get_levels()
+          .iter()
+          .map(|the_level| the_level.name.as_str())
+          .collect();
+        format!("no level {}, there are: {}", the_name, a_names.join(", "))
+      })?;
+    self.its_snapshot = None;
+    self.its_hyper = false;
+    self.setup_level(&a_level);
+    self.its_level = Some(a_level);
+    Ok(())
+  }
+  /// Switch the game's colors to the palette of `the_level` over
+  /// `the_duration`
+  fn apply_level_colors(&mut self, the_level: Option<&LevelDef>, the_duration: Duration) -> () {
+    let mut a_palette = self.its_level_style.clone();
+    if let Some(a_level) = the_level {
+      a_level.style.apply_to(&mut a_palette);
+    }
+    if the_level.is_some_and(|the_level| the_level.invert_colors) {
+      a_palette.map_colors(|the_color| the_color.inverted());
+    }
+    self
+      .its_game
+      .get_style_mut()
+      .fade_colors_to(&a_palette, the_duration);
+  }
+  /// The hyper level of the current level, if the run in `the_game` has
+  /// survived long enough to switch to it
+  fn get_due_hyper(&self, the_game: &model::GameState) -> Option<LevelDef> {
+    if !the_game.is_running() {
+      return None;
+    }
+    let a_level = self.its_level.as_ref()?;
+    if the_game.get_run().get_time().as_secs_f32() < a_level.hyper_after {
+      return None;
+    }
+    a_level
+      .hyper
+      .as_ref()
+      .map(|the_hyper| the_hyper.as_ref().clone())
+  }
+  /// Switch to the hyper level once the player has survived long enough
+  fn tick_hyper(&mut self) -> () {
+    if self.its_hyper {
+      return;
+    }
+    let a_hyper = match self.get_due_hyper(&self.its_game) {
+      Some(a_hyper) => a_hyper,
+      None => return,
+    };
+    let a_run_time = self.its_game.get_run().get_time().as_secs_f32();
+    self.its_hyper = true;
+    self.its_game.apply_level(&a_hyper);
+    self.setup_level(&a_hyper);
+    let a_seed = self.its_game.get_run().get_seed();
+    self.its_spawner.reset(a_seed ^ HYPER_SEED_SALT);
+    self.its_timeline.reset_at(a_run_time);
+    let a_fade = Duration::from_secs_f32(a_hyper.palette_fade.max(0.));
+    self.apply_level_colors(Some(&a_hyper), a_fade);
+    self
+      .its_game
+      .get_style_mut()
+      .set_flash_time(constants::FLASH_DURATION);
+  }
+
+  /// Begin a new attempt whose randomness derives from `the_seed`
+  /// Undoes whatever the level's events changed during the previous run.
+  pub fn start_run(&mut self, the_seed: u64) -> () {
+    self.its_upcoming = None;
+    if self.its_hyper {
+      self.its_hyper = false;
+      if let Some(a_level) = self.its_level.clone() {
+        self.setup_level(&a_level);
+      }
+    }
+    if let Some(a_level) = &self.its_level {
+      self.its_game.apply_level(a_level);
+    }
+    let a_goal = self.get_goal().and_then(|the_goal| the_goal.get_duration());
+    self.its_game.set_goal(a_goal);
+    self.its_renderer.set_medal(None);
+    let a_level = self.its_level.clone();
+    self.apply_level_colors(a_level.as_ref(), Duration::from_secs(0));
+    self.its_game.restart(the_seed);
+    self.its_spawner.reset(the_seed);
+    self.its_session_log.record(
+      0,
+      LoggedEvent::RunStarted {
+        level: self.get_record_key(),
+        seed: the_seed,
+      },
+    );
+    self.its_timeline.reset();
+    self.set_prompt(None);
+    self.its_intro = None;
+    self.its_renderer.set_intro_title(None, 0.);
+    self.its_death_effect = Duration::from_secs(0);
+    self.its_renderer.set_game_over_hidden(false);
+    self.its_countdown = Duration::from_secs(0);
+    self.its_renderer.set_countdown(None);
+    self.its_resumable = None;
+    self.its_since_checkpoint = Duration::from_secs(0);
+    self.its_recording = if self.its_demo || self.its_playback {
+      None
+    } else {
+      let mut a_replay = Replay::new(
+        self
+          .its_pack
+          .as_ref()
+          .map(|the_pack| the_pack.get_dir().to_path_buf()),
+        self
+          .its_level
+          .as_ref()
+          .map(|the_level| the_level.name.clone()),
+        the_seed,
+        self.its_game.get_run().get_tick_rate(),
+      );
+      // the opponent of a race steers our cursor as we did, relative to
+      // the playfield
+      a_replay.screen_relative =
+        self.its_settings.screen_relative_steering && self.its_race.is_none();
+      // both sides of a race have to come to the same results
+      a_replay.strict_math = self.its_settings.strict_math || self.its_race.is_some();
+      // races and daily runs are played with the level's speed curve
+      a_replay.speed_curve = self
+        .its_settings
+        .player_speed_curve
+        .filter(|_| self.its_race.is_none());
+      Some(a_replay)
+    };
+    self.its_daily = None;
+    if self.its_practice {
+      self.its_game.get_run_mut().mark_practice();
+    }
+    let a_best_splits = self
+      .its_records
+      .get(&self.get_record_key())
+      .map(|the_record| the_record.splits.clone())
+      .unwrap_or_default();
+    self.its_renderer.set_best_splits(a_best_splits);
+    let a_hue_speed = if self.its_zen {
+      ZEN_HUE_SPEED
+    } else {
+      self
+        .its_level
+        .as_ref()
+        .map_or(0., |the_level| the_level.hue_speed)
+    };
+    self.its_game.get_style_mut().set_hue_speed(a_hue_speed);
+    // the level may have changed
+    self.update_heatmap();
+  }
+
+  /// Play the intro of the current level, unless nobody would watch it:
+  /// replays, demos and races start right away
+  fn start_intro(&mut self) -> () {
+    let a_title = match &self.its_level {
+      Some(a_level) => a_level.name.clone(),
+      None => return,
+    };
+    if self.its_playback || self.its_demo || self.its_race.is_some() {
+      return;
+    }
+    let a_intro = TweenSequence::new()
+      .then(Box::new(IntroZoomTween::new(INTRO_ZOOM)), INTRO_ZOOM_TIME)
+      .then(
+        Box::new(IntroTitleTween::new(a_title.clone(), false)),
+        INTRO_TITLE_TIME,
+      )
+      .then(
+        Box::new(SpinUpTween::new(INTRO_SPIN_UP_TIME)),
+        INTRO_SPIN_UP_TIME,
+      )
+      .then(
+        Box::new(IntroTitleTween::new(a_title, true)),
+        INTRO_TITLE_TIME,
+      );
+    self.its_intro = Some(a_intro);
+  }
+  fn tick_intro(&mut self, the_delta: Duration) -> () {
+    let a_intro = match &mut self.its_intro {
+      Some(a_intro) => a_intro,
+      None => return,
+    };
+    let mut a_api = AppTweenAPI::new(
+      &mut self.its_game,
+      &mut self.its_renderer,
+      &self.its_settings,
+    );
+    a_intro.tick(&mut a_api, the_delta);
+    if a_intro.is_done() {
+      self.its_intro = None;
+    }
+  }
+  /// Jump to the end of the intro, so that the run starts right away
+  fn skip_intro(&mut self) -> () {
+    if let Some(mut a_intro) = self.its_intro.take() {
+      let mut a_api = AppTweenAPI::new(
+        &mut self.its_game,
+        &mut self.its_renderer,
+        &self.its_settings,
+      );
+      a_intro.finish(&mut a_api);
+    }
+  }
+
+  /// Slow down what still moves after a death and zoom in on the cursor,
+  /// holding the game over screen back meanwhile
+  fn start_death_effect(&mut self) -> () {
+    self.its_death_effect = DEATH_EFFECT_TIME;
+    self.its_renderer.set_game_over_hidden(true);
+    self.its_tweens.register(
+      Box::new(DeathZoomTween::new(DEATH_ZOOM)),
+      DEATH_EFFECT_TIME,
+      Duration::from_secs(0),
+      1,
+    );
+  }
+  /// Speed of the simulation relative to the game speed, which is only
+  /// lowered by the death effect
+  fn get_time_scale(&self) -> f32 {
+    let a_left = self.its_death_effect.as_secs_f32() / DEATH_EFFECT_TIME.as_secs_f32();
+    1. - (1. - DEATH_TIME_SCALE) * a_left
+  }
+  fn tick_death_effect(&mut self, the_delta: Duration) -> () {
+    if self.its_death_effect == Duration::from_secs(0) {
+      return;
+    }
+    self.its_death_effect = self.its_death_effect.saturating_sub(the_delta);
+    if self.its_death_effect == Duration::from_secs(0) {
+      self.its_renderer.set_game_over_hidden(false);
+    }
+  }
+
+  /// Count down to the end of the wait after a pause
+  fn tick_countdown(&mut self, the_delta: Duration) -> () {
+    if self.its_countdown == Duration::from_secs(0) {
+      return;
+    }
+    self.its_countdown = self.its_countdown.saturating_sub(the_delta);
+    let a_seconds = self.its_countdown.as_secs_f32().ceil() as u32;
+    self
+      .its_renderer
+      .set_countdown(Some(a_seconds).filter(|the_seconds| *the_seconds > 0));
+  }
+
+  /// Show the level and the state of the run in the window title
+  fn update_title(&mut self, the_window: &Window) -> () {
+    let mut a_title = window::APP_NAME.to_string();
+    if let Some(a_level) = self.get_current_level() {
+      a_title += " - ";
+      a_title += &a_level.name;
+    }
+    let a_state = if self.its_paused || self.its_device_lost {
+      Some("title.paused")
+    } else if !self.its_game.is_running() {
+      Some("title.game_over")
+    } else {
+      None
+    };
+    if let Some(a_key) = a_state {
+      a_title += " ";
+      a_title += self.its_catalog.get(a_key);
+    }
+    let a_run = self.its_game.get_run();
+    if a_run.is_assisted() {
+      let a_percent = (a_run.get_min_game_speed() * 100.).round() as u32;
+      a_title += " ";
+      a_title += &self
+        .its_catalog
+        .format("title.assisted", &[("percent", &a_percent)]);
+    }
+    if a_title != self.its_title {
+      the_window.set_title(&a_title);
+      self.its_title = a_title;
+    }
+  }
+
+  /// Measure the frame rate and show it if the overlay is enabled
+  fn update_fps(&mut self, the_delta: Duration) -> () {
+    let a_frame_time = self.its_renderer.get_frame_time();
+    self.its_fps_meter.add(the_delta, a_frame_time);
+    let a_stats = if self.its_settings.fps_overlay {
+      self.its_fps_meter.get_stats()
+    } else {
+      None
+    };
+    self.its_renderer.set_fps(a_stats);
+  }
+
+  /// What the debug overlay lists: where the spawner is at and the latest
+  /// entries of the session log
+  fn get_debug_lines(&self) -> Vec<String> {
+    let a_time = self.its_game.get_run().get_time();
+    let mut a_lines = vec![
+      format!("difficulty {:.2}", self.its_spawner.get_difficulty(a_time)),
+      format!(
+        "next wave in {:.2}",
+        self.its_spawner.get_distance_to_next()
+      ),
+      format!(
+        "last pattern {}",
+        self.its_spawner.get_last_pattern().unwrap_or("-")
+      ),
+    ];
+    if let Some((_, a_upcoming)) = &self.its_upcoming {
+      if !a_upcoming.is_empty() {
+        a_lines.push(String::from("upcoming waves"));
+      }
+      for a_wave in a_upcoming {
+        let a_slots: Vec<String> = a_wave.slots.iter().map(usize::to_string).collect();
+        a_lines.push(format!(
+          "  in {:.2}s {} [{}]",
+          a_wave.time.saturating_sub(a_time).as_secs_f32(),
+          a_wave.pattern,
+          a_slots.join(" ")
+        ));
+      }
+    }
+    a_lines.extend(
+      self
+        .its_session_log
+        .get_recent(DEBUG_LOG_LINES)
+        .iter()
+        .map(|the_entry| the_entry.to_string()),
+    );
+    a_lines
+  }
+
+  /// Predict the spawner's next waves again once one of them has spawned,
+  /// or the run has started or ended
+  fn update_upcoming(&mut self) -> () {
+    if self.its_zen {
+      // nothing spawns in zen mode
+      self.its_upcoming = None;
+      return;
+    }
+    let a_key = (
+      self.its_spawner.get_wave_count(),
+      self.its_game.is_running(),
+    );
+    match &self.its_upcoming {
+      Some((a_predicted_at, _)) if *a_predicted_at == a_key => (),
+      _ => {
+        let a_upcoming = self
+          .its_spawner
+          .get_upcoming(&self.its_game, DEBUG_UPCOMING_WAVES);
+        self.its_upcoming = Some((a_key, a_upcoming));
+      }
+    }
+  }
+
+  fn update_debug_lines(&mut self) -> () {
+    if self.its_debug_overlay || self.its_debug_view {
+      self.update_upcoming();
+    }
+    let a_lines = if self.its_debug_overlay {
+      Some(self.get_debug_lines())
+    } else {
+      None
+    };
+    self.its_renderer.set_debug_lines(a_lines);
+  }
+
+  /// Whether the debug overlay is to be shown in a window of its own
+  pub fn is_debug_view_enabled(&self) -> bool {
+    self.its_debug_view
+  }
+
+  /// Note that the window of the debug view has been closed
+  pub fn close_debug_view(&mut self) -> () {
+    self.its_debug_view = false;
+  }
+
+  /// Render the game as the main window shows it into `the_renderer`, which
+  /// draws the debug view's window with the debug overlay
+  pub fn render_debug_view<D: renderer::Renderer>(
+    &self,
+    the_renderer: &mut D,
+    the_delta: Duration,
+  ) -> () {
+    the_renderer.set_debug_lines(Some(self.get_debug_lines()));
+    let a_cursor_shape = self
+      .get_cursor_skin()
+      .map(|the_skin| the_skin.shape.clone());
+    the_renderer.set_cursor_shape(a_cursor_shape);
+    let a_style = self.get_display_style();
+    match &self.its_editor {
+      Some(a_editor) => the_renderer.render(a_editor.get_state(), &a_style, the_delta),
+      None => the_renderer.render(&self.its_game, &a_style, the_delta),
+    }
+  }
+
+  fn set_prompt(&mut self, the_prompt: Option<Prompt>) -> () {
+    let a_text = the_prompt
+      .as_ref()
+      .map(|the_prompt| self.its_catalog.get(the_prompt.get_text()).to_string());
+    self.its_renderer.set_prompt(a_text);
+    self.its_prompt = the_prompt;
+  }
+  /// Show `the_text` as a notice for `the_time`, in place of the current one
+  fn notify(&mut self, the_text: String, the_time: Duration) -> () {
+    self.its_renderer.set_notice(Some(the_text));
+    self.its_notice_left = the_time;
+  }
+  fn tick_notice(&mut self, the_delta: Duration) -> () {
+    if self.its_notice_left == Duration::from_secs(0) {
+      return;
+    }
+    self.its_notice_left = self.its_notice_left.saturating_sub(the_delta);
+    if self.its_notice_left == Duration::from_secs(0) {
+      self.its_renderer.set_notice(None);
+    }
+  }
+  /// Wait for the player to steer as the pending prompt asks
+  fn tick_prompt(&mut self, the_delta: Duration) -> () {
+    let a_direction = self.its_controls.poll_direction(&self.its_game);
+    let a_followed = match &mut self.its_prompt {
+      Some(a_prompt) => a_prompt.update(a_direction, the_delta),
+      None => return,
+    };
+    if a_followed {
+      self.set_prompt(None);
+    }
+  }
+
+  /// Time is frozen for the game while the pattern editor is open
+  fn tick_editor(&mut self, the_delta: Duration) -> () {
+    let a_new_keys = self.its_controls.get_new_keys().clone();
+    self.its_controls.clear_new_keys();
+    let a_editor = match &mut self.its_editor {
+      Some(a_editor) => a_editor,
+      None => return,
+    };
+    a_editor.handle_keys(&a_new_keys);
+    a_editor.tick(the_delta);
+    if a_editor.wants_export(&a_new_keys) {
+      let a_dir = match &self.its_pack {
+        Some(a_pack) => a_pack.get_dir().to_path_buf(),
+        None => PathBuf::from("."),
+      };
+      match a_editor.export(&a_dir) {
+        Ok((a_path, a_pattern)) => {
+          println!("Exported pattern to {}", a_path.display());
+          self.its_spawner.add_pattern(a_pattern);
+        }
+        Err(a_err) => eprintln!("Failed to export pattern: {}", a_err),
+      }
+    }
+  }
+
+  fn toggle_console(&mut self) -> () {
+    if self.its_console.is_some() {
+      self.its_console = None;
+    } else if self.is_fairness_locked() {
+      println!("The console is not available in daily challenges and races");
+    } else {
+      self.its_console = Some(Console::new());
+    }
+    // neither the toggle key nor what has been typed before belongs to the
+    // input line
+    self.its_controls.take_typed_text();
+    let a_lines = self
+      .its_console
+      .as_ref()
+      .map(|the_console| the_console.get_lines(console::VISIBLE_LINES));
+    self.its_renderer.set_console(a_lines);
+  }
+
+  fn tick_console(&mut self) -> () {
+    let a_new_keys = self.its_controls.get_new_keys().clone();
+    self.its_controls.clear_new_keys();
+    let a_typed = self.its_controls.take_typed_text();
+    let a_line = match &mut self.its_console {
+      Some(a_console) => {
+        a_console.type_text(&a_typed);
+        a_console.handle_keys(&a_new_keys)
+      }
+      None => return,
+    };
+    if let Some(a_line) = a_line {
+      let a_result = self.run_command(&a_line);
+      if let Some(a_console) = &mut self.its_console {
+        match a_result {
+          Ok(a_output) => a_console.print(&a_output),
+          Err(a_err) => a_console.print(&format!("Error: {}", a_err)),
+        }
+      }
+    }
+    let a_lines = self
+      .its_console
+      .as_ref()
+      .map(|the_console| the_console.get_lines(console::VISIBLE_LINES));
+    self.its_renderer.set_console(a_lines);
+  }
+
+  /// Simulate the run at once for as long as `the_going_on` holds and the
+  /// run does, invincible so that the player gets to see what comes after
+  fn skip_while<F: Fn(&model::GameState) -> bool>(&mut self, the_going_on: F) -> () {
+    let a_invincible = self.its_game.is_invincible();
+    self.its_game.set_invincible(true);
+    while self.its_game.is_running() && the_going_on(&self.its_game) {
+      self.tick_simulation();
+    }
+    self.its_game.set_invincible(a_invincible);
+    self.its_game.get_run_mut().mark_cheated();
+  }
+  /// Simulate `the_duration` of the run at once, see `skip_while`
+  fn skip_time(&mut self, the_duration: Duration) -> () {
+    let a_end = self.its_game.get_run().get_time() + the_duration;
+    self.skip_while(|the_game| the_game.get_run().get_time() < a_end);
+  }
+
+  /// Execute a line typed into the console. Runs that have been changed by
+  /// commands no longer count as records. Returns the command's output.
+  fn run_command(&mut self, the_line: &str) -> Result<String, String> {
+    match Command::parse(the_line)? {
+      Command::Spawn { slot, height } => {
+        if slot >= self.its_game.get_slots().len() || height.is_nan() || height <= 0. {
+          return Err("slot has to be 0 to 5 and height positive".to_string());
+        }
+        let a_obstacle = model::Obstacle::new_at(self.its_game.get_spawn_distance(), height);
+        self.its_game.add_obstacle(slot, a_obstacle);
+        self.its_game.get_run_mut().mark_cheated();
+        Ok(format!("Spawned an obstacle in slot {}", slot))
+      }
+      Command::SetSpeed { which, speed } => {
+        match which {
+          Speed::Player => self.its_game.set_player_speed(speed),
+          Speed::Obstacle => self.its_game.set_obstacle_speed(speed),
+          Speed::Rotation => self
+            .its_game
+            .submit_style_command(model::StyleCommand::SetRotationSpeed(speed)),
+        }
+        self.its_game.get_run_mut().mark_cheated();
+        Ok(format!("Set {:?} speed to {}", which, speed))
+      }
+      Command::Seed(None) => Ok(summary::format_seed(
+        &self.its_catalog,
+        self.its_game.get_run(),
+      )),
+      Command::Seed(Some(a_seed)) => {
+        self.start_run(a_seed);
+        Ok(format!("Restarted with seed {}", a_seed))
+      }
+      Command::TextSeed(a_text) => {
+        self.start_run(Rng::seed_from_text(&a_text));
+        let a_output = format!("Restarted with seed {}", a_text);
+        self.its_game.get_run_mut().set_seed_text(a_text);
+        Ok(a_output)
+      }
+      Command::LoadLevel(a_name) => {
+        self.select_level(&a_name)?;
+        self.start_run(Rng::time_seed());
+        self.start_intro();
+        Ok(format!("Loaded level {}", a_name))
+      }
+      Command::Theme(a_path) => {
+        let a_theme =
+          loader::load_style(Path::new(&a_path)).map_err(|the_err| the_err.to_string())?;
+        a_theme.apply_to(self.its_game.get_style_mut());
+        Ok(format!("Applied theme {}", a_path))
+      }
+      Command::God => {
+        let a_invincible = !self.its_game.is_invincible();
+        self.its_game.set_invincible(a_invincible);
+        if a_invincible {
+          self.its_game.get_run_mut().mark_cheated();
+          Ok("Invincibility on".to_string())
+        } else {
+          Ok("Invincibility off".to_string())
+        }
+      }
+      Command::Skip(a_seconds) => {
+        if !(a_seconds > 0. && a_seconds <= MAX_SKIP.as_secs_f32()) {
+          return Err(format!("can skip up to {}s", MAX_SKIP.as_secs()));
+        }
+        if !self.its_game.is_running() {
+          return Err("the run is over".to_string());
+        }
+        self.skip_time(Duration::from_secs_f32(a_seconds));
+        Ok(format!(
+          "Skipped to {}s",
+          summary::format_time(self.its_game.get_run().get_time())
+        ))
+      }
+      Command::Stage(a_stage) => {
+        let a_name = model::STAGES[a_stage].0;
+        let a_reached =
+          |the_game: &model::GameState| the_game.get_run().get_splits().len() > a_stage;
+        if !self.its_game.is_running() || a_reached(&self.its_game) {
+          return Err(format!("the run is already past {}", a_name));
+        }
+        // stages come with dodged walls, which may never come, e.g. in zen
+        // mode
+        let a_end = self.its_game.get_run().get_time() + MAX_SKIP;
+        self.skip_while(|the_game| !a_reached(the_game) && the_game.get_run().get_time() < a_end);
+        if !a_reached(&self.its_game) {
+          return Err(format!(
+            "{} was not reached within {}s",
+            a_name,
+            MAX_SKIP.as_secs()
+          ));
+        }
+        Ok(format!("Skipped to {}", a_name))
+      }
+      Command::Fps(a_limit) => {
+        if let Some(a_limit) = a_limit {
+          if !(settings::MIN_FPS_LIMIT..=settings::MAX_FPS_LIMIT).contains(&a_limit) {
+            return Err(format!(
+              "the limit has to be {} to {}",
+              settings::MIN_FPS_LIMIT,
+              settings::MAX_FPS_LIMIT
+            ));
+          }
+        }
+        self.its_settings.fps_limit = a_limit;
+        self.save_settings();
+        Ok(match a_limit {
+          Some(a_limit) => format!("Limited the frame rate to {} FPS", a_limit),
+          None => "Uncapped the frame rate".to_string(),
+        })
+      }
+      Command::Export(a_path) => {
+        self.its_history.export(Path::new(&a_path))?;
+        Ok(format!(
+          "Exported {} runs to {}",
+          self.its_history.get_run_count(),
+          a_path
+        ))
+      }
+      Command::ExportLog(a_path) => {
+        self.its_session_log.export(Path::new(&a_path))?;
+        Ok(format!("Exported the session log to {}", a_path))
+      }
+      Command::Clear => {
+        if let Some(a_console) = &mut self.its_console {
+          a_console.clear();
+        }
+        Ok(String::new())
+      }
+      Command::Help => Ok(console::HELP.to_string()),
+    }
+  }
+
+  /// Time is frozen for the game while the theme editor is open
+  fn tick_theme_editor(&mut self, the_delta: Duration) -> () {
+    let a_new_keys = self.its_controls.get_new_keys().clone();
+    self.its_controls.clear_new_keys();
+    let a_editor = match &mut self.its_theme_editor {
+      Some(a_editor) => a_editor,
+      None => return,
+    };
+    a_editor.handle_keys(&a_new_keys);
+    a_editor.tick(the_delta);
+    if a_editor.wants_export(&a_new_keys) {
+      let a_dir = match &self.its_pack {
+        Some(a_pack) => a_pack.get_dir().to_path_buf(),
+        None => PathBuf::from("."),
+      };
+      match a_editor.export(&a_dir) {
+        Ok(a_path) => println!("Exported theme to {}", a_path.display()),
+        Err(a_err) => eprintln!("Failed to export theme: {}", a_err),
+      }
+    }
+    let a_text = a_editor.describe_selection(&self.its_catalog);
+    self.notify(a_text, NOTICE_TIME);
+  }
+
+  /// Advance everything that affects gameplay by one tick
+  fn tick_simulation(&mut self) -> () {
+    let a_delta = self.its_game.get_tick_duration();
+    let a_tick = self.its_game.get_run().get_ticks();
+    self.its_previous_position = Some((a_tick, self.its_game.get_position()));
+    // steered as the run is recorded, or as it was when played back
+    let a_replay = self
+      .its_controls
+      .get_playback()
+      .or(self.its_recording.as_ref());
+    let a_screen_relative = a_replay.is_some_and(|the_replay| the_replay.screen_relative);
+    let a_strict_math = a_replay.is_some_and(|the_replay| the_replay.strict_math);
+    let a_speed_curve = a_replay.and_then(|the_replay| the_replay.speed_curve);
+    self.its_game.set_screen_relative(a_screen_relative);
+    self.its_game.set_strict_math(a_strict_math);
+    self.its_game.set_speed_curve_override(a_speed_curve);
+    let a_inputs = match &mut self.its_race {
+      Some(a_race) if a_race.is_stepping() => {
+        let a_direction = self.its_controls.poll_direction(&self.its_game);
+        a_race.step(a_direction)
+      }
+      _ => None,
+    };
+    match a_inputs {
+      Some((a_own, _)) => self
+        .its_controls
+        .tick_as(&mut self.its_game, a_delta, a_own),
+      None => self.its_controls.tick(&mut self.its_game, a_delta),
+    }
+    if let Some(a_recording) = &mut self.its_recording {
+      if self.its_game.is_running() {
+        a_recording.record(a_tick, self.its_controls.get_direction());
+      }
+    }
+    let a_waves = self.its_spawner.get_wave_count();
+    if !self.its_zen {
+      self.its_spawner.tick(&mut self.its_game, a_delta);
+    }
+    let a_intensity = if self.is_fairness_locked() {
+      1.
+    } else {
+      self.its_settings.get_motion_intensity()
+    };
+    let a_limits = MotionLimits::from_intensity(a_intensity);
+    self
+      .its_game
+      .set_max_rotation_speed(a_limits.max_rotation_speed);
+    self.its_game.tick();
+    // the buffer is put back afterwards to reuse its storage
+    let mut a_events = std::mem::take(&mut self.its_events);
+    self.its_game.swap_events(&mut a_events);
+    if self.its_spawner.get_wave_count() != a_waves {
+      let mut a_slots: Vec<usize> = a_events
+        .iter()
+        .filter_map(|the_event| match the_event {
+          model::GameEvent::ObstacleSpawned { slot, .. } => Some(*slot),
+          _ => None,
+        })
+        .collect();
+      // a pattern may put several walls into one slot
+      a_slots.sort_unstable();
+      a_slots.dedup();
+      let a_pattern = self.its_spawner.get_last_pattern().unwrap_or_default();
+      self.its_session_log.record(
+        a_tick,
+        LoggedEvent::Spawned {
+          pattern: a_pattern.to_string(),
+          slots: a_slots,
+        },
+      );
+    }
+    for a_event in &a_events {
+      self.handle_game_event(a_event);
+    }
+    a_events.clear();
+    self.its_events = a_events;
+    self.tick_hyper();
+    let mut a_api = AppTweenAPI::new(
+      &mut self.its_game,
+      &mut self.its_renderer,
+      &self.its_settings,
+    );
+    self.its_timeline.tick(&mut a_api, a_delta);
+    if let Some((a_text, a_input)) = self.its_timeline.take_prompt() {
+      self.set_prompt(Some(Prompt::new(a_text, a_input)));
+    }
+    if let Some((_, a_opponent)) = a_inputs {
+      self.tick_rival(a_opponent);
+      let a_rival_running = self
+        .its_rival
+        .as_ref()
+        .is_some_and(|the_rival| the_rival.its_game.is_running());
+      if !self.its_game.is_running() && !a_rival_running {
+        if let Some(a_race) = &mut self.its_race {
+          a_race.end_round();
+        }
+      }
+    }
+  }
+
+  /// Draw the cursor as far between its positions before and after the
+  /// last tick as the time not simulated yet reaches into the next tick, so
+  /// that it moves fluidly at frame rates above the tick rate. Only what is
+  /// drawn lags behind, collisions are still checked at the ticks.
+  fn update_cursor_position(&mut self) -> () {
+    let a_position = self.its_game.get_position();
+    let a_ticks = self.its_game.get_run().get_ticks();
+    let a_drawn = match self.its_previous_position {
+      // e.g. a restart or rewind happened since, which must not sweep the
+      // cursor across the playfield
+      Some((a_tick, _)) if a_tick + 1 != a_ticks || !self.its_game.is_running() => None,
+      Some((_, a_previous)) => {
+        let a_progress =
+          self.its_sim_backlog.as_secs_f32() / self.its_game.get_tick_duration().as_secs_f32();
+        // the shorter way round, positions wrap around at 1
+        let a_delta = (a_position - a_previous + 0.5).rem_euclid(1.) - 0.5;
+        Some((a_previous + a_delta * a_progress.min(1.)).rem_euclid(1.))
+      }
+      None => None,
+    };
+    self.its_renderer.set_cursor_position(a_drawn);
+  }
+
   pub fn tick(&mut self, the_window: &Window, the_delta: Duration) -> () {
-    self.its_controls.tick(&mut self.its_game, the_delta);
-    self.its_tweens.tick(
-      &mut AppTweenAPI::new(&mut self.its_game, &self.its_renderer, the_window),
-      the_delta,
+    self.tick_notice(the_delta);
+    if self.is_loading() {
+      // Keep rendering so that the window does not appear frozen
+      self.tick_loader(the_window);
+      self.update_cursor_position();
+      let a_style = self.get_display_style();
+      self
+        .its_renderer
+        .render(&self.its_game, &a_style, the_delta);
+      return;
+    }
+    self.tick_race(the_delta);
+    let mut a_pressed_keys = self.its_controls.take_pressed_keys();
+    if !a_pressed_keys.is_empty() {
+      self.its_idle_time = Duration::from_secs(0);
+      if self.its_demo {
+        // any key ends the demo and does nothing else
+        self.stop_demo();
+        a_pressed_keys.clear();
+      }
+    }
+    for a_key in a_pressed_keys {
+      if a_key == controls::CONSOLE_KEY {
+        self.toggle_console();
+      } else if self.its_console.is_none() {
+        // typing into the console must not trigger hotkeys
+        self.handle_hotkey(a_key, the_window);
+      }
+    }
+    if self.its_console.is_some() {
+      self.tick_console();
+      self.update_cursor_position();
+      let a_style = self.get_display_style();
+      self
+        .its_renderer
+        .render(&self.its_game, &a_style, the_delta);
+      return;
+    }
+    // text is only typed into the console
+    self.its_controls.take_typed_text();
+    if self.its_editor.is_some() {
+      self.tick_editor(the_delta);
+      // the edited level has a cursor of its own
+      self.its_renderer.set_cursor_position(None);
+      let a_style = self.get_display_style();
+      if let Some(a_editor) = &self.its_editor {
+        self
+          .its_renderer
+          .render(a_editor.get_state(), &a_style, the_delta);
+      }
+      return;
+    }
+    if self.its_theme_editor.is_some() {
+      self.tick_theme_editor(the_delta);
+      self.update_cursor_position();
+      // shown as is, the accessibility settings would hide the changes
+      if let Some(a_editor) = &self.its_theme_editor {
+        self
+          .its_renderer
+          .render(&self.its_game, a_editor.get_style(), the_delta);
+      }
+      return;
+    }
+    // the opponent would not wait
+    let a_frozen = self.its_paused || self.its_device_lost || self.its_show_stats;
+    if a_frozen && self.its_race.is_none() {
+      // Keys pressed in the meantime must not count once the game resumes
+      self.its_controls.clear_new_keys();
+      // nobody is ready for the obstacle that was about to arrive
+      if self.its_game.is_running() {
+        self.its_countdown = RESUME_COUNTDOWN;
+      }
+      self.update_title(the_window);
+      self.update_fps(the_delta);
+      self.update_debug_lines();
+      self.update_cursor_position();
+      let a_style = self.get_display_style();
+      self
+        .its_renderer
+        .render(&self.its_game, &a_style, the_delta);
+      return;
+    }
+    self.tick_attract(the_delta);
+    self.tick_checkpoint(the_delta);
+    if self.its_prompt.is_some() {
+      self.tick_prompt(the_delta);
+    }
+    self.tick_countdown(the_delta);
+    // Everything that is simulated runs at the accessibility game speed, in
+    // ticks of a fixed length
+    // playback runs one tick per frame, however it was played
+    let a_game_speed = if self.is_fairness_locked() || self.its_playback {
+      settings::MAX_GAME_SPEED
+    } else {
+      self.its_settings.get_game_speed()
+    };
+    self.its_game.get_run_mut().note_game_speed(a_game_speed);
+    // not noted, the run is over while the death effect slows it down
+    self.its_sim_backlog += the_delta.mul_f32(a_game_speed * self.get_time_scale());
+    self.tick_death_effect(the_delta);
+    let a_tick = self.its_game.get_tick_duration();
+    if self.its_sim_backlog > MAX_CATCH_UP {
+      // give up on catching up after a hitch rather than freezing
+      self.its_sim_backlog = a_tick;
+    }
+    while self.its_sim_backlog >= a_tick {
+      let a_waiting = self.its_countdown > Duration::from_secs(0);
+      if self.its_prompt.is_some() || self.its_intro.is_some() || a_waiting {
+        // the run waits for the player and does not catch up afterwards
+        self.its_sim_backlog = Duration::from_secs(0);
+        break;
+      }
+      if self.is_waiting_for_opponent() {
+        // catches up once the input has arrived
+        break;
+      }
+      self.its_sim_backlog -= a_tick;
+      self.tick_simulation();
+    }
+    let mut a_api = AppTweenAPI::new(
+      &mut self.its_game,
+      &mut self.its_renderer,
+      &self.its_settings,
     );
+    self.its_tweens.tick(&mut a_api, the_delta);
     self.its_tweens.cleanup();
-    self.its_renderer.render(&self.its_game, the_delta);
+    // after the other tweens, which the intro overrides
+    self.tick_intro(the_delta);
+    self.update_title(the_window);
+    self.update_fps(the_delta);
+    self.update_debug_lines();
+    let a_style = self.get_display_style();
+    let a_effects = self.get_post_effects();
+    self.its_renderer.set_post_effects(a_effects);
+    let a_input_display = if self.its_settings.input_display {
+      Some(self.its_controls.get_input_display())
+    } else {
+      None
+    };
+    self.its_renderer.set_input_display(a_input_display);
+    self.update_cursor_position();
+    self
+      .its_renderer
+      .render(&self.its_game, &a_style, the_delta);
   }
 }
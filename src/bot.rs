@@ -0,0 +1,75 @@
+use super::model::GameState;
+
+/// Steers the cursor by itself, e.g. for demo runs. It heads for the slot
+/// whose nearest obstacle is farthest away, the shorter way round, and stays
+/// there until another slot is clearer. It does not plan around walls on
+/// the way, so it is beatable.
+#[derive(Clone, Default)]
+pub struct Bot {}
+
+impl Bot {
+  pub fn new() -> Bot {
+    Bot {}
+  }
+  /// How far the nearest obstacle in `the_slot` that has not passed the
+  /// cursor yet is from the cursor tip, or from its base for obstacles that
+  /// move outward
+  fn get_clearance(the_game: &GameState, the_slot: usize) -> f32 {
+    the_game.get_slots()[the_slot]
+      .get_obstacles()
+      .iter()
+      .filter_map(|the_obstacle| the_obstacle.get_clearance())
+      .fold(f32::INFINITY, f32::min)
+  }
+  /// Returns -1 to move left, 1 to move right and 0 to stay
+  pub fn get_direction(&self, the_game: &GameState) -> i8 {
+    let a_current = the_game.get_current_slot_idx();
+    let mut a_target = a_current;
+    let mut a_best = Bot::get_clearance(the_game, a_current);
+    for (a_idx, a_slot) in the_game.get_slots().iter().enumerate() {
+      let a_clearance = Bot::get_clearance(the_game, a_idx);
+      if a_slot.get_width() > 0. && a_clearance > a_best {
+        a_target = a_idx;
+        a_best = a_clearance;
+      }
+    }
+    if a_target == a_current {
+      return 0;
+    }
+    let a_sum = the_game.get_slot_width_sum();
+    let a_left = the_game.get_slots()[..a_target]
+      .iter()
+      .fold(0., |the_acc, the_slot| the_acc + the_slot.get_width());
+    let a_center = (a_left + the_game.get_slots()[a_target].get_width() / 2.) / a_sum;
+    let a_delta = (a_center - the_game.get_position() + 0.5).rem_euclid(1.) - 0.5;
+    if a_delta < 0. {
+      -1
+    } else {
+      1
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Bot;
+  use crate::model::{GameState, Obstacle};
+
+  fn block_all_but(the_free: usize) -> GameState {
+    let mut a_game = GameState::new();
+    for a_slot in 0..6 {
+      if a_slot != the_free {
+        a_game.add_obstacle(a_slot, Obstacle::new_at(0.2, 0.1));
+      }
+    }
+    a_game
+  }
+
+  #[test]
+  fn heads_for_the_clearest_slot() {
+    // the cursor starts in slot 0
+    assert_eq!(Bot::new().get_direction(&block_all_but(0)), 0);
+    assert_eq!(Bot::new().get_direction(&block_all_but(1)), 1);
+    assert_eq!(Bot::new().get_direction(&block_all_but(5)), -1);
+  }
+}
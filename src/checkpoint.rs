@@ -0,0 +1,101 @@
+use super::model::GameState;
+use super::replay::Replay;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The run in progress as of a few seconds ago, written regularly so that it
+/// can be resumed after the game has crashed or been killed
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+  /// How the run has been played up to `state`, which brings back what the
+  /// state does not hold, e.g. the spawner
+  pub replay: Replay,
+  pub state: GameState,
+}
+
+impl Checkpoint {
+  pub fn new(the_replay: Replay, the_state: GameState) -> Checkpoint {
+    Checkpoint {
+      replay: the_replay,
+      state: the_state,
+    }
+  }
+  /// The checkpoint at `the_path`, if there is a usable one
+  pub fn load(the_path: &Path) -> Option<Checkpoint> {
+    let a_data = std::fs::read(the_path).ok()?;
+    let a_result = serde_json::from_slice::<Checkpoint>(&a_data)
+      .map_err(|the_err| the_err.to_string())
+      .and_then(|the_checkpoint| {
+        the_checkpoint.state.validate()?;
+        Ok(the_checkpoint)
+      });
+    match a_result {
+      Ok(a_checkpoint) => Some(a_checkpoint),
+      Err(a_err) => {
+        eprintln!(
+          "Ignoring malformed checkpoint {}: {}",
+          the_path.display(),
+          a_err
+        );
+        None
+      }
+    }
+  }
+  /// Write the checkpoint to `the_path`. It goes to a temporary file first,
+  /// so that a crash while writing leaves the previous checkpoint intact.
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec(self)?;
+    let a_temp = the_path.with_extension("tmp");
+    std::fs::write(&a_temp, a_json)?;
+    std::fs::rename(&a_temp, the_path)
+  }
+  /// Forget the checkpoint at `the_path`, since its run is over
+  pub fn remove(the_path: &Path) -> () {
+    match std::fs::remove_file(the_path) {
+      Err(a_err) if a_err.kind() != std::io::ErrorKind::NotFound => eprintln!(
+        "Failed to remove checkpoint {}: {}",
+        the_path.display(),
+        a_err
+      ),
+      _ => (),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Checkpoint;
+  use crate::model::GameState;
+  use crate::replay::Replay;
+
+  #[test]
+  fn survives_a_round_trip() {
+    let a_path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+    let mut a_state = GameState::new();
+    a_state.restart(7);
+    for _ in 0..30 {
+      a_state.tick();
+    }
+    let mut a_replay = Replay::new(None, None, 7, 60);
+    a_replay.record(10, 1);
+    Checkpoint::new(a_replay.clone(), a_state)
+      .save(&a_path)
+      .unwrap();
+    let a_loaded = Checkpoint::load(&a_path).unwrap();
+    assert_eq!(a_loaded.replay, a_replay);
+    assert_eq!(a_loaded.state.get_run().get_ticks(), 30);
+    Checkpoint::remove(&a_path);
+    assert!(Checkpoint::load(&a_path).is_none());
+  }
+
+  #[test]
+  fn rejects_states_without_a_tick_rate() {
+    let mut a_json: serde_json::Value =
+      serde_json::from_slice(&GameState::new().to_json().unwrap()).unwrap();
+    a_json["its_run"]["its_tick_rate"] = 0.into();
+    assert!(GameState::from_json(a_json.to_string().as_bytes()).is_err());
+  }
+}
@@ -0,0 +1,51 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard tools that read the text to copy from stdin, tried in order
+#[cfg(target_os = "macos")]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "windows")]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[&["clip"]];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+  &["wl-copy"],
+  &["xclip", "-selection", "clipboard"],
+  &["xsel", "--clipboard", "--input"],
+];
+
+fn copy_with(the_command: &[&str], the_text: &str) -> std::io::Result<()> {
+  let mut a_child = Command::new(the_command[0])
+    .args(&the_command[1..])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .spawn()?;
+  if let Some(a_stdin) = a_child.stdin.as_mut() {
+    a_stdin.write_all(the_text.as_bytes())?;
+  }
+  // Closing stdin lets the tool know the text is complete
+  drop(a_child.stdin.take());
+  let a_status = a_child.wait()?;
+  if a_status.success() {
+    Ok(())
+  } else {
+    Err(std::io::Error::other(format!(
+      "{} exited with {}",
+      the_command[0], a_status
+    )))
+  }
+}
+
+/// Put `the_text` on the system clipboard using the first clipboard tool
+/// that is available
+pub fn copy(the_text: &str) -> std::io::Result<()> {
+  let mut a_last_err =
+    std::io::Error::new(std::io::ErrorKind::NotFound, "no clipboard tool available");
+  for a_command in CLIPBOARD_COMMANDS {
+    match copy_with(a_command, the_text) {
+      Ok(()) => return Ok(()),
+      Err(a_err) => a_last_err = a_err,
+    }
+  }
+  Err(a_last_err)
+}
@@ -0,0 +1,284 @@
+use super::model::STAGES;
+use std::collections::BTreeSet;
+
+const ENTER_KEY: u32 = 28;
+const BACKSPACE_KEY: u32 = 14;
+const UP_KEY: u32 = 103;
+const DOWN_KEY: u32 = 108;
+
+/// Lines of output shown above the input line
+pub const VISIBLE_LINES: usize = 12;
+/// Most lines of output the console keeps
+const MAX_LOG_LINES: usize = 200;
+/// Most commands the history keeps
+const MAX_HISTORY: usize = 50;
+
+pub const HELP: &str = "Commands:
+  spawn <slot> <height>  add an obstacle at the spawn distance
+  setspeed <player|obstacle|rotation> <speed>
+  seed [seed|text]       show the seed or restart the run with it, any
+                         text that is no number works as a seed as well
+  loadlevel <name>       switch to a level of the loaded pack
+  theme <file>           apply a style file to the game
+  god                    toggle invincibility
+  skip <seconds>         fast-forward the run
+  stage <name|number>    fast-forward the run to the start of a stage
+  fps <limit|off>        limit the frame rate or uncap it
+  export <file>          write the run history to a .csv or .json file
+  exportlog <file>       write this session's event log to a .json file
+  clear                  clear the console
+  help                   show this text
+Runs changed by spawn, setspeed, god, skip or stage are no records.";
+
+/// Something the console has been asked to do
+#[derive(Debug, PartialEq)]
+pub enum Command {
+  Spawn {
+    slot: usize,
+    height: f32,
+  },
+  SetSpeed {
+    which: Speed,
+    speed: f32,
+  },
+  Seed(Option<u64>),
+  /// Seed given as text, which is hashed into the actual seed
+  TextSeed(String),
+  LoadLevel(String),
+  Theme(String),
+  God,
+  Skip(f32),
+  /// Index into `STAGES`
+  Stage(usize),
+  /// Frame rate limit, none for uncapped
+  Fps(Option<u32>),
+  /// File to write the run history to
+  Export(String),
+  /// File to write the session's event log to
+  ExportLog(String),
+  Clear,
+  Help,
+}
+
+/// A speed of the game that `setspeed` can change
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Speed {
+  Player,
+  Obstacle,
+  Rotation,
+}
+
+fn parse_arg<T: std::str::FromStr>(the_arg: Option<&str>, the_name: &str) -> Result<T, String> {
+  let a_arg = the_arg.ok_or_else(|| format!("missing {}", the_name))?;
+  a_arg
+    .parse()
+    .map_err(|_| format!("invalid {}: {}", the_name, a_arg))
+}
+
+impl Command {
+  /// Parse a line as typed into the console
+  pub fn parse(the_line: &str) -> Result<Command, String> {
+    let mut a_words = the_line.split_whitespace();
+    let a_name = a_words.next().unwrap_or("");
+    let a_command = match a_name {
+      "spawn" => Command::Spawn {
+        slot: parse_arg(a_words.next(), "slot")?,
+        height: parse_arg(a_words.next(), "height")?,
+      },
+      "setspeed" => {
+        let a_which = match a_words.next() {
+          Some("player") => Speed::Player,
+          Some("obstacle") => Speed::Obstacle,
+          Some("rotation") => Speed::Rotation,
+          _ => return Err("setspeed needs player, obstacle or rotation".to_string()),
+        };
+        Command::SetSpeed {
+          which: a_which,
+          speed: parse_arg(a_words.next(), "speed")?,
+        }
+      }
+      "seed" => {
+        // text seeds may contain spaces
+        let a_rest = the_line.trim_start()[a_name.len()..].trim();
+        return Ok(if a_rest.is_empty() {
+          Command::Seed(None)
+        } else if let Ok(a_seed) = a_rest.parse() {
+          Command::Seed(Some(a_seed))
+        } else {
+          Command::TextSeed(a_rest.to_string())
+        });
+      }
+      // level names and paths may contain spaces
+      "loadlevel" | "theme" | "export" | "exportlog" => {
+        let a_rest = the_line.trim_start()[a_name.len()..].trim().to_string();
+        if a_rest.is_empty() {
+          return Err(format!("{} needs an argument", a_name));
+        }
+        return Ok(match a_name {
+          "theme" => Command::Theme(a_rest),
+          "export" => Command::Export(a_rest),
+          "exportlog" => Command::ExportLog(a_rest),
+          _ => Command::LoadLevel(a_rest),
+        });
+      }
+      "god" => Command::God,
+      "skip" => Command::Skip(parse_arg(a_words.next(), "seconds")?),
+      "stage" => {
+        let a_arg = a_words.next().ok_or("missing stage")?;
+        let a_stage = STAGES
+          .iter()
+          .position(|(the_name, _)| the_name.eq_ignore_ascii_case(a_arg))
+          .or_else(|| a_arg.parse().ok().filter(|the_idx| *the_idx < STAGES.len()))
+          .ok_or_else(|| format!("unknown stage {}", a_arg))?;
+        Command::Stage(a_stage)
+      }
+      "fps" => match a_words.next() {
+        Some("off") => Command::Fps(None),
+        a_arg => Command::Fps(Some(parse_arg(a_arg, "limit")?)),
+      },
+      "clear" => Command::Clear,
+      "help" => Command::Help,
+      _ => return Err(format!("unknown command {}, try help", a_name)),
+    };
+    match a_words.next() {
+      Some(a_extra) => Err(format!("unexpected argument {}", a_extra)),
+      None => Ok(a_command),
+    }
+  }
+}
+
+/// Drop-down console for typing commands against the running game
+#[derive(Default)]
+pub struct Console {
+  its_input: String,
+  its_log: Vec<String>,
+  its_history: Vec<String>,
+  /// Entry of the history being edited, counted from the newest
+  its_history_pos: Option<usize>,
+}
+
+impl Console {
+  pub fn new() -> Console {
+    let mut a_console = Console::default();
+    a_console.print("Type help for a list of commands");
+    a_console
+  }
+  /// Add `the_text` to the output, line by line
+  pub fn print(&mut self, the_text: &str) -> () {
+    self.its_log.extend(the_text.lines().map(str::to_string));
+    if self.its_log.len() > MAX_LOG_LINES {
+      let a_excess = self.its_log.len() - MAX_LOG_LINES;
+      self.its_log.drain(..a_excess);
+    }
+  }
+  pub fn clear(&mut self) -> () {
+    self.its_log.clear();
+  }
+  /// Add typed characters to the input line
+  pub fn type_text(&mut self, the_text: &str) -> () {
+    self
+      .its_input
+      .extend(the_text.chars().filter(|the_char| !the_char.is_control()));
+  }
+  /// Edit the input line with `the_keys`. Returns the line once it has been
+  /// entered.
+  pub fn handle_keys(&mut self, the_keys: &BTreeSet<u32>) -> Option<String> {
+    for a_key in the_keys {
+      match *a_key {
+        BACKSPACE_KEY => {
+          self.its_input.pop();
+        }
+        UP_KEY | DOWN_KEY => {
+          let a_pos = match (self.its_history_pos, *a_key == UP_KEY) {
+            (None, true) => Some(0),
+            (None, false) => None,
+            (Some(a_pos), true) => Some((a_pos + 1).min(self.its_history.len().saturating_sub(1))),
+            (Some(a_pos), false) => a_pos.checked_sub(1),
+          };
+          self.its_history_pos = a_pos.filter(|the_pos| *the_pos < self.its_history.len());
+          self.its_input = match self.its_history_pos {
+            Some(a_pos) => self.its_history[self.its_history.len() - 1 - a_pos].clone(),
+            None => String::new(),
+          };
+        }
+        ENTER_KEY => {
+          let a_line = std::mem::take(&mut self.its_input);
+          self.its_history_pos = None;
+          if a_line.trim().is_empty() {
+            continue;
+          }
+          self.print(&format!("> {}", a_line));
+          if self.its_history.last() != Some(&a_line) {
+            self.its_history.push(a_line.clone());
+            if self.its_history.len() > MAX_HISTORY {
+              self.its_history.remove(0);
+            }
+          }
+          return Some(a_line);
+        }
+        _ => (),
+      }
+    }
+    None
+  }
+  /// The latest `the_count` lines of output followed by the input line
+  pub fn get_lines(&self, the_count: usize) -> Vec<String> {
+    let a_skip = self.its_log.len().saturating_sub(the_count);
+    let mut a_lines = self.its_log[a_skip..].to_vec();
+    a_lines.push(format!("> {}_", self.its_input));
+    a_lines
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Command, Speed};
+
+  #[test]
+  fn parses_commands() {
+    assert_eq!(
+      Command::parse(" spawn 2 0.1 "),
+      Ok(Command::Spawn {
+        slot: 2,
+        height: 0.1
+      })
+    );
+    assert_eq!(
+      Command::parse("setspeed rotation -1.5"),
+      Ok(Command::SetSpeed {
+        which: Speed::Rotation,
+        speed: -1.5
+      })
+    );
+    assert_eq!(Command::parse("seed"), Ok(Command::Seed(None)));
+    assert_eq!(Command::parse("seed 42"), Ok(Command::Seed(Some(42))));
+    assert_eq!(
+      Command::parse("seed PANCAKE"),
+      Ok(Command::TextSeed("PANCAKE".to_string()))
+    );
+    assert_eq!(
+      Command::parse("loadlevel  The Hexagon "),
+      Ok(Command::LoadLevel("The Hexagon".to_string()))
+    );
+    assert_eq!(Command::parse("stage square"), Ok(Command::Stage(2)));
+    assert_eq!(Command::parse("stage 4"), Ok(Command::Stage(4)));
+    assert!(Command::parse("stage 5").is_err());
+    assert_eq!(Command::parse("fps 75"), Ok(Command::Fps(Some(75))));
+    assert_eq!(Command::parse("fps off"), Ok(Command::Fps(None)));
+    assert_eq!(
+      Command::parse("export my runs.csv"),
+      Ok(Command::Export("my runs.csv".to_string()))
+    );
+    assert_eq!(
+      Command::parse("exportlog bug 12.json"),
+      Ok(Command::ExportLog("bug 12.json".to_string()))
+    );
+    assert!(Command::parse("exportlog").is_err());
+    assert!(Command::parse("spawn 2").is_err());
+    assert_eq!(
+      Command::parse("seed 1 2"),
+      Ok(Command::TextSeed("1 2".to_string()))
+    );
+    assert!(Command::parse("teleport").is_err());
+  }
+}
@@ -3,6 +3,45 @@ pub const OUTER_HEXAGON_Y: f32 = 0.03;
 pub const CURSOR_Y: f32 = 0.035;
 pub const CURSOR_W: f32 = 0.05;
 pub const CURSOR_H: f32 = 0.008;
+/// Radial extent of a pickup, it spans half of its slot's width
+pub const PICKUP_H: f32 = 0.02;
 pub const FLASH_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
 pub const GOD_MODE: bool = false;
-pub const TARGET_TICK_TIME: f32 = 16.7;
+pub const ZOOM_PULSE_DEPTH: f32 = 0.5;
+pub const CAMERA_WOBBLE: f32 = 0.02;
+/// Distance from the center at which new obstacles appear, unless the
+/// level gives its own
+pub const SPAWN_DISTANCE: f32 = 2.;
+/// Distance from the center at which obstacles of the outer ring appear
+/// along with `SPAWN_DISTANCE`, levels with their own spawn distance keep
+/// the ratio
+pub const OUTER_RING_DISTANCE: f32 = 3.;
+/// Closest to the center that levels may let obstacles appear or vanish,
+/// so that there is still time to react
+pub const MIN_SPAWN_DISTANCE: f32 = 0.5;
+/// Default speed of the outer ring relative to the inner one, at which its
+/// obstacles arrive along with the inner ones spawned at the same time
+pub const OUTER_RING_SPEED: f32 = OUTER_RING_DISTANCE / SPAWN_DISTANCE;
+/// Distance from the center at which the far end of obstacles that move
+/// outward appears, from under the inner hexagon
+pub const REVERSE_SPAWN_DISTANCE: f32 = INNER_HEXAGON_Y;
+/// Speeds are given as the distance covered per reference frame of this
+/// length, whatever the tick or frame rate
+pub const SPEED_REFERENCE_FRAME: std::time::Duration = std::time::Duration::from_micros(16_700);
+/// Rate in ticks per second at which the simulation advances, independent of
+/// the frame rate, unless the settings ask for another one
+pub const DEFAULT_TICK_RATE: u32 = 60;
+pub const MIN_TICK_RATE: u32 = 30;
+pub const MAX_TICK_RATE: u32 = 480;
+/// Frame rate while the window is in the background, if the settings ask
+/// for saving power
+pub const POWER_SAVER_FPS: u32 = 10;
+
+/// How many reference frames `the_delta` lasts, to scale speeds with
+pub fn get_speed_scale(the_delta: std::time::Duration) -> f32 {
+  the_delta.as_secs_f32() / SPEED_REFERENCE_FRAME.as_secs_f32()
+}
+/// Longest frame that is taken into account. Longer ones, e.g. while the
+/// window is dragged or the driver stalls, are cut short so that neither the
+/// simulation nor any animation jumps ahead.
+pub const MAX_FRAME_DELTA: std::time::Duration = std::time::Duration::from_millis(100);
@@ -1,8 +1,237 @@
+use super::bot::Bot;
 use super::constants;
 use super::model;
+use super::replay::Replay;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
-const LEFT_KEY: u32 = 105;
-const RIGHT_KEY: u32 = 106;
+pub const LEFT_KEY: u32 = 105;
+pub const RIGHT_KEY: u32 = 106;
+/// Space, the only key used by the one-button schemes
+pub const ACTION_KEY: u32 = 57;
+const LEFT_SHIFT_KEY: u32 = 42;
+const RIGHT_SHIFT_KEY: u32 = 54;
+/// F5
+pub const PALETTE_KEY: u32 = 63;
+/// F6
+pub const REDUCED_FLASH_KEY: u32 = 64;
+/// F7
+pub const HIGH_CONTRAST_KEY: u32 = 65;
+/// F8
+pub const GAME_SPEED_KEY: u32 = 66;
+/// F9
+pub const MOTION_KEY: u32 = 67;
+/// F10
+pub const CONTROL_SCHEME_KEY: u32 = 68;
+/// Enter
+pub const RESTART_KEY: u32 = 28;
+/// Escape skips the intro of a level
+pub const SKIP_INTRO_KEY: u32 = 1;
+/// F2
+pub const EDITOR_KEY: u32 = 60;
+/// F3 toggles the debug overlay, Shift+F3 shows it in a window of its own
+pub const DEBUG_OVERLAY_KEY: u32 = 61;
+/// F4
+pub const MOTION_TRAIL_KEY: u32 = 62;
+/// F1 cycles through the monitors, Shift+F1 through their video modes
+pub const DISPLAY_KEY: u32 = 59;
+/// C
+pub const COPY_SUMMARY_KEY: u32 = 46;
+/// F11
+pub const FULLSCREEN_KEY: u32 = 87;
+/// F12
+pub const FORCED_ASPECT_KEY: u32 = 88;
+/// I
+pub const INPUT_DISPLAY_KEY: u32 = 23;
+/// H toggles proximity shading
+pub const PROXIMITY_SHADING_KEY: u32 = 35;
+/// M toggles steering relative to the screen
+pub const SCREEN_RELATIVE_KEY: u32 = 50;
+/// T toggles practice mode
+pub const PRACTICE_KEY: u32 = 20;
+/// S snapshots the run in practice mode
+pub const SAVE_STATE_KEY: u32 = 31;
+/// L rewinds the run to the snapshot in practice mode
+pub const LOAD_STATE_KEY: u32 = 38;
+/// K
+pub const THEME_EDITOR_KEY: u32 = 37;
+/// D starts the daily challenge of the current level
+pub const DAILY_KEY: u32 = 32;
+/// F cycles the frame rate limit
+pub const FPS_LIMIT_KEY: u32 = 33;
+/// P toggles the frame rate overlay
+pub const FPS_OVERLAY_KEY: u32 = 25;
+/// Tab shows the lifetime statistics
+pub const STATS_KEY: u32 = 15;
+/// Z toggles zen mode
+pub const ZEN_KEY: u32 = 44;
+/// E opens the console to enter a seed on the game over screen
+pub const SEED_KEY: u32 = 18;
+/// R resumes the run interrupted by a crash, when offered at launch
+pub const RESUME_KEY: u32 = 19;
+/// ` (grave accent)
+pub const CONSOLE_KEY: u32 = 41;
+
+/// How long a tap stays in the input display
+const TAP_DISPLAY_TIME: std::time::Duration = std::time::Duration::from_secs(2);
+/// Most taps the input display keeps at once
+const MAX_DISPLAYED_TAPS: usize = 12;
+/// Halvings of a blocked movement to find how far the cursor gets
+const CONTACT_ITERATIONS: usize = 8;
+
+/// Move the cursor from `the_from` by `the_distance`, signed, but only as far
+/// as it goes without a corner running into an obstacle. The movement is
+/// checked in steps of half the cursor's width so that it cannot pass
+/// through thin walls. Corners that are covered already do not block, so
+/// that the cursor can always back out.
+fn sweep(the_game: &model::GameState, the_from: f32, the_distance: f32) -> f32 {
+  let a_covered = the_game.get_blocked_corners(the_from);
+  let a_at = |the_t: f32| {
+    let a_position = (the_from + the_distance * the_t).rem_euclid(1.);
+    if a_position >= 1. {
+      0.
+    } else {
+      a_position
+    }
+  };
+  let a_is_blocked = |the_t: f32| {
+    let a_corners = the_game.get_blocked_corners(a_at(the_t));
+    (0..3).any(|the_idx| a_corners[the_idx] && !a_covered[the_idx])
+  };
+  let a_steps = (the_distance.abs() / (constants::CURSOR_W / 2.))
+    .ceil()
+    .max(1.) as usize;
+  let mut a_free = 0.;
+  for a_step in 1..a_steps + 1 {
+    let a_t = a_step as f32 / a_steps as f32;
+    if a_is_blocked(a_t) {
+      let mut a_blocked = a_t;
+      for _ in 0..CONTACT_ITERATIONS {
+        let a_mid = (a_free + a_blocked) / 2.;
+        if a_is_blocked(a_mid) {
+          a_blocked = a_mid;
+        } else {
+          a_free = a_mid;
+        }
+      }
+      break;
+    }
+    a_free = a_t;
+  }
+  if a_free == 0. {
+    the_from
+  } else {
+    a_at(a_free)
+  }
+}
+
+/// A key press that steers the cursor, as shown by the input display
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tap {
+  Left,
+  Right,
+  Action,
+}
+
+impl Tap {
+  fn from_scancode(the_scancode: u32) -> Option<Tap> {
+    match the_scancode {
+      LEFT_KEY => Some(Tap::Left),
+      RIGHT_KEY => Some(Tap::Right),
+      ACTION_KEY => Some(Tap::Action),
+      _ => None,
+    }
+  }
+  pub fn get_symbol(self) -> char {
+    match self {
+      Tap::Left => '<',
+      Tap::Right => '>',
+      Tap::Action => '*',
+    }
+  }
+}
+
+/// What the player is doing right now, for showing it on screen
+#[derive(Clone, Default)]
+pub struct InputDisplay {
+  /// The direction the cursor is steered in, -1 for left and 1 for right
+  pub direction: i8,
+  /// Recent taps with the newest last
+  pub taps: Vec<Tap>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ControlScheme {
+  /// Hold left or right to move
+  Keyboard,
+  /// Hold the action key to move, every new press reverses the direction
+  OneButton,
+  /// The cursor always moves, pressing the action key reverses it
+  AutoMove,
+}
+
+impl ControlScheme {
+  pub fn next(self) -> ControlScheme {
+    match self {
+      ControlScheme::Keyboard => ControlScheme::OneButton,
+      ControlScheme::OneButton => ControlScheme::AutoMove,
+      ControlScheme::AutoMove => ControlScheme::Keyboard,
+    }
+  }
+  fn create_source(self) -> Box<dyn ControlSource> {
+    match self {
+      ControlScheme::Keyboard => Box::new(KeyboardSource {}),
+      ControlScheme::OneButton => Box::new(OneButtonSource::new(false)),
+      ControlScheme::AutoMove => Box::new(OneButtonSource::new(true)),
+    }
+  }
+}
+
+/// Translates the raw key state into the direction the cursor should move
+pub trait ControlSource {
+  /// Returns -1 to move left, 1 to move right and 0 to stay
+  fn get_direction(&mut self, the_keys: &BTreeSet<u32>, the_new_keys: &BTreeSet<u32>) -> i8;
+}
+
+struct KeyboardSource {}
+impl ControlSource for KeyboardSource {
+  fn get_direction(&mut self, the_keys: &BTreeSet<u32>, _the_new_keys: &BTreeSet<u32>) -> i8 {
+    let left = the_keys.contains(&LEFT_KEY);
+    let right = the_keys.contains(&RIGHT_KEY);
+    if left == right {
+      0
+    } else if left {
+      -1
+    } else {
+      1
+    }
+  }
+}
+
+struct OneButtonSource {
+  its_direction: i8,
+  its_auto_move: bool,
+}
+impl OneButtonSource {
+  pub fn new(the_auto_move: bool) -> OneButtonSource {
+    OneButtonSource {
+      its_direction: 1,
+      its_auto_move: the_auto_move,
+    }
+  }
+}
+impl ControlSource for OneButtonSource {
+  fn get_direction(&mut self, the_keys: &BTreeSet<u32>, the_new_keys: &BTreeSet<u32>) -> i8 {
+    if the_new_keys.contains(&ACTION_KEY) {
+      self.its_direction = -self.its_direction;
+    }
+    if self.its_auto_move || the_keys.contains(&ACTION_KEY) {
+      self.its_direction
+    } else {
+      0
+    }
+  }
+}
 
 pub struct Controls {
   /// All keys that are currently pressed
@@ -12,6 +241,20 @@ pub struct Controls {
    * the present event loop iteration
    */
   its_new_keys: std::collections::BTreeSet<u32>,
+  /// Keys pressed since the last call to `take_pressed_keys`. Unlike the new
+  /// keys, these are handed out once per frame rather than once per tick.
+  its_pressed_keys: Vec<u32>,
+  /// Text typed since the last call to `take_typed_text`
+  its_typed_text: String,
+  its_source: Box<dyn ControlSource>,
+  /// Steers instead of the keys if set
+  its_bot: Option<Bot>,
+  /// Steers as in the recorded run instead of the keys if set
+  its_playback: Option<Replay>,
+  /// Direction of the latest tick
+  its_direction: i8,
+  /// Recent taps along with how long ago they happened
+  its_taps: Vec<(Tap, std::time::Duration)>,
 }
 
 impl Controls {
@@ -19,16 +262,125 @@ impl Controls {
     Controls {
       its_keys: std::collections::BTreeSet::new(),
       its_new_keys: std::collections::BTreeSet::new(),
+      its_pressed_keys: Vec::new(),
+      its_typed_text: String::new(),
+      its_source: ControlScheme::Keyboard.create_source(),
+      its_bot: None,
+      its_playback: None,
+      its_direction: 0,
+      its_taps: Vec::new(),
     }
   }
+  pub fn set_scheme(&mut self, the_scheme: ControlScheme) -> () {
+    self.its_source = the_scheme.create_source();
+  }
+  /// Let `the_bot` steer, or the player again if it is `None`
+  pub fn set_bot(&mut self, the_bot: Option<Bot>) -> () {
+    self.its_bot = the_bot;
+  }
+  /// Steer as recorded in `the_replay`, or let the player steer again if it
+  /// is `None`
+  pub fn set_playback(&mut self, the_replay: Option<Replay>) -> () {
+    self.its_playback = the_replay;
+  }
+  pub fn get_playback(&self) -> Option<&Replay> {
+    self.its_playback.as_ref()
+  }
   pub fn key_pressed(&mut self, the_scancode: u32) -> () {
+    // key repeat sends presses of held keys, those are no taps
+    let a_repeat = self.its_keys.contains(&the_scancode);
+    if let Some(a_tap) = Tap::from_scancode(the_scancode).filter(|_| !a_repeat) {
+      self
+        .its_taps
+        .push((a_tap, std::time::Duration::from_secs(0)));
+      if self.its_taps.len() > MAX_DISPLAYED_TAPS {
+        self.its_taps.remove(0);
+      }
+    }
     self.its_keys.insert(the_scancode);
     self.its_new_keys.insert(the_scancode);
+    self.its_pressed_keys.push(the_scancode);
+  }
+  /// Hand out and forget the keys pressed since the last call, e.g. for
+  /// hotkeys
+  pub fn take_pressed_keys(&mut self) -> Vec<u32> {
+    std::mem::take(&mut self.its_pressed_keys)
+  }
+  /// Note `the_char` as typed, taking the keyboard layout into account
+  pub fn char_typed(&mut self, the_char: char) -> () {
+    self.its_typed_text.push(the_char);
+  }
+  /// Hand out and forget the text typed since the last call
+  pub fn take_typed_text(&mut self) -> String {
+    std::mem::take(&mut self.its_typed_text)
   }
   pub fn key_released(&mut self, the_scancode: u32) -> () {
     self.its_keys.remove(&the_scancode);
   }
+  /// Forget about all held keys, e.g. because the device holding them is
+  /// gone
+  pub fn release_all(&mut self) -> () {
+    self.its_keys.clear();
+  }
+  pub fn is_shift_pressed(&self) -> bool {
+    self.its_keys.contains(&LEFT_SHIFT_KEY) || self.its_keys.contains(&RIGHT_SHIFT_KEY)
+  }
+  /// Keys that have been pressed since the last tick
+  pub fn get_new_keys(&self) -> &std::collections::BTreeSet<u32> {
+    &self.its_new_keys
+  }
+  /// Consume the new keys without applying them to the game, e.g. because
+  /// another mode handles input
+  pub fn clear_new_keys(&mut self) -> () {
+    self.its_new_keys.clear();
+  }
+  /// The direction the cursor has been steered in during the latest tick
+  pub fn get_direction(&self) -> i8 {
+    self.its_direction
+  }
+  pub fn get_input_display(&self) -> InputDisplay {
+    InputDisplay {
+      direction: self.its_direction,
+      taps: self.its_taps.iter().map(|(the_tap, _)| *the_tap).collect(),
+    }
+  }
+  fn read_direction(&mut self, the_game: &model::GameState) -> i8 {
+    let a_direction = match (&self.its_bot, &self.its_playback) {
+      (Some(a_bot), _) => a_bot.get_direction(the_game),
+      (None, Some(a_replay)) => a_replay.get_direction(the_game.get_run().get_ticks()),
+      (None, None) => self
+        .its_source
+        .get_direction(&self.its_keys, &self.its_new_keys),
+    };
+    self.its_direction = a_direction;
+    a_direction
+  }
+  /// Read where the player steers without moving the cursor, e.g. while a
+  /// prompt waits for it
+  pub fn poll_direction(&mut self, the_game: &model::GameState) -> i8 {
+    let a_direction = self.read_direction(the_game);
+    self.its_new_keys.clear();
+    a_direction
+  }
   pub fn tick(&mut self, the_game: &mut model::GameState, the_delta: std::time::Duration) -> () {
+    let a_direction = self.read_direction(the_game);
+    self.tick_as(the_game, the_delta, a_direction);
+  }
+  /// Like `tick`, but steers in `the_direction` rather than where the
+  /// player steers right now, e.g. since lockstep netplay delays inputs
+  pub fn tick_as(
+    &mut self,
+    the_game: &mut model::GameState,
+    the_delta: std::time::Duration,
+    the_direction: i8,
+  ) -> () {
+    self.its_direction = the_direction;
+    for (_, a_age) in self.its_taps.iter_mut() {
+      *a_age += the_delta;
+    }
+    self
+      .its_taps
+      .retain(|(_, the_age)| *the_age < TAP_DISPLAY_TIME);
     // Forward key information to key event listeners
     if self.its_new_keys.len() > 0 {
       // for key_listener in self.its_key_listeners {
@@ -38,51 +390,97 @@ impl Controls {
     }
     // Apply controls on game state
     // TODO this feels like bad separation of concerns
-    if !the_game.is_running() {
-      return;
-    }
-    let effect = the_delta.as_millis() as f32 / constants::TARGET_TICK_TIME;
-    let left = self.its_keys.contains(&LEFT_KEY);
-    let right = self.its_keys.contains(&RIGHT_KEY);
-    if (left || right) && !(left && right) {
-      let a_move_dist = the_game.get_player_speed() * effect;
-      let sign = if left { -1. } else { 1. };
-      let mut newpos = the_game.get_position() + a_move_dist * sign;
-      let wrapcorrection = if newpos >= 1. {
-        -1.
-      } else {
-        if newpos < 0. {
-          1.
-        } else {
-          0.
-        }
-      };
-      newpos += wrapcorrection;
-      // Check for sideways collisions
-      let slots = the_game.get_slots();
-      let slot_width_sum = the_game.get_slot_width_sum();
-      let mut s = the_game.get_slot_idx_at_position(newpos); // the index of the slot we *should* move onto
-      let target_slot = &slots[s];
-      let cursor_tip = constants::CURSOR_Y + constants::CURSOR_H;
-      for obstacle in target_slot.get_obstacles() {
-        if obstacle.get_distance() <= cursor_tip
-          && obstacle.get_distance() + obstacle.get_height() > cursor_tip
-        {
-          // collision - can't move here
-          s = the_game.get_current_slot_idx();
-          let mut pos_in_slot = slots
-            .iter()
-            .enumerate()
-            .filter(|(idx, _)| idx < &s)
-            .fold(0., |acc, (_, slot)| acc + slot.get_width());
-          if right {
-            pos_in_slot += slots[s].get_width() - 0.0001;
-          }
-          newpos = pos_in_slot / slot_width_sum;
-          break;
-        }
-      }
-      the_game.set_position(newpos);
+    steer(the_game, the_direction, the_delta);
+  }
+}
+
+/// Move the cursor of `the_game` in `the_direction` for `the_delta`, -1 for
+/// left, 1 for right and 0 for not at all
+pub fn steer(
+  the_game: &mut model::GameState,
+  the_direction: i8,
+  the_delta: std::time::Duration,
+) -> () {
+  if !the_game.is_running() {
+    return;
+  }
+  if the_game.is_screen_relative() {
+    // by as much as the tick is about to turn the playfield
+    let a_turn = the_game.get_rotation_step(the_delta);
+    let a_position = sweep(the_game, the_game.get_position(), -a_turn);
+    the_game.set_position(a_position);
+  }
+  let effect = constants::get_speed_scale(the_delta);
+  let left = the_direction < 0;
+  let right = the_direction > 0;
+  let a_move_dist = the_game.hold_direction(the_direction, effect);
+  if left || right {
+    let sign = if left { -1. } else { 1. };
+    // Check for sideways collisions, moving up to the obstacle if any
+    let newpos = sweep(the_game, the_game.get_position(), a_move_dist * sign);
+    the_game.set_position(newpos);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::steer;
+  use crate::constants;
+  use crate::level::LevelDef;
+  use crate::model::{GameState, SpeedCurve};
+
+  #[test]
+  fn screen_relative_steering_holds_the_cursor_on_screen() {
+    let mut a_game = GameState::new();
+    a_game.restart(1);
+    a_game.set_rotation_speed(0.5);
+    a_game.set_screen_relative(true);
+    let a_start = a_game.get_position() + a_game.get_style().get_rotation();
+    // how far the cursor has moved on the screen, the shorter way round
+    let a_moved = |the_game: &GameState| {
+      let a_now = the_game.get_position() + the_game.get_style().get_rotation();
+      (a_now - a_start + 0.5).rem_euclid(1.) - 0.5
+    };
+    let a_delta = a_game.get_tick_duration();
+    for _ in 0..30 {
+      steer(&mut a_game, 0, a_delta);
+      a_game.tick();
+    }
+    assert!(a_moved(&a_game).abs() < 1e-4);
+    // steering still moves the cursor, and relative to the screen
+    steer(&mut a_game, 1, a_delta);
+    a_game.tick();
+    assert!(a_moved(&a_game) > 0.);
+  }
+
+  #[test]
+  fn held_directions_speed_the_cursor_up() {
+    let a_level = LevelDef::from_json(
+      br#"{"name": "ramp", "player_speed_curve": {"initial": 0.01, "acceleration": 0.005, "max": 0.02}}"#,
+    )
+    .unwrap();
+    let mut a_game = GameState::new();
+    a_game.apply_level(&a_level);
+    a_game.restart(7);
+    let a_delta = a_game.get_tick_duration();
+    let a_effect = constants::get_speed_scale(a_delta);
+    let a_step = |the_game: &mut GameState, the_direction: i8| {
+      let a_from = the_game.get_position();
+      steer(the_game, the_direction, a_delta);
+      (the_game.get_position() - a_from) / a_effect
+    };
+    let a_moved: Vec<f32> = (0..4).map(|_| a_step(&mut a_game, 1)).collect();
+    for (a_speed, a_expected) in a_moved.iter().zip(&[0.01, 0.015, 0.02, 0.02]) {
+      assert!((a_speed - a_expected).abs() < 1e-4);
     }
+    // turning around starts over
+    assert!((a_step(&mut a_game, -1) + 0.01).abs() < 1e-4);
+    assert_eq!(a_game.get_player_speed(), 0.02);
+    // scripted speed changes keep the shape of the curve
+    a_game.set_player_speed(0.04);
+    assert_eq!(a_game.get_speed_curve().initial, 0.02);
+    // an override, e.g. from the settings, replaces the level's curve
+    a_game.set_speed_curve_override(Some(SpeedCurve::constant(0.03)));
+    assert!((a_step(&mut a_game, -1) + 0.03).abs() < 1e-4);
   }
 }
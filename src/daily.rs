@@ -0,0 +1,90 @@
+use super::model::Run;
+use super::rng::Rng;
+use super::summary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The current UTC date as an ISO 8601 date, which is what daily challenges
+/// are told apart by
+pub fn get_today() -> String {
+  let a_secs = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|the_duration| the_duration.as_secs())
+    .unwrap_or(0);
+  summary::format_day((a_secs / 86400) as i64)
+}
+
+/// The seed of the daily challenge of the level `the_level_key` on
+/// `the_date`, the same for every player
+pub fn get_seed(the_date: &str, the_level_key: &str) -> u64 {
+  Rng::seed_from_text(&format!("{}/{}", the_date, the_level_key))
+}
+
+/// The one attempt at a daily challenge
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DailyResult {
+  pub seed: u64,
+  /// Survived time in seconds, 0 if the attempt has not ended properly
+  pub time: f32,
+}
+
+/// Results of the daily challenges, kept apart from the free-play records.
+/// Keyed by pack and level, then by date.
+#[derive(Serialize, Deserialize, Default)]
+pub struct DailyResults {
+  levels: BTreeMap<String, BTreeMap<String, DailyResult>>,
+}
+
+impl DailyResults {
+  /// Load the results from `the_path`, starting over if there are none or
+  /// they cannot be read
+  pub fn load(the_path: &Path) -> DailyResults {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_results) => a_results,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed daily results file {}: {}",
+            the_path.display(),
+            a_err
+          );
+          DailyResults::default()
+        }
+      },
+      Err(_) => DailyResults::default(),
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  pub fn get(&self, the_key: &str, the_date: &str) -> Option<&DailyResult> {
+    self.levels.get(the_key)?.get(the_date)
+  }
+  /// Note that the challenge of `the_date` has been started, so that quitting
+  /// during the attempt does not allow another one
+  pub fn begin(&mut self, the_key: &str, the_date: &str, the_seed: u64) -> () {
+    self.levels.entry(the_key.to_string()).or_default().insert(
+      the_date.to_string(),
+      DailyResult {
+        seed: the_seed,
+        time: 0.,
+      },
+    );
+  }
+  /// Record how long the attempt started with `begin` lasted
+  pub fn finish(&mut self, the_key: &str, the_date: &str, the_run: &Run) -> () {
+    if let Some(a_result) = self
+      .levels
+      .get_mut(the_key)
+      .and_then(|the_days| the_days.get_mut(the_date))
+    {
+      a_result.time = the_run.get_time().as_secs_f32();
+    }
+  }
+}
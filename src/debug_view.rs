@@ -0,0 +1,124 @@
+use super::app::App;
+use super::model;
+use super::renderer::{OGLRenderer, Renderer};
+use super::window;
+use glutin::dpi::{LogicalSize, PhysicalSize};
+use glutin::event_loop::EventLoopWindowTarget;
+use glutin::window::{WindowBuilder, WindowId};
+use glutin::{ContextBuilder, PossiblyCurrent, WindowedContext};
+use std::time::Instant;
+
+/// Initial size of the debug view's window in logical pixels
+const DEBUG_VIEW_SIZE: f64 = 640.;
+
+/// A windowed context that can be made current again after another one has
+/// been, which glutin only allows by value
+pub struct SwitchableContext {
+    /// Only empty while it is being made current
+    its_context: Option<WindowedContext<PossiblyCurrent>>,
+}
+
+impl SwitchableContext {
+    pub fn new(the_context: WindowedContext<PossiblyCurrent>) -> SwitchableContext {
+        SwitchableContext {
+            its_context: Some(the_context),
+        }
+    }
+
+    /// Direct the GL calls that follow to this context and its window
+    pub fn make_current(&mut self) -> () {
+        let a_context = match self.its_context.take() {
+            Some(a_context) => a_context,
+            None => return,
+        };
+        self.its_context = Some(match unsafe { a_context.make_current() } {
+            Ok(a_context) => a_context,
+            Err((a_context, a_err)) => {
+                eprintln!("Failed to switch GL contexts: {}", a_err);
+                a_context
+            }
+        });
+    }
+}
+
+impl std::ops::Deref for SwitchableContext {
+    type Target = WindowedContext<PossiblyCurrent>;
+
+    fn deref(&self) -> &WindowedContext<PossiblyCurrent> {
+        self.its_context.as_ref().unwrap()
+    }
+}
+
+/// A second window that shows the game with the debug overlay, so that the
+/// main window stays clean, e.g. while it is being streamed. It has a GL
+/// context and renderer of its own, so every use leaves its context current
+/// and the caller has to switch back to the main one.
+pub struct DebugView {
+    // dropped before the context, whose GL objects it deletes
+    its_renderer: OGLRenderer,
+    its_context: SwitchableContext,
+    its_last_frame: Instant,
+}
+
+impl DebugView {
+    pub fn open(the_target: &EventLoopWindowTarget<()>) -> Result<DebugView, String> {
+        let a_winbuilder = WindowBuilder::new()
+            .with_title(format!("{} (debug)", window::APP_NAME))
+            .with_window_icon(window::create_icon())
+            .with_inner_size(LogicalSize::new(DEBUG_VIEW_SIZE, DEBUG_VIEW_SIZE));
+        let a_context = ContextBuilder::new()
+            .build_windowed(a_winbuilder, the_target)
+            .map_err(|the_err| the_err.to_string())?;
+        let a_context =
+            unsafe { a_context.make_current() }.map_err(|(_, the_err)| the_err.to_string())?;
+        let a_size = a_context.window().inner_size();
+        let a_game = model::GameState::new();
+        let mut a_renderer =
+            OGLRenderer::new(&a_game, a_context.context(), a_size.width, a_size.height);
+        a_renderer.resize(a_size.width, a_size.height);
+        a_renderer.set_debug_overlay(true);
+        Ok(DebugView {
+            its_renderer: a_renderer,
+            its_context: SwitchableContext::new(a_context),
+            its_last_frame: Instant::now(),
+        })
+    }
+
+    pub fn get_window_id(&self) -> WindowId {
+        self.its_context.window().id()
+    }
+
+    pub fn resize(&mut self, the_size: PhysicalSize<u32>) -> () {
+        // nothing to draw into while minimized
+        if the_size.width == 0 || the_size.height == 0 {
+            return;
+        }
+        self.its_context.make_current();
+        self.its_context.resize(the_size);
+        self.its_renderer.resize(the_size.width, the_size.height);
+    }
+
+    /// Draw what `the_app` shows in the main window along with the debug
+    /// overlay
+    pub fn render<R: Renderer>(&mut self, the_app: &App<R>) -> () {
+        let a_size = self.its_context.window().inner_size();
+        if a_size.width == 0 || a_size.height == 0 {
+            return;
+        }
+        let a_now = Instant::now();
+        let a_delta = a_now - self.its_last_frame;
+        self.its_last_frame = a_now;
+        self.its_context.make_current();
+        the_app.render_debug_view(&mut self.its_renderer, a_delta);
+        if let Err(a_err) = self.its_context.swap_buffers() {
+            eprintln!("Failed to show the debug view: {}", a_err);
+        }
+    }
+}
+
+impl Drop for DebugView {
+    fn drop(&mut self) -> () {
+        // the renderer deletes its GL objects right after
+        self.its_context.make_current();
+    }
+}
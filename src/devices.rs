@@ -0,0 +1,77 @@
+/// What became of the device the player is using
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DeviceChange {
+  /// The device has been unplugged
+  Lost,
+  /// A device has been plugged in or used after the one in use was lost,
+  /// and replaces it
+  Rebound,
+}
+
+/// Keeps track of which input device the player is using, e.g. a
+/// controller, so that the game can pause when it is unplugged and go on
+/// once it is back. Generic over the id so that it does not depend on the
+/// windowing system.
+pub struct DeviceTracker<Id> {
+  its_active: Option<Id>,
+  /// The active device has been unplugged and not been replaced yet
+  its_lost: bool,
+}
+
+impl<Id: PartialEq> DeviceTracker<Id> {
+  pub fn new() -> DeviceTracker<Id> {
+    DeviceTracker {
+      its_active: None,
+      its_lost: false,
+    }
+  }
+  /// Input has come from `the_device`, which makes it the one in use
+  pub fn input_from(&mut self, the_device: Id) -> Option<DeviceChange> {
+    self.its_active = Some(the_device);
+    self.rebind()
+  }
+  /// `the_device` has been plugged in. A reconnected controller usually
+  /// gets a new id, so whatever is plugged in replaces a lost device.
+  pub fn added(&mut self, the_device: Id) -> Option<DeviceChange> {
+    if !self.its_lost {
+      return None;
+    }
+    self.its_active = Some(the_device);
+    self.rebind()
+  }
+  pub fn removed(&mut self, the_device: Id) -> Option<DeviceChange> {
+    if self.its_active.as_ref() != Some(&the_device) {
+      return None;
+    }
+    self.its_active = None;
+    self.its_lost = true;
+    Some(DeviceChange::Lost)
+  }
+  fn rebind(&mut self) -> Option<DeviceChange> {
+    if std::mem::replace(&mut self.its_lost, false) {
+      Some(DeviceChange::Rebound)
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{DeviceChange, DeviceTracker};
+
+  #[test]
+  fn tracks_the_device_in_use() {
+    let mut a_tracker = DeviceTracker::new();
+    assert_eq!(a_tracker.input_from(1), None);
+    // other devices may come and go
+    assert_eq!(a_tracker.added(2), None);
+    assert_eq!(a_tracker.removed(2), None);
+    assert_eq!(a_tracker.removed(1), Some(DeviceChange::Lost));
+    assert_eq!(a_tracker.added(3), Some(DeviceChange::Rebound));
+    assert_eq!(a_tracker.removed(3), Some(DeviceChange::Lost));
+    // switching to another device works as well
+    assert_eq!(a_tracker.input_from(4), Some(DeviceChange::Rebound));
+    assert_eq!(a_tracker.input_from(4), None);
+  }
+}
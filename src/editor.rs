@@ -0,0 +1,246 @@
+use super::constants;
+use super::level::{GapDef, PatternDef, WallDef};
+use super::model;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const LEFT_KEY: u32 = 105;
+const RIGHT_KEY: u32 = 106;
+const UP_KEY: u32 = 103;
+const DOWN_KEY: u32 = 108;
+const PAGE_UP_KEY: u32 = 104;
+const PAGE_DOWN_KEY: u32 = 109;
+const TAB_KEY: u32 = 15;
+const DELETE_KEY: u32 = 111;
+/// N
+const NEW_WALL_KEY: u32 = 49;
+/// P
+const PREVIEW_KEY: u32 = 25;
+/// E
+const EXPORT_KEY: u32 = 18;
+/// O
+const OUTER_RING_KEY: u32 = 24;
+/// R
+const REVERSED_KEY: u32 = 19;
+/// G
+const GAP_KEY: u32 = 34;
+
+/// Distance from the center at which the pattern is shown while editing
+const EDIT_BASE_DISTANCE: f32 = 0.3;
+const OFFSET_STEP: f32 = 0.02;
+const HEIGHT_STEP: f32 = 0.01;
+const MIN_HEIGHT: f32 = 0.01;
+const DEFAULT_HEIGHT: f32 = 0.08;
+
+/// Frozen-time mode for building patterns wall by wall
+pub struct PatternEditor {
+  its_walls: Vec<WallDef>,
+  its_slot: usize,
+  /// Index into `its_walls` of the wall being edited
+  its_selected: Option<usize>,
+  /// Distance the pattern has travelled in the approach preview, if running
+  its_preview: Option<f32>,
+  its_state: model::GameState,
+}
+
+impl PatternEditor {
+  pub fn new(the_game: &model::GameState) -> PatternEditor {
+    let mut a_state = model::GameState::new();
+    a_state.copy_speeds_from(the_game);
+    let mut a_editor = PatternEditor {
+      its_walls: Vec::new(),
+      its_slot: 0,
+      its_selected: None,
+      its_preview: None,
+      its_state: a_state,
+    };
+    a_editor.update_state();
+    a_editor
+  }
+  /// The game state to render: the pattern at its editing position or
+  /// somewhere along its approach while previewing
+  pub fn get_state(&self) -> &model::GameState {
+    &self.its_state
+  }
+  fn selected_wall(&mut self) -> Option<&mut WallDef> {
+    match self.its_selected {
+      Some(a_idx) => self.its_walls.get_mut(a_idx),
+      None => None,
+    }
+  }
+  /// Select the next wall in the current slot
+  fn cycle_selection(&mut self) -> () {
+    let a_slot = self.its_slot;
+    let a_in_slot: Vec<usize> = (0..self.its_walls.len())
+      .filter(|the_idx| self.its_walls[*the_idx].slot == a_slot)
+      .collect();
+    self.its_selected = match self.its_selected {
+      Some(a_current) => match a_in_slot.iter().position(|the_idx| *the_idx == a_current) {
+        Some(a_pos) => a_in_slot.get((a_pos + 1) % a_in_slot.len()).cloned(),
+        None => a_in_slot.first().cloned(),
+      },
+      None => a_in_slot.first().cloned(),
+    };
+  }
+  fn select_slot(&mut self, the_slot: usize) -> () {
+    self.its_slot = the_slot;
+    self.its_selected = None;
+    self.cycle_selection();
+  }
+  pub fn handle_keys(&mut self, the_new_keys: &BTreeSet<u32>) -> () {
+    let a_slot_count = self.its_state.get_slots().len();
+    for a_key in the_new_keys {
+      match *a_key {
+        LEFT_KEY => self.select_slot((self.its_slot + a_slot_count - 1) % a_slot_count),
+        RIGHT_KEY => self.select_slot((self.its_slot + 1) % a_slot_count),
+        TAB_KEY => self.cycle_selection(),
+        NEW_WALL_KEY => {
+          self.its_walls.push(WallDef {
+            slot: self.its_slot,
+            offset: 0.,
+            height: Some(DEFAULT_HEIGHT),
+            color_group: 0,
+            shape: None,
+            outer_ring: false,
+            reversed: false,
+            gap: None,
+          });
+          self.its_selected = Some(self.its_walls.len() - 1);
+        }
+        DELETE_KEY => {
+          if let Some(a_idx) = self.its_selected {
+            self.its_walls.remove(a_idx);
+            self.its_selected = None;
+            self.cycle_selection();
+          }
+        }
+        UP_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.offset += OFFSET_STEP;
+          }
+        }
+        DOWN_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.offset = (a_wall.offset - OFFSET_STEP).max(0.);
+          }
+        }
+        PAGE_UP_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.height = Some(a_wall.height.unwrap_or(DEFAULT_HEIGHT) + HEIGHT_STEP);
+          }
+        }
+        PAGE_DOWN_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            let a_height = a_wall.height.unwrap_or(DEFAULT_HEIGHT) - HEIGHT_STEP;
+            a_wall.height = Some(a_height.max(MIN_HEIGHT));
+          }
+        }
+        OUTER_RING_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.outer_ring = !a_wall.outer_ring;
+          }
+        }
+        REVERSED_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.reversed = !a_wall.reversed;
+          }
+        }
+        GAP_KEY => {
+          if let Some(a_wall) = self.selected_wall() {
+            a_wall.gap = match a_wall.gap {
+              Some(_) => None,
+              None => Some(GapDef::default()),
+            };
+          }
+        }
+        PREVIEW_KEY => {
+          self.its_preview = match self.its_preview {
+            Some(_) => None,
+            None => Some(0.),
+          }
+        }
+        _ => (),
+      }
+    }
+    self.update_state();
+  }
+  /// Advance the approach preview, if one is running
+  pub fn tick(&mut self, the_delta: Duration) -> () {
+    if let Some(a_travelled) = self.its_preview {
+      let a_effect = constants::get_speed_scale(the_delta);
+      let a_travelled = a_travelled + self.its_state.get_obstacle_speed() * a_effect;
+      let a_depth = self.to_pattern(String::new()).get_depth_at(0.);
+      // Loop the preview once the whole pattern has passed the center
+      self.its_preview = if a_travelled > self.its_state.get_spawn_distance() + a_depth {
+        Some(0.)
+      } else {
+        Some(a_travelled)
+      };
+      self.update_state();
+    }
+  }
+  fn update_state(&mut self) -> () {
+    self.its_state.clear_obstacles();
+    for a_wall in &self.its_walls {
+      let a_height = a_wall.height.unwrap_or(DEFAULT_HEIGHT);
+      let mut a_obstacle = model::Obstacle::new_at(0., a_height);
+      a_obstacle.set_outer_ring(a_wall.outer_ring);
+      a_obstacle.set_reversed(a_wall.reversed);
+      a_obstacle.set_gap(a_wall.gap.as_ref().map(|the_gap| the_gap.to_gap(false)));
+      let a_factor = self.its_state.get_speed_factor(&a_obstacle);
+      let a_distance = match self.its_preview {
+        Some(a_travelled) if a_wall.reversed => {
+          constants::REVERSE_SPAWN_DISTANCE - a_wall.offset - a_height - a_travelled * a_factor
+        }
+        Some(a_travelled) if a_wall.outer_ring => {
+          self.its_state.get_outer_ring_distance() + a_wall.offset - a_travelled * a_factor
+        }
+        Some(a_travelled) => self.its_state.get_spawn_distance() + a_wall.offset - a_travelled,
+        None => EDIT_BASE_DISTANCE + a_wall.offset,
+      };
+      a_obstacle.set_distance(a_distance);
+      a_obstacle.set_color_group(a_wall.color_group);
+      self.its_state.add_obstacle(a_wall.slot, a_obstacle);
+    }
+    // The cursor marks the slot being edited
+    let a_slot_count = self.its_state.get_slots().len() as f32;
+    self
+      .its_state
+      .set_position((self.its_slot as f32 + 0.5) / a_slot_count);
+    // Nothing reacts to the preview's events, don't let them pile up
+    self.its_state.take_events();
+  }
+  pub fn to_pattern(&self, the_name: String) -> PatternDef {
+    PatternDef {
+      name: the_name,
+      difficulty: 0.,
+      weight: 1.,
+      thickness: DEFAULT_HEIGHT,
+      spacing: 0.25,
+      thickness_scaling: 0.,
+      spacing_scaling: 0.,
+      walls: self.its_walls.clone(),
+      pickups: Vec::new(),
+      pulse: None,
+    }
+  }
+  /// Whether the export key has been pressed in `the_new_keys`
+  pub fn wants_export(&self, the_new_keys: &BTreeSet<u32>) -> bool {
+    the_new_keys.contains(&EXPORT_KEY) && !self.its_walls.is_empty()
+  }
+  /// Write the pattern to `<the_dir>/patterns/<name>.json` so that it can be
+  /// referenced from a level's `pattern_files`
+  pub fn export(&self, the_dir: &Path) -> std::io::Result<(PathBuf, PatternDef)> {
+    let a_stamp = match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+      Ok(a_time) => a_time.as_secs(),
+      Err(_) => 0,
+    };
+    let a_pattern = self.to_pattern(format!("custom-{}", a_stamp));
+    let a_dir = the_dir.join("patterns");
+    std::fs::create_dir_all(&a_dir)?;
+    let a_path = a_dir.join(format!("{}.json", a_pattern.name));
+    std::fs::write(&a_path, serde_json::to_vec_pretty(&a_pattern)?)?;
+    Ok((a_path, a_pattern))
+  }
+}
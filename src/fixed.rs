@@ -0,0 +1,13 @@
+/// Fractional bits of the fixed-point grid that the strict math mode keeps
+/// the simulation on. Values up to 16 in magnitude, far beyond any
+/// distance in the game, are exact on it as `f32`.
+pub const FRACTION_BITS: i32 = 20;
+
+/// `the_value` rounded to the nearest point of the fixed-point grid. Only
+/// scales by powers of two and rounds, so the result is the same on every
+/// platform, even where intermediate results are kept in extended
+/// precision.
+pub fn snap(the_value: f32) -> f32 {
+  let a_scale = (1u32 << FRACTION_BITS) as f32;
+  (the_value * a_scale).round() / a_scale
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+
+/// Width of a glyph in font pixels
+pub const GLYPH_W: usize = 3;
+/// Height of a glyph in font pixels
+pub const GLYPH_H: usize = 5;
+/// Horizontal distance between the starts of two glyphs in font pixels
+const ADVANCE: usize = GLYPH_W + 1;
+/// Vertical distance between the starts of two lines in font pixels
+const LINE_ADVANCE: usize = GLYPH_H + 2;
+/// Line height in pixels at which TrueType fonts are rasterized. They are
+/// scaled to the line height of the built-in font when drawn.
+const RASTER_SIZE: f32 = 14.;
+/// Coverage from which a rasterized pixel counts as lit
+const RASTER_THRESHOLD: f32 = 0.5;
+/// Stands in for characters a rasterized font does not have
+const REPLACEMENT_CHAR: char = '?';
+
+/// Rows of the built-in 3x5 pixel font, top to bottom. The highest of the
+/// three bits is the leftmost pixel. Lowercase letters are shown as uppercase
+/// and accented letters without their accents.
+fn glyph(the_char: char) -> [u8; GLYPH_H] {
+  match strip_accent(the_char).to_ascii_uppercase() {
+    '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+    '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+    '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+    '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+    '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+    '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+    '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+    '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+    '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+    '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+    'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+    'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+    'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+    'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+    'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+    'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+    'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+    'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+    'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+    'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+    'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+    'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+    'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+    'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+    'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+    'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+    'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+    'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+    'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+    'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+    'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+    'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+    'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+    'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+    'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+    'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+    ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+    '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+    ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+    ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+    '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+    '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+    '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+    '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+    '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+    '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+    ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+    '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+    ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+    '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+    '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+    '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+    '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+    '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+    '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+    '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+    '*' => [0b101, 0b010, 0b101, 0b000, 0b000],
+    _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+  }
+}
+
+/// The unaccented letter of the accented Latin-1 letters, for translations
+/// shown in the built-in font
+fn strip_accent(the_char: char) -> char {
+  match the_char {
+    'À'..='Å' | 'à'..='å' => 'A',
+    'Ç' | 'ç' => 'C',
+    'È'..='Ë' | 'è'..='ë' => 'E',
+    'Ì'..='Ï' | 'ì'..='ï' => 'I',
+    'Ñ' | 'ñ' => 'N',
+    'Ò'..='Ö' | 'Ø' | 'ò'..='ö' | 'ø' => 'O',
+    'Ù'..='Ü' | 'ù'..='ü' => 'U',
+    'Ý' | 'ý' | 'ÿ' => 'Y',
+    'ß' => 'S',
+    _ => the_char,
+  }
+}
+
+/// A glyph of a TrueType font as lit pixels, placed relative to the pen
+/// position on the baseline
+struct RasterGlyph {
+  its_left: i32,
+  its_top: i32,
+  its_width: usize,
+  its_pixels: Vec<bool>,
+  its_advance: f32,
+}
+
+/// The printable ASCII and Latin-1 glyphs of a TrueType font, rasterized once so that
+/// they can be drawn with the same pixel quads as the built-in font
+pub struct RasterFont {
+  its_glyphs: HashMap<char, RasterGlyph>,
+  its_ascent: f32,
+}
+
+impl RasterFont {
+  pub fn from_truetype(the_data: &[u8]) -> Result<RasterFont, String> {
+    let a_font =
+      rusttype::Font::from_bytes(the_data.to_vec()).map_err(|the_err| the_err.to_string())?;
+    let a_scale = rusttype::Scale::uniform(RASTER_SIZE);
+    let mut a_glyphs = HashMap::new();
+    for a_char in (' '..='~')
+      .chain('\u{a1}'..='\u{ff}')
+      .chain(std::iter::once(REPLACEMENT_CHAR))
+    {
+      let a_scaled = a_font.glyph(a_char).scaled(a_scale);
+      let a_advance = a_scaled.h_metrics().advance_width;
+      let a_positioned = a_scaled.positioned(rusttype::point(0., 0.));
+      let a_glyph = match a_positioned.pixel_bounding_box() {
+        Some(a_box) => {
+          let a_width = a_box.width() as usize;
+          let mut a_pixels = vec![false; a_width * a_box.height() as usize];
+          a_positioned.draw(|the_x, the_y, the_coverage| {
+            a_pixels[the_y as usize * a_width + the_x as usize] = the_coverage >= RASTER_THRESHOLD;
+          });
+          RasterGlyph {
+            its_left: a_box.min.x,
+            its_top: a_box.min.y,
+            its_width: a_width,
+            its_pixels: a_pixels,
+            its_advance: a_advance,
+          }
+        }
+        // blanks have nothing to draw
+        None => RasterGlyph {
+          its_left: 0,
+          its_top: 0,
+          its_width: 0,
+          its_pixels: Vec::new(),
+          its_advance: a_advance,
+        },
+      };
+      a_glyphs.insert(a_char, a_glyph);
+    }
+    Ok(RasterFont {
+      its_glyphs: a_glyphs,
+      its_ascent: a_font.v_metrics(a_scale).ascent,
+    })
+  }
+  fn get_glyph(&self, the_char: char) -> &RasterGlyph {
+    self
+      .its_glyphs
+      .get(&the_char)
+      .unwrap_or(&self.its_glyphs[&REPLACEMENT_CHAR])
+  }
+  /// Size of a rasterized pixel when drawing with built-in font pixels of
+  /// `the_pixel_size`, so that lines are equally high in both fonts
+  fn get_scale(the_pixel_size: f32) -> f32 {
+    the_pixel_size * LINE_ADVANCE as f32 / RASTER_SIZE
+  }
+  fn measure(&self, the_text: &str, the_pixel_size: f32) -> (f32, f32) {
+    let a_scale = RasterFont::get_scale(the_pixel_size);
+    let a_width = the_text
+      .lines()
+      .map(|the_line| {
+        the_line
+          .chars()
+          .map(|the_char| self.get_glyph(the_char).its_advance)
+          .sum::<f32>()
+      })
+      .fold(0., f32::max);
+    let a_lines = the_text.lines().count().max(1);
+    (a_width * a_scale, a_lines as f32 * RASTER_SIZE * a_scale)
+  }
+  fn layout(
+    &self,
+    the_text: &str,
+    the_x: f32,
+    the_y: f32,
+    the_pixel_size: f32,
+    the_out: &mut Vec<f32>,
+  ) -> () {
+    let a_scale = RasterFont::get_scale(the_pixel_size);
+    for (a_line_idx, a_line) in the_text.lines().enumerate() {
+      let a_baseline = the_y + (a_line_idx as f32 * RASTER_SIZE + self.its_ascent) * a_scale;
+      let mut a_pen = the_x;
+      for a_char in a_line.chars() {
+        let a_glyph = self.get_glyph(a_char);
+        for (a_idx, a_lit) in a_glyph.its_pixels.iter().enumerate() {
+          if !a_lit {
+            continue;
+          }
+          let a_col = (a_idx % a_glyph.its_width) as i32 + a_glyph.its_left;
+          let a_row = (a_idx / a_glyph.its_width) as i32 + a_glyph.its_top;
+          let a_x0 = a_pen + a_col as f32 * a_scale;
+          let a_y0 = a_baseline + a_row as f32 * a_scale;
+          push_pixel(a_x0, a_y0, a_scale, the_out);
+        }
+        a_pen += a_glyph.its_advance * a_scale;
+      }
+    }
+  }
+}
+
+/// The font of all text in the overlay
+pub enum Font {
+  /// The 3x5 pixel font below
+  BuiltIn,
+  /// A font that a pack brings along
+  Raster(RasterFont),
+}
+
+impl Font {
+  /// Size in screen pixels of `the_text` when drawn with font pixels of
+  /// `the_pixel_size`
+  pub fn measure(&self, the_text: &str, the_pixel_size: f32) -> (f32, f32) {
+    match self {
+      Font::BuiltIn => measure(the_text, the_pixel_size),
+      Font::Raster(a_font) => a_font.measure(the_text, the_pixel_size),
+    }
+  }
+  /// Append two triangles per lit pixel of `the_text` to `the_out`, see
+  /// `layout`
+  pub fn layout(
+    &self,
+    the_text: &str,
+    the_x: f32,
+    the_y: f32,
+    the_pixel_size: f32,
+    the_out: &mut Vec<f32>,
+  ) -> () {
+    match self {
+      Font::BuiltIn => layout(the_text, the_x, the_y, the_pixel_size, the_out),
+      Font::Raster(a_font) => a_font.layout(the_text, the_x, the_y, the_pixel_size, the_out),
+    }
+  }
+}
+
+/// Append the two triangles of a square pixel with its top left corner at
+/// `the_x`/`the_y`
+fn push_pixel(the_x: f32, the_y: f32, the_size: f32, the_out: &mut Vec<f32>) -> () {
+  let a_x1 = the_x + the_size;
+  let a_y1 = the_y + the_size;
+  the_out.extend_from_slice(&[
+    the_x, the_y, a_x1, the_y, the_x, a_y1, a_x1, the_y, a_x1, a_y1, the_x, a_y1,
+  ]);
+}
+
+/// Size in screen pixels of `the_text` when drawn with font pixels of
+/// `the_pixel_size`
+fn measure(the_text: &str, the_pixel_size: f32) -> (f32, f32) {
+  let a_lines = the_text.lines().count().max(1);
+  let a_columns = the_text
+    .lines()
+    .map(|the_line| the_line.chars().count())
+    .max()
+    .unwrap_or(0);
+  let a_width = (a_columns * ADVANCE).saturating_sub(1);
+  let a_height = (a_lines - 1) * LINE_ADVANCE + GLYPH_H;
+  (
+    a_width as f32 * the_pixel_size,
+    a_height as f32 * the_pixel_size,
+  )
+}
+
+/// Append two triangles per lit font pixel of `the_text` to `the_out`, as
+/// x/y pairs in screen pixels with the origin in the top left corner.
+/// `the_x` and `the_y` are the top left corner of the text.
+fn layout(
+  the_text: &str,
+  the_x: f32,
+  the_y: f32,
+  the_pixel_size: f32,
+  the_out: &mut Vec<f32>,
+) -> () {
+  for (a_line_idx, a_line) in the_text.lines().enumerate() {
+    let a_top = the_y + (a_line_idx * LINE_ADVANCE) as f32 * the_pixel_size;
+    for (a_col, a_char) in a_line.chars().enumerate() {
+      let a_left = the_x + (a_col * ADVANCE) as f32 * the_pixel_size;
+      for (a_row, a_bits) in glyph(a_char).iter().enumerate() {
+        for a_bit in 0..GLYPH_W {
+          if a_bits & (1 << (GLYPH_W - 1 - a_bit)) == 0 {
+            continue;
+          }
+          let a_x0 = a_left + a_bit as f32 * the_pixel_size;
+          let a_y0 = a_top + a_row as f32 * the_pixel_size;
+          push_pixel(a_x0, a_y0, the_pixel_size, the_out);
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,51 @@
+use super::level::{ColorDef, LevelDef, PatternDef};
+use super::model::{Color, GameState};
+use super::spawner::Spawner;
+use super::timeline::Timeline;
+
+/// Ticks to simulate with parsed content, enough for a few spawns
+const SIMULATED_TICKS: usize = 200;
+
+/// Parse a level file and play it for a moment, as far as that works
+/// without a window
+pub fn parse_level(the_data: &[u8]) -> () {
+  let _ = PatternDef::from_json(the_data);
+  let a_level = match LevelDef::from_json(the_data) {
+    Ok(a_level) => a_level,
+    Err(_) => return,
+  };
+  let _ = Timeline::new(&a_level.events, a_level.bpm);
+  let mut a_spawner = Spawner::new(
+    a_level.patterns.clone(),
+    a_level.difficulty_ramp,
+    a_level.spawn_strategy,
+  );
+  let mut a_game = GameState::new();
+  a_game.apply_level(&a_level);
+  a_game.restart(0);
+  a_spawner.reset(0);
+  for _ in 0..SIMULATED_TICKS {
+    let a_delta = a_game.get_tick_duration();
+    a_spawner.tick(&mut a_game, a_delta);
+    a_game.tick();
+  }
+}
+
+/// Parse a color the ways a level file may give it
+pub fn parse_color(the_data: &[u8]) -> () {
+  if let Ok(a_text) = std::str::from_utf8(the_data) {
+    let _ = Color::parse(a_text);
+  }
+  let _ = serde_json::from_slice::<ColorDef>(the_data);
+}
+
+/// Restore a save-state and keep simulating it
+pub fn parse_save_state(the_data: &[u8]) -> () {
+  let mut a_game = match GameState::from_json(the_data) {
+    Ok(a_game) => a_game,
+    Err(_) => return,
+  };
+  for _ in 0..SIMULATED_TICKS {
+    a_game.tick();
+  }
+}
@@ -0,0 +1,510 @@
+use super::constants;
+use super::model;
+use super::rng::Rng;
+use super::shape::Shape;
+
+/// Number of concentric rings the vignette is made of
+pub const VIGNETTE_RINGS: usize = 4;
+/// Segments of every vignette ring, each ring is a strip of
+/// `2 * (VIGNETTE_SEGMENTS + 1)` vertices
+pub const VIGNETTE_SEGMENTS: usize = 24;
+const VIGNETTE_INNER_Y: f32 = 0.45;
+const VIGNETTE_OUTER_Y: f32 = 1.05;
+/// Share of its slot's width a pickup spans
+const PICKUP_WIDTH: f32 = 0.5;
+/// How far slots are brightened towards white once an obstacle in them
+/// reaches the cursor, when proximity shading is enabled
+const PROXIMITY_BRIGHTNESS: f32 = 0.5;
+/// How far obstacles of the outer ring are darkened, so that they read as
+/// a layer behind the inner one
+const OUTER_RING_DIM: f32 = 0.4;
+/// How far out the background reaches, beyond the slots so that it still
+/// fills the screen while the camera is zoomed out
+const BACKGROUND_DISTANCE: f32 = 4.;
+/// Size of a star on the background, in the same units as distances
+const STAR_SIZE: f32 = 0.01;
+/// Seed of the star positions, the same for every level so that the stars
+/// do not jump around when the style changes
+const STAR_SEED: u64 = 0x5eed;
+
+/// Convex outline of the pickups of `the_power_up`, x across the pickup
+/// and y from its near to its far edge, both from 0 to 1
+fn get_pickup_outline(the_power_up: model::PowerUp) -> &'static [[f32; 2]] {
+  match the_power_up {
+    model::PowerUp::SlowTime => &[[0.5, 0.], [1., 0.5], [0.5, 1.], [0., 0.5]],
+    model::PowerUp::ShrinkCursor => &[[0.5, 0.], [1., 1.], [0., 1.]],
+    model::PowerUp::ScoreMultiplier => &[
+      [0.25, 0.],
+      [0.75, 0.],
+      [1., 0.5],
+      [0.75, 1.],
+      [0.25, 1.],
+      [0., 0.5],
+    ],
+  }
+}
+
+/// A corner of a triangle, laid out in memory as the vertex attributes
+/// expect it
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+  /// Angle from 0 to 1
+  pub x: f32,
+  /// Distance from the center
+  pub y: f32,
+  /// Red, green and blue
+  pub color: [f32; 3],
+}
+
+impl Vertex {
+  /// Byte offset of `color` within a vertex
+  pub const COLOR_OFFSET: usize = 2 * std::mem::size_of::<f32>();
+
+  pub fn new(the_x: f32, the_y: f32, the_color: &model::Color) -> Vertex {
+    Vertex {
+      x: the_x,
+      y: the_y,
+      color: [the_color.its_r, the_color.its_g, the_color.its_b],
+    }
+  }
+}
+
+/// The bytes of `the_vertices`, as they are uploaded to the GPU
+pub fn as_bytes(the_vertices: &[Vertex]) -> &[u8] {
+  // Vertex is repr(C) and made of f32 only, so it has no padding and every
+  // byte of it is initialized
+  unsafe {
+    std::slice::from_raw_parts(
+      the_vertices.as_ptr() as *const u8,
+      std::mem::size_of_val(the_vertices),
+    )
+  }
+}
+
+/// The vertices of a frame as triangles in polar coordinates, x being the
+/// angle from 0 to 1 and y the distance from the center, each followed by
+/// its color. Knows nothing about how they are drawn, so every renderer can
+/// share it. The data is kept between frames to avoid allocations.
+pub struct Geometry {
+  its_data: Vec<Vertex>,
+  /// First vertex and vertex count of the background, empty without one
+  its_background_range: (usize, usize),
+  /// First vertex and vertex count of the slots
+  its_slot_range: (usize, usize),
+  /// First vertex and vertex count of the obstacles, the pickups, the
+  /// outer hexagon's ring and the inner hexagon, which are all drawn alike
+  its_shape_range: (usize, usize),
+  /// First vertex of the cursor shadow, the cursor follows right after it
+  its_cursor_offset: usize,
+  /// Vertices of the cursor, and as many of its shadow
+  its_cursor_vertices: usize,
+  /// First vertex of the vignette rings, see `VIGNETTE_SEGMENTS`
+  its_vignette_offset: usize,
+}
+
+impl Geometry {
+  pub fn new() -> Geometry {
+    Geometry {
+      its_data: Vec::new(),
+      its_background_range: (0, 0),
+      its_slot_range: (0, 0),
+      its_shape_range: (0, 0),
+      its_cursor_offset: 0,
+      its_cursor_vertices: 3,
+      its_vignette_offset: 0,
+    }
+  }
+  pub fn get_data(&self) -> &[Vertex] {
+    &self.its_data
+  }
+  pub fn get_vertex_count(&self) -> usize {
+    self.its_data.len()
+  }
+  pub fn get_background_range(&self) -> (usize, usize) {
+    self.its_background_range
+  }
+  pub fn get_slot_range(&self) -> (usize, usize) {
+    self.its_slot_range
+  }
+  pub fn get_shape_range(&self) -> (usize, usize) {
+    self.its_shape_range
+  }
+  pub fn get_cursor_offset(&self) -> usize {
+    self.its_cursor_offset
+  }
+  pub fn get_cursor_vertices(&self) -> usize {
+    self.its_cursor_vertices
+  }
+  pub fn get_vignette_offset(&self) -> usize {
+    self.its_vignette_offset
+  }
+
+  /// Append a vertex of the given color
+  fn push_vertex(&mut self, the_x: f32, the_y: f32, the_color: &model::Color) -> () {
+    self.its_data.push(Vertex::new(the_x, the_y, the_color));
+  }
+
+  /// Append a quad as two triangles
+  fn push_quad(
+    &mut self,
+    the_left: f32,
+    the_right: f32,
+    the_near: f32,
+    the_far: f32,
+    the_color: &model::Color,
+  ) -> () {
+    self.push_vertex(the_left, the_near, the_color);
+    self.push_vertex(the_left, the_far, the_color);
+    self.push_vertex(the_right, the_near, the_color);
+    self.push_vertex(the_right, the_near, the_color);
+    self.push_vertex(the_left, the_far, the_color);
+    self.push_vertex(the_right, the_far, the_color);
+  }
+
+  /// Replace the vertices by those of `the_game` in `the_style`, with the
+  /// cursor drawn at `the_cursor_position` as `the_cursor_shape`, or as
+  /// the plain triangle if it is `None`
+  pub fn build(
+    &mut self,
+    the_game: &model::GameState,
+    the_style: &model::Style,
+    the_cursor_position: f32,
+    the_cursor_shape: Option<&Shape>,
+    the_proximity_shading: bool,
+  ) -> () {
+    self.its_data.clear();
+    if let Some(a_background) = the_style.get_background() {
+      self.push_background(a_background);
+    }
+    self.its_background_range = (0, self.get_vertex_count());
+    // create slot vertices
+    let a_slot_colors = the_style.get_slot_colors();
+    let slot_width_sum = the_game.get_slot_width_sum();
+    let mut x = 0.;
+    // the playfield ends where obstacles vanish, nothing is drawn past it
+    let sl = the_game.get_despawn_distance();
+    for i in 0..the_game.get_slots().len() {
+      let mut a_slot_colr = if a_slot_colors.is_empty() {
+        model::Color::rgba(1., 1., 1., 1.)
+      } else {
+        a_slot_colors[i % a_slot_colors.len()].clone()
+      };
+      if the_proximity_shading {
+        let a_proximity = the_game.get_proximity(i);
+        let a_white = model::Color::rgba(1., 1., 1., 1.);
+        a_slot_colr = a_slot_colr.mix(&a_white, PROXIMITY_BRIGHTNESS * a_proximity);
+      }
+      let a_left = x;
+      x += the_game.get_slots()[i].get_width() / slot_width_sum;
+      // collapsed slots would only make degenerate triangles
+      if x > a_left {
+        self.push_quad(a_left, x, 0., sl, &a_slot_colr);
+      }
+    }
+    self.its_slot_range = (
+      self.its_background_range.1,
+      self.get_vertex_count() - self.its_background_range.1,
+    );
+    // create obstacle vertices, each colored by its color group. Those of
+    // the outer ring come first so that the inner ones are drawn on top.
+    x = 0.;
+    let a_black = model::Color::rgba(0., 0., 0., 1.);
+    for (a_slot_idx, slot) in the_game.get_slots().iter().enumerate() {
+      let slot_width = slot.get_width() / slot_width_sum;
+      if slot_width <= 0. {
+        continue;
+      }
+      let a_outer = slot
+        .get_obstacles()
+        .iter()
+        .filter(|the_obstacle| the_obstacle.is_outer_ring());
+      let a_inner = slot
+        .get_obstacles()
+        .iter()
+        .filter(|the_obstacle| !the_obstacle.is_outer_ring());
+      for obstacle in a_outer.chain(a_inner) {
+        let near = obstacle.get_distance().max(0.);
+        let far = (obstacle.get_distance() + obstacle.get_height()).min(sl);
+        if near >= far {
+          continue;
+        }
+        let mut a_obst_colr = the_style
+          .get_obstacle_color(obstacle.get_color_group())
+          .clone();
+        if obstacle.is_outer_ring() {
+          a_obst_colr = a_obst_colr.mix(&a_black, OUTER_RING_DIM);
+        }
+        // walls with a gap span the rest of the ring, which may wrap
+        // around past the end of the last slot
+        if let Some(a_gap) = obstacle.get_gap() {
+          let a_center = the_game.get_gap_center(a_slot_idx, a_gap, obstacle.get_distance());
+          let a_start = (a_center + a_gap.get_width() / 2.).rem_euclid(1.);
+          let a_end = a_start + 1. - a_gap.get_width();
+          self.push_quad(a_start, a_end.min(1.), near, far, &a_obst_colr);
+          if a_end > 1. {
+            self.push_quad(0., a_end - 1., near, far, &a_obst_colr);
+          }
+          continue;
+        }
+        let (a_left, a_right) = obstacle.get_span();
+        let left = x + a_left * slot_width;
+        let span_width = (a_right - a_left) * slot_width;
+        match obstacle.get_shape() {
+          Some(a_shape) => {
+            for a_triangle in a_shape.triangulate() {
+              for a_idx in &a_triangle {
+                let [a_x, a_y] = a_shape.get_point(*a_idx);
+                let a_y = obstacle.get_distance() + a_y * obstacle.get_height();
+                self.push_vertex(left + a_x * span_width, a_y.max(0.).min(sl), &a_obst_colr);
+              }
+            }
+          }
+          None => self.push_quad(left, left + span_width, near, far, &a_obst_colr),
+        }
+      }
+      // pickups as fans in the middle of their slot, in the cursor's
+      // color so that they stand out from the obstacles
+      let a_pickup_w = slot_width * PICKUP_WIDTH;
+      let a_pickup_left = x + (slot_width - a_pickup_w) / 2.;
+      for a_pickup in slot.get_pickups() {
+        let a_outline = get_pickup_outline(a_pickup.get_power_up());
+        let a_point = |the_idx: usize| {
+          let [a_x, a_y] = a_outline[the_idx];
+          (
+            a_pickup_left + a_x * a_pickup_w,
+            (a_pickup.get_distance() + a_y * constants::PICKUP_H)
+              .max(0.)
+              .min(sl),
+          )
+        };
+        for a_idx in 1..a_outline.len() - 1 {
+          for (a_x, a_y) in &[a_point(0), a_point(a_idx), a_point(a_idx + 1)] {
+            self.push_vertex(*a_x, *a_y, the_style.get_cursor_color());
+          }
+        }
+      }
+      x += slot_width;
+    }
+    // create outer hexagon ring vertices
+    self.push_outer_ring(the_style);
+    // create inner hexagon vertices
+    let a_ih_colr = the_style.get_inner_hexagon_color();
+    for i in 0..6 {
+      self.push_vertex(0., 0., a_ih_colr);
+      self.push_vertex(i as f32 / 6., constants::INNER_HEXAGON_Y, a_ih_colr);
+      self.push_vertex(
+        ((i + 1) as f32 / 6.).fract(),
+        constants::INNER_HEXAGON_Y,
+        a_ih_colr,
+      );
+    }
+    let a_shape_start = self.its_slot_range.0 + self.its_slot_range.1;
+    self.its_shape_range = (a_shape_start, self.get_vertex_count() - a_shape_start);
+    // cursor coordinates
+    let a_position = the_cursor_position;
+    let c_width = constants::CURSOR_W * the_game.get_cursor_scale();
+    let c_height = constants::CURSOR_H * the_game.get_cursor_scale();
+    let c_left = a_position - c_width / 2.;
+    let c_right = a_position + c_width / 2.;
+    let c_top = constants::CURSOR_Y + c_height;
+    // create cursorShadow vertices followed by the cursor vertices
+    self.its_cursor_offset = self.get_vertex_count();
+    for a_colr in &[
+      the_style.get_cursor_shadow_color(),
+      the_style.get_cursor_color(),
+    ] {
+      match the_cursor_shape {
+        Some(a_shape) => {
+          for a_triangle in a_shape.triangulate() {
+            for a_idx in &a_triangle {
+              let [a_x, a_y] = a_shape.get_point(*a_idx);
+              self.push_vertex(
+                c_left + a_x * c_width,
+                constants::CURSOR_Y + a_y * c_height,
+                a_colr,
+              );
+            }
+          }
+        }
+        None => {
+          self.push_vertex(c_left, constants::CURSOR_Y, a_colr);
+          self.push_vertex(c_right, constants::CURSOR_Y, a_colr);
+          self.push_vertex(a_position, c_top, a_colr);
+        }
+      }
+    }
+    self.its_cursor_vertices = (self.get_vertex_count() - self.its_cursor_offset) / 2;
+    // create vignette ring vertices, from the innermost ring outwards
+    self.its_vignette_offset = self.get_vertex_count();
+    let a_black = model::Color::rgba(0., 0., 0., 1.);
+    let a_ring_height = (VIGNETTE_OUTER_Y - VIGNETTE_INNER_Y) / VIGNETTE_RINGS as f32;
+    for r in 0..VIGNETTE_RINGS {
+      let a_inner = VIGNETTE_INNER_Y + r as f32 * a_ring_height;
+      for i in 0..VIGNETTE_SEGMENTS + 1 {
+        let a_x = (i as f32 / VIGNETTE_SEGMENTS as f32).fract();
+        self.push_vertex(a_x, a_inner, &a_black);
+        self.push_vertex(a_x, a_inner + a_ring_height, &a_black);
+      }
+    }
+  }
+
+  /// Append `the_background` as triangles: its rays, or a single disc
+  /// without them, followed by the stars
+  fn push_background(&mut self, the_background: &model::Background) -> () {
+    let a_near = the_background.get_color().clone();
+    let a_far = the_background
+      .get_gradient_color()
+      .unwrap_or(the_background.get_color())
+      .clone();
+    let a_black = model::Color::rgba(0., 0., 0., 1.);
+    // a disc still needs a few pieces, a single quad would be a line
+    let a_pieces = the_background.get_rays().max(6);
+    for i in 0..a_pieces {
+      let a_dark = the_background.get_rays() > 0 && i % 2 == 1;
+      let a_contrast = if a_dark {
+        the_background.get_ray_contrast()
+      } else {
+        0.
+      };
+      let a_near = a_near.mix(&a_black, a_contrast);
+      let a_far = a_far.mix(&a_black, a_contrast);
+      let a_left = i as f32 / a_pieces as f32;
+      let a_right = (i + 1) as f32 / a_pieces as f32;
+      self.push_vertex(a_left, 0., &a_near);
+      self.push_vertex(a_left, BACKGROUND_DISTANCE, &a_far);
+      self.push_vertex(a_right, 0., &a_near);
+      self.push_vertex(a_right, 0., &a_near);
+      self.push_vertex(a_left, BACKGROUND_DISTANCE, &a_far);
+      self.push_vertex(a_right, BACKGROUND_DISTANCE, &a_far);
+    }
+    let mut a_rng = Rng::new(STAR_SEED);
+    let a_star_color = the_background.get_star_color().clone();
+    for _ in 0..the_background.get_stars() {
+      let a_x = a_rng.next_f32();
+      let a_y = constants::INNER_HEXAGON_Y
+        + a_rng.next_f32() * (BACKGROUND_DISTANCE - constants::INNER_HEXAGON_Y);
+      // x is an angle, so stars farther out need less of it
+      let a_width = STAR_SIZE / (2. * std::f32::consts::PI * a_y);
+      self.push_quad(a_x, a_x + a_width, a_y, a_y + STAR_SIZE, &a_star_color);
+    }
+  }
+
+  /// Append the ring around the inner hexagon as triangles, split into
+  /// dashes if the style asks for it
+  fn push_outer_ring(&mut self, the_style: &model::Style) -> () {
+    let a_colr = the_style.get_outer_hexagon_color();
+    let a_inner = constants::INNER_HEXAGON_Y;
+    let a_outer = a_inner + the_style.get_outer_hexagon_width();
+    let a_dashes = the_style.get_outer_hexagon_dashes();
+    // A solid edge is a single piece, a dashed one has gaps in between
+    let a_pieces = if a_dashes == 0 { 1 } else { 2 * a_dashes };
+    let a_edge = 1. / 6.;
+    // The shader places vertices on a circle, points along an edge need
+    // to be pulled in to keep it straight
+    let a_edge_radius = |the_x: f32, the_y: f32| {
+      let a_center = ((the_x / a_edge).floor() + 0.5) * a_edge;
+      let a_angle = (the_x - a_center) * 2. * std::f32::consts::PI;
+      the_y * (std::f32::consts::PI / 6.).cos() / a_angle.cos()
+    };
+    for e in 0..6 {
+      for p in (0..a_pieces).step_by(if a_dashes == 0 { 1 } else { 2 }) {
+        let a_x0 = (e as f32 + p as f32 / a_pieces as f32) * a_edge;
+        let a_x1 = (e as f32 + (p + 1) as f32 / a_pieces as f32) * a_edge;
+        // keep the end on the same edge as the start
+        let a_x1_edge = a_x1 - 0.0001;
+        let a_in0 = a_edge_radius(a_x0, a_inner);
+        let a_out0 = a_edge_radius(a_x0, a_outer);
+        let a_in1 = a_edge_radius(a_x1_edge, a_inner);
+        let a_out1 = a_edge_radius(a_x1_edge, a_outer);
+        self.push_vertex(a_x0, a_in0, a_colr);
+        self.push_vertex(a_x0, a_out0, a_colr);
+        self.push_vertex(a_x1, a_in1, a_colr);
+        self.push_vertex(a_x1, a_in1, a_colr);
+        self.push_vertex(a_x0, a_out0, a_colr);
+        self.push_vertex(a_x1, a_out1, a_colr);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Geometry, Vertex, VIGNETTE_RINGS, VIGNETTE_SEGMENTS};
+  use crate::model::{Color, GameState, Obstacle};
+
+  fn build(the_game: &GameState) -> Geometry {
+    let mut a_geometry = Geometry::new();
+    a_geometry.build(
+      the_game,
+      the_game.get_style(),
+      the_game.get_position(),
+      None,
+      false,
+    );
+    a_geometry
+  }
+
+  #[test]
+  fn vertices_are_packed_floats() {
+    let a_color = Color::rgba(0.25, 0.5, 0.75, 1.);
+    let a_vertices = [
+      Vertex::new(0.1, 0.2, &a_color),
+      Vertex::new(0.3, 0.4, &a_color),
+    ];
+    let a_bytes = super::as_bytes(&a_vertices);
+    assert_eq!(a_bytes.len(), 2 * 5 * std::mem::size_of::<f32>());
+    let a_float = |the_offset: usize| {
+      let mut a_raw = [0; 4];
+      a_raw.copy_from_slice(&a_bytes[the_offset..the_offset + 4]);
+      f32::from_ne_bytes(a_raw)
+    };
+    assert_eq!(a_float(std::mem::size_of::<Vertex>()), 0.3);
+    assert_eq!(a_float(Vertex::COLOR_OFFSET + 4), 0.5);
+  }
+
+  #[test]
+  fn ranges_cover_all_vertices() {
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    a_game.add_obstacle(2, Obstacle::new_at(0.5, 0.1));
+    let a_geometry = build(&a_game);
+    let (a_bg_start, a_bg_count) = a_geometry.get_background_range();
+    let (a_slot_start, a_slot_count) = a_geometry.get_slot_range();
+    let (a_shape_start, a_shape_count) = a_geometry.get_shape_range();
+    assert_eq!(a_bg_start, 0);
+    assert_eq!(a_slot_start, a_bg_count);
+    assert_eq!(a_slot_count, 6 * 6);
+    assert_eq!(a_shape_start, a_slot_start + a_slot_count);
+    assert_eq!(
+      a_geometry.get_cursor_offset(),
+      a_shape_start + a_shape_count
+    );
+    assert_eq!(a_geometry.get_cursor_vertices(), 3);
+    assert_eq!(
+      a_geometry.get_vignette_offset(),
+      a_geometry.get_cursor_offset() + 2 * a_geometry.get_cursor_vertices()
+    );
+    assert_eq!(
+      a_geometry.get_vertex_count(),
+      a_geometry.get_vignette_offset() + VIGNETTE_RINGS * 2 * (VIGNETTE_SEGMENTS + 1)
+    );
+  }
+
+  #[test]
+  fn obstacles_are_cut_off_at_the_despawn_distance() {
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    let a_empty = build(&a_game).get_shape_range().1;
+    let a_far = a_game.get_despawn_distance();
+    a_game.add_obstacle(0, Obstacle::new_at(a_far + 0.1, 0.1));
+    assert_eq!(build(&a_game).get_shape_range().1, a_empty);
+    a_game.add_obstacle(1, Obstacle::new_at(a_far - 0.05, 0.1));
+    let a_geometry = build(&a_game);
+    let (a_start, a_count) = a_geometry.get_shape_range();
+    assert_eq!(a_count, a_empty + 6);
+    for a_vertex in &a_geometry.get_data()[a_start..a_start + a_count] {
+      assert!(a_vertex.y <= a_far);
+    }
+  }
+}
@@ -0,0 +1,148 @@
+use super::headless;
+use super::model::{GameState, Obstacle};
+use super::png;
+use super::renderer::{OGLRenderer, Renderer};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How much a channel may differ from the reference before the pixel counts
+/// as different, to allow for rounding differences between drivers
+const CHANNEL_TOLERANCE: u8 = 8;
+/// Fraction of pixels that may differ, for edges that drivers rasterize
+/// slightly differently
+const MAX_DIFFERING_PIXELS: f32 = 0.002;
+/// Set this environment variable to write the current output as the new
+/// reference images instead of comparing against them
+const BLESS_VAR: &str = "HEXAGON_BLESS";
+
+struct Case {
+    name: &'static str,
+    width: u32,
+    height: u32,
+    game: GameState,
+}
+
+fn empty_field() -> GameState {
+    let mut a_game = GameState::new();
+    a_game.restart(0);
+    a_game
+}
+
+fn dense_obstacles() -> GameState {
+    let mut a_game = empty_field();
+    for a_slot in 0..a_game.get_slots().len() {
+        for a_wave in 0..6 {
+            if (a_slot + a_wave) % 3 != 0 {
+                let a_distance = 0.1 + a_wave as f32 * 0.15;
+                a_game.add_obstacle(a_slot, Obstacle::new_at(a_distance, 0.08));
+            }
+        }
+    }
+    a_game
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        Case {
+            name: "empty_field",
+            width: 256,
+            height: 256,
+            game: empty_field(),
+        },
+        Case {
+            name: "dense_obstacles",
+            width: 256,
+            height: 256,
+            game: dense_obstacles(),
+        },
+        Case {
+            name: "tiny_window",
+            width: 16,
+            height: 16,
+            game: dense_obstacles(),
+        },
+        Case {
+            name: "extreme_aspect",
+            width: 512,
+            height: 64,
+            game: dense_obstacles(),
+        },
+    ]
+}
+
+fn reference_path(the_name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(format!("{}.png", the_name))
+}
+
+/// Number of pixels of `the_actual` that differ from `the_expected` by more
+/// than the tolerance in any channel
+fn count_differing_pixels(the_actual: &[u8], the_expected: &[u8]) -> usize {
+    the_actual
+        .chunks(4)
+        .zip(the_expected.chunks(4))
+        .filter(|(the_a, the_b)| {
+            the_a.iter().zip(the_b.iter()).any(|(the_x, the_y)| {
+                (*the_x as i16 - *the_y as i16).abs() > CHANNEL_TOLERANCE as i16
+            })
+        })
+        .count()
+}
+
+/// Compare `the_pixels` of `the_case` against its reference image. Returns
+/// what went wrong, if anything.
+fn check(the_case: &Case, the_pixels: &[u8]) -> Result<(), String> {
+    let a_path = reference_path(the_case.name);
+    let a_png = png::encode(the_case.width, the_case.height, the_pixels);
+    if std::env::var_os(BLESS_VAR).is_some() {
+        std::fs::create_dir_all(a_path.parent().unwrap()).map_err(|the_err| the_err.to_string())?;
+        return std::fs::write(&a_path, &a_png).map_err(|the_err| the_err.to_string());
+    }
+    let a_reference = std::fs::read(&a_path)
+        .map_err(|the_err| format!("cannot read {}: {}", a_path.display(), the_err))?;
+    let (a_width, a_height, a_expected) = png::decode(&a_reference)?;
+    if (a_width, a_height) != (the_case.width, the_case.height) {
+        return Err(format!("reference is {}x{}", a_width, a_height));
+    }
+    let a_differing = count_differing_pixels(the_pixels, &a_expected);
+    let a_allowed = (a_expected.len() / 4) as f32 * MAX_DIFFERING_PIXELS;
+    if a_differing as f32 > a_allowed {
+        let a_actual_path = std::env::temp_dir().join(format!("{}.actual.png", the_case.name));
+        let _ = std::fs::write(&a_actual_path, &a_png);
+        return Err(format!(
+            "{} pixels differ, output written to {}",
+            a_differing,
+            a_actual_path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Renders every case with a headless GL context, so it needs a GPU or a
+/// software rasterizer and is not run by default. Run it with
+/// `cargo test -- --ignored` and write missing or outdated references by
+/// setting `HEXAGON_BLESS`.
+#[test]
+#[ignore]
+fn renders_like_the_references() {
+    let a_event_loop = headless::create_event_loop();
+    let mut a_failures = Vec::new();
+    for a_case in cases() {
+        let a_context = headless::create_context(&a_event_loop, a_case.width, a_case.height)
+            .expect("Failed to create a headless GL context");
+        let mut a_renderer =
+            OGLRenderer::new(&a_case.game, &a_context, a_case.width, a_case.height);
+        a_renderer.resize(a_case.width, a_case.height);
+        a_renderer.render(
+            &a_case.game,
+            a_case.game.get_style(),
+            Duration::from_millis(16),
+        );
+        let a_pixels = headless::read_pixels(a_case.width, a_case.height);
+        if let Err(a_err) = check(&a_case, &a_pixels) {
+            a_failures.push(format!("{}: {}", a_case.name, a_err));
+        }
+    }
+    assert!(a_failures.is_empty(), "{}", a_failures.join("\n"));
+}
@@ -0,0 +1,52 @@
+use glutin::dpi::PhysicalSize;
+use glutin::event_loop::EventLoop;
+use glutin::{Context, ContextBuilder, PossiblyCurrent};
+
+/// An event loop to create headless contexts with. It may be created off the
+/// main thread, e.g. by tests.
+pub fn create_event_loop() -> EventLoop<()> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let a_event_loop: EventLoop<()> = glutin::platform::unix::EventLoopExtUnix::new_any_thread();
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let a_event_loop: EventLoop<()> = EventLoop::new();
+    a_event_loop
+}
+
+/// A current GL context without a window whose default framebuffer is
+/// `the_width` by `the_height` pixels. It needs a GPU or a software
+/// rasterizer.
+pub fn create_context(
+    the_event_loop: &EventLoop<()>,
+    the_width: u32,
+    the_height: u32,
+) -> Result<Context<PossiblyCurrent>, String> {
+    let a_size = PhysicalSize::new(the_width, the_height);
+    let a_context = ContextBuilder::new()
+        .build_headless(the_event_loop, a_size)
+        .map_err(|the_err| format!("cannot create a headless GL context: {}", the_err))?;
+    unsafe { a_context.make_current() }.map_err(|(_, the_err)| the_err.to_string())
+}
+
+/// The framebuffer's contents as RGBA rows from top to bottom
+pub fn read_pixels(the_width: u32, the_height: u32) -> Vec<u8> {
+    let a_stride = the_width as usize * 4;
+    let mut a_pixels = vec![0u8; a_stride * the_height as usize];
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            the_width as i32,
+            the_height as i32,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            a_pixels.as_mut_ptr() as *mut _,
+        );
+    }
+    // GL starts at the bottom row
+    let mut a_flipped = Vec::with_capacity(a_pixels.len());
+    for a_row in a_pixels.chunks(a_stride).rev() {
+        a_flipped.extend_from_slice(a_row);
+    }
+    a_flipped
+}
@@ -0,0 +1,129 @@
+use super::model::{GameState, STAGES};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Columns of a heatmap, one per slot
+pub const HEATMAP_SLOTS: usize = 6;
+/// Rows of a heatmap, one before the first stage and one per stage
+pub const HEATMAP_ROWS: usize = STAGES.len() + 1;
+/// Most deaths kept per level, older ones are dropped
+const MAX_DEATHS: usize = 1000;
+
+/// Where and when a run ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Death {
+  /// Position of the cursor in turns
+  pub position: f32,
+  pub slot: usize,
+  /// Run time in seconds
+  pub time: f32,
+  /// How many of the `STAGES` the run had reached
+  #[serde(default)]
+  pub stage: usize,
+}
+
+/// How often the player has died in each slot during each stage
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Heatmap {
+  /// Indexed by row, then slot
+  pub counts: [[u32; HEATMAP_SLOTS]; HEATMAP_ROWS],
+}
+
+impl Heatmap {
+  pub fn add(&mut self, the_death: &Death) -> () {
+    let a_row = the_death.stage.min(HEATMAP_ROWS - 1);
+    let a_slot = the_death.slot.min(HEATMAP_SLOTS - 1);
+    self.counts[a_row][a_slot] += 1;
+  }
+  /// The count of the deadliest cell
+  pub fn get_max(&self) -> u32 {
+    self
+      .counts
+      .iter()
+      .flat_map(|the_row| the_row.iter())
+      .cloned()
+      .max()
+      .unwrap_or(0)
+  }
+}
+
+/// The deaths of all levels that have been played, keyed by pack and level
+#[derive(Serialize, Deserialize, Default)]
+pub struct DeathLog {
+  levels: BTreeMap<String, Vec<Death>>,
+}
+
+impl DeathLog {
+  /// Load the deaths from `the_path`, starting over if there are none or
+  /// they cannot be read
+  pub fn load(the_path: &Path) -> DeathLog {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_log) => a_log,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed death log {}: {}",
+            the_path.display(),
+            a_err
+          );
+          DeathLog::default()
+        }
+      },
+      Err(_) => DeathLog::default(),
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  /// Note where the run of `the_game` in the level `the_key` has just ended
+  pub fn record(&mut self, the_key: &str, the_game: &GameState) -> () {
+    let a_deaths = self.levels.entry(the_key.to_string()).or_default();
+    a_deaths.push(Death {
+      position: the_game.get_position(),
+      slot: the_game.get_current_slot_idx(),
+      time: the_game.get_run().get_time().as_secs_f32(),
+      stage: the_game.get_run().get_splits().len(),
+    });
+    if a_deaths.len() > MAX_DEATHS {
+      let a_excess = a_deaths.len() - MAX_DEATHS;
+      a_deaths.drain(..a_excess);
+    }
+  }
+  /// All deaths in the level `the_key` counted by slot and stage
+  pub fn get_heatmap(&self, the_key: &str) -> Heatmap {
+    let mut a_heatmap = Heatmap::default();
+    for a_death in self.levels.get(the_key).into_iter().flatten() {
+      a_heatmap.add(a_death);
+    }
+    a_heatmap
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Death, Heatmap};
+
+  #[test]
+  fn counts_deaths_by_stage_and_slot() {
+    let mut a_heatmap = Heatmap::default();
+    let a_death = |the_slot, the_stage| Death {
+      position: 0.,
+      slot: the_slot,
+      time: 0.,
+      stage: the_stage,
+    };
+    a_heatmap.add(&a_death(2, 0));
+    a_heatmap.add(&a_death(2, 0));
+    a_heatmap.add(&a_death(0, 1));
+    a_heatmap.add(&a_death(9, 5));
+    assert_eq!(a_heatmap.counts[0][2], 2);
+    assert_eq!(a_heatmap.counts[1][0], 1);
+    assert_eq!(a_heatmap.counts[5][5], 1);
+    assert_eq!(a_heatmap.get_max(), 2);
+  }
+}
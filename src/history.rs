@@ -0,0 +1,147 @@
+use super::model::GameState;
+use super::summary;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Most runs kept, older ones are dropped
+const MAX_ENTRIES: usize = 10000;
+
+/// A run that has ended
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+  /// When the run ended, in seconds since the epoch
+  pub timestamp: u64,
+  /// Pack and level, as in the records
+  pub level: String,
+  pub seed: u64,
+  /// Survived time in seconds
+  pub duration: f32,
+  /// What ended the run
+  pub cause: String,
+}
+
+/// All runs played, oldest first
+#[derive(Serialize, Deserialize, Default)]
+pub struct RunHistory {
+  runs: Vec<HistoryEntry>,
+}
+
+/// `the_field` quoted for CSV if it contains anything that needs it
+fn escape_csv(the_field: &str) -> String {
+  if the_field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", the_field.replace('"', "\"\""))
+  } else {
+    the_field.to_string()
+  }
+}
+
+/// `the_secs` since the epoch as an ISO 8601 UTC date and time
+fn format_timestamp(the_secs: u64) -> String {
+  let a_secs_of_day = the_secs % 86400;
+  format!(
+    "{}T{:02}:{:02}:{:02}Z",
+    summary::format_day((the_secs / 86400) as i64),
+    a_secs_of_day / 3600,
+    a_secs_of_day % 3600 / 60,
+    a_secs_of_day % 60
+  )
+}
+
+impl RunHistory {
+  /// Load the history from `the_path`, starting over if there is none or it
+  /// cannot be read
+  pub fn load(the_path: &Path) -> RunHistory {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_history) => a_history,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed run history {}: {}",
+            the_path.display(),
+            a_err
+          );
+          RunHistory::default()
+        }
+      },
+      Err(_) => RunHistory::default(),
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  /// Note that the run of `the_game` in the level `the_key` has just ended
+  pub fn add(&mut self, the_key: &str, the_game: &GameState) -> () {
+    let a_timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|the_duration| the_duration.as_secs())
+      .unwrap_or(0);
+    self.runs.push(HistoryEntry {
+      timestamp: a_timestamp,
+      level: the_key.to_string(),
+      seed: the_game.get_run().get_seed(),
+      duration: the_game.get_run().get_time().as_secs_f32(),
+      cause: format!("obstacle in slot {}", the_game.get_current_slot_idx()),
+    });
+    if self.runs.len() > MAX_ENTRIES {
+      let a_excess = self.runs.len() - MAX_ENTRIES;
+      self.runs.drain(..a_excess);
+    }
+  }
+  pub fn get_run_count(&self) -> usize {
+    self.runs.len()
+  }
+  /// One line per run after a header, with the timestamps in ISO 8601
+  pub fn to_csv(&self) -> String {
+    let mut a_csv = String::from("timestamp,level,seed,duration,cause\n");
+    for a_entry in &self.runs {
+      a_csv += &format!(
+        "{},{},{},{:.3},{}\n",
+        format_timestamp(a_entry.timestamp),
+        escape_csv(&a_entry.level),
+        a_entry.seed,
+        a_entry.duration,
+        escape_csv(&a_entry.cause)
+      );
+    }
+    a_csv
+  }
+  /// Write the history to `the_path` as CSV or JSON, depending on its
+  /// extension
+  pub fn export(&self, the_path: &Path) -> Result<(), String> {
+    let a_data = match the_path.extension().and_then(|the_ext| the_ext.to_str()) {
+      Some("csv") => self.to_csv().into_bytes(),
+      Some("json") => {
+        serde_json::to_vec_pretty(&self.runs).map_err(|the_err| the_err.to_string())?
+      }
+      _ => return Err("the file has to end in .csv or .json".to_string()),
+    };
+    std::fs::write(the_path, a_data).map_err(|the_err| the_err.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{HistoryEntry, RunHistory};
+
+  #[test]
+  fn exports_csv() {
+    let mut a_history = RunHistory::default();
+    a_history.runs.push(HistoryEntry {
+      timestamp: 86400 + 3661,
+      level: "My \"Pack\"/A, B".to_string(),
+      seed: 42,
+      duration: 12.5,
+      cause: "obstacle in slot 3".to_string(),
+    });
+    assert_eq!(
+      a_history.to_csv(),
+      "timestamp,level,seed,duration,cause\n\
+       1970-01-02T01:01:01Z,\"My \"\"Pack\"\"/A, B\",42,12.500,obstacle in slot 3\n"
+    );
+  }
+}
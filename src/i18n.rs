@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Language whose texts stand in for the ones a catalog is missing
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// Catalogs that ship with the game, by language code
+const BUILT_IN: [(&str, &str); 2] = [
+  ("en", include_str!("../lang/en.json")),
+  ("de", include_str!("../lang/de.json")),
+];
+
+/// The user-facing texts of one language, keyed like `hud.time`. Texts may
+/// contain placeholders such as `{time}` that `format` fills in.
+///
+/// Translations are plain JSON objects from key to text, see `lang/en.json`
+/// for all keys. Files named after a language code in `get_user_dir` add
+/// languages or replace single texts of the built-in ones.
+#[derive(Clone)]
+pub struct Catalog {
+  its_language: String,
+  its_texts: BTreeMap<String, String>,
+}
+
+fn parse(the_json: &[u8]) -> Result<BTreeMap<String, String>, serde_json::Error> {
+  serde_json::from_slice(the_json)
+}
+
+/// The language of the user's locale, e.g. `de` for `de_DE.UTF-8`
+fn get_system_language() -> Option<String> {
+  ["LC_ALL", "LC_MESSAGES", "LANG"]
+    .iter()
+    .filter_map(|the_var| std::env::var(the_var).ok())
+    .find(|the_value| !the_value.is_empty())
+    .map(|the_value| {
+      the_value
+        .split(['_', '.', '@'])
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+    })
+    .filter(|the_language| !the_language.is_empty() && the_language != "c")
+}
+
+impl Catalog {
+  /// The texts of `the_language`, or of the user's locale if it is `None`.
+  /// Texts that are missing in the language are taken from the fallback
+  /// language.
+  pub fn load(the_language: Option<&str>) -> Catalog {
+    let a_language = match the_language {
+      Some(a_language) => a_language.to_string(),
+      None => get_system_language().unwrap_or_else(|| FALLBACK_LANGUAGE.to_string()),
+    };
+    let mut a_catalog = Catalog {
+      its_language: a_language.clone(),
+      ..Catalog::default()
+    };
+    if a_language == FALLBACK_LANGUAGE {
+      a_catalog.add_user_texts();
+      return a_catalog;
+    }
+    let mut a_found = false;
+    if let Some((_, a_json)) = BUILT_IN
+      .iter()
+      .find(|(the_code, _)| *the_code == a_language)
+    {
+      a_catalog
+        .its_texts
+        .extend(parse(a_json.as_bytes()).unwrap());
+      a_found = true;
+    }
+    a_found |= a_catalog.add_user_texts();
+    if !a_found {
+      eprintln!(
+        "No translation for language {}, using {}",
+        a_language, FALLBACK_LANGUAGE
+      );
+    }
+    a_catalog
+  }
+  /// Where translations by the user are looked up, next to the settings
+  pub fn get_user_dir() -> PathBuf {
+    super::settings::Settings::default_path()
+      .parent()
+      .unwrap_or_else(|| Path::new("."))
+      .join("lang")
+  }
+  /// Take over the texts of the user's file for the language, if there is
+  /// one. Returns whether there was.
+  fn add_user_texts(&mut self) -> bool {
+    let a_path = Catalog::get_user_dir().join(format!("{}.json", self.its_language));
+    let a_data = match std::fs::read(&a_path) {
+      Ok(a_data) => a_data,
+      Err(_) => return false,
+    };
+    match parse(&a_data) {
+      Ok(a_texts) => {
+        self.its_texts.extend(a_texts);
+        true
+      }
+      Err(a_err) => {
+        eprintln!(
+          "Ignoring malformed translation {}: {}",
+          a_path.display(),
+          a_err
+        );
+        false
+      }
+    }
+  }
+  /// The text for `the_key`, or the key itself if no catalog has it
+  pub fn get<'a>(&'a self, the_key: &'a str) -> &'a str {
+    self
+      .its_texts
+      .get(the_key)
+      .map(|the_text| the_text.as_str())
+      .unwrap_or(the_key)
+  }
+  /// The text for `the_key` with every `{name}` of `the_args` replaced
+  pub fn format(&self, the_key: &str, the_args: &[(&str, &dyn Display)]) -> String {
+    let mut a_text = self.get(the_key).to_string();
+    for (a_name, a_value) in the_args {
+      a_text = a_text.replace(&format!("{{{}}}", a_name), &a_value.to_string());
+    }
+    a_text
+  }
+}
+
+/// The built-in texts of the fallback language
+impl Default for Catalog {
+  fn default() -> Catalog {
+    Catalog {
+      its_language: FALLBACK_LANGUAGE.to_string(),
+      its_texts: parse(BUILT_IN[0].1.as_bytes()).unwrap(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{parse, Catalog, BUILT_IN};
+
+  #[test]
+  fn built_in_catalogs_have_every_text() {
+    let a_keys: Vec<String> = parse(BUILT_IN[0].1.as_bytes())
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect();
+    for (a_code, a_json) in BUILT_IN.iter() {
+      let a_texts = parse(a_json.as_bytes()).expect(a_code);
+      for a_key in &a_keys {
+        assert!(a_texts.contains_key(a_key), "{} lacks {}", a_code, a_key);
+      }
+    }
+  }
+
+  #[test]
+  fn fills_in_placeholders() {
+    let a_catalog = Catalog::default();
+    assert_eq!(
+      a_catalog.format("hud.time", &[("time", &"1.500")]),
+      "Time: 1.500s"
+    );
+    assert_eq!(a_catalog.get("no.such.key"), "no.such.key");
+  }
+}
@@ -0,0 +1,698 @@
+use super::constants;
+use super::model::{Background, Color, Gap, PowerUp, Pulse, SpeedCurve, Style};
+use super::offscreen::PostEffects;
+use super::shape::Shape;
+use super::spawner::SpawnStrategy;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+fn default_player_speed() -> f32 {
+  0.03
+}
+fn default_obstacle_speed() -> f32 {
+  0.005
+}
+fn default_outer_ring_speed() -> f32 {
+  constants::OUTER_RING_SPEED
+}
+fn default_spawn_distance() -> f32 {
+  constants::SPAWN_DISTANCE
+}
+fn default_gap_width() -> f32 {
+  1. / 6.
+}
+fn default_difficulty_ramp() -> f32 {
+  20.
+}
+fn default_hyper_after() -> f32 {
+  60.
+}
+fn default_palette_fade() -> f32 {
+  1.
+}
+fn default_weight() -> f32 {
+  1.
+}
+fn default_thickness() -> f32 {
+  0.08
+}
+fn default_spacing() -> f32 {
+  0.25
+}
+fn default_ray_contrast() -> f32 {
+  0.3
+}
+fn default_star_color() -> ColorDef {
+  ColorDef(Color::rgba(1., 1., 1., 1.))
+}
+fn default_slot_opacity() -> f32 {
+  0.6
+}
+
+const MIN_THICKNESS: f32 = 0.01;
+const MIN_SPACING_FACTOR: f32 = 0.2;
+
+/// A single wall of a pattern
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WallDef {
+  /// Slot index relative to the (random) slot the pattern is spawned at
+  pub slot: usize,
+  /// How much farther out than the pattern's start the wall begins
+  #[serde(default)]
+  pub offset: f32,
+  /// Radial thickness, defaults to the pattern's thickness
+  #[serde(default)]
+  pub height: Option<f32>,
+  /// Color group relative to the one of the wave the wall is spawned with
+  #[serde(default)]
+  pub color_group: usize,
+  /// Outline as a list of `[x, y]` points, x across the slot and y from
+  /// the wall's near to its far edge, both from 0 to 1. The wall fills its
+  /// whole slot without one.
+  #[serde(default)]
+  pub shape: Option<Shape>,
+  /// Approach from the outer ring, which starts farther out and moves at
+  /// the level's `outer_ring_speed`
+  #[serde(default)]
+  pub outer_ring: bool,
+  /// Emerge from under the inner hexagon and move outward, the later the
+  /// greater the offset
+  #[serde(default)]
+  pub reversed: bool,
+  /// Span the whole ring but for this gap
+  #[serde(default)]
+  pub gap: Option<GapDef>,
+}
+
+/// Opening of a wall that spans the whole ring. It is centered on the
+/// wall's slot once the wall reaches the center.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GapDef {
+  /// Share of the ring that is open
+  #[serde(default = "default_gap_width")]
+  pub width: f32,
+  /// Turns the gap makes while the wall travels a distance of 1, towards
+  /// the right if positive
+  #[serde(default)]
+  pub spin: f32,
+}
+
+impl Default for GapDef {
+  fn default() -> GapDef {
+    GapDef {
+      width: default_gap_width(),
+      spin: 0.,
+    }
+  }
+}
+
+impl GapDef {
+  /// The gap, turning the other way if its pattern is `the_mirrored`
+  pub fn to_gap(&self, the_mirrored: bool) -> Gap {
+    let a_spin = if the_mirrored { -self.spin } else { self.spin };
+    Gap::new(self.width, a_spin)
+  }
+}
+
+/// A formation of walls that the spawner places as a whole
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PatternDef {
+  pub name: String,
+  /// Patterns only appear once the difficulty ramp has reached this value
+  #[serde(default)]
+  pub difficulty: f32,
+  /// Relative likelihood of being picked among the eligible patterns
+  #[serde(default = "default_weight")]
+  pub weight: f32,
+  /// Thickness of walls that do not specify their own height
+  #[serde(default = "default_thickness")]
+  pub thickness: f32,
+  /// Radial gap to the next pattern
+  #[serde(default = "default_spacing")]
+  pub spacing: f32,
+  /// Relative change of the thickness per difficulty step beyond the
+  /// pattern's own difficulty, e.g. -0.1 makes walls 10% thinner per step
+  #[serde(default)]
+  pub thickness_scaling: f32,
+  /// Relative change of all distances between waves (wall offsets and the
+  /// gap to the next pattern) per difficulty step
+  #[serde(default)]
+  pub spacing_scaling: f32,
+  pub walls: Vec<WallDef>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub pickups: Vec<PickupDef>,
+  /// Makes the pattern's walls swell on every beat of the level's `bpm`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub pulse: Option<PulseDef>,
+}
+
+/// How the walls of a pattern swell on every beat and shrink back until the
+/// next one
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PulseDef {
+  /// Share of their thickness the walls grow by on the beat
+  #[serde(default)]
+  pub height: f32,
+  /// Share of their slot's width the walls leave out right before the beat
+  #[serde(default)]
+  pub width: f32,
+}
+
+impl PulseDef {
+  pub fn to_pulse(&self) -> Pulse {
+    Pulse::new(self.height, self.width)
+  }
+}
+
+/// A pickup that is spawned along with the walls of a pattern
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PickupDef {
+  /// Slot index relative to the (random) slot the pattern is spawned at
+  pub slot: usize,
+  /// How much farther out than the pattern's start the pickup begins
+  #[serde(default)]
+  pub offset: f32,
+  pub power_up: PowerUp,
+}
+
+impl PatternDef {
+  fn get_excess_difficulty(&self, the_difficulty: f32) -> f32 {
+    (the_difficulty - self.difficulty).max(0.)
+  }
+  pub fn get_thickness_at(&self, the_difficulty: f32) -> f32 {
+    let a_scale = 1. + self.thickness_scaling * self.get_excess_difficulty(the_difficulty);
+    (self.thickness * a_scale).max(MIN_THICKNESS)
+  }
+  fn get_spacing_factor_at(&self, the_difficulty: f32) -> f32 {
+    let a_scale = 1. + self.spacing_scaling * self.get_excess_difficulty(the_difficulty);
+    a_scale.max(MIN_SPACING_FACTOR)
+  }
+  /// Radial gap to the next pattern at the given difficulty
+  pub fn get_spacing_at(&self, the_difficulty: f32) -> f32 {
+    self.spacing * self.get_spacing_factor_at(the_difficulty)
+  }
+  /// Offset and height of `the_wall` at the given difficulty
+  pub fn get_wall_at(&self, the_wall: &WallDef, the_difficulty: f32) -> (f32, f32) {
+    let a_height = match the_wall.height {
+      Some(a_height) => a_height,
+      None => self.get_thickness_at(the_difficulty),
+    };
+    (
+      the_wall.offset * self.get_spacing_factor_at(the_difficulty),
+      a_height,
+    )
+  }
+  /// Offset of `the_pickup` at the given difficulty
+  pub fn get_pickup_offset_at(&self, the_pickup: &PickupDef, the_difficulty: f32) -> f32 {
+    the_pickup.offset * self.get_spacing_factor_at(the_difficulty)
+  }
+  /// Radial extent of the pattern, i.e. where its farthest wall ends
+  pub fn get_depth_at(&self, the_difficulty: f32) -> f32 {
+    self.walls.iter().fold(0., |the_acc, the_wall| {
+      let (a_offset, a_height) = self.get_wall_at(the_wall, the_difficulty);
+      the_acc.max(a_offset + a_height)
+    })
+  }
+}
+
+pub fn to_colors(the_colors: &[ColorDef]) -> Vec<Color> {
+  the_colors
+    .iter()
+    .map(|the_color| the_color.0.clone())
+    .collect()
+}
+
+/// Colors and camera of a level. Everything that is not set is taken from
+/// the `base` style if there is one, and from the game's default style
+/// otherwise. Style files, e.g. the themes exported by the theme editor,
+/// hold one of these.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct StyleDef {
+  /// Style file to inherit from. In a level file it is relative to the pack
+  /// directory, in a style file relative to the style file itself, so
+  /// that bases can be shared between packs. Resolved by the loader.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub base: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cursor_color: Option<ColorDef>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cursor_shadow_color: Option<ColorDef>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub inner_hexagon_color: Option<ColorDef>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub outer_hexagon_color: Option<ColorDef>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub slot_colors: Option<Vec<ColorDef>>,
+  /// One color per color group
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub obstacle_colors: Option<Vec<ColorDef>>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub zoom: Option<f32>,
+  /// Position of the camera, it looks from there at `look_at`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub eye: Option<[f32; 2]>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub look_at: Option<[f32; 2]>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub background: Option<BackgroundDef>,
+}
+
+/// Layer drawn behind the slots, see `Background`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BackgroundDef {
+  pub color: ColorDef,
+  /// Number of rays around the center, none for a single color
+  #[serde(default)]
+  pub rays: u32,
+  /// How much darker every other ray is, from 0 to 1
+  #[serde(default = "default_ray_contrast")]
+  pub ray_contrast: f32,
+  /// Color the background fades to towards the edge
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub gradient: Option<ColorDef>,
+  #[serde(default)]
+  pub stars: u32,
+  #[serde(default = "default_star_color")]
+  pub star_color: ColorDef,
+  /// How much the slots in front of the background cover it, from 0 to 1
+  #[serde(default = "default_slot_opacity")]
+  pub slot_opacity: f32,
+}
+
+impl BackgroundDef {
+  pub fn capture(the_background: &Background) -> BackgroundDef {
+    BackgroundDef {
+      color: ColorDef(the_background.get_color().clone()),
+      rays: the_background.get_rays(),
+      ray_contrast: the_background.get_ray_contrast(),
+      gradient: the_background.get_gradient_color().cloned().map(ColorDef),
+      stars: the_background.get_stars(),
+      star_color: ColorDef(the_background.get_star_color().clone()),
+      slot_opacity: the_background.get_slot_opacity(),
+    }
+  }
+  pub fn to_background(&self) -> Background {
+    let mut a_background = Background::new(self.color.0.clone());
+    a_background.set_rays(self.rays, self.ray_contrast);
+    a_background.set_gradient_color(self.gradient.as_ref().map(|the_color| the_color.0.clone()));
+    a_background.set_stars(self.stars, self.star_color.0.clone());
+    a_background.set_slot_opacity(self.slot_opacity);
+    a_background
+  }
+}
+
+impl StyleDef {
+  pub fn from_json(the_json: &[u8]) -> Result<StyleDef, serde_json::Error> {
+    serde_json::from_slice(the_json)
+  }
+  /// Every field of `the_style`, e.g. for writing it to a style file
+  pub fn capture(the_style: &Style) -> StyleDef {
+    let a_def = |the_colors: &Vec<Color>| Some(the_colors.iter().cloned().map(ColorDef).collect());
+    StyleDef {
+      base: None,
+      cursor_color: Some(ColorDef(the_style.get_cursor_color().clone())),
+      cursor_shadow_color: Some(ColorDef(the_style.get_cursor_shadow_color().clone())),
+      inner_hexagon_color: Some(ColorDef(the_style.get_inner_hexagon_color().clone())),
+      outer_hexagon_color: Some(ColorDef(the_style.get_outer_hexagon_color().clone())),
+      slot_colors: a_def(the_style.get_slot_colors()),
+      obstacle_colors: a_def(the_style.get_obstacle_colors()),
+      zoom: Some(the_style.get_zoom()),
+      eye: Some([the_style.get_eye().x, the_style.get_eye().y]),
+      look_at: Some([the_style.get_look_at().x, the_style.get_look_at().y]),
+      background: the_style.get_background().map(BackgroundDef::capture),
+    }
+  }
+  /// Take over every field this style does not set from `the_base`,
+  /// including the base's own base
+  pub fn inherit(&mut self, the_base: StyleDef) -> () {
+    self.base = the_base.base;
+    self.cursor_color = self.cursor_color.take().or(the_base.cursor_color);
+    self.cursor_shadow_color = self
+      .cursor_shadow_color
+      .take()
+      .or(the_base.cursor_shadow_color);
+    self.inner_hexagon_color = self
+      .inner_hexagon_color
+      .take()
+      .or(the_base.inner_hexagon_color);
+    self.outer_hexagon_color = self
+      .outer_hexagon_color
+      .take()
+      .or(the_base.outer_hexagon_color);
+    self.slot_colors = self.slot_colors.take().or(the_base.slot_colors);
+    self.obstacle_colors = self.obstacle_colors.take().or(the_base.obstacle_colors);
+    self.zoom = self.zoom.or(the_base.zoom);
+    self.eye = self.eye.or(the_base.eye);
+    self.look_at = self.look_at.or(the_base.look_at);
+    self.background = self.background.take().or(the_base.background);
+  }
+  /// Override the fields of `the_style` that this style sets
+  pub fn apply_to(&self, the_style: &mut Style) -> () {
+    if let Some(a_color) = &self.cursor_color {
+      the_style.set_cursor_color(a_color.0.clone());
+    }
+    if let Some(a_color) = &self.cursor_shadow_color {
+      the_style.set_cursor_shadow_color(a_color.0.clone());
+    }
+    if let Some(a_color) = &self.inner_hexagon_color {
+      the_style.set_inner_hexagon_color(a_color.0.clone());
+    }
+    if let Some(a_color) = &self.outer_hexagon_color {
+      the_style.set_outer_hexagon_color(a_color.0.clone());
+    }
+    if let Some(a_colors) = &self.slot_colors {
+      the_style.set_slot_colors(to_colors(a_colors));
+    }
+    if let Some(a_colors) = &self.obstacle_colors {
+      the_style.set_obstacle_colors(to_colors(a_colors));
+    }
+    if let Some(a_zoom) = self.zoom {
+      the_style.set_zoom(a_zoom);
+    }
+    if let Some([a_x, a_y]) = self.eye {
+      the_style.set_eye(a_x, a_y);
+    }
+    if let Some([a_x, a_y]) = self.look_at {
+      the_style.set_look_at(a_x, a_y);
+    }
+    if let Some(a_background) = &self.background {
+      the_style.set_background(Some(a_background.to_background()));
+    }
+  }
+}
+
+/// A single level as described by a level file inside a pack
+#[derive(Deserialize, Clone)]
+pub struct LevelDef {
+  pub name: String,
+  /// Speed of the cursor in turns per reference frame, unless the level
+  /// gives a `player_speed_curve`
+  #[serde(default = "default_player_speed")]
+  pub player_speed: f32,
+  /// How the cursor speeds up while a direction is held, replaces
+  /// `player_speed`
+  #[serde(default)]
+  pub player_speed_curve: Option<SpeedCurve>,
+  #[serde(default = "default_obstacle_speed")]
+  pub obstacle_speed: f32,
+  /// Speed of obstacles of the outer ring relative to `obstacle_speed`
+  #[serde(default = "default_outer_ring_speed")]
+  pub outer_ring_speed: f32,
+  /// In turns per second, negative values rotate counter-clockwise
+  #[serde(default)]
+  pub rotation_speed: f32,
+  /// Distance from the center at which new obstacles appear. The outer
+  /// ring's appear farther out by the same ratio as by default.
+  #[serde(default = "default_spawn_distance")]
+  pub spawn_distance: f32,
+  /// Distance from the center at which obstacles moving outward vanish and
+  /// up to which the playfield is drawn, `spawn_distance` if unset
+  #[serde(default)]
+  pub despawn_distance: Option<f32>,
+  /// Path of the music track relative to the pack directory
+  #[serde(default)]
+  pub music: Option<String>,
+  /// TrueType font file relative to the pack directory for all text, the
+  /// built-in pixel font is used if it is not given or cannot be loaded
+  #[serde(default)]
+  pub font: Option<String>,
+  /// Seconds of survival it takes the difficulty to rise by one
+  #[serde(default = "default_difficulty_ramp")]
+  pub difficulty_ramp: f32,
+  /// Patterns to spawn, the built-in ones are used if there are none
+  #[serde(default)]
+  pub patterns: Vec<PatternDef>,
+  /// Files relative to the pack directory that contain one pattern each,
+  /// e.g. those exported from the pattern editor
+  #[serde(default)]
+  pub pattern_files: Vec<String>,
+  #[serde(default)]
+  pub spawn_strategy: SpawnStrategy,
+  /// Tempo of the music, needed for events that are placed on beats
+  #[serde(default)]
+  pub bpm: Option<f32>,
+  /// Scripted changes during a run, see `EventDef`
+  #[serde(default)]
+  pub events: Vec<EventDef>,
+  /// Level file relative to the pack directory that the level turns into
+  /// once the player has survived for `hyper_after` seconds
+  #[serde(default)]
+  pub hyper_level: Option<String>,
+  #[serde(default = "default_hyper_after")]
+  pub hyper_after: f32,
+  /// The level referenced by `hyper_level`, filled in by the loader
+  #[serde(skip)]
+  pub hyper: Option<Box<LevelDef>>,
+  #[serde(default)]
+  pub style: StyleDef,
+  /// Show every color of the style inverted while the level is played
+  #[serde(default)]
+  pub invert_colors: bool,
+  /// Seconds it takes the colors to fade to the ones of this level when it
+  /// takes over during a run, e.g. as a hyper level
+  #[serde(default = "default_palette_fade")]
+  pub palette_fade: f32,
+  #[serde(default)]
+  pub post_effects: PostEffects,
+  /// GLSL ES 1.0 fragment shader file relative to the pack directory that
+  /// replaces the flat colors of the slots. See the renderer for the
+  /// uniforms that are available.
+  #[serde(default)]
+  pub fragment_shader: Option<String>,
+  /// The contents of `fragment_shader`, filled in by the loader
+  #[serde(skip)]
+  pub fragment_shader_source: Option<String>,
+  /// Thickness of the ring around the center, the style's default if unset
+  #[serde(default)]
+  pub outer_hexagon_width: Option<f32>,
+  /// Split every edge of the ring into this many dashes, 0 for a solid ring
+  #[serde(default)]
+  pub outer_hexagon_dashes: u32,
+  /// Let all colors cycle through the hues at this many degrees per second
+  #[serde(default)]
+  pub hue_speed: f32,
+  /// Look of the cursor, the plain triangle if unset
+  #[serde(default)]
+  pub cursor: Option<CursorDef>,
+  /// Survive for a fixed time instead of as long as possible
+  #[serde(default)]
+  pub goal: Option<GoalDef>,
+}
+
+/// Point in run time given either in seconds or as a `"m:ss"` clock string
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum Timestamp {
+  Seconds(f32),
+  Clock(String),
+}
+
+impl Timestamp {
+  pub fn to_seconds(&self) -> Option<f32> {
+    match self {
+      Timestamp::Seconds(a_seconds) => Some(*a_seconds),
+      Timestamp::Clock(a_clock) => {
+        let mut a_parts = a_clock.rsplitn(2, ':');
+        let a_seconds = a_parts.next()?.trim().parse::<f32>().ok()?;
+        let a_minutes = match a_parts.next() {
+          Some(a_minutes) => a_minutes.trim().parse::<f32>().ok()?,
+          None => 0.,
+        };
+        Some(a_minutes * 60. + a_seconds)
+      }
+    }
+  }
+}
+
+/// A color in a level file. Either `[r, g, b]` or `[r, g, b, a]` with
+/// components from 0 to 1, a hex string like `"#ff00aa"` or a name like
+/// `"orange"`.
+#[derive(Clone, Debug)]
+pub struct ColorDef(pub Color);
+
+/// Written as `[r, g, b, a]`
+impl Serialize for ColorDef {
+  fn serialize<S: Serializer>(&self, the_serializer: S) -> Result<S::Ok, S::Error> {
+    let a_color = &self.0;
+    [a_color.its_r, a_color.its_g, a_color.its_b, a_color.its_a].serialize(the_serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for ColorDef {
+  fn deserialize<D: Deserializer<'de>>(the_deserializer: D) -> Result<ColorDef, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawColor {
+      Components(Vec<f32>),
+      Text(String),
+    }
+    match RawColor::deserialize(the_deserializer)? {
+      RawColor::Components(a_c) => match a_c.len() {
+        3 => Ok(ColorDef(Color::rgba(a_c[0], a_c[1], a_c[2], 1.))),
+        4 => Ok(ColorDef(Color::rgba(a_c[0], a_c[1], a_c[2], a_c[3]))),
+        a_len => Err(de::Error::custom(format!(
+          "expected 3 or 4 color components, got {}",
+          a_len
+        ))),
+      },
+      RawColor::Text(a_text) => Color::parse(&a_text)
+        .map(ColorDef)
+        .map_err(de::Error::custom),
+    }
+  }
+}
+
+/// Look of the cursor. Only what is drawn changes, collisions are still
+/// checked against the plain triangle so that skins cannot make a level
+/// easier.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CursorDef {
+  /// Outline in cursor-local coordinates: x runs across the cursor's width
+  /// from 0 to 1 and y from its base (0) to its tip (1)
+  pub shape: Shape,
+  /// Colors instead of the ones of the style, which follow the palette
+  #[serde(default)]
+  pub color: Option<ColorDef>,
+  #[serde(default)]
+  pub shadow_color: Option<ColorDef>,
+}
+
+/// Goal of a time attack: the run is complete once it has lasted
+/// `duration` seconds. Medals are awarded for the time survived, whether the
+/// run has been completed or not, e.g. `{"bronze": 20, "gold": 60}`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct GoalDef {
+  pub duration: f32,
+  #[serde(default)]
+  pub medals: BTreeMap<Medal, f32>,
+}
+
+impl GoalDef {
+  /// How long the run has to last, none if `duration` is no usable time
+  pub fn get_duration(&self) -> Option<Duration> {
+    if self.duration.is_finite() && self.duration > 0. {
+      Some(Duration::from_secs_f32(self.duration))
+    } else {
+      None
+    }
+  }
+  /// The best medal for surviving `the_time`
+  pub fn get_medal(&self, the_time: Duration) -> Option<Medal> {
+    let a_time = the_time.as_secs_f32();
+    self
+      .medals
+      .iter()
+      .filter(|(_, the_threshold)| a_time >= **the_threshold)
+      .map(|(the_medal, _)| *the_medal)
+      .max()
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Medal {
+  Bronze,
+  Silver,
+  Gold,
+}
+
+impl Medal {
+  /// Key of the medal's name in the catalog
+  pub fn get_key(&self) -> &'static str {
+    match self {
+      Medal::Bronze => "medal.bronze",
+      Medal::Silver => "medal.silver",
+      Medal::Gold => "medal.gold",
+    }
+  }
+}
+
+/// What a level event changes
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EventAction {
+  /// In turns per second
+  RotationSpeed {
+    value: f32,
+  },
+  ObstacleSpeed {
+    value: f32,
+  },
+  OuterHexagonWidth {
+    value: f32,
+  },
+  PlayerSpeed {
+    value: f32,
+  },
+  /// Relative to the other slots, a width of 0 makes the slot collapse
+  SlotWidth {
+    slot: usize,
+    value: f32,
+  },
+  SlotColors {
+    colors: Vec<ColorDef>,
+  },
+  /// One color per color group
+  ObstacleColors {
+    colors: Vec<ColorDef>,
+  },
+  Flash,
+  /// Halts the run and shows `text`, which is looked up in the catalog,
+  /// until the player steers as asked, e.g. in a tutorial
+  Prompt {
+    text: String,
+    input: PromptInput,
+  },
+}
+
+/// How the player has to steer to get past a prompt
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptInput {
+  Left,
+  Right,
+  /// Either direction
+  Any,
+}
+
+/// A change that happens once a run has lasted until `time` (or `beat`).
+/// Numeric changes and color swaps are eased in over `duration` seconds.
+#[derive(Deserialize, Clone, Debug)]
+pub struct EventDef {
+  #[serde(default)]
+  pub time: Option<Timestamp>,
+  /// Counted from the start of the run, requires the level's `bpm`
+  #[serde(default)]
+  pub beat: Option<f32>,
+  #[serde(default)]
+  pub duration: f32,
+  #[serde(flatten)]
+  pub action: EventAction,
+}
+
+impl EventDef {
+  /// Run time in seconds at which the event fires, `None` if it cannot be
+  /// determined
+  pub fn get_seconds(&self, the_bpm: Option<f32>) -> Option<f32> {
+    match (&self.time, self.beat) {
+      (Some(a_time), _) => a_time.to_seconds(),
+      (None, Some(a_beat)) => the_bpm
+        .filter(|the_bpm| *the_bpm > 0.)
+        .map(|the_bpm| a_beat * 60. / the_bpm),
+      (None, None) => Some(0.),
+    }
+  }
+}
+
+impl PatternDef {
+  pub fn from_json(the_json: &[u8]) -> Result<PatternDef, serde_json::Error> {
+    serde_json::from_slice(the_json)
+  }
+}
+
+impl LevelDef {
+  pub fn from_json(the_json: &[u8]) -> Result<LevelDef, serde_json::Error> {
+    serde_json::from_slice(the_json)
+  }
+}
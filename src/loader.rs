@@ -0,0 +1,207 @@
+use super::level::{LevelDef, PatternDef, StyleDef};
+use super::pack::{self, LoadError, Pack, PackManifest};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+/// Size of the blocks in which assets are read, i.e. the granularity of
+/// progress updates
+const CHUNK_SIZE: usize = 64 * 1024;
+
+enum LoadEvent {
+  Progress(u64, u64, String),
+  Done(Result<Pack, LoadError>),
+}
+
+/// Loads a pack on a background thread so that the window stays responsive.
+/// Call `poll` once per tick to receive progress and the final result.
+pub struct PackLoader {
+  its_dir: PathBuf,
+  its_receiver: Receiver<LoadEvent>,
+  its_bytes_done: u64,
+  its_bytes_total: u64,
+  its_current_file: String,
+}
+
+impl PackLoader {
+  pub fn spawn(the_dir: PathBuf) -> PackLoader {
+    let (a_sender, a_receiver) = channel();
+    let a_dir = the_dir.clone();
+    std::thread::spawn(move || {
+      let a_result = load_pack(&a_dir, &a_sender);
+      // The receiving end may already be gone if loading was cancelled
+      let _ = a_sender.send(LoadEvent::Done(a_result));
+    });
+    PackLoader {
+      its_dir: the_dir,
+      its_receiver: a_receiver,
+      its_bytes_done: 0,
+      its_bytes_total: 0,
+      its_current_file: String::new(),
+    }
+  }
+  pub fn get_dir(&self) -> &Path {
+    &self.its_dir
+  }
+  /// Loading progress in [0, 1]
+  pub fn get_progress(&self) -> f32 {
+    if self.its_bytes_total == 0 {
+      0.
+    } else {
+      self.its_bytes_done as f32 / self.its_bytes_total as f32
+    }
+  }
+  pub fn get_current_file(&self) -> &str {
+    &self.its_current_file
+  }
+  /// Process all events sent by the loader thread so far. Returns the
+  /// loaded pack (or the error that stopped loading) once it is finished.
+  pub fn poll(&mut self) -> Option<Result<Pack, LoadError>> {
+    loop {
+      match self.its_receiver.try_recv() {
+        Ok(LoadEvent::Progress(the_done, the_total, the_file)) => {
+          self.its_bytes_done = the_done;
+          self.its_bytes_total = the_total;
+          self.its_current_file = the_file;
+        }
+        Ok(LoadEvent::Done(the_result)) => return Some(the_result),
+        Err(TryRecvError::Empty) => return None,
+        Err(TryRecvError::Disconnected) => return Some(Err(LoadError::Disconnected)),
+      }
+    }
+  }
+}
+
+fn read_file(the_path: &Path) -> Result<Vec<u8>, LoadError> {
+  std::fs::read(the_path).map_err(|the_err| LoadError::Io(the_path.to_path_buf(), the_err))
+}
+
+/// Merge the chain of base styles into `the_style`, whose base is relative
+/// to `the_dir`
+fn resolve_style(the_dir: &Path, the_style: &mut StyleDef) -> Result<(), LoadError> {
+  let mut a_dir = the_dir.to_path_buf();
+  let mut a_visited: Vec<PathBuf> = Vec::new();
+  while let Some(a_base) = the_style.base.take() {
+    let a_path = a_dir.join(&a_base);
+    // compare canonical paths, the same file can be reached in many ways
+    let a_canonical =
+      std::fs::canonicalize(&a_path).map_err(|the_err| LoadError::Io(a_path.clone(), the_err))?;
+    if a_visited.contains(&a_canonical) {
+      return Err(LoadError::StyleCycle(a_path));
+    }
+    let a_base_style = StyleDef::from_json(&read_file(&a_path)?)
+      .map_err(|the_err| LoadError::Parse(a_path.clone(), the_err))?;
+    the_style.inherit(a_base_style);
+    a_dir = a_canonical
+      .parent()
+      .map_or_else(PathBuf::new, |the_parent| the_parent.to_path_buf());
+    a_visited.push(a_canonical);
+  }
+  Ok(())
+}
+
+/// Load the style file at `the_path` with all of its bases
+pub fn load_style(the_path: &Path) -> Result<StyleDef, LoadError> {
+  let mut a_style = StyleDef::from_json(&read_file(the_path)?)
+    .map_err(|the_err| LoadError::Parse(the_path.to_path_buf(), the_err))?;
+  let a_dir = the_path.parent().unwrap_or_else(|| Path::new(""));
+  resolve_style(a_dir, &mut a_style)?;
+  Ok(a_style)
+}
+
+/// Load a level file together with its pattern files and, if `the_with_hyper`
+/// is set, its hyper level. Hyper levels cannot have hyper levels themselves.
+fn load_level(the_dir: &Path, the_file: &str, the_with_hyper: bool) -> Result<LevelDef, LoadError> {
+  let a_path = the_dir.join(the_file);
+  let mut a_level = LevelDef::from_json(&read_file(&a_path)?)
+    .map_err(|the_err| LoadError::Parse(a_path, the_err))?;
+  for a_pattern_file in &a_level.pattern_files {
+    let a_path = the_dir.join(a_pattern_file);
+    let a_pattern = PatternDef::from_json(&read_file(&a_path)?)
+      .map_err(|the_err| LoadError::Parse(a_path, the_err))?;
+    a_level.patterns.push(a_pattern);
+  }
+  resolve_style(the_dir, &mut a_level.style)?;
+  if let Some(a_shader_file) = &a_level.fragment_shader {
+    let a_path = the_dir.join(a_shader_file);
+    let a_source = String::from_utf8(read_file(&a_path)?).map_err(|the_err| {
+      let a_err = std::io::Error::new(std::io::ErrorKind::InvalidData, the_err);
+      LoadError::Io(a_path.clone(), a_err)
+    })?;
+    a_level.fragment_shader_source = Some(a_source);
+  }
+  if the_with_hyper {
+    if let Some(a_hyper_file) = a_level.hyper_level.clone() {
+      a_level.hyper = Some(Box::new(load_level(the_dir, &a_hyper_file, false)?));
+    }
+  }
+  Ok(a_level)
+}
+
+fn load_pack(the_dir: &Path, the_sender: &Sender<LoadEvent>) -> Result<Pack, LoadError> {
+  // Manifest and level files are small, so they are read in one go
+  let a_manifest_path = the_dir.join(pack::MANIFEST_FILE);
+  let a_manifest: PackManifest = serde_json::from_slice(&read_file(&a_manifest_path)?)
+    .map_err(|the_err| LoadError::Parse(a_manifest_path, the_err))?;
+  let mut a_pack = Pack::new(a_manifest.name, the_dir.to_path_buf());
+  for a_level_file in &a_manifest.levels {
+    a_pack.add_level(load_level(the_dir, a_level_file, true)?);
+  }
+
+  // Music and other assets can be large, so we report progress per chunk
+  let a_levels: Vec<&LevelDef> = a_pack
+    .get_levels()
+    .iter()
+    .flat_map(|the_level| std::iter::once(the_level).chain(the_level.hyper.as_deref()))
+    .collect();
+  let mut a_assets: Vec<String> = a_levels
+    .iter()
+    .filter_map(|the_level| the_level.music.clone())
+    .collect();
+  // fonts fall back to the built-in one if they are missing
+  let mut a_optional: Vec<String> = a_levels
+    .iter()
+    .filter_map(|the_level| the_level.font.clone())
+    .collect();
+  a_optional.retain(|the_asset| {
+    let a_exists = the_dir.join(the_asset).is_file();
+    if !a_exists {
+      eprintln!("Pack asset {} is missing, using the default", the_asset);
+    }
+    a_exists
+  });
+  a_assets.append(&mut a_optional);
+  a_assets.sort();
+  a_assets.dedup();
+  let mut a_bytes_total = 0;
+  for a_asset in &a_assets {
+    let a_path = the_dir.join(a_asset);
+    let a_meta = std::fs::metadata(&a_path).map_err(|the_err| LoadError::Io(a_path, the_err))?;
+    a_bytes_total += a_meta.len();
+  }
+  let mut a_bytes_done = 0;
+  for a_asset in a_assets {
+    let a_path = the_dir.join(&a_asset);
+    let mut a_file =
+      std::fs::File::open(&a_path).map_err(|the_err| LoadError::Io(a_path.clone(), the_err))?;
+    let mut a_data = Vec::new();
+    let mut a_chunk = vec![0; CHUNK_SIZE];
+    loop {
+      let a_read = a_file
+        .read(&mut a_chunk)
+        .map_err(|the_err| LoadError::Io(a_path.clone(), the_err))?;
+      if a_read == 0 {
+        break;
+      }
+      a_data.extend_from_slice(&a_chunk[..a_read]);
+      a_bytes_done += a_read as u64;
+      let a_event = LoadEvent::Progress(a_bytes_done, a_bytes_total, a_asset.clone());
+      if the_sender.send(a_event).is_err() {
+        // Nobody is interested in the result anymore
+        return Err(LoadError::Disconnected);
+      }
+    }
+    a_pack.add_asset(a_asset, a_data);
+  }
+  Ok(a_pack)
+}
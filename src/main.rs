@@ -1,9 +1,59 @@
+mod accessibility;
+#[cfg(test)]
+mod alloc_counter;
 mod app;
+mod bot;
+mod checkpoint;
+mod clipboard;
+mod console;
 mod constants;
 mod controls;
+mod daily;
+mod debug_view;
+mod devices;
+mod editor;
+mod fixed;
+mod font;
+#[cfg(fuzzing)]
+pub mod fuzzing;
+mod geometry;
+#[cfg(test)]
+mod golden;
+mod headless;
+mod heatmap;
+mod history;
+mod i18n;
+mod level;
+mod loader;
 mod model;
+mod offscreen;
+mod overlay;
+mod pacing;
+mod pack;
+mod png;
+mod preview;
+mod race;
+mod records;
 mod renderer;
+mod replay;
+mod rng;
+#[cfg(test)]
+mod scenario;
+mod session_log;
+mod settings;
+mod shape;
+mod solvability;
+mod spawner;
+mod stats;
+mod stream;
+mod summary;
+mod theme;
+mod timeline;
+mod tutorial;
+mod video;
+mod window;
 
+use glutin::dpi::PhysicalSize;
 use glutin::event::{DeviceEvent, ElementState, Event, WindowEvent};
 use glutin::event_loop::{ControlFlow, EventLoop};
 use glutin::window::WindowBuilder;
@@ -11,42 +61,223 @@ use glutin::ContextBuilder;
 
 use renderer::Renderer;
 
+/// Size of the videos rendered from replays in pixels
+const RENDER_WIDTH: u32 = 1280;
+const RENDER_HEIGHT: u32 = 720;
+
+/// hexagon preview <pattern file or name> [output dir]
+fn run_preview(mut the_args: impl Iterator<Item = String>) -> i32 {
+    let a_pattern = match the_args.next() {
+        Some(a_arg) => match preview::load_pattern(&a_arg) {
+            Ok(a_pattern) => a_pattern,
+            Err(a_err) => {
+                eprintln!("Failed to load pattern: {}", a_err);
+                return 1;
+            }
+        },
+        None => {
+            eprintln!("preview needs a pattern file or the name of a built-in pattern");
+            return 2;
+        }
+    };
+    let a_dir = std::path::PathBuf::from(the_args.next().unwrap_or_else(|| "preview".to_string()));
+    match preview::render_approach(a_pattern, &a_dir) {
+        Ok(a_count) => {
+            println!("Rendered {} images to {}", a_count, a_dir.display());
+            0
+        }
+        Err(a_err) => {
+            eprintln!("Failed to render the preview: {}", a_err);
+            1
+        }
+    }
+}
+
+/// hexagon render <replay file> [output dir or video file]
+fn run_render(mut the_args: impl Iterator<Item = String>) -> i32 {
+    let a_replay = match the_args.next() {
+        Some(a_arg) => match replay::Replay::load(std::path::Path::new(&a_arg)) {
+            Ok(a_replay) => a_replay,
+            Err(a_err) => {
+                eprintln!("Failed to load replay: {}", a_err);
+                return 1;
+            }
+        },
+        None => {
+            eprintln!("render needs a replay file");
+            return 2;
+        }
+    };
+    let a_out = std::path::PathBuf::from(the_args.next().unwrap_or_else(|| "render".to_string()));
+    let a_event_loop = EventLoop::new();
+    let a_winbuilder = WindowBuilder::new()
+        .with_title(window::APP_NAME)
+        .with_visible(false)
+        .with_inner_size(PhysicalSize::new(RENDER_WIDTH, RENDER_HEIGHT));
+    let a_win_ctx = match ContextBuilder::new().build_windowed(a_winbuilder, &a_event_loop) {
+        Ok(a_win_ctx) => a_win_ctx,
+        Err(a_err) => {
+            eprintln!("Failed to create a window: {}", a_err);
+            return 1;
+        }
+    };
+    let a_win_ctx = unsafe { a_win_ctx.make_current().unwrap() };
+    let a_settings_path = settings::Settings::default_path();
+    let a_settings = settings::Settings::load(&a_settings_path);
+    let mut a_app = {
+        let a_game = model::GameState::new();
+        let a_renderer = renderer::OGLRenderer::new(&a_game, a_win_ctx.context(), 1, 1);
+        let a_controls = controls::Controls::new();
+        app::App::new(a_game, a_controls, a_renderer, a_settings, &a_settings_path)
+    };
+    match video::render_replay(&mut a_app, a_win_ctx.window(), a_replay, &a_out) {
+        Ok(a_count) => {
+            println!("Rendered {} frames to {}", a_count, a_out.display());
+            0
+        }
+        Err(a_err) => {
+            eprintln!("Failed to render the replay: {}", a_err);
+            1
+        }
+    }
+}
+
 fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("preview") => std::process::exit(run_preview(std::env::args().skip(2))),
+        Some("render") => std::process::exit(run_render(std::env::args().skip(2))),
+        _ => (),
+    }
     let a_event_loop = EventLoop::new();
-    let a_winbuilder = WindowBuilder::new().with_title("Libre Hexagon");
+    let a_settings_path = settings::Settings::default_path();
+    let a_settings = settings::Settings::load(&a_settings_path);
+    let mut a_winbuilder = WindowBuilder::new()
+        .with_title(window::APP_NAME)
+        .with_window_icon(window::create_icon());
+    if let Some(a_geometry) = &a_settings.window {
+        a_winbuilder = a_geometry.configure(a_winbuilder);
+    }
 
     let a_win_ctx = ContextBuilder::new()
         .build_windowed(a_winbuilder, &a_event_loop)
         .unwrap();
-    let a_win_ctx = unsafe { a_win_ctx.make_current().unwrap() };
+    // switched back to after drawing the debug view
+    let mut a_win_ctx =
+        debug_view::SwitchableContext::new(unsafe { a_win_ctx.make_current().unwrap() });
+    if let Some(a_geometry) = &a_settings.window {
+        a_geometry.restore(a_win_ctx.window());
+    }
 
     // We give an initial size of 1 by 1 because there will be a resize event anyways after window opening
     let mut a_app = {
         let a_game = model::GameState::new();
         let a_renderer = renderer::OGLRenderer::new(&a_game, &a_win_ctx.context(), 1, 1);
         let a_controls = controls::Controls::new();
-        app::App::new(a_game, a_controls, a_renderer)
+        app::App::new(a_game, a_controls, a_renderer, a_settings, &a_settings_path)
     };
+    if let Some(a_geometry) = a_app.get_settings().window.clone() {
+        a_app.apply_display_settings(a_win_ctx.window(), a_geometry.fullscreen);
+    }
+    // hexagon [pack dir] [--host <port> | --lockstep <port> | --join <host>:<port>]
+    let mut a_args = std::env::args().skip(1);
+    while let Some(a_arg) = a_args.next() {
+        let a_race = match a_arg.as_str() {
+            "--host" | "--lockstep" => {
+                match a_args.next().map(|the_port| the_port.parse::<u16>()) {
+                    Some(Ok(a_port)) => race::Race::host(a_port).map(|mut the_race| {
+                        // the guest learns from the host that rounds are played in lockstep
+                        the_race.set_lockstep(a_arg == "--lockstep");
+                        the_race
+                    }),
+                    _ => {
+                        eprintln!("{} needs a port", a_arg);
+                        continue;
+                    }
+                }
+            }
+            "--join" => match a_args.next() {
+                Some(a_address) => race::Race::join(&a_address),
+                None => {
+                    eprintln!("--join needs an address");
+                    continue;
+                }
+            },
+            _ => {
+                a_app.load_pack(std::path::PathBuf::from(a_arg));
+                continue;
+            }
+        };
+        match a_race {
+            Ok(a_race) => a_app.set_race(a_race),
+            Err(a_err) => eprintln!("Failed to set up the race: {}", a_err),
+        }
+    }
 
     let mut a_time_last_upd = std::time::Instant::now();
+    // Nothing is drawn while the window is minimized
+    let mut a_minimized = false;
+    let mut a_pacer = pacing::FramePacer::new(a_app.get_fps_limit());
+    let mut a_resize = pacing::ResizeDebouncer::new();
+    let mut a_devices = devices::DeviceTracker::new();
+    let mut a_debug_view: Option<debug_view::DebugView> = None;
 
-    a_event_loop.run(move |event, _, control_flow| {
+    a_event_loop.run(move |event, the_target, control_flow| {
         *control_flow = ControlFlow::Wait;
 
         let a_controls = a_app.get_controls();
+        let a_debug_window = a_debug_view
+            .as_ref()
+            .map(|the_view| the_view.get_window_id());
 
         match event {
             Event::LoopDestroyed => return,
+            Event::WindowEvent { window_id, event } if Some(window_id) == a_debug_window => {
+                match event {
+                    WindowEvent::Resized(the_size) => {
+                        if let Some(a_view) = &mut a_debug_view {
+                            a_view.resize(the_size);
+                        }
+                        a_win_ctx.make_current();
+                    }
+                    WindowEvent::CloseRequested => a_app.close_debug_view(),
+                    _ => (),
+                }
+            }
             Event::WindowEvent { event, .. } => {
                 // println!("{:?}", event);
                 match event {
                     WindowEvent::Resized(the_size) => {
+                        // Some platforms report minimized windows as empty
+                        let a_was_minimized = a_minimized;
+                        a_minimized = the_size.width == 0 || the_size.height == 0;
+                        if a_minimized {
+                            return;
+                        }
+                        if a_was_minimized {
+                            // Don't let the time spent minimized count as a
+                            // single frame
+                            a_time_last_upd = std::time::Instant::now();
+                            a_win_ctx.window().request_redraw();
+                        }
                         a_win_ctx.resize(the_size);
-                        a_app
-                            .get_renderer_mut()
-                            .resize(the_size.width, the_size.height);
+                        a_resize.resized(std::time::Instant::now());
+                        let a_renderer = a_app.get_renderer_mut();
+                        a_renderer.set_resizing(true);
+                        a_renderer.resize(the_size.width, the_size.height);
+                        // Some platforms run a loop of their own while the
+                        // window edge is dragged, which starves ours. Draw
+                        // every size right away instead of waiting for it.
+                        a_win_ctx.window().request_redraw();
+                    }
+                    WindowEvent::ReceivedCharacter(the_char) => {
+                        a_controls.char_typed(the_char);
+                    }
+                    WindowEvent::Focused(the_focused) => {
+                        a_app.focus_changed(the_focused);
+                        a_pacer.set_limit(a_app.get_fps_limit());
                     }
                     WindowEvent::CloseRequested => {
+                        a_app.remember_window(a_win_ctx.window());
                         *control_flow = ControlFlow::Exit;
                         return;
                     }
@@ -56,29 +287,76 @@ fn main() {
             Event::RedrawRequested(_) => {
                 let a_time_old = a_time_last_upd;
                 a_time_last_upd = std::time::Instant::now();
-                let a_delta = a_time_last_upd - a_time_old;
+                let a_delta = (a_time_last_upd - a_time_old).min(constants::MAX_FRAME_DELTA);
 
                 a_app.tick(a_win_ctx.window(), a_delta);
                 a_win_ctx.swap_buffers().unwrap();
-
-                a_win_ctx.window().request_redraw();
+                if let Some(a_view) = &mut a_debug_view {
+                    a_view.render(&a_app);
+                    a_win_ctx.make_current();
+                }
+                // the limit may have been changed during the tick
+                a_pacer.set_limit(a_app.get_fps_limit());
+                a_pacer.frame_drawn(std::time::Instant::now());
             }
-            Event::DeviceEvent { event, .. } => match event {
-                DeviceEvent::Key(the_input) => match the_input.state {
-                    ElementState::Pressed => a_controls.key_pressed(the_input.scancode),
-                    ElementState::Released => a_controls.key_released(the_input.scancode),
-                },
-                _ => (),
-            },
-            Event::MainEventsCleared => {
-                let a_time_frame_end = std::time::Instant::now();
-                let a_frame_duration = a_time_frame_end - a_time_last_upd;
-                let a_time_remaining =
-                    constants::TARGET_TICK_TIME - (a_frame_duration.as_micros() as f32 / 1000.);
-                if a_time_remaining > 0. {
-                    std::thread::sleep(std::time::Duration::from_micros(
-                        (a_time_remaining * 1000.) as u64,
-                    ));
+            Event::DeviceEvent { device_id, event } => {
+                let a_change = match event {
+                    DeviceEvent::Added => a_devices.added(device_id),
+                    DeviceEvent::Removed => a_devices.removed(device_id),
+                    DeviceEvent::Key(_) | DeviceEvent::Button { .. } => {
+                        a_devices.input_from(device_id)
+                    }
+                    _ => None,
+                };
+                if let Some(a_change) = a_change {
+                    a_app.device_changed(a_change);
+                }
+                if let DeviceEvent::Key(the_input) = event {
+                    let a_controls = a_app.get_controls();
+                    match the_input.state {
+                        ElementState::Pressed => a_controls.key_pressed(the_input.scancode),
+                        ElementState::Released => a_controls.key_released(the_input.scancode),
+                    }
+                }
+            }
+            Event::RedrawEventsCleared => {
+                match (a_app.is_debug_view_enabled(), &a_debug_view) {
+                    (true, None) => {
+                        match debug_view::DebugView::open(the_target) {
+                            Ok(a_view) => a_debug_view = Some(a_view),
+                            Err(a_err) => {
+                                eprintln!("Failed to open the debug view: {}", a_err);
+                                a_app.close_debug_view();
+                            }
+                        }
+                        a_win_ctx.make_current();
+                    }
+                    (false, Some(_)) => {
+                        a_debug_view = None;
+                        a_win_ctx.make_current();
+                    }
+                    _ => (),
+                }
+                // Stop the render loop while there is nothing to see, it is
+                // picked up again once the window is restored
+                if a_minimized {
+                    return;
+                }
+                let a_now = std::time::Instant::now();
+                if a_resize.poll(a_now) {
+                    a_app.get_renderer_mut().set_resizing(false);
+                    a_win_ctx.window().request_redraw();
+                }
+                let a_wait_until = match (a_pacer.get_wait_until(a_now), a_resize.get_deadline()) {
+                    (Some(a_frame), Some(a_settled)) => Some(a_frame.min(a_settled)),
+                    (a_frame, _) => a_frame,
+                };
+                match a_wait_until {
+                    Some(a_time) => *control_flow = ControlFlow::WaitUntil(a_time),
+                    None => {
+                        *control_flow = ControlFlow::Poll;
+                        a_win_ctx.window().request_redraw();
+                    }
                 }
             }
             _ => (),
@@ -1,30 +1,296 @@
 extern crate nalgebra_glm as glm;
+use super::constants;
+use super::fixed;
+use super::level::LevelDef;
+use super::shape::Shape;
 use glm::Vec2;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// Serializes glm vectors as plain arrays, nalgebra's own serde support is
+/// not enabled
+mod vec2_serde {
+  use super::Vec2;
+  use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+  pub fn serialize<S: Serializer>(the_vec: &Vec2, the_serializer: S) -> Result<S::Ok, S::Error> {
+    [the_vec.x, the_vec.y].serialize(the_serializer)
+  }
+  pub fn deserialize<'de, D: Deserializer<'de>>(the_deserializer: D) -> Result<Vec2, D::Error> {
+    let [a_x, a_y] = <[f32; 2]>::deserialize(the_deserializer)?;
+    Ok(Vec2::new(a_x, a_y))
+  }
+}
+
+/// Opening of a wall that spans the whole ring instead of its slot. The
+/// gap is centered on the wall's slot when the wall reaches the center and
+/// turns while the wall travels.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gap {
+  /// Share of the ring that is open
+  its_width: f32,
+  /// Turns the gap makes per distance the wall travels, towards greater
+  /// positions if positive
+  its_spin: f32,
+}
+
+impl Gap {
+  pub fn new(the_width: f32, the_spin: f32) -> Gap {
+    Gap {
+      its_width: the_width,
+      its_spin: the_spin,
+    }
+  }
+  pub fn get_width(&self) -> f32 {
+    self.its_width
+  }
+  pub fn get_spin(&self) -> f32 {
+    self.its_spin
+  }
+  /// Whether the ring position `the_position` is open if the gap is
+  /// centered on `the_center`
+  pub fn contains(&self, the_center: f32, the_position: f32) -> bool {
+    let a_offset = (the_position - the_center + 0.5).rem_euclid(1.) - 0.5;
+    a_offset.abs() < self.its_width / 2.
+  }
+}
+
+/// Tempo at which walls pulse in levels that do not give their own
+const DEFAULT_BPM: f32 = 120.;
+/// Distance from the cursor within which obstacles count as close, see
+/// `GameState::get_proximity`
+const PROXIMITY_RANGE: f32 = 0.5;
+
+/// Animation of a wall that swells on every beat and shrinks back until the
+/// next one
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pulse {
+  /// Share of its thickness the wall grows by on the beat
+  its_height: f32,
+  /// Share of its slot's width the wall leaves out right before the beat
+  its_width: f32,
+}
+
+impl Pulse {
+  pub fn new(the_height: f32, the_width: f32) -> Pulse {
+    Pulse {
+      its_height: the_height,
+      its_width: the_width.clamp(0., 1.),
+    }
+  }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Obstacle {
   its_distance: f32,
   its_height: f32,
+  /// Index into the style's obstacle colors (modulo their count)
+  its_color_group: usize,
+  /// Outline within the slot and the obstacle's radial extent, the whole
+  /// rectangle if there is none
+  #[serde(default)]
+  its_shape: Option<Shape>,
+  /// Approaches at the outer ring's speed, see `GameState::get_speed_factor`
+  #[serde(default)]
+  its_outer_ring: bool,
+  /// Moves away from the center instead of towards it
+  #[serde(default)]
+  its_reversed: bool,
+  /// Makes the obstacle span the whole ring but for this gap
+  #[serde(default)]
+  its_gap: Option<Gap>,
+  #[serde(default)]
+  its_pulse: Option<Pulse>,
+  /// How far the pulse has swelled the obstacle, 1 on the beat
+  #[serde(default)]
+  its_swell: f32,
 }
 
 impl Obstacle {
   pub fn new(the_height: f32) -> Obstacle {
+    Obstacle::new_at(0., the_height)
+  }
+  pub fn new_at(the_distance: f32, the_height: f32) -> Obstacle {
     Obstacle {
-      its_distance: 0.,
+      its_distance: the_distance,
       its_height: the_height,
+      its_color_group: 0,
+      its_shape: None,
+      its_outer_ring: false,
+      its_reversed: false,
+      its_gap: None,
+      its_pulse: None,
+      its_swell: 0.,
     }
   }
+  pub fn get_color_group(&self) -> usize {
+    self.its_color_group
+  }
+  pub fn set_color_group(&mut self, the_group: usize) -> () {
+    self.its_color_group = the_group;
+  }
+  /// Radial extent, including the swell of the pulse
   pub fn get_height(&self) -> f32 {
-    self.its_height
+    match &self.its_pulse {
+      Some(a_pulse) => self.its_height * (1. + a_pulse.its_height * self.its_swell),
+      None => self.its_height,
+    }
+  }
+  /// Left and right edge across the slot, from 0 to 1. Pulsing obstacles
+  /// leave out the sides of their slot between beats.
+  pub fn get_span(&self) -> (f32, f32) {
+    let a_margin = match &self.its_pulse {
+      Some(a_pulse) => a_pulse.its_width * (1. - self.its_swell) / 2.,
+      None => 0.,
+    };
+    (a_margin, 1. - a_margin)
+  }
+  pub fn set_pulse(&mut self, the_pulse: Option<Pulse>) -> () {
+    self.its_pulse = the_pulse;
+  }
+  /// Swell the obstacle as far as its pulse does at `the_swell`, from 0
+  /// right before a beat to 1 on it
+  pub fn set_swell(&mut self, the_swell: f32) -> () {
+    self.its_swell = the_swell;
+  }
+  pub fn get_distance(&self) -> f32 {
+    self.its_distance
+  }
+  pub fn set_distance(&mut self, the_distance: f32) -> () {
+    self.its_distance = the_distance;
+  }
+  pub fn get_shape(&self) -> Option<&Shape> {
+    self.its_shape.as_ref()
+  }
+  pub fn set_shape(&mut self, the_shape: Option<Shape>) -> () {
+    self.its_shape = the_shape;
+  }
+  pub fn is_outer_ring(&self) -> bool {
+    self.its_outer_ring
+  }
+  pub fn set_outer_ring(&mut self, the_outer_ring: bool) -> () {
+    self.its_outer_ring = the_outer_ring;
+  }
+  pub fn set_reversed(&mut self, the_reversed: bool) -> () {
+    self.its_reversed = the_reversed;
+  }
+  pub fn get_gap(&self) -> Option<&Gap> {
+    self.its_gap.as_ref()
+  }
+  pub fn set_gap(&mut self, the_gap: Option<Gap>) -> () {
+    self.its_gap = the_gap;
+  }
+  /// How much faster than the obstacle speed the obstacle approaches if the
+  /// outer ring moves at `the_outer_ring_speed`, negative if it moves away
+  fn get_speed_factor(&self, the_outer_ring_speed: f32) -> f32 {
+    let a_factor = if self.its_outer_ring {
+      the_outer_ring_speed
+    } else {
+      1.
+    };
+    if self.its_reversed {
+      -a_factor
+    } else {
+      a_factor
+    }
+  }
+  /// How far the obstacle is from reaching the cursor, none if it has
+  /// passed it. Reversed obstacles reach the cursor's base, the others its
+  /// tip.
+  pub fn get_clearance(&self) -> Option<f32> {
+    let a_cursor_tip = constants::CURSOR_Y + constants::CURSOR_H;
+    let a_far = self.its_distance + self.get_height();
+    match self.its_reversed {
+      true if self.its_distance <= a_cursor_tip => Some((constants::CURSOR_Y - a_far).max(0.)),
+      false if a_far > a_cursor_tip => Some((self.its_distance - a_cursor_tip).max(0.)),
+      _ => None,
+    }
+  }
+  /// Whether the obstacle's radial extent includes `the_y`, whatever its
+  /// shape
+  pub fn covers(&self, the_y: f32) -> bool {
+    self.its_distance <= the_y && self.its_distance + self.get_height() > the_y
+  }
+  /// Whether the obstacle covers the radial position `the_y` at `the_x`
+  /// across its slot, from 0 to 1. Obstacles with a gap do not count, they
+  /// are looked up by ring position, see `GameState::get_gap_center`.
+  pub fn covers_at(&self, the_x: f32, the_y: f32) -> bool {
+    let (a_left, a_right) = self.get_span();
+    if self.its_gap.is_some() || !self.covers(the_y) || the_x < a_left || the_x > a_right {
+      return false;
+    }
+    match &self.its_shape {
+      Some(a_shape) => a_shape.contains(
+        (the_x - a_left) / (a_right - a_left),
+        (the_y - self.its_distance) / self.get_height(),
+      ),
+      None => true,
+    }
+  }
+}
+
+/// Effect a pickup grants for `POWER_UP_DURATION` once it is collected
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerUp {
+  /// Obstacles approach at `SLOW_TIME_FACTOR` of their speed
+  SlowTime,
+  /// The cursor shrinks to `SHRINK_CURSOR_FACTOR` of its size, also when
+  /// checking for collisions
+  ShrinkCursor,
+  /// Dodged walls score `SCORE_MULTIPLIER` points each
+  ScoreMultiplier,
+}
+
+/// How long the effect of a pickup lasts, collecting it again while it is
+/// active starts over
+pub const POWER_UP_DURATION: Duration = Duration::from_secs(5);
+const SLOW_TIME_FACTOR: f32 = 0.5;
+const SHRINK_CURSOR_FACTOR: f32 = 0.6;
+const SCORE_MULTIPLIER: u32 = 2;
+/// Walls to pass in a row for each step up of the combo multiplier
+const COMBO_STEP: u32 = 10;
+const MAX_COMBO_MULTIPLIER: u32 = 4;
+/// How long the cursor may press against the side of a wall before its
+/// combo is lost
+const HUG_LIMIT: Duration = Duration::from_secs(1);
+/// Distance in turns beyond the corners of the cursor's base within which a
+/// wall counts as touched, as movement stops just short of it
+const HUG_MARGIN: f32 = 0.005;
+
+/// Something that rides toward the center like an obstacle but is
+/// collected instead of ending the run when the cursor touches it
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pickup {
+  its_distance: f32,
+  its_power_up: PowerUp,
+}
+
+impl Pickup {
+  pub fn new_at(the_distance: f32, the_power_up: PowerUp) -> Pickup {
+    Pickup {
+      its_distance: the_distance,
+      its_power_up: the_power_up,
+    }
   }
   pub fn get_distance(&self) -> f32 {
     self.its_distance
   }
+  pub fn get_power_up(&self) -> PowerUp {
+    self.its_power_up
+  }
+  /// Whether the pickup's radial extent overlaps `the_near` to `the_far`
+  fn overlaps(&self, the_near: f32, the_far: f32) -> bool {
+    self.its_distance < the_far && self.its_distance + constants::PICKUP_H > the_near
+  }
 }
 
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Slot {
   its_width: f32,
   its_obstacles: Vec<Obstacle>,
+  #[serde(default)]
+  its_pickups: Vec<Pickup>,
 }
 
 impl Slot {
@@ -32,8 +298,12 @@ impl Slot {
     Slot {
       its_width: 1.0,
       its_obstacles: Vec::new(),
+      its_pickups: Vec::new(),
     }
   }
+  pub fn get_pickups(&self) -> &Vec<Pickup> {
+    &self.its_pickups
+  }
   pub fn get_width(&self) -> f32 {
     self.its_width
   }
@@ -43,9 +313,120 @@ impl Slot {
   pub fn add_obstacle(&mut self, the_obstacle: Obstacle) -> () {
     self.its_obstacles.push(the_obstacle);
   }
+  /// Move all obstacles `the_distance` times their speed factor closer to
+  /// the center, or away from it for reversed ones, and drop the ones that
+  /// have completely passed the center or `the_despawn_distance`
+  /// respectively.
+  /// Returns how many obstacles have moved past the cursor tip and how many
+  /// have been dropped.
+  fn advance_obstacles(
+    &mut self,
+    the_distance: f32,
+    the_outer_ring_speed: f32,
+    the_despawn_distance: f32,
+  ) -> (usize, usize) {
+    let a_cursor_tip = constants::CURSOR_Y + constants::CURSOR_H;
+    let mut a_passed = 0;
+    for a_obstacle in self.its_obstacles.iter_mut() {
+      let a_distance = the_distance * a_obstacle.get_speed_factor(the_outer_ring_speed);
+      let a_near = a_obstacle.its_distance;
+      let a_far = a_near + a_obstacle.get_height();
+      a_obstacle.its_distance -= a_distance;
+      let a_passed_in = a_far > a_cursor_tip && a_far - a_distance <= a_cursor_tip;
+      let a_passed_out = a_near <= a_cursor_tip && a_near - a_distance > a_cursor_tip;
+      if a_passed_in || a_passed_out {
+        a_passed += 1;
+      }
+    }
+    let a_count = self.its_obstacles.len();
+    self.its_obstacles.retain(|the_obstacle| {
+      if the_obstacle.its_reversed {
+        the_obstacle.its_distance < the_despawn_distance
+      } else {
+        the_obstacle.its_distance + the_obstacle.get_height() > 0.
+      }
+    });
+    (a_passed, a_count - self.its_obstacles.len())
+  }
+  /// Round every distance to the fixed-point grid, see `fixed::snap`
+  fn snap_distances(&mut self) -> () {
+    for a_obstacle in self.its_obstacles.iter_mut() {
+      a_obstacle.its_distance = fixed::snap(a_obstacle.its_distance);
+    }
+    for a_pickup in self.its_pickups.iter_mut() {
+      a_pickup.its_distance = fixed::snap(a_pickup.its_distance);
+    }
+  }
+  /// Move all pickups `the_distance` closer to the center and drop the ones
+  /// that have completely passed it
+  fn advance_pickups(&mut self, the_distance: f32) -> () {
+    for a_pickup in self.its_pickups.iter_mut() {
+      a_pickup.its_distance -= the_distance;
+    }
+    self
+      .its_pickups
+      .retain(|the_pickup| the_pickup.its_distance + constants::PICKUP_H > 0.);
+  }
+}
+
+pub fn srgb_to_linear(the_c: f32) -> f32 {
+  if the_c <= 0.04045 {
+    the_c / 12.92
+  } else {
+    ((the_c + 0.055) / 1.055).powf(2.4)
+  }
+}
+pub fn linear_to_srgb(the_c: f32) -> f32 {
+  if the_c <= 0.0031308 {
+    the_c * 12.92
+  } else {
+    1.055 * the_c.powf(1. / 2.4) - 0.055
+  }
+}
+
+/// Why a color given as text could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorParseError {
+  /// Not a hex color of the form `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+  InvalidHex(String),
+  /// Not one of the `NAMED_COLORS`
+  UnknownName(String),
 }
 
-#[derive(Clone)]
+impl std::fmt::Display for ColorParseError {
+  fn fmt(&self, the_f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ColorParseError::InvalidHex(a_text) => write!(the_f, "invalid hex color \"{}\"", a_text),
+      ColorParseError::UnknownName(a_text) => write!(the_f, "unknown color name \"{}\"", a_text),
+    }
+  }
+}
+
+impl std::error::Error for ColorParseError {}
+
+/// Colors that can be referred to by name, as 8 bit sRGB
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+  ("black", [0, 0, 0]),
+  ("white", [255, 255, 255]),
+  ("gray", [128, 128, 128]),
+  ("grey", [128, 128, 128]),
+  ("red", [255, 0, 0]),
+  ("green", [0, 255, 0]),
+  ("blue", [0, 0, 255]),
+  ("yellow", [255, 255, 0]),
+  ("cyan", [0, 255, 255]),
+  ("magenta", [255, 0, 255]),
+  ("orange", [255, 165, 0]),
+  ("purple", [128, 0, 128]),
+  ("pink", [255, 192, 203]),
+  ("brown", [165, 42, 42]),
+  ("navy", [0, 0, 128]),
+  ("teal", [0, 128, 128]),
+  ("olive", [128, 128, 0]),
+  ("maroon", [128, 0, 0]),
+];
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Color {
   pub its_r: f32,
   pub its_g: f32,
@@ -62,20 +443,245 @@ impl Color {
       its_a: the_a,
     }
   }
+  /// Parse `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional
+  pub fn from_hex(the_hex: &str) -> Result<Color, ColorParseError> {
+    let a_invalid = || ColorParseError::InvalidHex(the_hex.to_string());
+    let a_digits = the_hex.trim();
+    let a_digits = a_digits.strip_prefix('#').unwrap_or(a_digits);
+    if !a_digits
+      .chars()
+      .all(|the_char| the_char.is_ascii_hexdigit())
+    {
+      return Err(a_invalid());
+    }
+    let a_channel_len = match a_digits.len() {
+      3 | 4 => 1,
+      6 | 8 => 2,
+      _ => return Err(a_invalid()),
+    };
+    let mut a_channels = [1.; 4];
+    for (a_idx, a_channel) in a_digits.as_bytes().chunks(a_channel_len).enumerate() {
+      // only ASCII hex digits are left, so this is valid UTF-8
+      let a_text = std::str::from_utf8(a_channel).map_err(|_| a_invalid())?;
+      let a_value = u8::from_str_radix(a_text, 16).map_err(|_| a_invalid())?;
+      // a single digit stands for itself repeated, e.g. f for ff
+      let a_value = if a_channel_len == 1 {
+        a_value * 17
+      } else {
+        a_value
+      };
+      a_channels[a_idx] = a_value as f32 / 255.;
+    }
+    Ok(Color::rgba(
+      a_channels[0],
+      a_channels[1],
+      a_channels[2],
+      a_channels[3],
+    ))
+  }
+  /// Look up one of the `NAMED_COLORS`, ignoring case
+  pub fn from_name(the_name: &str) -> Result<Color, ColorParseError> {
+    let a_name = the_name.trim().to_ascii_lowercase();
+    NAMED_COLORS
+      .iter()
+      .find(|(the_name, _)| *the_name == a_name)
+      .map(|(_, the_rgb)| {
+        Color::rgba(
+          the_rgb[0] as f32 / 255.,
+          the_rgb[1] as f32 / 255.,
+          the_rgb[2] as f32 / 255.,
+          1.,
+        )
+      })
+      .ok_or_else(|| ColorParseError::UnknownName(the_name.to_string()))
+  }
+  /// Color from hue in degrees, saturation and value (both 0 to 1)
+  pub fn from_hsv(the_h: f32, the_s: f32, the_v: f32, the_a: f32) -> Color {
+    let a_h = the_h.rem_euclid(360.) / 60.;
+    let a_c = the_v * the_s;
+    let a_x = a_c * (1. - (a_h % 2. - 1.).abs());
+    let (a_r, a_g, a_b) = match a_h as u32 {
+      0 => (a_c, a_x, 0.),
+      1 => (a_x, a_c, 0.),
+      2 => (0., a_c, a_x),
+      3 => (0., a_x, a_c),
+      4 => (a_x, 0., a_c),
+      _ => (a_c, 0., a_x),
+    };
+    let a_m = the_v - a_c;
+    Color::rgba(a_r + a_m, a_g + a_m, a_b + a_m, the_a)
+  }
+  /// Hue in degrees [0, 360), saturation and value (both 0 to 1). Grays
+  /// have a hue of 0.
+  pub fn to_hsv(&self) -> (f32, f32, f32) {
+    let a_max = self.its_r.max(self.its_g).max(self.its_b);
+    let a_min = self.its_r.min(self.its_g).min(self.its_b);
+    let a_delta = a_max - a_min;
+    let a_h = if a_delta == 0. {
+      0.
+    } else if a_max == self.its_r {
+      60. * ((self.its_g - self.its_b) / a_delta).rem_euclid(6.)
+    } else if a_max == self.its_g {
+      60. * ((self.its_b - self.its_r) / a_delta + 2.)
+    } else {
+      60. * ((self.its_r - self.its_g) / a_delta + 4.)
+    };
+    let a_s = if a_max == 0. { 0. } else { a_delta / a_max };
+    (a_h, a_s, a_max)
+  }
+  /// The same color with its hue turned by `the_degrees`
+  pub fn rotate_hue(&self, the_degrees: f32) -> Color {
+    let (a_h, a_s, a_v) = self.to_hsv();
+    Color::from_hsv(a_h + the_degrees, a_s, a_v, self.its_a)
+  }
+  /// Parse a hex color if `the_text` starts with `#`, otherwise look it up
+  /// by name
+  pub fn parse(the_text: &str) -> Result<Color, ColorParseError> {
+    if the_text.trim().starts_with('#') {
+      Color::from_hex(the_text)
+    } else {
+      Color::from_name(the_text)
+    }
+  }
+  /// Linear interpolation between `self` (at 0) and `the_other` (at 1)
+  pub fn mix(&self, the_other: &Color, the_t: f32) -> Color {
+    Color::rgba(
+      self.its_r + (the_other.its_r - self.its_r) * the_t,
+      self.its_g + (the_other.its_g - self.its_g) * the_t,
+      self.its_b + (the_other.its_b - self.its_b) * the_t,
+      self.its_a + (the_other.its_a - self.its_a) * the_t,
+    )
+  }
+  pub fn inverted(&self) -> Color {
+    Color::rgba(
+      1. - self.its_r,
+      1. - self.its_g,
+      1. - self.its_b,
+      self.its_a,
+    )
+  }
+  /// The gray of the same luminance
+  pub fn desaturated(&self) -> Color {
+    let a_gray = linear_to_srgb(self.luminance());
+    Color::rgba(a_gray, a_gray, a_gray, self.its_a)
+  }
+  /// Relative luminance as defined by WCAG
+  pub fn luminance(&self) -> f32 {
+    0.2126 * srgb_to_linear(self.its_r)
+      + 0.7152 * srgb_to_linear(self.its_g)
+      + 0.0722 * srgb_to_linear(self.its_b)
+  }
 }
 
+/// Layer drawn behind the slots, which let it show through as far as they
+/// are transparent. It also fills what the slots leave uncovered, e.g.
+/// while the camera zooms out.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Background {
+  its_color: Color,
+  /// Number of rays around the center, every other one darker. With none,
+  /// the background is a single color.
+  its_rays: u32,
+  /// How much darker every other ray is, from 0 to 1
+  its_ray_contrast: f32,
+  /// Color the background fades to towards the edge of the playfield
+  its_gradient_color: Option<Color>,
+  /// Number of stars scattered over the background
+  its_stars: u32,
+  its_star_color: Color,
+  /// Opacity of the slots in front of the background
+  its_slot_opacity: f32,
+}
+
+impl Background {
+  /// A plain background of `the_color` behind half transparent slots
+  pub fn new(the_color: Color) -> Background {
+    Background {
+      its_color: the_color,
+      its_rays: 0,
+      its_ray_contrast: 0.,
+      its_gradient_color: None,
+      its_stars: 0,
+      its_star_color: Color::rgba(1., 1., 1., 1.),
+      its_slot_opacity: 0.5,
+    }
+  }
+  pub fn get_color(&self) -> &Color {
+    &self.its_color
+  }
+  pub fn get_rays(&self) -> u32 {
+    self.its_rays
+  }
+  pub fn get_ray_contrast(&self) -> f32 {
+    self.its_ray_contrast
+  }
+  pub fn set_rays(&mut self, the_rays: u32, the_contrast: f32) -> () {
+    self.its_rays = the_rays;
+    self.its_ray_contrast = the_contrast.clamp(0., 1.);
+  }
+  pub fn get_gradient_color(&self) -> Option<&Color> {
+    self.its_gradient_color.as_ref()
+  }
+  pub fn set_gradient_color(&mut self, the_color: Option<Color>) -> () {
+    self.its_gradient_color = the_color;
+  }
+  pub fn get_stars(&self) -> u32 {
+    self.its_stars
+  }
+  pub fn get_star_color(&self) -> &Color {
+    &self.its_star_color
+  }
+  pub fn set_stars(&mut self, the_stars: u32, the_color: Color) -> () {
+    self.its_stars = the_stars;
+    self.its_star_color = the_color;
+  }
+  pub fn get_slot_opacity(&self) -> f32 {
+    self.its_slot_opacity
+  }
+  pub fn set_slot_opacity(&mut self, the_opacity: f32) -> () {
+    self.its_slot_opacity = the_opacity.clamp(0., 1.);
+  }
+  fn map_colors<F: Fn(&Color) -> Color>(&mut self, the_fn: F) -> () {
+    self.its_color = the_fn(&self.its_color);
+    self.its_gradient_color = self.its_gradient_color.as_ref().map(&the_fn);
+    self.its_star_color = the_fn(&self.its_star_color);
+  }
+}
+
+/// A fade of all colors of a style from one palette to another
+#[derive(Clone, Serialize, Deserialize)]
+struct ColorTransition {
+  its_from: Box<Style>,
+  its_to: Box<Style>,
+  its_duration: Duration,
+  its_elapsed: Duration,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Style {
   its_cursor_color: Color,
   its_cursor_shadow_color: Color,
   its_inner_hexagon_color: Color,
   its_outer_hexagon_color: Color,
-  its_obstacle_color: Color,
+  its_obstacle_colors: Vec<Color>,
   its_slot_colors: Vec<Color>,
   its_rotation: f32,
   its_zoom: f32,
+  #[serde(with = "vec2_serde")]
   its_eye: Vec2,
+  #[serde(with = "vec2_serde")]
   its_look_at: Vec2,
   its_flash_time: Duration,
+  /// Thickness of the outer hexagon's ring
+  its_outer_hexagon_width: f32,
+  /// Number of dashes per hexagon edge, 0 for a solid ring
+  its_outer_hexagon_dashes: u32,
+  /// How fast all colors cycle through the hues, in degrees per second
+  its_hue_speed: f32,
+  #[serde(default)]
+  its_background: Option<Background>,
+  /// Palette swap in progress, resolved into the colors above every tick
+  its_transition: Option<ColorTransition>,
 }
 
 impl Style {
@@ -85,25 +691,39 @@ impl Style {
       its_cursor_shadow_color: Color::rgba(0., 0., 0., 0.),
       its_inner_hexagon_color: Color::rgba(0., 0., 0., 1.),
       its_outer_hexagon_color: Color::rgba(1., 0., 0., 1.),
-      its_obstacle_color: Color::rgba(0., 1., 0., 1.),
+      its_obstacle_colors: vec![Color::rgba(0., 1., 0., 1.), Color::rgba(0., 0.6, 1., 1.)],
       its_slot_colors: vec![Color::rgba(1., 0., 0., 1.), Color::rgba(1., 1., 1., 1.)],
       its_rotation: 0.,
       its_zoom: 1.,
       its_eye: Vec2::new(0., 0.),
       its_look_at: Vec2::new(0., 0.),
       its_flash_time: Duration::from_millis(0),
+      its_outer_hexagon_width: constants::OUTER_HEXAGON_Y - constants::INNER_HEXAGON_Y,
+      its_outer_hexagon_dashes: 0,
+      its_hue_speed: 0.,
+      its_background: None,
+      its_transition: None,
     }
   }
 
   pub fn get_eye(&self) -> &Vec2 {
     &self.its_eye
   }
+  pub fn set_eye(&mut self, the_x: f32, the_y: f32) -> () {
+    self.its_eye = Vec2::new(the_x, the_y);
+  }
   pub fn get_look_at(&self) -> &Vec2 {
     &self.its_look_at
   }
+  pub fn set_look_at(&mut self, the_x: f32, the_y: f32) -> () {
+    self.its_look_at = Vec2::new(the_x, the_y);
+  }
   pub fn get_rotation(&self) -> f32 {
     self.its_rotation
   }
+  pub fn set_rotation(&mut self, the_rotation: f32) -> () {
+    self.its_rotation = the_rotation;
+  }
   pub fn set_zoom(&mut self, the_zoom: f32) -> () {
     self.its_zoom = the_zoom;
   }
@@ -113,41 +733,671 @@ impl Style {
   pub fn get_slot_colors(&self) -> &Vec<Color> {
     &self.its_slot_colors
   }
-  pub fn get_obstacle_color(&self) -> &Color {
-    &self.its_obstacle_color
+  pub fn set_slot_colors(&mut self, the_colors: Vec<Color>) -> () {
+    self.its_slot_colors = the_colors;
+  }
+  pub fn get_obstacle_colors(&self) -> &Vec<Color> {
+    &self.its_obstacle_colors
+  }
+  /// Empty lists are ignored, there always is at least one obstacle color
+  pub fn set_obstacle_colors(&mut self, the_colors: Vec<Color>) -> () {
+    if !the_colors.is_empty() {
+      self.its_obstacle_colors = the_colors;
+    }
+  }
+  /// The color of obstacles in color group `the_group`
+  pub fn get_obstacle_color(&self, the_group: usize) -> &Color {
+    &self.its_obstacle_colors[the_group % self.its_obstacle_colors.len()]
   }
   pub fn get_outer_hexagon_color(&self) -> &Color {
     &self.its_outer_hexagon_color
   }
+  pub fn set_outer_hexagon_color(&mut self, the_color: Color) -> () {
+    self.its_outer_hexagon_color = the_color;
+  }
   pub fn get_inner_hexagon_color(&self) -> &Color {
     &self.its_inner_hexagon_color
   }
+  pub fn set_inner_hexagon_color(&mut self, the_color: Color) -> () {
+    self.its_inner_hexagon_color = the_color;
+  }
   pub fn get_cursor_color(&self) -> &Color {
     &self.its_cursor_color
   }
+  pub fn set_cursor_color(&mut self, the_color: Color) -> () {
+    self.its_cursor_color = the_color;
+  }
   pub fn get_cursor_shadow_color(&self) -> &Color {
     &self.its_cursor_shadow_color
   }
+  pub fn set_cursor_shadow_color(&mut self, the_color: Color) -> () {
+    self.its_cursor_shadow_color = the_color;
+  }
+  pub fn get_outer_hexagon_width(&self) -> f32 {
+    self.its_outer_hexagon_width
+  }
+  pub fn set_outer_hexagon_width(&mut self, the_width: f32) -> () {
+    self.its_outer_hexagon_width = the_width.max(0.);
+  }
+  pub fn get_outer_hexagon_dashes(&self) -> u32 {
+    self.its_outer_hexagon_dashes
+  }
+  pub fn set_outer_hexagon_dashes(&mut self, the_dashes: u32) -> () {
+    self.its_outer_hexagon_dashes = the_dashes;
+  }
+  pub fn get_background(&self) -> Option<&Background> {
+    self.its_background.as_ref()
+  }
+  pub fn set_background(&mut self, the_background: Option<Background>) -> () {
+    self.its_background = the_background;
+  }
+  pub fn set_hue_speed(&mut self, the_speed: f32) -> () {
+    self.its_hue_speed = the_speed;
+  }
+  /// Advance everything about the style that changes over time
+  pub fn tick(&mut self, the_delta: Duration) -> () {
+    self.its_flash_time = self
+      .its_flash_time
+      .checked_sub(the_delta)
+      .unwrap_or_default();
+    self.tick_transition(the_delta);
+    if self.its_hue_speed != 0. {
+      let a_degrees = self.its_hue_speed * the_delta.as_secs_f32();
+      let a_rotate = |the_color: &Color| the_color.rotate_hue(a_degrees);
+      self.map_colors(a_rotate);
+      if let Some(a_transition) = &mut self.its_transition {
+        a_transition.its_from.map_colors(a_rotate);
+        a_transition.its_to.map_colors(a_rotate);
+      }
+    }
+  }
+  fn tick_transition(&mut self, the_delta: Duration) -> () {
+    let (a_colors, a_done) = match &mut self.its_transition {
+      Some(a_transition) => {
+        a_transition.its_elapsed += the_delta;
+        let a_t = (a_transition.its_elapsed.as_secs_f32()
+          / a_transition.its_duration.as_secs_f32())
+        .min(1.);
+        let mut a_colors = (*a_transition.its_to).clone();
+        a_colors.blend_colors_from(&a_transition.its_from, a_t);
+        (a_colors, a_t >= 1.)
+      }
+      None => return,
+    };
+    self.copy_colors_from(&a_colors);
+    if a_done {
+      self.its_transition = None;
+    }
+  }
+  /// Take over every color of `the_other`
+  pub fn copy_colors_from(&mut self, the_other: &Style) -> () {
+    self.its_cursor_color = the_other.its_cursor_color.clone();
+    self.its_cursor_shadow_color = the_other.its_cursor_shadow_color.clone();
+    self.its_inner_hexagon_color = the_other.its_inner_hexagon_color.clone();
+    self.its_outer_hexagon_color = the_other.its_outer_hexagon_color.clone();
+    self.its_obstacle_colors = the_other.its_obstacle_colors.clone();
+    self.its_slot_colors = the_other.its_slot_colors.clone();
+    self.its_background = the_other.its_background.clone();
+  }
+  /// Fade every color to the one of `the_target` over `the_duration`, or
+  /// switch right away if it is zero. Replaces a fade that is in progress,
+  /// starting from the colors it has reached.
+  pub fn fade_colors_to(&mut self, the_target: &Style, the_duration: Duration) -> () {
+    let mut a_to = the_target.clone();
+    a_to.its_transition = None;
+    if the_duration == Duration::from_secs(0) {
+      self.its_transition = None;
+      self.copy_colors_from(&a_to);
+      return;
+    }
+    let mut a_from = self.clone();
+    a_from.its_transition = None;
+    self.its_transition = Some(ColorTransition {
+      its_from: Box::new(a_from),
+      its_to: Box::new(a_to),
+      its_duration: the_duration,
+      its_elapsed: Duration::from_secs(0),
+    });
+  }
   pub fn get_flash_time(&self) -> std::time::Duration {
     self.its_flash_time
   }
+  pub fn set_flash_time(&mut self, the_time: Duration) -> () {
+    self.its_flash_time = the_time;
+  }
+  /// Replace every color of the style by the result of `the_fn`
+  pub fn map_colors<F: Fn(&Color) -> Color>(&mut self, the_fn: F) -> () {
+    self.its_cursor_color = the_fn(&self.its_cursor_color);
+    self.its_cursor_shadow_color = the_fn(&self.its_cursor_shadow_color);
+    self.its_inner_hexagon_color = the_fn(&self.its_inner_hexagon_color);
+    self.its_outer_hexagon_color = the_fn(&self.its_outer_hexagon_color);
+    for a_color in self.its_obstacle_colors.iter_mut() {
+      *a_color = the_fn(a_color);
+    }
+    for a_color in self.its_slot_colors.iter_mut() {
+      *a_color = the_fn(a_color);
+    }
+    if let Some(a_background) = &mut self.its_background {
+      a_background.map_colors(&the_fn);
+    }
+  }
+  /// Blend every color with the corresponding one of `the_from`, where a
+  /// `the_t` of 0 yields the colors of `the_from` and 1 leaves them as is
+  pub fn blend_colors_from(&mut self, the_from: &Style, the_t: f32) -> () {
+    let a_blend = |the_to: &mut Color, the_from: &Color| *the_to = the_from.mix(the_to, the_t);
+    a_blend(&mut self.its_cursor_color, &the_from.its_cursor_color);
+    a_blend(
+      &mut self.its_cursor_shadow_color,
+      &the_from.its_cursor_shadow_color,
+    );
+    a_blend(
+      &mut self.its_inner_hexagon_color,
+      &the_from.its_inner_hexagon_color,
+    );
+    a_blend(
+      &mut self.its_outer_hexagon_color,
+      &the_from.its_outer_hexagon_color,
+    );
+    // Slots and color groups without a counterpart keep their color
+    for (a_to, a_from) in self
+      .its_obstacle_colors
+      .iter_mut()
+      .zip(the_from.its_obstacle_colors.iter())
+    {
+      a_blend(a_to, a_from);
+    }
+    for (a_to, a_from) in self
+      .its_slot_colors
+      .iter_mut()
+      .zip(the_from.its_slot_colors.iter())
+    {
+      a_blend(a_to, a_from);
+    }
+    // only backgrounds on both sides can be blended, otherwise the target's
+    // background appears or disappears right away
+    if let (Some(a_to), Some(a_from)) = (&mut self.its_background, &the_from.its_background) {
+      a_blend(&mut a_to.its_color, &a_from.its_color);
+      a_blend(&mut a_to.its_star_color, &a_from.its_star_color);
+      if let (Some(a_to), Some(a_from)) = (&mut a_to.its_gradient_color, &a_from.its_gradient_color)
+      {
+        a_blend(a_to, a_from);
+      }
+    }
+  }
+}
+
+/// Names of the stages of a run and how many walls it takes to reach each
+/// of them. Since stages depend on dodged walls rather than time, the split
+/// times show how quickly the player got through the level's walls.
+pub const STAGES: [(&str, u32); 5] = [
+  ("Line", 20),
+  ("Triangle", 50),
+  ("Square", 100),
+  ("Pentagon", 175),
+  ("Hexagon", 275),
+];
+
+/// Bookkeeping about the current attempt at a level
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Run {
+  its_seed: u64,
+  /// What the player typed to get the seed, if it was derived from a text
+  #[serde(default)]
+  its_seed_text: Option<String>,
+  /// Simulation ticks survived
+  its_ticks: u64,
+  /// Ticks per second the run has been simulated at
+  #[serde(default = "default_tick_rate")]
+  its_tick_rate: u32,
+  its_min_game_speed: f32,
+  /// Obstacles that have moved past the cursor
+  its_walls_dodged: u32,
+  /// Total distance the cursor has moved, in turns around the center
+  its_distance: f32,
+  /// How often the cursor has started moving the other way round
+  its_direction_changes: u32,
+  /// Sign of the latest cursor movement, 0 before the first one
+  its_move_direction: i8,
+  /// Run time at which each of the `STAGES` reached so far was entered
+  its_splits: Vec<Duration>,
+  /// Practice runs may have been rewound to a savestate
+  #[serde(default)]
+  its_practice: bool,
+  /// Debug commands such as invincibility have been used during the run
+  #[serde(default)]
+  its_cheated: bool,
+  /// The run has lasted until the goal of a time attack
+  #[serde(default)]
+  its_completed: bool,
+  /// A point per dodged wall, times the combo multiplier and more while
+  /// the score multiplier is active
+  #[serde(default)]
+  its_score: u32,
+  /// Walls passed since the combo was last lost
+  #[serde(default)]
+  its_combo: u32,
+  /// How long the cursor has been pressing against a wall without a break
+  #[serde(default)]
+  its_hug_time: Duration,
 }
 
+fn default_tick_rate() -> u32 {
+  constants::DEFAULT_TICK_RATE
+}
+
+impl Run {
+  pub fn new(the_seed: u64, the_tick_rate: u32) -> Run {
+    Run {
+      its_seed: the_seed,
+      its_seed_text: None,
+      its_ticks: 0,
+      its_tick_rate: the_tick_rate,
+      its_min_game_speed: 1.,
+      its_walls_dodged: 0,
+      its_distance: 0.,
+      its_direction_changes: 0,
+      its_move_direction: 0,
+      its_splits: Vec::new(),
+      its_practice: false,
+      its_cheated: false,
+      its_completed: false,
+      its_score: 0,
+      its_combo: 0,
+      its_hug_time: Duration::from_secs(0),
+    }
+  }
+  /// The seed all randomness of the run is derived from
+  pub fn get_seed(&self) -> u64 {
+    self.its_seed
+  }
+  pub fn get_seed_text(&self) -> Option<&str> {
+    self.its_seed_text.as_deref()
+  }
+  /// Note that the seed has been derived from `the_text`, see
+  /// `Rng::seed_from_text`
+  pub fn set_seed_text(&mut self, the_text: String) -> () {
+    self.its_seed_text = Some(the_text);
+  }
+  /// Survived time in simulation time, i.e. after game speed scaling.
+  /// Counted in ticks so that it does not depend on the frame rate.
+  pub fn get_time(&self) -> Duration {
+    Duration::from_nanos(self.its_ticks * 1_000_000_000 / self.its_tick_rate as u64)
+  }
+  /// Simulation ticks survived
+  pub fn get_ticks(&self) -> u64 {
+    self.its_ticks
+  }
+  /// Ticks per second the run is simulated at
+  pub fn get_tick_rate(&self) -> u32 {
+    self.its_tick_rate
+  }
+  /// Length of one of the run's ticks
+  pub fn get_tick_duration(&self) -> Duration {
+    Duration::from_nanos(1_000_000_000 / self.its_tick_rate as u64)
+  }
+  /// The slowest game speed that was in effect at any point of the run
+  pub fn get_min_game_speed(&self) -> f32 {
+    self.its_min_game_speed
+  }
+  /// Runs that have been slowed down are still valid but have to be marked
+  /// as such in replays and leaderboards
+  pub fn is_assisted(&self) -> bool {
+    self.its_min_game_speed < 1.
+  }
+  pub fn note_game_speed(&mut self, the_speed: f32) -> () {
+    self.its_min_game_speed = self.its_min_game_speed.min(the_speed);
+  }
+  /// Practice runs never count as records
+  pub fn is_practice(&self) -> bool {
+    self.its_practice
+  }
+  pub fn mark_practice(&mut self) -> () {
+    self.its_practice = true;
+  }
+  /// Runs changed by debug commands never count as records either
+  pub fn is_cheated(&self) -> bool {
+    self.its_cheated
+  }
+  pub fn mark_cheated(&mut self) -> () {
+    self.its_cheated = true;
+  }
+  pub fn is_completed(&self) -> bool {
+    self.its_completed
+  }
+  pub fn get_walls_dodged(&self) -> u32 {
+    self.its_walls_dodged
+  }
+  pub fn get_score(&self) -> u32 {
+    self.its_score
+  }
+  /// Walls passed in a row without dying or hugging a wall for longer than
+  /// `HUG_LIMIT`
+  #[cfg(test)]
+  pub fn get_combo(&self) -> u32 {
+    self.its_combo
+  }
+  /// What each passed wall's points are multiplied by, one more for every
+  /// `COMBO_STEP` walls of the combo
+  pub fn get_combo_multiplier(&self) -> u32 {
+    (1 + self.its_combo / COMBO_STEP).min(MAX_COMBO_MULTIPLIER)
+  }
+  /// Account for a wall passed by the cursor, worth `the_points` before the
+  /// combo multiplier
+  fn note_wall_passed(&mut self, the_points: u32) -> () {
+    self.its_walls_dodged += 1;
+    self.its_score += the_points * self.get_combo_multiplier();
+    self.its_combo += 1;
+  }
+  /// Account for the cursor pressing against a wall during a tick of
+  /// `the_delta`, or not
+  fn note_hugging(&mut self, the_hugging: bool, the_delta: Duration) -> () {
+    if !the_hugging {
+      self.its_hug_time = Duration::from_secs(0);
+      return;
+    }
+    self.its_hug_time += the_delta;
+    if self.its_hug_time > HUG_LIMIT {
+      self.its_combo = 0;
+    }
+  }
+  /// Distance the cursor has moved in turns around the center
+  pub fn get_distance(&self) -> f32 {
+    self.its_distance
+  }
+  pub fn get_direction_changes(&self) -> u32 {
+    self.its_direction_changes
+  }
+  /// Run time at which each stage reached so far was entered, see `STAGES`
+  pub fn get_splits(&self) -> &[Duration] {
+    &self.its_splits
+  }
+  /// Account for a cursor movement by `the_delta` turns, signed
+  fn note_movement(&mut self, the_delta: f32) -> () {
+    let a_direction = if the_delta > 0. {
+      1
+    } else if the_delta < 0. {
+      -1
+    } else {
+      return;
+    };
+    self.its_distance += the_delta.abs();
+    if self.its_move_direction != 0 && a_direction != self.its_move_direction {
+      self.its_direction_changes += 1;
+    }
+    self.its_move_direction = a_direction;
+  }
+}
+
+/// Something that happened during the simulation, so that features can react
+/// to gameplay without each of them comparing states between ticks
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+  ObstacleSpawned {
+    slot: usize,
+    color_group: usize,
+  },
+  /// An obstacle has moved past the center and is gone
+  ObstacleCleared {
+    slot: usize,
+  },
+  /// The cursor has moved from slot `from` onto slot `slot`
+  SlotEntered {
+    slot: usize,
+    from: usize,
+  },
+  /// The far end of an obstacle has moved past the cursor, i.e. it can no
+  /// longer be hit
+  WallPassed {
+    slot: usize,
+  },
+  /// The run has entered the stage with the given index into `STAGES`
+  StageReached {
+    stage: usize,
+  },
+  /// The cursor has hit an obstacle, ending the run
+  Died,
+  /// The run has lasted until the goal, which ends it as well
+  Completed,
+  /// The cursor has touched a pickup in slot `slot`, its effect has begun
+  PickupCollected {
+    slot: usize,
+    power_up: PowerUp,
+  },
+  /// The playfield has started to rotate the other way round, `speed` is
+  /// the new one in turns per second
+  RotationFlipped {
+    speed: f32,
+  },
+}
+
+/// A change to the style or its motion that the level's timeline, the
+/// console and effects submit rather than making directly. The queue is
+/// worked off at the start of the next tick, so that the style only ever
+/// changes in between ticks.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StyleCommand {
+  /// Rotate the playfield at this many turns per second
+  SetRotationSpeed(f32),
+  /// Switch to the colors a palette fade has reached, either palette is
+  /// left as is if it is `None`
+  FadePalette {
+    slot_colors: Option<Vec<Color>>,
+    obstacle_colors: Option<Vec<Color>>,
+  },
+  /// Set the zoom a pulse has reached
+  PulseZoom(f32),
+}
+
+impl StyleCommand {
+  /// Take over `the_newer` if it is of the same kind, since only the
+  /// latest value of each kind counts. Returns whether it was taken over.
+  fn absorb(&mut self, the_newer: &StyleCommand) -> bool {
+    match (self, the_newer) {
+      (StyleCommand::SetRotationSpeed(a_speed), StyleCommand::SetRotationSpeed(a_newer)) => {
+        *a_speed = *a_newer;
+      }
+      (
+        StyleCommand::FadePalette {
+          slot_colors: a_slots,
+          obstacle_colors: a_obstacles,
+        },
+        StyleCommand::FadePalette {
+          slot_colors: a_newer_slots,
+          obstacle_colors: a_newer_obstacles,
+        },
+      ) => {
+        if a_newer_slots.is_some() {
+          *a_slots = a_newer_slots.clone();
+        }
+        if a_newer_obstacles.is_some() {
+          *a_obstacles = a_newer_obstacles.clone();
+        }
+      }
+      (StyleCommand::PulseZoom(a_zoom), StyleCommand::PulseZoom(a_newer)) => {
+        *a_zoom = *a_newer;
+      }
+      _ => return false,
+    }
+    true
+  }
+}
+
+fn default_outer_ring_speed() -> f32 {
+  constants::OUTER_RING_SPEED
+}
+
+fn default_spawn_distance() -> f32 {
+  constants::SPAWN_DISTANCE
+}
+
+fn unlimited() -> f32 {
+  f32::INFINITY
+}
+
+/// Events reserved for, more than a tick normally produces, so that event
+/// buffers do not have to grow during play
+const EVENT_CAPACITY: usize = 64;
+
+/// How the cursor responds to a held direction. Speeds are in turns per
+/// `constants::SPEED_REFERENCE_FRAME`.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpeedCurve {
+  /// Speed right after the direction is pressed, i.e. of a short tap
+  pub initial: f32,
+  /// Added to the speed every reference frame the direction stays held
+  #[serde(default)]
+  pub acceleration: f32,
+  /// Speed the acceleration stops at
+  pub max: f32,
+}
+
+impl SpeedCurve {
+  /// The cursor moves at `the_speed` from the start
+  pub fn constant(the_speed: f32) -> SpeedCurve {
+    SpeedCurve {
+      initial: the_speed,
+      acceleration: 0.,
+      max: the_speed,
+    }
+  }
+  /// Speed after the direction has been held at `the_speed` for another
+  /// `the_effect` reference frames. Kept within the curve, which may have
+  /// changed while the direction was held.
+  fn accelerate(&self, the_speed: f32, the_effect: f32) -> f32 {
+    (the_speed + self.acceleration * the_effect)
+      .max(self.initial)
+      .min(self.max.max(self.initial))
+  }
+}
+
+impl Default for SpeedCurve {
+  fn default() -> SpeedCurve {
+    SpeedCurve::constant(0.03)
+  }
+}
+
+/// Everything that is simulated. Can be written to and restored from JSON
+/// as a whole, e.g. for save-states.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameState {
   its_player_position: f32,
-  its_player_speed: f32,
+  /// Set by the level and its scripted changes
+  #[serde(default)]
+  its_speed_curve: SpeedCurve,
+  /// Replaces `its_speed_curve`, e.g. for accessibility, see
+  /// `settings::Settings::player_speed_curve`
+  #[serde(default)]
+  its_speed_curve_override: Option<SpeedCurve>,
+  /// Direction the cursor has been steered in since the last change, and the
+  /// speed it has reached in it
+  #[serde(default)]
+  its_held_direction: i8,
+  #[serde(default)]
+  its_held_speed: f32,
   its_obstacle_speed: f32,
+  /// Speed of the outer ring's obstacles relative to `its_obstacle_speed`
+  #[serde(default = "default_outer_ring_speed")]
+  its_outer_ring_speed: f32,
+  /// Distance from the center at which new obstacles appear
+  #[serde(default = "default_spawn_distance")]
+  its_spawn_distance: f32,
+  /// Distance from the center at which obstacles moving outward vanish
+  #[serde(default = "default_spawn_distance")]
+  its_despawn_distance: f32,
+  /// Tempo of the level's music, which pulsing obstacles follow
+  #[serde(default)]
+  its_bpm: Option<f32>,
+  /// In turns per second, negative values rotate counter-clockwise
+  its_rotation_speed: f32,
+  /// Sign of the last rotation speed other than 0, to notice flips even
+  /// when the speed passes through 0 on the way
+  its_rotation_direction: i8,
+  /// Magnitude the rotation speed is clamped to while integrating, not part
+  /// of the state since it comes from the user's settings
+  #[serde(skip, default = "unlimited")]
+  its_max_rotation_speed: f32,
   its_slots: [Slot; 6],
   its_style: Style,
   its_is_running: bool,
+  its_run: Run,
+  /// Ticks per second of the runs to come, the current run keeps its own
+  #[serde(default = "default_tick_rate")]
+  its_tick_rate: u32,
+  /// Collisions do not end the run, but mark it as cheated
+  #[serde(default)]
+  its_invincible: bool,
+  /// The cursor turns against the playfield, so that it stays in place on
+  /// the screen unless steered
+  #[serde(default)]
+  its_screen_relative: bool,
+  /// Positions, distances and what they change by each tick are kept on a
+  /// fixed-point grid, so that the run goes exactly the same way on every
+  /// platform
+  #[serde(default)]
+  its_strict_math: bool,
+  /// Run time at which the run is complete, see `level::GoalDef`. Without
+  /// it the run lasts until the cursor is hit.
+  #[serde(default)]
+  its_goal: Option<Duration>,
+  /// Effects of collected pickups and how long they still last
+  #[serde(default)]
+  its_power_ups: Vec<(PowerUp, Duration)>,
+  /// Submitted since the last tick, applied at the start of the next one
+  #[serde(default)]
+  its_style_commands: Vec<StyleCommand>,
+  /// Events since the last call to `take_events`
+  #[serde(skip)]
+  its_events: Vec<GameEvent>,
 }
 
 impl GameState {
+  #[cfg(any(test, fuzzing))]
+  pub fn from_json(the_json: &[u8]) -> Result<GameState, serde_json::Error> {
+    let a_state: GameState = serde_json::from_slice(the_json)?;
+    a_state.validate().map_err(serde::de::Error::custom)?;
+    Ok(a_state)
+  }
+  /// Reject states the simulation cannot work with, e.g. from a damaged
+  /// save-state
+  pub fn validate(&self) -> Result<(), &'static str> {
+    let a_widths_valid = self
+      .its_slots
+      .iter()
+      .all(|the_slot| the_slot.its_width.is_finite() && the_slot.its_width >= 0.);
+    if !a_widths_valid || self.get_slot_width_sum() <= 0. {
+      return Err("invalid slot widths");
+    }
+    if !(self.its_player_position >= 0. && self.its_player_position < 1.) {
+      return Err("player position out of range");
+    }
+    // both are divided by
+    let a_tick_rates = constants::MIN_TICK_RATE..=constants::MAX_TICK_RATE;
+    if !a_tick_rates.contains(&self.its_tick_rate)
+      || !a_tick_rates.contains(&self.its_run.its_tick_rate)
+    {
+      return Err("tick rate out of range");
+    }
+    Ok(())
+  }
+  #[cfg(test)]
+  pub fn to_json(&self) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(self)
+  }
   pub fn new() -> GameState {
     GameState {
       its_player_position: 1. / 12.,
-      its_player_speed: 0.03,
+      its_speed_curve: SpeedCurve::default(),
+      its_speed_curve_override: None,
+      its_held_direction: 0,
+      its_held_speed: 0.,
       its_obstacle_speed: 0.005,
+      its_outer_ring_speed: constants::OUTER_RING_SPEED,
+      its_spawn_distance: constants::SPAWN_DISTANCE,
+      its_despawn_distance: constants::SPAWN_DISTANCE,
+      its_bpm: None,
+      its_rotation_speed: 0.,
+      its_rotation_direction: 0,
+      its_max_rotation_speed: unlimited(),
       its_slots: [
         Slot::new(),
         Slot::new(),
@@ -158,47 +1408,244 @@ impl GameState {
       ],
       its_style: Style::new(),
       its_is_running: true,
+      its_run: Run::new(0, constants::DEFAULT_TICK_RATE),
+      its_tick_rate: constants::DEFAULT_TICK_RATE,
+      its_invincible: constants::GOD_MODE,
+      its_screen_relative: false,
+      its_strict_math: false,
+      its_goal: None,
+      its_power_ups: Vec::new(),
+      its_style_commands: Vec::new(),
+      its_events: Vec::with_capacity(EVENT_CAPACITY),
     }
   }
   pub fn get_position(&self) -> f32 {
     self.its_player_position
   }
   pub fn set_position(&mut self, the_position: f32) -> () {
-    self.its_player_position = the_position;
+    // wraps around in case it is rounded up to 1
+    let a_position = self.to_grid(the_position).rem_euclid(1.);
+    let a_from = self.get_current_slot_idx();
+    if self.its_is_running {
+      // the shorter way round, positions wrap around at 1
+      let a_delta = (a_position - self.its_player_position + 0.5).rem_euclid(1.) - 0.5;
+      self.its_run.note_movement(a_delta);
+    }
+    self.its_player_position = a_position;
+    let a_slot = self.get_current_slot_idx();
+    if a_slot != a_from {
+      self.its_events.push(GameEvent::SlotEntered {
+        slot: a_slot,
+        from: a_from,
+      });
+    }
   }
+  /// The fastest the cursor moves once a direction has been held long
+  /// enough
   pub fn get_player_speed(&self) -> f32 {
-    self.its_player_speed
+    let a_curve = self.get_speed_curve();
+    a_curve.max.max(a_curve.initial)
+  }
+  /// The response of the cursor in effect, the override if there is one
+  pub fn get_speed_curve(&self) -> &SpeedCurve {
+    self
+      .its_speed_curve_override
+      .as_ref()
+      .unwrap_or(&self.its_speed_curve)
+  }
+  /// Like `set_screen_relative`, set before every tick from what the run
+  /// records
+  pub fn set_speed_curve_override(&mut self, the_curve: Option<SpeedCurve>) -> () {
+    self.its_speed_curve_override = the_curve;
+  }
+  /// Note that the cursor is steered in `the_direction` for `the_effect`
+  /// reference frames, see `constants::get_speed_scale`, and return how far
+  /// it moves. The speed starts over whenever the direction changes.
+  pub fn hold_direction(&mut self, the_direction: i8, the_effect: f32) -> f32 {
+    let a_curve = *self.get_speed_curve();
+    self.its_held_speed = if the_direction == 0 {
+      0.
+    } else if the_direction != self.its_held_direction {
+      a_curve.initial
+    } else {
+      a_curve.accelerate(self.its_held_speed, the_effect)
+    };
+    self.its_held_direction = the_direction;
+    self.its_held_speed * the_effect
+  }
+  pub fn get_obstacle_speed(&self) -> f32 {
+    self.its_obstacle_speed
+  }
+  /// The speed obstacles actually approach at, i.e. while time is slowed
+  pub fn get_effective_obstacle_speed(&self) -> f32 {
+    if self.has_power_up(PowerUp::SlowTime) {
+      self.its_obstacle_speed * SLOW_TIME_FACTOR
+    } else {
+      self.its_obstacle_speed
+    }
+  }
+  /// Scale the level's speed curve so that the cursor moves at most at
+  /// `the_speed`
+  pub fn set_player_speed(&mut self, the_speed: f32) -> () {
+    let a_curve = &mut self.its_speed_curve;
+    let a_max = a_curve.max.max(a_curve.initial);
+    if a_max <= 0. {
+      *a_curve = SpeedCurve::constant(the_speed);
+      return;
+    }
+    let a_factor = the_speed / a_max;
+    a_curve.initial *= a_factor;
+    a_curve.acceleration *= a_factor;
+    a_curve.max *= a_factor;
+  }
+  pub fn set_obstacle_speed(&mut self, the_speed: f32) -> () {
+    self.its_obstacle_speed = the_speed;
+  }
+  /// How far pulsing obstacles are swelled at the current time of the run,
+  /// 1 on every beat and falling off until the next one
+  pub fn get_swell(&self) -> f32 {
+    let a_bpm = self
+      .its_bpm
+      .filter(|the_bpm| *the_bpm > 0.)
+      .unwrap_or(DEFAULT_BPM);
+    let a_phase = (self.its_run.get_time().as_secs_f32() * a_bpm / 60.).fract();
+    // multiplied out, `powi` does not round the same way everywhere
+    (1. - a_phase) * (1. - a_phase)
+  }
+  /// How much faster than the obstacle speed `the_obstacle` approaches,
+  /// negative if it moves away from the center
+  pub fn get_speed_factor(&self, the_obstacle: &Obstacle) -> f32 {
+    the_obstacle.get_speed_factor(self.its_outer_ring_speed)
+  }
+  pub fn get_spawn_distance(&self) -> f32 {
+    self.its_spawn_distance
+  }
+  /// Distance from the center at which obstacles of the outer ring appear
+  pub fn get_outer_ring_distance(&self) -> f32 {
+    self.its_spawn_distance * constants::OUTER_RING_DISTANCE / constants::SPAWN_DISTANCE
+  }
+  /// Distance from the center at which obstacles moving outward vanish,
+  /// the playfield ends there
+  pub fn get_despawn_distance(&self) -> f32 {
+    self.its_despawn_distance
+  }
+  pub fn get_rotation_speed(&self) -> f32 {
+    self.its_rotation_speed
+  }
+  pub fn set_rotation_speed(&mut self, the_speed: f32) -> () {
+    self.its_rotation_speed = the_speed;
+    let a_direction = if the_speed > 0. {
+      1
+    } else if the_speed < 0. {
+      -1
+    } else {
+      return;
+    };
+    if self.its_rotation_direction != 0 && a_direction != self.its_rotation_direction {
+      self
+        .its_events
+        .push(GameEvent::RotationFlipped { speed: the_speed });
+    }
+    self.its_rotation_direction = a_direction;
+  }
+  pub fn set_max_rotation_speed(&mut self, the_speed: f32) -> () {
+    self.its_max_rotation_speed = the_speed;
+  }
+  /// The rotation speed that is actually applied, i.e. after the limit
+  pub fn get_effective_rotation_speed(&self) -> f32 {
+    self
+      .its_rotation_speed
+      .max(-self.its_max_rotation_speed)
+      .min(self.its_max_rotation_speed)
   }
   pub fn get_slots(&self) -> &[Slot; 6] {
     &self.its_slots
   }
+  /// Widths of 0 and below make the slot disappear until it gets a width
+  /// again. The last slot that has a width cannot collapse, since there
+  /// would be no playfield left.
+  pub fn set_slot_width(&mut self, the_slot: usize, the_width: f32) -> () {
+    let a_width = if the_width > 0. {
+      self.to_grid(the_width)
+    } else {
+      0.
+    };
+    let a_others = self.get_slot_width_sum() - self.its_slots[the_slot].its_width;
+    if a_width <= 0. && a_others <= 0. {
+      return;
+    }
+    self.its_slots[the_slot].its_width = a_width;
+  }
+  pub fn add_pickup(&mut self, the_slot: usize, mut the_pickup: Pickup) -> () {
+    the_pickup.its_distance = self.to_grid(the_pickup.its_distance);
+    self.its_slots[the_slot].its_pickups.push(the_pickup);
+  }
+  pub fn add_obstacle(&mut self, the_slot: usize, mut the_obstacle: Obstacle) -> () {
+    self.its_events.push(GameEvent::ObstacleSpawned {
+      slot: the_slot,
+      color_group: the_obstacle.get_color_group(),
+    });
+    the_obstacle.set_swell(self.get_swell());
+    the_obstacle.its_distance = self.to_grid(the_obstacle.its_distance);
+    self.its_slots[the_slot].add_obstacle(the_obstacle);
+  }
+  /// Hand out and forget everything that happened since the last call
+  pub fn take_events(&mut self) -> Vec<GameEvent> {
+    std::mem::take(&mut self.its_events)
+  }
+  /// Like `take_events`, but hands the events out in `the_events`, which
+  /// has to be empty, and keeps its storage for the next ones so that
+  /// nothing is allocated
+  pub fn swap_events(&mut self, the_events: &mut Vec<GameEvent>) -> () {
+    debug_assert!(the_events.is_empty());
+    std::mem::swap(&mut self.its_events, the_events);
+    self.its_events.reserve(EVENT_CAPACITY);
+  }
   pub fn get_style(&self) -> &Style {
     &self.its_style
   }
   pub fn get_style_mut(&mut self) -> &mut Style {
     &mut self.its_style
   }
+  /// The slot that `the_position` lies on. Slots without a width are never
+  /// picked, they have collapsed.
   pub fn get_slot_idx_at_position(&self, the_position: f32) -> usize {
-    // we are on a slot if it's a) wider than 0 and b) the slot's right
-    // border is the first that is greater than position
-    let slots = self.get_slots();
-    let slot_width_sum = self.get_slot_width_sum(); // in [0, 6], position in [0, 1)
-    let mut s = 0; // the index of the slot we're on according to `position`
-                   // we are on slot s if position in [left, right).
-    let mut x = slots[0].get_width();
-    while x <= the_position * slot_width_sum {
-      x += slots[(s + 1) % slots.len()].get_width();
-      s += 1;
-    }
-    assert!(
-      s < slots.len(),
-      "Target slot out of bounds (${s}/${this.slots.length})",
-    );
-    return s;
+    // we are on the last slot with a width whose left border is not greater
+    // than the position. Positions just below 1 may round up to the full
+    // sum, they belong to the last slot with a width all the same.
+    let a_target = the_position * self.get_slot_width_sum();
+    let mut a_found = None;
+    let mut a_left = 0.;
+    for (a_idx, a_slot) in self.its_slots.iter().enumerate() {
+      if a_slot.get_width() <= 0. {
+        continue;
+      }
+      if a_found.is_some() && a_left > a_target {
+        break;
+      }
+      a_found = Some(a_idx);
+      a_left += a_slot.get_width();
+    }
+    a_found.unwrap_or(0)
   }
   pub fn get_current_slot_idx(&self) -> usize {
     self.get_slot_idx_at_position(self.its_player_position)
   }
+  /// The slot at `the_position` and how far across it the position is,
+  /// from 0 to 1
+  pub fn get_position_in_slot(&self, the_position: f32) -> (usize, f32) {
+    let a_slot = self.get_slot_idx_at_position(the_position);
+    let a_sum = self.get_slot_width_sum();
+    let a_left = self.its_slots[..a_slot]
+      .iter()
+      .fold(0., |the_acc, the_slot| the_acc + the_slot.get_width())
+      / a_sum;
+    let a_width = self.its_slots[a_slot].get_width() / a_sum;
+    if a_width <= 0. {
+      return (a_slot, 0.5);
+    }
+    (a_slot, ((the_position - a_left) / a_width).clamp(0., 1.))
+  }
 
   pub fn get_slot_width_sum(&self) -> f32 {
     self
@@ -209,4 +1656,578 @@ impl GameState {
   pub fn is_running(&self) -> bool {
     self.its_is_running
   }
+  pub fn get_run(&self) -> &Run {
+    &self.its_run
+  }
+  pub fn get_run_mut(&mut self) -> &mut Run {
+    &mut self.its_run
+  }
+  /// Simulate the runs started from now on at `the_rate` ticks per second
+  pub fn set_tick_rate(&mut self, the_rate: u32) -> () {
+    self.its_tick_rate = the_rate.clamp(constants::MIN_TICK_RATE, constants::MAX_TICK_RATE);
+  }
+  /// Length of a tick of the current run
+  pub fn get_tick_duration(&self) -> Duration {
+    self.its_run.get_tick_duration()
+  }
+  pub fn is_invincible(&self) -> bool {
+    self.its_invincible
+  }
+  pub fn set_invincible(&mut self, the_invincible: bool) -> () {
+    self.its_invincible = the_invincible;
+  }
+  pub fn is_screen_relative(&self) -> bool {
+    self.its_screen_relative
+  }
+  pub fn set_screen_relative(&mut self, the_screen_relative: bool) -> () {
+    self.its_screen_relative = the_screen_relative;
+  }
+  pub fn is_strict_math(&self) -> bool {
+    self.its_strict_math
+  }
+  /// Takes effect for what changes from now on, the values so far are only
+  /// rounded as they change
+  pub fn set_strict_math(&mut self, the_strict_math: bool) -> () {
+    self.its_strict_math = the_strict_math;
+  }
+  /// `the_value` on the fixed-point grid in strict math mode, as is
+  /// otherwise
+  fn to_grid(&self, the_value: f32) -> f32 {
+    if self.its_strict_math {
+      fixed::snap(the_value)
+    } else {
+      the_value
+    }
+  }
+  /// How far the playfield turns during `the_delta`
+  pub fn get_rotation_step(&self, the_delta: Duration) -> f32 {
+    self.to_grid(self.get_effective_rotation_speed() * the_delta.as_secs_f32())
+  }
+  /// How far obstacles approach during `the_delta`, before their own speed
+  /// factor
+  pub fn get_obstacle_step(&self, the_delta: Duration) -> f32 {
+    self.to_grid(self.get_effective_obstacle_speed() * constants::get_speed_scale(the_delta))
+  }
+  /// Effects of collected pickups and how long they still last
+  pub fn get_power_ups(&self) -> &[(PowerUp, Duration)] {
+    &self.its_power_ups
+  }
+  pub fn has_power_up(&self, the_power_up: PowerUp) -> bool {
+    self
+      .its_power_ups
+      .iter()
+      .any(|(the_active, _)| *the_active == the_power_up)
+  }
+  /// Size of the cursor relative to its normal one
+  pub fn get_cursor_scale(&self) -> f32 {
+    if self.has_power_up(PowerUp::ShrinkCursor) {
+      SHRINK_CURSOR_FACTOR
+    } else {
+      1.
+    }
+  }
+  pub fn get_goal(&self) -> Option<Duration> {
+    self.its_goal
+  }
+  pub fn set_goal(&mut self, the_goal: Option<Duration>) -> () {
+    self.its_goal = the_goal;
+  }
+  /// Queue `the_command` for the start of the next tick. It replaces a
+  /// queued command of the same kind, so that the queue stays short while
+  /// no ticks run, e.g. during a level's intro.
+  pub fn submit_style_command(&mut self, the_command: StyleCommand) -> () {
+    for a_queued in self.its_style_commands.iter_mut() {
+      if a_queued.absorb(&the_command) {
+        return;
+      }
+    }
+    self.its_style_commands.push(the_command);
+  }
+  fn apply_style_commands(&mut self) -> () {
+    let a_commands = std::mem::take(&mut self.its_style_commands);
+    for a_command in a_commands {
+      match a_command {
+        StyleCommand::SetRotationSpeed(a_speed) => self.set_rotation_speed(a_speed),
+        StyleCommand::FadePalette {
+          slot_colors,
+          obstacle_colors,
+        } => {
+          if let Some(a_colors) = slot_colors {
+            self.its_style.set_slot_colors(a_colors);
+          }
+          if let Some(a_colors) = obstacle_colors {
+            self.its_style.set_obstacle_colors(a_colors);
+          }
+        }
+        StyleCommand::PulseZoom(a_zoom) => self.its_style.set_zoom(a_zoom),
+      }
+    }
+  }
+  /// Advance the simulation by one tick of `get_tick_duration`
+  pub fn tick(&mut self) -> () {
+    let a_delta = self.get_tick_duration();
+    self.apply_style_commands();
+    self.its_style.tick(a_delta);
+    // the playfield keeps spinning on the game over screen
+    let a_rotation = self.its_style.get_rotation() + self.get_rotation_step(a_delta);
+    let a_rotation = self.to_grid(a_rotation).rem_euclid(1.);
+    self.its_style.set_rotation(a_rotation);
+    if !self.its_is_running {
+      return;
+    }
+    self.its_run.its_ticks += 1;
+    for (_, a_left) in self.its_power_ups.iter_mut() {
+      *a_left = a_left.saturating_sub(a_delta);
+    }
+    self
+      .its_power_ups
+      .retain(|(_, the_left)| *the_left > Duration::from_secs(0));
+    let a_points = if self.has_power_up(PowerUp::ScoreMultiplier) {
+      SCORE_MULTIPLIER
+    } else {
+      1
+    };
+    let a_distance = self.get_obstacle_step(a_delta);
+    let a_strict_math = self.its_strict_math;
+    let a_outer_ring_speed = self.its_outer_ring_speed;
+    let a_despawn_distance = self.its_despawn_distance;
+    let a_swell = self.get_swell();
+    for (a_idx, a_slot) in self.its_slots.iter_mut().enumerate() {
+      for a_obstacle in a_slot.its_obstacles.iter_mut() {
+        a_obstacle.set_swell(a_swell);
+      }
+      let (a_passed, a_cleared) =
+        a_slot.advance_obstacles(a_distance, a_outer_ring_speed, a_despawn_distance);
+      a_slot.advance_pickups(a_distance);
+      if a_strict_math {
+        // e.g. the outer ring's obstacles move at a fraction of the step
+        a_slot.snap_distances();
+      }
+      for _ in 0..a_passed {
+        self.its_run.note_wall_passed(a_points);
+        self.its_events.push(GameEvent::WallPassed { slot: a_idx });
+      }
+      for _ in 0..a_cleared {
+        self
+          .its_events
+          .push(GameEvent::ObstacleCleared { slot: a_idx });
+      }
+    }
+    self.update_stage();
+    self.collect_pickups();
+    let a_hugging = self.is_hugging();
+    self.its_run.note_hugging(a_hugging, a_delta);
+    if self.is_colliding() {
+      // also when invincible, a hit is no pass
+      self.its_run.its_combo = 0;
+      if self.its_invincible {
+        self.its_run.mark_cheated();
+      } else {
+        self.die();
+      }
+    }
+    let a_reached = self
+      .its_goal
+      .is_some_and(|the_goal| self.its_run.get_time() >= the_goal);
+    if self.its_is_running && a_reached {
+      self.its_run.its_completed = true;
+      self.its_events.push(GameEvent::Completed);
+      self.its_is_running = false;
+    }
+  }
+  /// Start the effects of the pickups the cursor touches in its slot
+  fn collect_pickups(&mut self) -> () {
+    let a_slot = self.get_current_slot_idx();
+    let a_tip = constants::CURSOR_Y + constants::CURSOR_H * self.get_cursor_scale();
+    let a_pickups = &mut self.its_slots[a_slot].its_pickups;
+    while let Some(a_idx) = a_pickups
+      .iter()
+      .position(|the_pickup| the_pickup.overlaps(constants::CURSOR_Y, a_tip))
+    {
+      let a_power_up = a_pickups.remove(a_idx).its_power_up;
+      self
+        .its_power_ups
+        .retain(|(the_active, _)| *the_active != a_power_up);
+      self.its_power_ups.push((a_power_up, POWER_UP_DURATION));
+      self.its_events.push(GameEvent::PickupCollected {
+        slot: a_slot,
+        power_up: a_power_up,
+      });
+    }
+  }
+  /// Whether an obstacle has reached the tip of the cursor
+  pub fn is_colliding(&self) -> bool {
+    self.is_blocked_at(self.its_player_position)
+  }
+  /// Whether a corner of the cursor's base is up against the side of a
+  /// wall, see `HUG_MARGIN`
+  pub fn is_hugging(&self) -> bool {
+    let a_reach = constants::CURSOR_W * self.get_cursor_scale() / 2. + HUG_MARGIN;
+    let a_position = self.its_player_position;
+    self.is_point_blocked(a_position - a_reach, constants::CURSOR_Y)
+      || self.is_point_blocked(a_position + a_reach, constants::CURSOR_Y)
+  }
+  /// Whether an obstacle covers the cursor tip's radius at `the_position`
+  pub fn is_blocked_at(&self, the_position: f32) -> bool {
+    let a_tip = constants::CURSOR_Y + constants::CURSOR_H * self.get_cursor_scale();
+    self.is_point_blocked(the_position, a_tip)
+  }
+  /// Whether an obstacle covers the radius `the_y` at `the_position`, which
+  /// wraps around at 1
+  fn is_point_blocked(&self, the_position: f32, the_y: f32) -> bool {
+    let mut a_position = the_position.rem_euclid(1.);
+    if a_position >= 1. {
+      a_position = 0.;
+    }
+    let (a_slot, a_x) = self.get_position_in_slot(a_position);
+    let a_in_slot = self.its_slots[a_slot]
+      .get_obstacles()
+      .iter()
+      .any(|the_obstacle| the_obstacle.covers_at(a_x, the_y));
+    a_in_slot || self.is_ring_blocked(a_position, the_y)
+  }
+  /// Whether a wall that spans the whole ring covers the radius `the_y` at
+  /// `the_position`, i.e. its gap is elsewhere
+  fn is_ring_blocked(&self, the_position: f32, the_y: f32) -> bool {
+    self.its_slots.iter().enumerate().any(|(a_idx, a_slot)| {
+      a_slot
+        .get_obstacles()
+        .iter()
+        .any(|the_obstacle| match the_obstacle.get_gap() {
+          Some(a_gap) if the_obstacle.covers(the_y) => {
+            let a_center = self.get_gap_center(a_idx, a_gap, the_obstacle.get_distance());
+            !a_gap.contains(a_center, the_position)
+          }
+          _ => false,
+        })
+    })
+  }
+  /// How close the nearest obstacle in `the_slot` has come to the cursor,
+  /// from 0 while it is `PROXIMITY_RANGE` or farther away to 1 once it
+  /// reaches it. Walls with a gap count where the gap is not.
+  pub fn get_proximity(&self, the_slot: usize) -> f32 {
+    let a_center = self.get_slot_center(the_slot);
+    let mut a_nearest = f32::INFINITY;
+    for (a_idx, a_slot) in self.its_slots.iter().enumerate() {
+      for a_obstacle in a_slot.get_obstacles() {
+        let a_counts = match a_obstacle.get_gap() {
+          Some(a_gap) => {
+            let a_gap_center = self.get_gap_center(a_idx, a_gap, a_obstacle.get_distance());
+            !a_gap.contains(a_gap_center, a_center)
+          }
+          None => a_idx == the_slot,
+        };
+        if let Some(a_clearance) = a_obstacle.get_clearance().filter(|_| a_counts) {
+          a_nearest = a_nearest.min(a_clearance);
+        }
+      }
+    }
+    (1. - a_nearest / PROXIMITY_RANGE).max(0.)
+  }
+  /// Ring position of the middle of `the_slot`
+  pub fn get_slot_center(&self, the_slot: usize) -> f32 {
+    let a_left = self.its_slots[..the_slot]
+      .iter()
+      .fold(0., |the_acc, the_slot| the_acc + the_slot.get_width());
+    (a_left + self.its_slots[the_slot].get_width() / 2.) / self.get_slot_width_sum()
+  }
+  /// Ring position of the middle of `the_gap` of a wall in `the_slot` whose
+  /// near end is at `the_distance`
+  pub fn get_gap_center(&self, the_slot: usize, the_gap: &Gap, the_distance: f32) -> f32 {
+    (self.get_slot_center(the_slot) + the_gap.get_spin() * the_distance).rem_euclid(1.)
+  }
+  /// For the left and right corner of the cursor's base and its tip, whether
+  /// an obstacle covers it with the cursor at `the_position`
+  pub fn get_blocked_corners(&self, the_position: f32) -> [bool; 3] {
+    let a_half_width = constants::CURSOR_W * self.get_cursor_scale() / 2.;
+    [
+      self.is_point_blocked(the_position - a_half_width, constants::CURSOR_Y),
+      self.is_point_blocked(the_position + a_half_width, constants::CURSOR_Y),
+      self.is_blocked_at(the_position),
+    ]
+  }
+  /// Record a split for every stage the dodged walls have unlocked
+  fn update_stage(&mut self) -> () {
+    let a_run = &mut self.its_run;
+    while let Some((_, a_walls)) = STAGES.get(a_run.its_splits.len()) {
+      if a_run.its_walls_dodged < *a_walls {
+        break;
+      }
+      self.its_events.push(GameEvent::StageReached {
+        stage: a_run.its_splits.len(),
+      });
+      a_run.its_splits.push(a_run.get_time());
+    }
+  }
+  fn die(&mut self) -> () {
+    self.its_events.push(GameEvent::Died);
+    self.its_is_running = false;
+    self.its_style.set_flash_time(constants::FLASH_DURATION);
+  }
+  pub fn clear_obstacles(&mut self) -> () {
+    for a_slot in self.its_slots.iter_mut() {
+      a_slot.its_obstacles.clear();
+      a_slot.its_pickups.clear();
+    }
+  }
+  /// Take over player and obstacle speed of `the_other`, along with where
+  /// its obstacles appear and vanish
+  pub fn copy_speeds_from(&mut self, the_other: &GameState) -> () {
+    self.its_speed_curve = the_other.its_speed_curve;
+    self.its_obstacle_speed = the_other.its_obstacle_speed;
+    self.its_outer_ring_speed = the_other.its_outer_ring_speed;
+    self.its_spawn_distance = the_other.its_spawn_distance;
+    self.its_despawn_distance = the_other.its_despawn_distance;
+  }
+  /// Start a new run of the current level
+  pub fn restart(&mut self, the_seed: u64) -> () {
+    self.clear_obstacles();
+    self.its_events.clear();
+    self.its_run = Run::new(the_seed, self.its_tick_rate);
+    self.its_power_ups.clear();
+    self.its_held_direction = 0;
+    self.its_held_speed = 0.;
+    // meant for the previous run
+    self.its_style_commands.clear();
+    self.its_is_running = true;
+  }
+  pub fn set_running(&mut self, the_running: bool) -> () {
+    self.its_is_running = the_running;
+  }
+  pub fn apply_level(&mut self, the_level: &LevelDef) -> () {
+    self.its_speed_curve = the_level
+      .player_speed_curve
+      .unwrap_or_else(|| SpeedCurve::constant(the_level.player_speed));
+    self.its_obstacle_speed = the_level.obstacle_speed;
+    self.its_outer_ring_speed = the_level.outer_ring_speed;
+    self.its_spawn_distance = the_level.spawn_distance.max(constants::MIN_SPAWN_DISTANCE);
+    self.its_despawn_distance = the_level
+      .despawn_distance
+      .map_or(self.its_spawn_distance, |the_distance| {
+        the_distance.max(constants::MIN_SPAWN_DISTANCE)
+      });
+    self.its_bpm = the_level.bpm;
+    self.set_rotation_speed(the_level.rotation_speed);
+    self.its_style.set_outer_hexagon_width(
+      the_level
+        .outer_hexagon_width
+        .unwrap_or(constants::OUTER_HEXAGON_Y - constants::INNER_HEXAGON_Y),
+    );
+    self
+      .its_style
+      .set_outer_hexagon_dashes(the_level.outer_hexagon_dashes);
+    self.its_style.set_hue_speed(the_level.hue_speed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{GameEvent, GameState, Gap, Obstacle, Pickup, PowerUp, Pulse, StyleCommand};
+  use crate::constants;
+  use crate::fixed;
+  use crate::level::GapDef;
+  use crate::solvability::Solver;
+
+  /// A game with a run in progress and nothing on the playfield
+  fn started() -> GameState {
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    a_game
+  }
+
+  #[test]
+  fn style_commands_wait_for_the_next_tick() {
+    let mut a_game = started();
+    a_game.submit_style_command(StyleCommand::SetRotationSpeed(0.2));
+    a_game.submit_style_command(StyleCommand::PulseZoom(0.9));
+    a_game.submit_style_command(StyleCommand::SetRotationSpeed(-0.3));
+    assert_eq!(a_game.get_rotation_speed(), 0.);
+    assert_eq!(a_game.get_style().get_zoom(), 1.);
+    a_game.tick();
+    // only the latest of each kind counts
+    assert_eq!(a_game.get_rotation_speed(), -0.3);
+    assert_eq!(a_game.get_style().get_zoom(), 0.9);
+    // a new run does not take over what was meant for the previous one
+    a_game.submit_style_command(StyleCommand::SetRotationSpeed(0.5));
+    a_game.restart(2);
+    a_game.tick();
+    assert_eq!(a_game.get_rotation_speed(), -0.3);
+  }
+
+  #[test]
+  fn passed_walls_build_a_combo_that_hugging_loses() {
+    let mut a_game = started();
+    a_game.set_position(a_game.get_slot_center(0));
+    for a_idx in 0..12 {
+      a_game.add_obstacle(3, Obstacle::new_at(0.1 + a_idx as f32 * 0.2, 0.08));
+    }
+    while a_game.get_run().get_walls_dodged() < 12 {
+      a_game.tick();
+    }
+    // the last two walls count double
+    assert_eq!(a_game.get_run().get_combo(), 12);
+    assert_eq!(a_game.get_run().get_combo_multiplier(), 2);
+    assert_eq!(a_game.get_run().get_score(), 14);
+    // right up against a long wall in the next slot
+    let a_edge = (a_game.get_slot_center(0) + a_game.get_slot_center(1)) / 2.;
+    a_game.set_position(a_edge - constants::CURSOR_W / 2. - 0.002);
+    a_game.add_obstacle(1, Obstacle::new_at(constants::CURSOR_Y, 5.));
+    assert!(a_game.is_hugging());
+    for _ in 0..a_game.get_run().get_tick_rate() / 2 {
+      a_game.tick();
+    }
+    assert_eq!(a_game.get_run().get_combo(), 12);
+    for _ in 0..a_game.get_run().get_tick_rate() {
+      a_game.tick();
+    }
+    assert!(a_game.is_running());
+    assert_eq!(a_game.get_run().get_combo(), 0);
+  }
+
+  #[test]
+  fn pulsing_walls_swell_on_the_beat() {
+    let mut a_game = started();
+    let mut a_obstacle = Obstacle::new_at(1., 0.08);
+    a_obstacle.set_pulse(Some(Pulse::new(1., 0.5)));
+    a_game.add_obstacle(0, a_obstacle);
+    let a_wall = |the_game: &GameState| the_game.get_slots()[0].get_obstacles()[0].clone();
+    // the run starts on a beat
+    assert!((a_wall(&a_game).get_height() - 0.16).abs() < 1e-5);
+    assert_eq!(a_wall(&a_game).get_span(), (0., 1.));
+    // halfway to the next beat at the default tempo
+    for _ in 0..15 {
+      a_game.tick();
+    }
+    assert!((a_wall(&a_game).get_height() - 0.1).abs() < 1e-5);
+    let (a_left, a_right) = a_wall(&a_game).get_span();
+    assert!((a_left - 0.1875).abs() < 1e-5 && (a_right - 0.8125).abs() < 1e-5);
+    assert!(!a_wall(&a_game).covers_at(0.1, 0.95));
+    assert!(a_wall(&a_game).covers_at(0.5, 0.95));
+  }
+
+  #[test]
+  fn proximity_rises_as_walls_close_in() {
+    let a_tip = constants::CURSOR_Y + constants::CURSOR_H;
+    let mut a_game = GameState::new();
+    a_game.add_obstacle(2, Obstacle::new_at(1., 0.08));
+    assert_eq!(a_game.get_proximity(2), 0.);
+    a_game.add_obstacle(2, Obstacle::new_at(a_tip + 0.25, 0.08));
+    assert!((a_game.get_proximity(2) - 0.5).abs() < 1e-5);
+    assert_eq!(a_game.get_proximity(3), 0.);
+    // walls with a gap are close everywhere but in their gap
+    let mut a_ring = Obstacle::new_at(a_tip, 0.08);
+    a_ring.set_gap(Some(Gap::new(1. / 6., 0.)));
+    a_game.add_obstacle(0, a_ring);
+    assert_eq!(a_game.get_proximity(0), 0.);
+    assert_eq!(a_game.get_proximity(3), 1.);
+  }
+
+  #[test]
+  fn gap_walls_block_all_but_their_turning_gap() {
+    let a_ring = |the_gap: Gap, the_distance: f32| {
+      let mut a_game = started();
+      let mut a_obstacle = Obstacle::new_at(the_distance, 0.08);
+      a_obstacle.set_gap(Some(the_gap));
+      a_game.add_obstacle(0, a_obstacle);
+      a_game
+    };
+    let a_gap = GapDef {
+      width: 1. / 6.,
+      spin: 4.,
+    }
+    .to_gap(false);
+    let a_game = a_ring(a_gap.clone(), constants::CURSOR_Y);
+    let a_center = a_game.get_gap_center(0, &a_gap, constants::CURSOR_Y);
+    assert!(!a_game.is_blocked_at(a_center));
+    assert!(a_game.is_blocked_at(a_center + 0.5));
+    // the gap has turned away from the wall's slot on the way in
+    assert!(a_game.is_blocked_at(a_game.get_slot_center(0)));
+    let mut a_solver = Solver::new();
+    assert!(a_solver.is_solvable(&a_ring(a_gap, 0.3), &[]));
+    assert!(!a_solver.is_solvable(&a_ring(Gap::new(0., 0.), 0.3), &[]));
+  }
+
+  #[test]
+  fn pickups_ride_in_and_take_effect() {
+    let mut a_game = started();
+    a_game.set_position(a_game.get_slot_center(0));
+    let a_pickup = Pickup::new_at(a_game.get_spawn_distance(), PowerUp::ShrinkCursor);
+    a_game.add_pickup(0, a_pickup);
+    let mut a_collected = false;
+    for _ in 0..2000 {
+      a_game.tick();
+      a_collected |= a_game.take_events().iter().any(|the_event| {
+        matches!(
+          the_event,
+          GameEvent::PickupCollected {
+            power_up: PowerUp::ShrinkCursor,
+            ..
+          }
+        )
+      });
+      if a_collected {
+        break;
+      }
+    }
+    assert!(a_collected);
+    assert!(a_game.has_power_up(PowerUp::ShrinkCursor));
+    assert!(a_game.get_cursor_scale() < 1.);
+  }
+
+  #[test]
+  fn reversed_walls_move_out_past_the_cursor() {
+    let mut a_game = started();
+    a_game.set_position(a_game.get_slot_center(0));
+    let mut a_wall = Obstacle::new_at(constants::REVERSE_SPAWN_DISTANCE - 0.08, 0.08);
+    a_wall.set_reversed(true);
+    a_game.add_obstacle(3, a_wall);
+    let (mut a_passed, mut a_cleared) = (0, 0);
+    for _ in 0..1000 {
+      a_game.tick();
+      for a_event in a_game.take_events() {
+        match a_event {
+          GameEvent::WallPassed { .. } => a_passed += 1,
+          GameEvent::ObstacleCleared { .. } => a_cleared += 1,
+          _ => (),
+        }
+      }
+    }
+    assert_eq!((a_passed, a_cleared), (1, 1));
+  }
+
+  #[test]
+  fn strict_math_plays_like_native_math() {
+    let a_simulate = |the_strict_math: bool| {
+      let mut a_game = GameState::new();
+      a_game.set_strict_math(the_strict_math);
+      a_game.restart(7);
+      a_game.set_rotation_speed(0.3);
+      a_game.set_position(a_game.get_slot_center(0));
+      // five minutes with a wall every second in the opposite slot
+      for a_tick in 0..18000 {
+        if a_tick % 60 == 0 {
+          let a_distance = a_game.get_spawn_distance();
+          a_game.add_obstacle(3, Obstacle::new_at(a_distance, 0.08));
+        }
+        a_game.tick();
+        a_game.take_events();
+      }
+      a_game
+    };
+    let a_native = a_simulate(false);
+    let a_strict = a_simulate(true);
+    let a_on_grid = |the_value: f32| fixed::snap(the_value) == the_value;
+    assert!(a_on_grid(a_strict.get_style().get_rotation()));
+    for a_slot in a_strict.get_slots() {
+      for a_obstacle in a_slot.get_obstacles() {
+        assert!(a_on_grid(a_obstacle.get_distance()));
+      }
+    }
+    // rounding the steps only lets the modes drift apart by a few ticks
+    let a_turned = a_strict.get_style().get_rotation() - a_native.get_style().get_rotation();
+    assert!(((a_turned + 0.5).rem_euclid(1.) - 0.5).abs() < 0.02);
+    let a_dodged = |the_game: &GameState| the_game.get_run().get_walls_dodged() as i64;
+    assert!(a_dodged(&a_strict) > 100);
+    assert!((a_dodged(&a_strict) - a_dodged(&a_native)).abs() <= 1);
+  }
 }
@@ -0,0 +1,338 @@
+use super::model;
+use super::renderer::{gl_check_error, gl_get_attrib_location, gl_get_uniform_location};
+use gl::types::*;
+use serde::{Deserialize, Serialize};
+
+/// Offset of the red and blue channels at the image border right after a
+/// death, in texture coordinates
+const ABERRATION_STRENGTH: f32 = 0.03;
+/// How much the dark lines between two scanlines dim the image
+const SCANLINE_STRENGTH: f32 = 0.35;
+/// Barrel distortion of the CRT filter
+const CRT_CURVATURE: f32 = 0.06;
+/// How much the image is darkened in the corners
+const VIGNETTE_STRENGTH: f32 = 0.6;
+
+/// Optional full-screen effects applied when the offscreen image is copied
+/// to the window. Levels and settings can each switch effects on.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct PostEffects {
+    /// Split the color channels for a moment when the player dies
+    pub chromatic_aberration: bool,
+    /// Scanlines and a slightly curved screen
+    pub crt: bool,
+    /// Darken the image towards its corners
+    pub vignette: bool,
+}
+
+impl PostEffects {
+    pub fn any(&self) -> bool {
+        self.chromatic_aberration || self.crt || self.vignette
+    }
+    /// Every effect that is enabled in `self` or `the_other`
+    pub fn union(&self, the_other: &PostEffects) -> PostEffects {
+        PostEffects {
+            chromatic_aberration: self.chromatic_aberration || the_other.chromatic_aberration,
+            crt: self.crt || the_other.crt,
+            vignette: self.vignette || the_other.vignette,
+        }
+    }
+}
+
+const VS_SRC: &[u8] = b"
+#version 100
+precision mediump float;
+attribute vec2 vertex;
+varying vec2 uv;
+void main() {
+    uv = vertex * 0.5 + 0.5;
+    gl_Position = vec4(vertex, 0., 1.);
+}
+\0";
+
+/// Copies the offscreen image to the screen, applying the post effects.
+/// Every effect is disabled by a strength of 0.
+const POST_FS_SRC: &[u8] = b"
+#version 100
+precision mediump float;
+uniform sampler2D image;
+// size of the window in pixels
+uniform vec2 resolution;
+// offset of the red and blue channels at the border
+uniform float aberration;
+uniform float scanlines;
+uniform float curvature;
+uniform float vignette;
+varying vec2 uv;
+float PI = 3.14159265359;
+void main() {
+    vec2 p = uv;
+    if (curvature > 0.) {
+        vec2 c = p * 2. - 1.;
+        c *= 1. + curvature * dot(c.yx, c.yx);
+        p = c * 0.5 + 0.5;
+        if (p.x < 0. || p.x > 1. || p.y < 0. || p.y > 1.) {
+            gl_FragColor = vec4(0., 0., 0., 1.);
+            return;
+        }
+    }
+    vec2 shift = (p - 0.5) * aberration;
+    vec3 col = vec3(
+        texture2D(image, p + shift).r,
+        texture2D(image, p).g,
+        texture2D(image, p - shift).b);
+    float line = 0.5 + 0.5 * sin(p.y * resolution.y * PI);
+    col *= 1. - scanlines * (1. - line);
+    float d = length(uv - 0.5) * 1.41421356;
+    col *= 1. - vignette * d * d;
+    gl_FragColor = vec4(col, 1.);
+}
+\0";
+
+/// Covers the whole target in a (translucent) color
+const FILL_FS_SRC: &[u8] = b"
+#version 100
+precision mediump float;
+uniform vec3 color;
+uniform float alpha;
+varying vec2 uv;
+void main() {
+    gl_FragColor = vec4(color, alpha);
+}
+\0";
+
+/// Two triangles covering the viewport in normalized device coordinates
+const QUAD: [f32; 12] = [-1., -1., 1., -1., -1., 1., 1., -1., 1., 1., -1., 1.];
+
+fn create_program(the_fs_src: &[u8]) -> GLuint {
+    unsafe {
+        let vs = gl::CreateShader(gl::VERTEX_SHADER);
+        gl::ShaderSource(
+            vs,
+            1,
+            [VS_SRC.as_ptr() as *const _].as_ptr(),
+            std::ptr::null(),
+        );
+        gl::CompileShader(vs);
+        let fs = gl::CreateShader(gl::FRAGMENT_SHADER);
+        gl::ShaderSource(
+            fs,
+            1,
+            [the_fs_src.as_ptr() as *const _].as_ptr(),
+            std::ptr::null(),
+        );
+        gl::CompileShader(fs);
+        let program = gl::CreateProgram();
+        gl::AttachShader(program, vs);
+        gl::AttachShader(program, fs);
+        gl::LinkProgram(program);
+        // the shaders go away with the program
+        gl::DeleteShader(vs);
+        gl::DeleteShader(fs);
+        gl_check_error();
+
+        program
+    }
+}
+
+/// A color buffer that the game can be rendered into instead of the window.
+/// Unlike the window's back buffer, its contents survive between frames.
+pub struct OffscreenTarget {
+    its_framebuffer: GLuint,
+    its_texture: GLuint,
+    its_quad_glbuf: GLuint,
+    its_post_program: GLuint,
+    its_post_vertex_loc: GLint,
+    its_resolution_loc: Option<GLint>,
+    its_aberration_loc: Option<GLint>,
+    its_scanlines_loc: Option<GLint>,
+    its_curvature_loc: Option<GLint>,
+    its_vignette_loc: Option<GLint>,
+    its_fill_program: GLuint,
+    its_fill_vertex_loc: GLint,
+    its_fill_color_loc: Option<GLint>,
+    its_fill_alpha_loc: Option<GLint>,
+    its_width: u32,
+    its_height: u32,
+}
+
+impl OffscreenTarget {
+    pub fn new(the_width: u32, the_height: u32) -> OffscreenTarget {
+        let a_post_program = create_program(POST_FS_SRC);
+        let a_fill_program = create_program(FILL_FS_SRC);
+        let mut a_target = unsafe {
+            let mut a_framebuffer = std::mem::zeroed();
+            gl::GenFramebuffers(1, &mut a_framebuffer);
+            let mut a_texture = std::mem::zeroed();
+            gl::GenTextures(1, &mut a_texture);
+            let mut a_quad_glbuf = std::mem::zeroed();
+            gl::GenBuffers(1, &mut a_quad_glbuf);
+            gl::BindBuffer(gl::ARRAY_BUFFER, a_quad_glbuf);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (QUAD.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                QUAD.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            OffscreenTarget {
+                its_framebuffer: a_framebuffer,
+                its_texture: a_texture,
+                its_quad_glbuf: a_quad_glbuf,
+                its_post_program: a_post_program,
+                its_post_vertex_loc: gl_get_attrib_location(a_post_program, "vertex").unwrap(),
+                its_resolution_loc: gl_get_uniform_location(a_post_program, "resolution"),
+                its_aberration_loc: gl_get_uniform_location(a_post_program, "aberration"),
+                its_scanlines_loc: gl_get_uniform_location(a_post_program, "scanlines"),
+                its_curvature_loc: gl_get_uniform_location(a_post_program, "curvature"),
+                its_vignette_loc: gl_get_uniform_location(a_post_program, "vignette"),
+                its_fill_program: a_fill_program,
+                its_fill_vertex_loc: gl_get_attrib_location(a_fill_program, "vertex").unwrap(),
+                its_fill_color_loc: gl_get_uniform_location(a_fill_program, "color"),
+                its_fill_alpha_loc: gl_get_uniform_location(a_fill_program, "alpha"),
+                its_width: 0,
+                its_height: 0,
+            }
+        };
+        a_target.resize(the_width, the_height);
+        a_target
+    }
+
+    /// Reallocate the color buffer, which discards its contents
+    pub fn resize(&mut self, the_width: u32, the_height: u32) -> () {
+        let a_width = the_width.max(1);
+        let a_height = the_height.max(1);
+        if a_width == self.its_width && a_height == self.its_height {
+            return;
+        }
+        self.its_width = a_width;
+        self.its_height = a_height;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.its_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as GLint,
+                a_width as GLsizei,
+                a_height as GLsizei,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_EDGE as GLint,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.its_framebuffer);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.its_texture,
+                0,
+            );
+            gl::ClearColor(0., 0., 0., 1.);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl_check_error();
+        }
+    }
+
+    /// Direct all following draw calls into the target
+    pub unsafe fn bind(&self) -> () {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.its_framebuffer);
+    }
+
+    unsafe fn bind_quad(&self, the_vertex_loc: GLint) -> () {
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.its_quad_glbuf);
+        gl::VertexAttribPointer(
+            the_vertex_loc as GLuint,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            0,
+            std::ptr::null(),
+        );
+        gl::EnableVertexAttribArray(the_vertex_loc as GLuint);
+    }
+
+    /// Blend `the_color` over the whole target, `the_alpha` of 1 is
+    /// equivalent to clearing it. Requires the target to be bound.
+    pub unsafe fn fade(&self, the_color: &model::Color, the_alpha: f32) -> () {
+        gl::UseProgram(self.its_fill_program);
+        if let Some(a_color_loc) = self.its_fill_color_loc {
+            gl::Uniform3f(
+                a_color_loc,
+                the_color.its_r,
+                the_color.its_g,
+                the_color.its_b,
+            );
+        }
+        if let Some(a_alpha_loc) = self.its_fill_alpha_loc {
+            gl::Uniform1f(a_alpha_loc, the_alpha);
+        }
+        self.bind_quad(self.its_fill_vertex_loc);
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl::Disable(gl::BLEND);
+    }
+
+    /// Switch back to the window and copy the target's contents onto it.
+    /// `the_death_fade` goes from 1 right after a death to 0 when the
+    /// death effects are over.
+    pub unsafe fn present(&self, the_effects: &PostEffects, the_death_fade: f32) -> () {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::UseProgram(self.its_post_program);
+        let a_set = |the_loc: Option<GLint>, the_enabled: bool, the_value: f32| {
+            if let Some(a_loc) = the_loc {
+                gl::Uniform1f(a_loc, if the_enabled { the_value } else { 0. });
+            }
+        };
+        a_set(
+            self.its_aberration_loc,
+            the_effects.chromatic_aberration,
+            ABERRATION_STRENGTH * the_death_fade,
+        );
+        a_set(self.its_scanlines_loc, the_effects.crt, SCANLINE_STRENGTH);
+        a_set(self.its_curvature_loc, the_effects.crt, CRT_CURVATURE);
+        a_set(
+            self.its_vignette_loc,
+            the_effects.vignette,
+            VIGNETTE_STRENGTH,
+        );
+        if let Some(a_resolution_loc) = self.its_resolution_loc {
+            gl::Uniform2f(
+                a_resolution_loc,
+                self.its_width as f32,
+                self.its_height as f32,
+            );
+        }
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.its_texture);
+        self.bind_quad(self.its_post_vertex_loc);
+        gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        gl_check_error();
+    }
+}
+
+impl Drop for OffscreenTarget {
+    fn drop(&mut self) -> () {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.its_framebuffer);
+            gl::DeleteTextures(1, &self.its_texture);
+            gl::DeleteBuffers(1, &self.its_quad_glbuf);
+            gl::DeleteProgram(self.its_post_program);
+            gl::DeleteProgram(self.its_fill_program);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use super::font;
+use super::model;
+use super::renderer::{gl_check_error, gl_get_attrib_location, gl_get_uniform_location};
+use gl::types::*;
+
+const VS_SRC: &[u8] = b"
+#version 100
+precision mediump float;
+attribute vec2 vertex;
+// size of the window in pixels
+uniform vec2 screen;
+void main() {
+    // vertices are given in pixels, with the origin in the top left corner
+    vec2 pos = vertex / screen * 2. - 1.;
+    gl_Position = vec4(pos.x, -pos.y, 0., 1.);
+}
+\0";
+
+const FS_SRC: &[u8] = b"
+#version 100
+precision mediump float;
+uniform vec3 color;
+uniform float alpha;
+void main() {
+    gl_FragColor = vec4(color, alpha);
+}
+\0";
+
+/// Flat, screen-aligned shapes and text drawn on top of the game, e.g. for
+/// debugging aids. Shapes are collected between `clear` and `draw`.
+pub struct Overlay {
+    its_program: GLuint,
+    its_vertex_glbuf: GLuint,
+    its_screen_loc: Option<GLint>,
+    its_color_loc: Option<GLint>,
+    its_alpha_loc: Option<GLint>,
+    its_vertex_loc: GLint,
+    /// Triangle vertices in pixels, batched by color
+    its_batches: Vec<(model::Color, Vec<f32>)>,
+    its_font: font::Font,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        let a_program = Overlay::create_program();
+        let a_buf_id = unsafe {
+            let mut a_buf_id = std::mem::zeroed();
+            gl::GenBuffers(1, &mut a_buf_id);
+            a_buf_id
+        };
+        Overlay {
+            its_program: a_program,
+            its_vertex_glbuf: a_buf_id,
+            its_screen_loc: gl_get_uniform_location(a_program, "screen"),
+            its_color_loc: gl_get_uniform_location(a_program, "color"),
+            its_alpha_loc: gl_get_uniform_location(a_program, "alpha"),
+            its_vertex_loc: gl_get_attrib_location(a_program, "vertex").unwrap(),
+            its_batches: Vec::new(),
+            its_font: font::Font::BuiltIn,
+        }
+    }
+
+    pub fn set_font(&mut self, the_font: font::Font) -> () {
+        self.its_font = the_font;
+    }
+
+    /// Size in pixels of `the_text` in the current font
+    pub fn measure_text(&self, the_text: &str, the_pixel_size: f32) -> (f32, f32) {
+        self.its_font.measure(the_text, the_pixel_size)
+    }
+
+    fn create_program() -> GLuint {
+        unsafe {
+            let vs = gl::CreateShader(gl::VERTEX_SHADER);
+            gl::ShaderSource(
+                vs,
+                1,
+                [VS_SRC.as_ptr() as *const _].as_ptr(),
+                std::ptr::null(),
+            );
+            gl::CompileShader(vs);
+            let fs = gl::CreateShader(gl::FRAGMENT_SHADER);
+            gl::ShaderSource(
+                fs,
+                1,
+                [FS_SRC.as_ptr() as *const _].as_ptr(),
+                std::ptr::null(),
+            );
+            gl::CompileShader(fs);
+            let program = gl::CreateProgram();
+            gl::AttachShader(program, vs);
+            gl::AttachShader(program, fs);
+            gl::LinkProgram(program);
+            gl_check_error();
+
+            program
+        }
+    }
+
+    fn get_batch(&mut self, the_color: &model::Color) -> &mut Vec<f32> {
+        let a_idx = match self
+            .its_batches
+            .iter()
+            .position(|(the_batch_color, _)| the_batch_color == the_color)
+        {
+            Some(a_idx) => a_idx,
+            None => {
+                self.its_batches.push((the_color.clone(), Vec::new()));
+                self.its_batches.len() - 1
+            }
+        };
+        &mut self.its_batches[a_idx].1
+    }
+
+    pub fn clear(&mut self) -> () {
+        self.its_batches.clear();
+    }
+
+    /// Add a rectangle with its top left corner at `the_x`/`the_y` pixels
+    pub fn add_rect(
+        &mut self,
+        the_x: f32,
+        the_y: f32,
+        the_width: f32,
+        the_height: f32,
+        the_color: &model::Color,
+    ) -> () {
+        let (a_x0, a_y0) = (the_x, the_y);
+        let (a_x1, a_y1) = (the_x + the_width, the_y + the_height);
+        self.get_batch(the_color).extend_from_slice(&[
+            a_x0, a_y0, a_x1, a_y0, a_x1, a_y1, a_x0, a_y0, a_x1, a_y1, a_x0, a_y1,
+        ]);
+    }
+
+    /// Add `the_text` with its top left corner at `the_x`/`the_y` pixels
+    pub fn add_text(
+        &mut self,
+        the_text: &str,
+        the_x: f32,
+        the_y: f32,
+        the_pixel_size: f32,
+        the_color: &model::Color,
+    ) -> () {
+        let mut a_vertices = Vec::new();
+        self.its_font
+            .layout(the_text, the_x, the_y, the_pixel_size, &mut a_vertices);
+        self.get_batch(the_color).extend_from_slice(&a_vertices);
+    }
+
+    /// Add `the_text` centered on `the_x`/`the_y` pixels
+    pub fn add_text_centered(
+        &mut self,
+        the_text: &str,
+        the_x: f32,
+        the_y: f32,
+        the_pixel_size: f32,
+        the_color: &model::Color,
+    ) -> () {
+        let (a_width, a_height) = self.measure_text(the_text, the_pixel_size);
+        self.add_text(
+            the_text,
+            the_x - a_width / 2.,
+            the_y - a_height / 2.,
+            the_pixel_size,
+            the_color,
+        );
+    }
+
+    /// Draw everything added since the last `clear` onto a window of
+    /// `the_width`x`the_height` pixels. Leaves the overlay program bound.
+    pub unsafe fn draw(&self, the_width: f32, the_height: f32) -> () {
+        if self.its_batches.is_empty() {
+            return;
+        }
+        gl::UseProgram(self.its_program);
+        if let Some(a_screen_loc) = self.its_screen_loc {
+            gl::Uniform2f(a_screen_loc, the_width, the_height);
+        }
+        if let Some(a_alpha_loc) = self.its_alpha_loc {
+            gl::Uniform1f(a_alpha_loc, 1.);
+        }
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.its_vertex_glbuf);
+        gl::VertexAttribPointer(
+            self.its_vertex_loc as GLuint,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            0,
+            std::ptr::null(),
+        );
+        gl::EnableVertexAttribArray(self.its_vertex_loc as GLuint);
+        for (a_color, a_vertices) in &self.its_batches {
+            if let Some(a_color_loc) = self.its_color_loc {
+                gl::Uniform3f(a_color_loc, a_color.its_r, a_color.its_g, a_color.its_b);
+            }
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (a_vertices.len() * std::mem::size_of::<f32>()) as GLsizeiptr,
+                a_vertices.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+            gl::DrawArrays(gl::TRIANGLES, 0, (a_vertices.len() / 2) as GLsizei);
+        }
+        gl_check_error();
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How far back the frame rate statistics look
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+/// How long the window size has to stay the same for a resize to count as
+/// finished
+const RESIZE_SETTLE: Duration = Duration::from_millis(200);
+
+/// Decides when the next frame is drawn so that the frame rate stays below
+/// a limit. Instead of blocking, the event loop is told to wait until the
+/// frame is due, so that input keeps being handled in the meantime.
+pub struct FramePacer {
+  /// Shortest time between two frames, none if the frame rate is uncapped
+  its_frame_duration: Option<Duration>,
+  its_last_frame: Instant,
+  its_next_frame: Instant,
+}
+
+impl FramePacer {
+  pub fn new(the_limit: Option<u32>) -> FramePacer {
+    let a_now = Instant::now();
+    let mut a_pacer = FramePacer {
+      its_frame_duration: None,
+      its_last_frame: a_now,
+      its_next_frame: a_now,
+    };
+    a_pacer.set_limit(the_limit);
+    a_pacer
+  }
+  /// Allow at most `the_limit` frames per second, any number if unset
+  pub fn set_limit(&mut self, the_limit: Option<u32>) -> () {
+    let a_duration = the_limit
+      .filter(|the_limit| *the_limit > 0)
+      .map(|the_limit| Duration::from_secs(1) / the_limit);
+    if a_duration == self.its_frame_duration {
+      return;
+    }
+    self.its_frame_duration = a_duration;
+    // e.g. a raised limit takes effect without waiting out the old one
+    self.its_next_frame = self.its_last_frame + a_duration.unwrap_or_default();
+  }
+  /// Schedule the frame after the one drawn at `the_now`
+  pub fn frame_drawn(&mut self, the_now: Instant) -> () {
+    self.its_last_frame = the_now;
+    let a_duration = match self.its_frame_duration {
+      Some(a_duration) => a_duration,
+      None => {
+        self.its_next_frame = the_now;
+        return;
+      }
+    };
+    // Frames are spaced from when they were due rather than drawn, so that
+    // the rate does not drift below the limit. After a hitch, the frames
+    // do not try to catch up though.
+    let a_next = self.its_next_frame + a_duration;
+    self.its_next_frame = if a_next < the_now {
+      the_now + a_duration
+    } else {
+      a_next
+    };
+  }
+  /// When the next frame is due, none if it is already
+  pub fn get_wait_until(&self, the_now: Instant) -> Option<Instant> {
+    if self.its_frame_duration.is_some() && self.its_next_frame > the_now {
+      Some(self.its_next_frame)
+    } else {
+      None
+    }
+  }
+}
+
+/// Tells when the user has stopped resizing the window, which reports every
+/// step of the drag as a resize of its own
+pub struct ResizeDebouncer {
+  /// When the window was last resized, none once the resize has finished
+  its_last_resize: Option<Instant>,
+}
+
+impl ResizeDebouncer {
+  pub fn new() -> ResizeDebouncer {
+    ResizeDebouncer {
+      its_last_resize: None,
+    }
+  }
+  /// Note that the window has been resized at `the_now`
+  pub fn resized(&mut self, the_now: Instant) -> () {
+    self.its_last_resize = Some(the_now);
+  }
+  /// When the resize in progress counts as finished, none if there is none
+  pub fn get_deadline(&self) -> Option<Instant> {
+    self
+      .its_last_resize
+      .map(|the_last_resize| the_last_resize + RESIZE_SETTLE)
+  }
+  /// Whether the resize in progress has finished by `the_now`. Only reports
+  /// this once per resize.
+  pub fn poll(&mut self, the_now: Instant) -> bool {
+    match self.get_deadline() {
+      Some(a_deadline) if a_deadline <= the_now => {
+        self.its_last_resize = None;
+        true
+      }
+      _ => false,
+    }
+  }
+}
+
+/// Lowest, average and highest frame rate of the last second
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FpsStats {
+  pub min: u32,
+  pub avg: u32,
+  pub max: u32,
+}
+
+/// Collects the frame times the renderer measures, for the frame rate
+/// overlay
+pub struct FpsMeter {
+  /// Time since the previous frame and the frame time in milliseconds,
+  /// oldest first
+  its_samples: VecDeque<(Duration, f32)>,
+  /// Sum of the times since the previous frame of all samples
+  its_span: Duration,
+}
+
+impl FpsMeter {
+  pub fn new() -> FpsMeter {
+    FpsMeter {
+      its_samples: VecDeque::new(),
+      its_span: Duration::from_secs(0),
+    }
+  }
+  /// Note a frame `the_delta` after the previous one, for which the
+  /// renderer reports `the_frame_time`
+  pub fn add(&mut self, the_delta: Duration, the_frame_time: f32) -> () {
+    self.its_samples.push_back((the_delta, the_frame_time));
+    self.its_span += the_delta;
+    while let Some((a_delta, _)) = self.its_samples.front() {
+      if self.its_span - *a_delta < FPS_WINDOW {
+        break;
+      }
+      self.its_span -= *a_delta;
+      self.its_samples.pop_front();
+    }
+  }
+  /// None before the first frame with a usable frame time
+  pub fn get_stats(&self) -> Option<FpsStats> {
+    let a_rates: Vec<f32> = self
+      .its_samples
+      .iter()
+      .filter(|(_, the_frame_time)| *the_frame_time > 0.)
+      .map(|(_, the_frame_time)| 1000. / the_frame_time)
+      .collect();
+    if a_rates.is_empty() {
+      return None;
+    }
+    let a_min = a_rates.iter().cloned().fold(f32::INFINITY, f32::min);
+    let a_max = a_rates.iter().cloned().fold(0., f32::max);
+    let a_avg = a_rates.iter().sum::<f32>() / a_rates.len() as f32;
+    Some(FpsStats {
+      min: a_min.round() as u32,
+      avg: a_avg.round() as u32,
+      max: a_max.round() as u32,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{FpsMeter, FramePacer, ResizeDebouncer};
+  use std::time::{Duration, Instant};
+
+  #[test]
+  fn measures_the_last_second() {
+    let mut a_meter = FpsMeter::new();
+    assert_eq!(a_meter.get_stats(), None);
+    // a slow second followed by a fast one, of which only the latter counts
+    for _ in 0..30 {
+      a_meter.add(Duration::from_millis(40), 40.);
+    }
+    for a_frame in 0..100 {
+      let a_frame_time = if a_frame % 2 == 0 { 8. } else { 12. };
+      a_meter.add(Duration::from_millis(10), a_frame_time);
+    }
+    let a_stats = a_meter.get_stats().unwrap();
+    assert_eq!(a_stats.min, 83);
+    assert_eq!(a_stats.max, 125);
+    assert_eq!(a_stats.avg, 104);
+  }
+
+  #[test]
+  fn paces_frames() {
+    // later than the first frame the pacer expects
+    let a_start = Instant::now() + Duration::from_secs(1);
+    let mut a_pacer = FramePacer::new(Some(100));
+    a_pacer.frame_drawn(a_start);
+    assert_eq!(
+      a_pacer.get_wait_until(a_start),
+      Some(a_start + Duration::from_millis(10))
+    );
+    a_pacer.frame_drawn(a_start + Duration::from_millis(12));
+    assert_eq!(
+      a_pacer.get_wait_until(a_start + Duration::from_millis(12)),
+      Some(a_start + Duration::from_millis(20))
+    );
+    // frames after a hitch are paced from the late one
+    let a_late = a_start + Duration::from_millis(55);
+    assert_eq!(a_pacer.get_wait_until(a_late), None);
+    a_pacer.frame_drawn(a_late);
+    assert_eq!(
+      a_pacer.get_wait_until(a_late),
+      Some(a_late + Duration::from_millis(10))
+    );
+    // a changed limit applies to the frame that is waited for
+    a_pacer.set_limit(Some(200));
+    assert_eq!(
+      a_pacer.get_wait_until(a_late),
+      Some(a_late + Duration::from_millis(5))
+    );
+    a_pacer.set_limit(None);
+    a_pacer.frame_drawn(a_late);
+    assert_eq!(a_pacer.get_wait_until(a_late), None);
+  }
+
+  #[test]
+  fn waits_for_the_size_to_settle() {
+    let a_start = Instant::now();
+    let a_ms = |the_ms: u64| a_start + Duration::from_millis(the_ms);
+    let mut a_debouncer = ResizeDebouncer::new();
+    assert!(!a_debouncer.poll(a_start));
+    // a drag across several frames
+    for a_step in 0..5 {
+      a_debouncer.resized(a_ms(a_step * 50));
+      assert!(!a_debouncer.poll(a_ms(a_step * 50 + 10)));
+    }
+    assert_eq!(a_debouncer.get_deadline(), Some(a_ms(400)));
+    assert!(!a_debouncer.poll(a_ms(399)));
+    assert!(a_debouncer.poll(a_ms(400)));
+    assert!(!a_debouncer.poll(a_ms(500)));
+    assert_eq!(a_debouncer.get_deadline(), None);
+  }
+}
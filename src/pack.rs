@@ -0,0 +1,112 @@
+use super::font::{Font, RasterFont};
+use super::level::LevelDef;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "pack.json";
+
+/// The contents of a pack's `pack.json`
+#[derive(Deserialize)]
+pub struct PackManifest {
+  pub name: String,
+  /// Level files relative to the pack directory
+  pub levels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+  Io(PathBuf, std::io::Error),
+  Parse(PathBuf, serde_json::Error),
+  /// A style that is its own base, directly or through others
+  StyleCycle(PathBuf),
+  Disconnected,
+}
+
+impl std::fmt::Display for LoadError {
+  fn fmt(&self, the_f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      LoadError::Io(the_path, the_err) => write!(the_f, "{}: {}", the_path.display(), the_err),
+      LoadError::Parse(the_path, the_err) => {
+        write!(the_f, "{}: {}", the_path.display(), the_err)
+      }
+      LoadError::StyleCycle(the_path) => {
+        write!(the_f, "{}: style inherits from itself", the_path.display())
+      }
+      LoadError::Disconnected => write!(the_f, "loader thread terminated unexpectedly"),
+    }
+  }
+}
+
+/// A fully loaded pack, including the raw bytes of all referenced assets
+pub struct Pack {
+  its_name: String,
+  its_dir: PathBuf,
+  its_levels: Vec<LevelDef>,
+  its_assets: HashMap<String, Vec<u8>>,
+}
+
+impl Pack {
+  pub fn new(the_name: String, the_dir: PathBuf) -> Pack {
+    Pack {
+      its_name: the_name,
+      its_dir: the_dir,
+      its_levels: Vec::new(),
+      its_assets: HashMap::new(),
+    }
+  }
+  pub fn get_name(&self) -> &str {
+    &self.its_name
+  }
+  pub fn get_dir(&self) -> &Path {
+    &self.its_dir
+  }
+  pub fn get_levels(&self) -> &Vec<LevelDef> {
+    &self.its_levels
+  }
+  pub fn add_level(&mut self, the_level: LevelDef) -> () {
+    self.its_levels.push(the_level);
+  }
+  /// Get an asset by the path it was referenced with in the pack files
+  pub fn get_asset(&self, the_path: &str) -> Option<&Vec<u8>> {
+    self.its_assets.get(the_path)
+  }
+  pub fn add_asset(&mut self, the_path: String, the_data: Vec<u8>) -> () {
+    self.its_assets.insert(the_path, the_data);
+  }
+}
+
+/// Looks up the assets a level refers to in the level's pack, falling back
+/// to the built-in defaults for anything that is missing or unusable, e.g.
+/// when playing without a pack
+pub struct AssetResolver<'a> {
+  its_pack: Option<&'a Pack>,
+}
+
+impl<'a> AssetResolver<'a> {
+  pub fn new(the_pack: Option<&'a Pack>) -> AssetResolver<'a> {
+    AssetResolver { its_pack: the_pack }
+  }
+  fn get(&self, the_file: Option<&str>) -> Option<&'a [u8]> {
+    let a_data = self.its_pack?.get_asset(the_file?)?;
+    Some(a_data.as_slice())
+  }
+  /// The font in `the_file`, the built-in one if there is none
+  pub fn get_font(&self, the_file: Option<&str>) -> Font {
+    let a_data = match self.get(the_file) {
+      Some(a_data) => a_data,
+      None => return Font::BuiltIn,
+    };
+    match RasterFont::from_truetype(a_data) {
+      Ok(a_font) => Font::Raster(a_font),
+      Err(a_err) => {
+        eprintln!(
+          "Using the built-in font instead of {}: {}",
+          the_file.unwrap_or_default(),
+          a_err
+        );
+        Font::BuiltIn
+      }
+    }
+  }
+}
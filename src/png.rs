@@ -0,0 +1,205 @@
+/// The eight bytes every PNG file starts with
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+/// Largest payload of a stored deflate block
+const MAX_STORED_BLOCK: usize = 0xffff;
+const BYTES_PER_PIXEL: usize = 4;
+
+fn crc32(the_bytes: &[u8]) -> u32 {
+  let mut a_crc = 0xffff_ffffu32;
+  for a_byte in the_bytes {
+    a_crc ^= *a_byte as u32;
+    for _ in 0..8 {
+      let a_mask = (!(a_crc & 1)).wrapping_add(1);
+      a_crc = (a_crc >> 1) ^ (0xedb8_8320 & a_mask);
+    }
+  }
+  !a_crc
+}
+
+fn adler32(the_bytes: &[u8]) -> u32 {
+  let (mut a_a, mut a_b) = (1u32, 0u32);
+  for a_byte in the_bytes {
+    a_a = (a_a + *a_byte as u32) % 65521;
+    a_b = (a_b + a_a) % 65521;
+  }
+  (a_b << 16) | a_a
+}
+
+fn write_chunk(the_out: &mut Vec<u8>, the_kind: &[u8; 4], the_data: &[u8]) -> () {
+  the_out.extend_from_slice(&(the_data.len() as u32).to_be_bytes());
+  let a_start = the_out.len();
+  the_out.extend_from_slice(the_kind);
+  the_out.extend_from_slice(the_data);
+  let a_crc = crc32(&the_out[a_start..]);
+  the_out.extend_from_slice(&a_crc.to_be_bytes());
+}
+
+/// Encode 8 bit RGBA pixels, rows from top to bottom, as a PNG. The image
+/// data is stored uncompressed, which keeps this simple and is good enough
+/// for small images like test references and pattern previews.
+pub fn encode(the_width: u32, the_height: u32, the_pixels: &[u8]) -> Vec<u8> {
+  let a_stride = the_width as usize * BYTES_PER_PIXEL;
+  assert_eq!(the_pixels.len(), a_stride * the_height as usize);
+  // every scanline is preceded by its filter type, 0 for none
+  let mut a_raw = Vec::with_capacity((a_stride + 1) * the_height as usize);
+  for a_row in the_pixels.chunks(a_stride.max(1)) {
+    a_raw.push(0);
+    a_raw.extend_from_slice(a_row);
+  }
+  let mut a_zlib = vec![0x78, 0x01];
+  let a_blocks = a_raw.chunks(MAX_STORED_BLOCK).count().max(1);
+  for a_idx in 0..a_blocks {
+    let a_start = a_idx * MAX_STORED_BLOCK;
+    let a_block = &a_raw[a_start..(a_start + MAX_STORED_BLOCK).min(a_raw.len())];
+    let a_len = a_block.len() as u16;
+    a_zlib.push(if a_idx + 1 == a_blocks { 1 } else { 0 });
+    a_zlib.extend_from_slice(&a_len.to_le_bytes());
+    a_zlib.extend_from_slice(&(!a_len).to_le_bytes());
+    a_zlib.extend_from_slice(a_block);
+  }
+  a_zlib.extend_from_slice(&adler32(&a_raw).to_be_bytes());
+
+  let mut a_header = Vec::with_capacity(13);
+  a_header.extend_from_slice(&the_width.to_be_bytes());
+  a_header.extend_from_slice(&the_height.to_be_bytes());
+  // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlacing
+  a_header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+  let mut a_png = SIGNATURE.to_vec();
+  write_chunk(&mut a_png, b"IHDR", &a_header);
+  write_chunk(&mut a_png, b"IDAT", &a_zlib);
+  write_chunk(&mut a_png, b"IEND", &[]);
+  a_png
+}
+
+#[cfg(test)]
+fn read_u32(the_bytes: &[u8], the_pos: usize) -> Result<u32, String> {
+  the_bytes
+    .get(the_pos..the_pos + 4)
+    .map(|the_word| u32::from_be_bytes([the_word[0], the_word[1], the_word[2], the_word[3]]))
+    .ok_or_else(|| "unexpected end of file".to_string())
+}
+
+/// Undo the deflate stream of `the_zlib`, which has to consist of stored
+/// blocks only
+#[cfg(test)]
+fn unpack_stored(the_zlib: &[u8]) -> Result<Vec<u8>, String> {
+  let mut a_pos = 2;
+  let mut a_out = Vec::new();
+  loop {
+    let a_header = *the_zlib.get(a_pos).ok_or("truncated image data")?;
+    if a_header & 0b110 != 0 {
+      return Err("compressed image data is not supported".to_string());
+    }
+    let a_len = the_zlib
+      .get(a_pos + 1..a_pos + 3)
+      .map(|the_len| u16::from_le_bytes([the_len[0], the_len[1]]) as usize)
+      .ok_or("truncated image data")?;
+    let a_block = the_zlib
+      .get(a_pos + 5..a_pos + 5 + a_len)
+      .ok_or("truncated image data")?;
+    a_out.extend_from_slice(a_block);
+    a_pos += 5 + a_len;
+    if a_header & 1 != 0 {
+      return Ok(a_out);
+    }
+  }
+}
+
+#[cfg(test)]
+fn paeth(the_a: u8, the_b: u8, the_c: u8) -> u8 {
+  let a_p = the_a as i16 + the_b as i16 - the_c as i16;
+  let a_pa = (a_p - the_a as i16).abs();
+  let a_pb = (a_p - the_b as i16).abs();
+  let a_pc = (a_p - the_c as i16).abs();
+  if a_pa <= a_pb && a_pa <= a_pc {
+    the_a
+  } else if a_pb <= a_pc {
+    the_b
+  } else {
+    the_c
+  }
+}
+
+/// Read a PNG as written by `encode`, i.e. 8 bit RGBA without compression.
+/// Returns width, height and the pixels from top to bottom.
+#[cfg(test)]
+pub fn decode(the_png: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+  if !the_png.starts_with(&SIGNATURE) {
+    return Err("not a PNG file".to_string());
+  }
+  let mut a_pos = SIGNATURE.len();
+  let mut a_size = None;
+  let mut a_zlib = Vec::new();
+  loop {
+    let a_len = read_u32(the_png, a_pos)? as usize;
+    let a_chunk = the_png
+      .get(a_pos + 4..a_pos + 8 + a_len)
+      .ok_or("unexpected end of file")?;
+    if read_u32(the_png, a_pos + 8 + a_len)? != crc32(a_chunk) {
+      return Err("corrupt chunk".to_string());
+    }
+    let a_data = &a_chunk[4..];
+    match &a_chunk[..4] {
+      b"IHDR" => {
+        if a_data.len() != 13 || a_data[8..] != [8, 6, 0, 0, 0] {
+          return Err("only non-interlaced 8 bit RGBA images are supported".to_string());
+        }
+        a_size = Some((read_u32(a_data, 0)?, read_u32(a_data, 4)?));
+      }
+      b"IDAT" => a_zlib.extend_from_slice(a_data),
+      b"IEND" => break,
+      _ => {}
+    }
+    a_pos += 12 + a_len;
+  }
+  let (a_width, a_height) = a_size.ok_or("missing image header")?;
+  let a_raw = unpack_stored(&a_zlib)?;
+  let a_stride = a_width as usize * BYTES_PER_PIXEL;
+  if a_raw.len() != (a_stride + 1) * a_height as usize {
+    return Err("image data does not match the image size".to_string());
+  }
+  let mut a_pixels = vec![0u8; a_stride * a_height as usize];
+  for a_row in 0..a_height as usize {
+    let a_filter = a_raw[a_row * (a_stride + 1)];
+    let a_line = &a_raw[a_row * (a_stride + 1) + 1..(a_row + 1) * (a_stride + 1)];
+    for (a_x, a_byte) in a_line.iter().enumerate() {
+      let a_idx = a_row * a_stride + a_x;
+      let a_left = if a_x >= BYTES_PER_PIXEL {
+        a_pixels[a_idx - BYTES_PER_PIXEL]
+      } else {
+        0
+      };
+      let a_up = if a_row > 0 {
+        a_pixels[a_idx - a_stride]
+      } else {
+        0
+      };
+      let a_up_left = if a_row > 0 && a_x >= BYTES_PER_PIXEL {
+        a_pixels[a_idx - a_stride - BYTES_PER_PIXEL]
+      } else {
+        0
+      };
+      let a_predicted = match a_filter {
+        0 => 0,
+        1 => a_left,
+        2 => a_up,
+        3 => ((a_left as u16 + a_up as u16) / 2) as u8,
+        4 => paeth(a_left, a_up, a_up_left),
+        _ => return Err(format!("unknown filter type {}", a_filter)),
+      };
+      a_pixels[a_idx] = a_byte.wrapping_add(a_predicted);
+    }
+  }
+  Ok((a_width, a_height, a_pixels))
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn decodes_what_it_encodes() {
+    let a_pixels: Vec<u8> = (0..3 * 2 * 4).map(|the_i| (the_i * 11) as u8).collect();
+    let a_png = super::encode(3, 2, &a_pixels);
+    assert_eq!(super::decode(&a_png), Ok((3, 2, a_pixels)));
+  }
+}
@@ -0,0 +1,90 @@
+use super::headless;
+use super::level::PatternDef;
+use super::model::GameState;
+use super::png;
+use super::renderer::{OGLRenderer, Renderer};
+use super::spawner::{self, SpawnStrategy, Spawner};
+use std::path::Path;
+
+/// Size of the preview images in pixels
+const PREVIEW_SIZE: u32 = 256;
+/// Simulation ticks between two preview images
+const TICKS_PER_FRAME: usize = 4;
+/// Most images written, in case the pattern never passes the center
+const MAX_FRAMES: usize = 1000;
+
+/// The pattern in the file `the_arg`, or the built-in pattern of that name
+pub fn load_pattern(the_arg: &str) -> Result<PatternDef, String> {
+  let a_path = Path::new(the_arg);
+  if a_path.is_file() {
+    let a_data = std::fs::read(a_path).map_err(|the_err| the_err.to_string())?;
+    return PatternDef::from_json(&a_data).map_err(|the_err| the_err.to_string());
+  }
+  let a_patterns = spawner::default_patterns();
+  let a_names: Vec<&str> = a_patterns
+    .iter()
+    .map(|the_pattern| the_pattern.name.as_str())
+    .collect();
+  let a_error = format!(
+    "no pattern file {}, the built-in patterns are: {}",
+    the_arg,
+    a_names.join(", ")
+  );
+  a_patterns
+    .into_iter()
+    .find(|the_pattern| the_pattern.name == the_arg)
+    .ok_or(a_error)
+}
+
+/// Render how `the_pattern` approaches the center to numbered PNG files in
+/// `the_dir`, from the moment it spawns until it has passed the cursor.
+/// Returns the number of images written.
+pub fn render_approach(the_pattern: PatternDef, the_dir: &Path) -> Result<usize, String> {
+  std::fs::create_dir_all(the_dir).map_err(|the_err| the_err.to_string())?;
+  let a_event_loop = headless::create_event_loop();
+  let a_context = headless::create_context(&a_event_loop, PREVIEW_SIZE, PREVIEW_SIZE)?;
+  let mut a_game = GameState::new();
+  a_game.restart(0);
+  // the pattern has to pass the cursor wherever it is placed
+  a_game.set_invincible(true);
+  let mut a_spawner = Spawner::new(vec![the_pattern], 1., SpawnStrategy::Weighted);
+  a_spawner.reset(0);
+  // spawns right away, later waves are left out
+  let a_delta = a_game.get_tick_duration();
+  a_spawner.tick(&mut a_game, a_delta);
+  let mut a_renderer = OGLRenderer::new(&a_game, &a_context, PREVIEW_SIZE, PREVIEW_SIZE);
+  a_renderer.resize(PREVIEW_SIZE, PREVIEW_SIZE);
+  let a_frame_time = a_game.get_tick_duration() * TICKS_PER_FRAME as u32;
+  for a_frame in 0..MAX_FRAMES {
+    let a_remaining = a_game
+      .get_slots()
+      .iter()
+      .any(|the_slot| !the_slot.get_obstacles().is_empty());
+    if !a_remaining {
+      return Ok(a_frame);
+    }
+    a_renderer.render(&a_game, a_game.get_style(), a_frame_time);
+    let a_pixels = headless::read_pixels(PREVIEW_SIZE, PREVIEW_SIZE);
+    let a_path = the_dir.join(format!("frame_{:04}.png", a_frame));
+    std::fs::write(&a_path, png::encode(PREVIEW_SIZE, PREVIEW_SIZE, &a_pixels))
+      .map_err(|the_err| format!("cannot write {}: {}", a_path.display(), the_err))?;
+    for _ in 0..TICKS_PER_FRAME {
+      a_game.tick();
+      // nothing reacts to the preview's events, don't let them pile up
+      a_game.take_events();
+    }
+  }
+  Ok(MAX_FRAMES)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::load_pattern;
+
+  #[test]
+  fn finds_built_in_patterns() {
+    assert_eq!(load_pattern("barrage").unwrap().walls.len(), 5);
+    let a_err = load_pattern("no such pattern").err().unwrap();
+    assert!(a_err.contains("single, barrage"), "{}", a_err);
+  }
+}
@@ -0,0 +1,511 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// How long joining waits for the host to answer
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the own survival time is sent while alive
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+/// Ticks between reading an input and applying it in lockstep rounds, which
+/// hides the latency of sending it to the opponent
+const INPUT_DELAY: usize = 6;
+
+/// Inputs of lockstep rounds are sent as one byte per tick, since they make
+/// up nearly all of the traffic. They cannot be mistaken for messages, which
+/// start with `{`.
+fn encode_input(the_direction: i8) -> u8 {
+  match the_direction {
+    a_direction if a_direction < 0 => b'<',
+    0 => b'=',
+    _ => b'>',
+  }
+}
+fn decode_input(the_byte: u8) -> Option<i8> {
+  match the_byte {
+    b'<' => Some(-1),
+    b'=' => Some(0),
+    b'>' => Some(1),
+    _ => None,
+  }
+}
+
+/// What the two instances tell each other, one JSON object per line
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+  /// The host proposes the next round. In lockstep rounds both simulate both
+  /// players, which takes the same tick rate.
+  Round {
+    level: String,
+    seed: u64,
+    #[serde(default)]
+    lockstep: bool,
+    #[serde(default)]
+    tick_rate: Option<u32>,
+  },
+  /// The guest has set up the proposed round
+  Ready,
+  /// The guest starts the round as soon as it receives this
+  Start,
+  /// Survival time of the sender in the running round, in seconds
+  Time { time: f32 },
+  /// The sender died after `time` seconds
+  Died { time: f32 },
+}
+
+/// Something the app has to react to
+#[derive(Debug)]
+pub enum RaceEvent {
+  /// A guest has joined the race that is being hosted
+  Joined,
+  /// The host proposes to play the level `level` next. The guest has to
+  /// accept with `ready` once it has set up the level, at `tick_rate` if
+  /// the host asks for one.
+  Proposed {
+    level: String,
+    tick_rate: Option<u32>,
+  },
+  /// Both players start the round with `seed` now
+  Start { seed: u64 },
+  /// The connection has been lost, the race is over
+  Disconnected(String),
+}
+
+/// How the opponent is doing in the current round
+#[derive(Clone, Copy, Debug)]
+pub struct OpponentStatus {
+  pub time: Duration,
+  pub alive: bool,
+}
+
+/// Inputs of the running lockstep round that have not been applied yet
+struct InputQueues {
+  its_own: VecDeque<i8>,
+  its_opponent: VecDeque<i8>,
+}
+
+enum Connection {
+  /// Hosting and waiting for a guest
+  Listening(TcpListener),
+  Connected(TcpStream),
+  Closed,
+}
+
+/// A race between two instances over TCP. The host picks level and seed of
+/// every round, and both start once the guest has set them up. During the
+/// round both send their survival time and their death.
+///
+/// In lockstep mode both send their input of every tick instead, and every
+/// tick is only simulated once the opponent's input for it has arrived. Both
+/// instances simulate both players from the same inputs, so they see the
+/// same outcome without trusting each other's reports.
+pub struct Race {
+  its_connection: Connection,
+  its_host: bool,
+  /// Whether rounds are played in lockstep, decided by the host
+  its_lockstep: bool,
+  /// Inputs while a lockstep round is being stepped through
+  its_inputs: Option<InputQueues>,
+  /// The guest has accepted a round before its last one was over
+  its_ready_pending: bool,
+  /// Received bytes that do not make up a complete message yet
+  its_incoming: Vec<u8>,
+  /// Messages that could not be sent without blocking yet
+  its_outgoing: Vec<u8>,
+  /// Seed of the round the host has proposed and when it did so
+  its_proposal: Option<(u64, Instant)>,
+  /// When the host starts the round the guest has been told to start, so
+  /// that both start at about the same time despite the latency
+  its_start: Option<(u64, Instant)>,
+  its_opponent: Option<OpponentStatus>,
+  its_since_status: Duration,
+}
+
+impl Race {
+  fn new(the_connection: Connection, the_host: bool) -> Race {
+    Race {
+      its_connection: the_connection,
+      its_host: the_host,
+      its_lockstep: false,
+      its_inputs: None,
+      its_ready_pending: false,
+      its_incoming: Vec::new(),
+      its_outgoing: Vec::new(),
+      its_proposal: None,
+      its_start: None,
+      its_opponent: None,
+      its_since_status: Duration::from_secs(0),
+    }
+  }
+  /// Wait for a guest on `the_port` of every interface
+  pub fn host(the_port: u16) -> std::io::Result<Race> {
+    let a_listener = TcpListener::bind(("0.0.0.0", the_port))?;
+    a_listener.set_nonblocking(true)?;
+    Ok(Race::new(Connection::Listening(a_listener), true))
+  }
+  /// Join the race hosted at `the_address`, e.g. `example.org:7474`
+  pub fn join(the_address: &str) -> std::io::Result<Race> {
+    let mut a_last_err = std::io::Error::new(ErrorKind::NotFound, "unknown address");
+    for a_address in the_address.to_socket_addrs()? {
+      match TcpStream::connect_timeout(&a_address, CONNECT_TIMEOUT) {
+        Ok(a_stream) => {
+          a_stream.set_nonblocking(true)?;
+          a_stream.set_nodelay(true)?;
+          return Ok(Race::new(Connection::Connected(a_stream), false));
+        }
+        Err(a_err) => a_last_err = a_err,
+      }
+    }
+    Err(a_last_err)
+  }
+  pub fn is_host(&self) -> bool {
+    self.its_host
+  }
+  pub fn is_connected(&self) -> bool {
+    matches!(self.its_connection, Connection::Connected(_))
+  }
+  /// Play the coming rounds in lockstep (host only)
+  pub fn set_lockstep(&mut self, the_lockstep: bool) -> () {
+    self.its_lockstep = the_lockstep;
+  }
+  pub fn is_lockstep(&self) -> bool {
+    self.its_lockstep
+  }
+  /// Whether a lockstep round is running, i.e. ticks have to go through
+  /// `step`
+  pub fn is_stepping(&self) -> bool {
+    self.its_inputs.is_some()
+  }
+  /// Whether the opponent's input for the next tick has arrived
+  pub fn can_step(&self) -> bool {
+    self
+      .its_inputs
+      .as_ref()
+      .is_some_and(|the_inputs| !the_inputs.its_opponent.is_empty())
+  }
+  /// Send `the_direction` the player steers in now and get the own and the
+  /// opponent's direction for the next tick, or `None` if the opponent's
+  /// has not arrived yet
+  pub fn step(&mut self, the_direction: i8) -> Option<(i8, i8)> {
+    let a_inputs = self.its_inputs.as_mut()?;
+    let a_opponent = a_inputs.its_opponent.pop_front()?;
+    a_inputs.its_own.push_back(the_direction);
+    let a_own = a_inputs.its_own.pop_front().unwrap_or(0);
+    self.its_outgoing.push(encode_input(the_direction));
+    Some((a_own, a_opponent))
+  }
+  /// Stop stepping once both players are out. Both notice this after the
+  /// same tick, since both simulate both.
+  pub fn end_round(&mut self) -> () {
+    self.its_inputs = None;
+    if self.its_ready_pending {
+      self.its_ready_pending = false;
+      self.send(&Message::Ready);
+    }
+  }
+  /// The opponent's status in the current round, `None` before the first
+  pub fn get_opponent(&self) -> Option<OpponentStatus> {
+    self.its_opponent
+  }
+  fn send(&mut self, the_message: &Message) -> () {
+    if let Ok(a_json) = serde_json::to_vec(the_message) {
+      self.its_outgoing.extend_from_slice(&a_json);
+      self.its_outgoing.push(b'\n');
+    }
+  }
+  /// Propose the next round (host only)
+  pub fn propose(&mut self, the_level: &str, the_seed: u64, the_tick_rate: u32) -> () {
+    self.send(&Message::Round {
+      level: the_level.to_string(),
+      seed: the_seed,
+      lockstep: self.its_lockstep,
+      tick_rate: Some(the_tick_rate),
+    });
+    self.its_proposal = Some((the_seed, Instant::now()));
+  }
+  /// Accept the proposed round (guest only). Waits for the last lockstep
+  /// round to be over, since the host takes every input after this for
+  /// the new one.
+  pub fn ready(&mut self) -> () {
+    if self.is_stepping() {
+      self.its_ready_pending = true;
+    } else {
+      self.send(&Message::Ready);
+    }
+  }
+  /// Send the own survival time every now and then while alive
+  pub fn tick(&mut self, the_delta: Duration, the_time: Duration) -> () {
+    self.its_since_status += the_delta;
+    if self.its_since_status >= STATUS_INTERVAL {
+      self.its_since_status = Duration::from_secs(0);
+      self.send(&Message::Time {
+        time: the_time.as_secs_f32(),
+      });
+    }
+  }
+  pub fn report_death(&mut self, the_time: Duration) -> () {
+    self.send(&Message::Died {
+      time: the_time.as_secs_f32(),
+    });
+  }
+  fn begin_round(&mut self) -> () {
+    self.its_opponent = Some(OpponentStatus {
+      time: Duration::from_secs(0),
+      alive: true,
+    });
+    self.its_since_status = Duration::from_secs(0);
+    if self.its_lockstep {
+      // nothing has been read before the round, the first ticks go without
+      // input on both sides
+      let a_idle: VecDeque<i8> = std::iter::repeat_n(0, INPUT_DELAY).collect();
+      self.its_inputs = Some(InputQueues {
+        its_own: a_idle.clone(),
+        its_opponent: a_idle,
+      });
+    }
+  }
+  fn disconnect(&mut self, the_reason: String) -> Option<RaceEvent> {
+    self.its_connection = Connection::Closed;
+    self.its_inputs = None;
+    Some(RaceEvent::Disconnected(the_reason))
+  }
+  /// Exchange messages with the opponent. Returns the next event to react
+  /// to, call it until there is none.
+  pub fn poll(&mut self) -> Option<RaceEvent> {
+    if let Connection::Listening(a_listener) = &self.its_connection {
+      return match a_listener.accept() {
+        Ok((a_stream, _)) => {
+          if let Err(a_err) = a_stream
+            .set_nonblocking(true)
+            .and_then(|_| a_stream.set_nodelay(true))
+          {
+            return self.disconnect(a_err.to_string());
+          }
+          self.its_connection = Connection::Connected(a_stream);
+          Some(RaceEvent::Joined)
+        }
+        Err(a_err) if a_err.kind() == ErrorKind::WouldBlock => None,
+        Err(a_err) => self.disconnect(a_err.to_string()),
+      };
+    }
+    let a_stream = match &mut self.its_connection {
+      Connection::Connected(a_stream) => a_stream,
+      _ => return None,
+    };
+    if let Err(a_err) = exchange(a_stream, &mut self.its_outgoing, &mut self.its_incoming) {
+      return self.disconnect(a_err.to_string());
+    }
+    if let Some((a_seed, a_at)) = self.its_start {
+      if Instant::now() >= a_at {
+        self.its_start = None;
+        self.begin_round();
+        return Some(RaceEvent::Start { seed: a_seed });
+      }
+    }
+    loop {
+      let a_count = self
+        .its_incoming
+        .iter()
+        .take_while(|the_b| decode_input(**the_b).is_some())
+        .count();
+      if a_count > 0 {
+        for a_byte in self.its_incoming.drain(..a_count) {
+          // inputs left over from the previous round are dropped
+          if let (Some(a_inputs), Some(a_direction)) = (&mut self.its_inputs, decode_input(a_byte))
+          {
+            a_inputs.its_opponent.push_back(a_direction);
+          }
+        }
+        continue;
+      }
+      let a_end = match self.its_incoming.iter().position(|the_b| *the_b == b'\n') {
+        Some(a_end) => a_end,
+        None => break,
+      };
+      let a_line: Vec<u8> = self.its_incoming.drain(..=a_end).collect();
+      let a_message = match serde_json::from_slice::<Message>(&a_line) {
+        Ok(a_message) => a_message,
+        Err(a_err) => return self.disconnect(format!("invalid message: {}", a_err)),
+      };
+      match a_message {
+        Message::Round {
+          level,
+          seed,
+          lockstep,
+          tick_rate,
+        } if !self.its_host => {
+          self.its_proposal = Some((seed, Instant::now()));
+          self.its_lockstep = lockstep;
+          return Some(RaceEvent::Proposed { level, tick_rate });
+        }
+        Message::Ready if self.its_host => {
+          if let Some((a_seed, a_sent)) = self.its_proposal.take() {
+            self.send(&Message::Start);
+            if self.its_lockstep {
+              // waiting for each other's inputs keeps both in step, the
+              // inputs that follow belong to the new round
+              self.begin_round();
+              return Some(RaceEvent::Start { seed: a_seed });
+            }
+            // the guest starts about half a round trip from now
+            self.its_start = Some((a_seed, Instant::now() + a_sent.elapsed() / 2));
+          }
+        }
+        Message::Start if !self.its_host => {
+          if let Some((a_seed, _)) = self.its_proposal.take() {
+            self.begin_round();
+            return Some(RaceEvent::Start { seed: a_seed });
+          }
+        }
+        Message::Time { time } => {
+          if let Some(a_opponent) = &mut self.its_opponent {
+            a_opponent.time = Duration::from_secs_f32(time.max(0.));
+          }
+        }
+        Message::Died { time } => {
+          if let Some(a_opponent) = &mut self.its_opponent {
+            a_opponent.time = Duration::from_secs_f32(time.max(0.));
+            a_opponent.alive = false;
+          }
+        }
+        a_message => {
+          return self.disconnect(format!("unexpected message {:?}", a_message));
+        }
+      }
+    }
+    None
+  }
+}
+
+/// Write as much of `the_outgoing` and read as much into `the_incoming` as
+/// possible without blocking
+fn exchange(
+  the_stream: &mut TcpStream,
+  the_outgoing: &mut Vec<u8>,
+  the_incoming: &mut Vec<u8>,
+) -> std::io::Result<()> {
+  while !the_outgoing.is_empty() {
+    match the_stream.write(the_outgoing) {
+      Ok(0) => return Err(ErrorKind::WriteZero.into()),
+      Ok(a_count) => {
+        the_outgoing.drain(..a_count);
+      }
+      Err(a_err) if a_err.kind() == ErrorKind::WouldBlock => break,
+      Err(a_err) => return Err(a_err),
+    }
+  }
+  let mut a_buffer = [0u8; 1024];
+  loop {
+    match the_stream.read(&mut a_buffer) {
+      Ok(0) => {
+        return Err(std::io::Error::new(
+          ErrorKind::UnexpectedEof,
+          "opponent left",
+        ))
+      }
+      Ok(a_count) => the_incoming.extend_from_slice(&a_buffer[..a_count]),
+      Err(a_err) if a_err.kind() == ErrorKind::WouldBlock => return Ok(()),
+      Err(a_err) => return Err(a_err),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Connection, Race, RaceEvent, INPUT_DELAY};
+  use std::time::{Duration, Instant};
+
+  /// Poll both sides until `the_done` holds, with the events of the host
+  /// and the guest that have arrived in the meantime
+  fn poll_until(
+    the_host: &mut Race,
+    the_guest: &mut Race,
+    mut the_done: impl FnMut(&Race, &[RaceEvent], &[RaceEvent]) -> bool,
+  ) -> () {
+    let mut a_host_events = Vec::new();
+    let mut a_guest_events = Vec::new();
+    let a_deadline = Instant::now() + Duration::from_secs(5);
+    while !the_done(the_host, &a_host_events, &a_guest_events) {
+      assert!(Instant::now() < a_deadline, "timed out");
+      a_host_events.extend(std::iter::from_fn(|| the_host.poll()));
+      a_guest_events.extend(std::iter::from_fn(|| the_guest.poll()));
+      std::thread::sleep(Duration::from_millis(1));
+    }
+  }
+
+  #[test]
+  fn both_start_the_same_round() {
+    let mut a_host = Race::host(0).unwrap();
+    let a_port = match &a_host.its_connection {
+      Connection::Listening(a_listener) => a_listener.local_addr().unwrap().port(),
+      _ => unreachable!(),
+    };
+    let mut a_guest = Race::join(&format!("127.0.0.1:{}", a_port)).unwrap();
+    poll_until(&mut a_host, &mut a_guest, |_, the_host, _| {
+      matches!(the_host, [RaceEvent::Joined])
+    });
+    a_host.propose("built-in/built-in", 42, 60);
+    poll_until(&mut a_host, &mut a_guest, |_, _, the_guest| {
+      matches!(the_guest, [RaceEvent::Proposed { .. }])
+    });
+    a_guest.ready();
+    poll_until(&mut a_host, &mut a_guest, |_, the_host, the_guest| {
+      matches!(the_host, [RaceEvent::Start { seed: 42 }])
+        && matches!(the_guest, [RaceEvent::Start { seed: 42 }])
+    });
+    a_guest.report_death(Duration::from_secs(3));
+    poll_until(
+      &mut a_host,
+      &mut a_guest,
+      |the_host, _, _| matches!(the_host.get_opponent(), Some(the_status) if !the_status.alive),
+    );
+    assert_eq!(a_host.get_opponent().unwrap().time, Duration::from_secs(3));
+  }
+
+  #[test]
+  fn lockstep_exchanges_inputs_per_tick() {
+    let mut a_host = Race::host(0).unwrap();
+    a_host.set_lockstep(true);
+    let a_port = match &a_host.its_connection {
+      Connection::Listening(a_listener) => a_listener.local_addr().unwrap().port(),
+      _ => unreachable!(),
+    };
+    let mut a_guest = Race::join(&format!("127.0.0.1:{}", a_port)).unwrap();
+    poll_until(&mut a_host, &mut a_guest, |_, the_host, _| {
+      matches!(the_host, [RaceEvent::Joined])
+    });
+    a_host.propose("built-in/built-in", 42, 120);
+    poll_until(&mut a_host, &mut a_guest, |_, _, the_guest| {
+      matches!(
+        the_guest,
+        [RaceEvent::Proposed {
+          tick_rate: Some(120),
+          ..
+        }]
+      )
+    });
+    assert!(a_guest.is_lockstep());
+    a_guest.ready();
+    poll_until(&mut a_host, &mut a_guest, |_, the_host, the_guest| {
+      matches!(the_host, [RaceEvent::Start { seed: 42 }])
+        && matches!(the_guest, [RaceEvent::Start { seed: 42 }])
+    });
+    // nothing has been read for the first ticks
+    for _ in 0..INPUT_DELAY {
+      assert_eq!(a_host.step(1), Some((0, 0)));
+      assert_eq!(a_guest.step(-1), Some((0, 0)));
+    }
+    assert_eq!(a_host.step(0), None);
+    let a_deadline = Instant::now() + Duration::from_secs(5);
+    while !(a_host.can_step() && a_guest.can_step()) {
+      assert!(Instant::now() < a_deadline, "timed out");
+      assert!(a_host.poll().is_none());
+      assert!(a_guest.poll().is_none());
+      std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(a_host.step(0), Some((1, -1)));
+    assert_eq!(a_guest.step(0), Some((-1, 1)));
+  }
+}
@@ -0,0 +1,131 @@
+use super::level::Medal;
+use super::model::Run;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The best run of a level
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct LevelRecord {
+  /// Survived time in seconds
+  pub best_time: f32,
+  /// Split times in seconds of the run that set `best_time`, see
+  /// `model::STAGES`
+  pub splits: Vec<f32>,
+  /// Whether the goal of the level's time attack has been reached
+  #[serde(default)]
+  pub completed: bool,
+  /// Best medal of the level's time attack
+  #[serde(default)]
+  pub medal: Option<Medal>,
+}
+
+/// High scores of all levels that have been played, keyed by pack and level
+#[derive(Serialize, Deserialize, Default)]
+pub struct Records {
+  levels: BTreeMap<String, LevelRecord>,
+}
+
+impl Records {
+  /// Load the records from `the_path`, starting over if there are none or
+  /// they cannot be read
+  pub fn load(the_path: &Path) -> Records {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_records) => a_records,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed records file {}: {}",
+            the_path.display(),
+            a_err
+          );
+          Records::default()
+        }
+      },
+      Err(_) => Records::default(),
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  pub fn get(&self, the_key: &str) -> Option<&LevelRecord> {
+    self.levels.get(the_key)
+  }
+  /// Keep `the_run` as the record of the level `the_key` if it beats the
+  /// current one. Assisted, practice and cheated runs never count. Returns
+  /// whether it was kept.
+  pub fn submit(&mut self, the_key: &str, the_run: &Run) -> bool {
+    let a_time = the_run.get_time().as_secs_f32();
+    let a_beaten = match self.levels.get(the_key) {
+      Some(a_record) => a_time > a_record.best_time,
+      None => true,
+    };
+    if !a_beaten || !Records::counts(the_run) {
+      return false;
+    }
+    let a_record = self.levels.entry(the_key.to_string()).or_default();
+    a_record.best_time = a_time;
+    a_record.splits = the_run
+      .get_splits()
+      .iter()
+      .map(|the_split| the_split.as_secs_f32())
+      .collect();
+    true
+  }
+  /// Note that `the_run` of the level `the_key`'s time attack has been
+  /// completed, if it has, and earned `the_medal`, if it beats the medal
+  /// so far. Returns whether anything was new.
+  pub fn submit_goal(&mut self, the_key: &str, the_run: &Run, the_medal: Option<Medal>) -> bool {
+    if !Records::counts(the_run) {
+      return false;
+    }
+    let a_record = self.levels.entry(the_key.to_string()).or_default();
+    let a_completed = the_run.is_completed() && !a_record.completed;
+    let a_medal = the_medal > a_record.medal;
+    a_record.completed |= a_completed;
+    if a_medal {
+      a_record.medal = the_medal;
+    }
+    a_completed || a_medal
+  }
+  /// Assisted, practice and cheated runs never count
+  fn counts(the_run: &Run) -> bool {
+    !the_run.is_assisted() && !the_run.is_practice() && !the_run.is_cheated()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Records;
+  use crate::level::Medal;
+  use crate::model::GameState;
+  use std::time::Duration;
+
+  #[test]
+  fn keeps_completion_and_best_medal() {
+    let mut a_game = GameState::new();
+    a_game.set_goal(Some(Duration::from_secs(1)));
+    a_game.restart(7);
+    while a_game.is_running() {
+      a_game.tick();
+    }
+    assert!(a_game.get_run().is_completed());
+    assert_eq!(a_game.get_run().get_time(), Duration::from_secs(1));
+    let mut a_records = Records::default();
+    assert!(a_records.submit("pack/level", a_game.get_run()));
+    assert!(a_records.submit_goal("pack/level", a_game.get_run(), Some(Medal::Silver)));
+    assert!(!a_records.submit_goal("pack/level", a_game.get_run(), Some(Medal::Bronze)));
+    let a_record = a_records.get("pack/level").unwrap();
+    assert!(a_record.completed);
+    assert_eq!(a_record.medal, Some(Medal::Silver));
+    assert!(a_records.submit_goal("pack/level", a_game.get_run(), Some(Medal::Gold)));
+    assert_eq!(
+      a_records.get("pack/level").unwrap().medal,
+      Some(Medal::Gold)
+    );
+  }
+}
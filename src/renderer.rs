@@ -1,24 +1,317 @@
+use super::accessibility;
 use super::constants;
+use super::controls::InputDisplay;
+use super::font;
+use super::geometry::{self, Geometry, Vertex};
+use super::heatmap::{Heatmap, HEATMAP_ROWS, HEATMAP_SLOTS};
+use super::i18n;
+use super::level::Medal;
 use super::model;
+use super::offscreen::{OffscreenTarget, PostEffects};
+use super::overlay::Overlay;
+use super::pacing::FpsStats;
+use super::race::OpponentStatus;
+use super::shape::Shape;
+use super::stats::LifetimeStats;
+use super::stream::StreamBuffer;
+use super::summary;
 use gl::types::*;
 use glutin::{self, PossiblyCurrent};
 use nalgebra_glm as glm;
 
+/// The stages a frame is rendered in, see `Renderer::render_pass`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderPass {
+    /// The background and the slots
+    Background,
+    /// Obstacles, pickups, the hexagon and the cursor
+    Playfield,
+    /// Short-lived effects on top of the playfield
+    Particles,
+    /// Full-screen effects applied to everything drawn so far
+    Post,
+    /// Text and indicators, which the post effects leave alone
+    Hud,
+}
+
+impl RenderPass {
+    /// All passes in the order they are rendered in
+    pub const ALL: [RenderPass; 5] = [
+        RenderPass::Background,
+        RenderPass::Playfield,
+        RenderPass::Particles,
+        RenderPass::Post,
+        RenderPass::Hud,
+    ];
+}
+
 pub trait Renderer {
     fn resize(&mut self, the_width: u32, the_height: u32) -> ();
-    fn render(&mut self, the_game: &model::GameState, the_delta: std::time::Duration) -> ();
+    /**
+     * Render `the_game` using `the_style`, which is the game's style after
+     * all user-selected post-processing has been applied. Renders every
+     * pass of `RenderPass::ALL` in between `begin_frame` and `end_frame`.
+     */
+    fn render(
+        &mut self,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+        the_delta: std::time::Duration,
+    ) -> () {
+        if !self.begin_frame(the_game, the_style, the_delta) {
+            return;
+        }
+        for a_pass in RenderPass::ALL.iter() {
+            self.render_pass(*a_pass, the_game, the_style);
+        }
+        self.end_frame();
+    }
+    /**
+     * Start a frame of `the_game` in `the_style`, `the_delta` after the
+     * previous one. Returns whether its passes are to be rendered, which
+     * they are not if the frame is complete already, e.g. a full-screen
+     * flash.
+     */
+    fn begin_frame(
+        &mut self,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+        the_delta: std::time::Duration,
+    ) -> bool;
+    /**
+     * Render `the_pass` of the frame started by `begin_frame`, with the
+     * same game and style
+     */
+    fn render_pass(
+        &mut self,
+        the_pass: RenderPass,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+    ) -> ();
+    /**
+     * Finish the frame after all of its passes have been rendered
+     */
+    fn end_frame(&mut self) -> ();
     /**
      * Get the (low-pass filtered) time between two frames in milliseconds
      */
     fn get_frame_time(&self) -> f32;
+    /**
+     * Replace flashes by gentler effects, whatever the style requests
+     */
+    fn set_reduced_flash(&mut self, the_enabled: bool) -> ();
+    /**
+     * Show debugging aids like slot labels on top of the game
+     */
+    fn set_debug_overlay(&mut self, the_enabled: bool) -> ();
+    /**
+     * List `the_lines`, e.g. the spawner's state and the latest game
+     * events, in the bottom left corner of the debug overlay
+     */
+    fn set_debug_lines(&mut self, the_lines: Option<Vec<String>>) -> ();
+    /**
+     * Let previous frames fade out instead of clearing them
+     */
+    fn set_motion_trail(&mut self, the_enabled: bool) -> ();
+    /**
+     * Brighten every slot the closer its nearest obstacle has come, see
+     * `GameState::get_proximity`
+     */
+    fn set_proximity_shading(&mut self, the_enabled: bool) -> ();
+    /**
+     * Select the full-screen effects applied to every frame
+     */
+    fn set_post_effects(&mut self, the_effects: PostEffects) -> ();
+    /**
+     * Use the fragment shader `the_source` for the slots instead of flat
+     * colors, or go back to flat colors if it is `None`. If the shader
+     * fails to compile, the flat colors are kept and the compiler's log is
+     * returned.
+     */
+    fn set_level_shader(&mut self, the_source: Option<&str>) -> Result<(), String>;
+    /**
+     * Lock the playfield to the aspect ratio (width / height) `the_aspect`
+     * and fill the rest of the window with bars, or use the whole window if
+     * it is `None`
+     */
+    fn set_forced_aspect(&mut self, the_aspect: Option<f32>) -> ();
+    /**
+     * Note whether the window is being resized. In the meantime, work that
+     * would be redone for every size is put off until it has settled, e.g.
+     * by drawing without post effects.
+     */
+    fn set_resizing(&mut self, the_resizing: bool) -> ();
+    /**
+     * Compare the splits of the current run in the HUD against
+     * `the_splits`, in seconds
+     */
+    fn set_best_splits(&mut self, the_splits: Vec<f32>) -> ();
+    /**
+     * Show the held direction and recent taps in the HUD, or nothing if
+     * `the_display` is `None`
+     */
+    fn set_input_display(&mut self, the_display: Option<InputDisplay>) -> ();
+    /**
+     * Draw the playfield over the solid `the_color` instead of the slots and
+     * without any full-screen flashes, or normally if it is `None`
+     */
+    fn set_chroma_key(&mut self, the_color: Option<model::Color>) -> ();
+    /**
+     * Draw the cursor as `the_shape`, see `level::CursorDef`, or as the
+     * plain triangle if it is `None`
+     */
+    fn set_cursor_shape(&mut self, the_shape: Option<Shape>) -> ();
+    /**
+     * Draw the cursor at `the_position` instead of where the game has it,
+     * e.g. in between two ticks, or where the game has it if it is `None`
+     */
+    fn set_cursor_position(&mut self, the_position: Option<f32>) -> ();
+    /**
+     * Draw all text in `the_font`
+     */
+    fn set_font(&mut self, the_font: font::Font) -> ();
+    /**
+     * Take the texts of the HUD from `the_catalog`
+     */
+    fn set_catalog(&mut self, the_catalog: i18n::Catalog) -> ();
+    /**
+     * Show how the opponent of a race is doing in the HUD, or nothing if
+     * `the_status` is `None`
+     */
+    fn set_opponent(&mut self, the_status: Option<OpponentStatus>) -> ();
+    /**
+     * Show `the_lines` in a drop-down console across the top of the
+     * window, or no console if it is `None`
+     */
+    fn set_console(&mut self, the_lines: Option<Vec<String>>) -> ();
+    /**
+     * Show `the_stats` on a screen covering the game, or the game if it is
+     * `None`
+     */
+    fn set_stats(&mut self, the_stats: Option<LifetimeStats>) -> ();
+    /**
+     * Show where the player tends to die as a grid of slots by stages in
+     * the HUD, or nothing if `the_heatmap` is `None`
+     */
+    fn set_heatmap(&mut self, the_heatmap: Option<Heatmap>) -> ();
+    /**
+     * Show `the_text` across the playfield, e.g. while the run waits for
+     * the player to follow it, or nothing if it is `None`
+     */
+    fn set_prompt(&mut self, the_text: Option<String>) -> ();
+    /**
+     * Show `the_text` near the bottom of the window, e.g. to confirm that a
+     * hotkey toggled something, or nothing if it is `None`
+     */
+    fn set_notice(&mut self, the_text: Option<String>) -> ();
+    /**
+     * Show the level's title `the_title` during its intro, shifted to the
+     * right by `the_offset` times the window width, or nothing if it is
+     * `None`
+     */
+    fn set_intro_title(&mut self, the_title: Option<String>, the_offset: f32) -> ();
+    /**
+     * Hold back the game over screen while `the_hidden` is set, e.g. during
+     * the slow motion after a death
+     */
+    fn set_game_over_hidden(&mut self, the_hidden: bool) -> ();
+    /**
+     * Show the seconds left until the run goes on after a pause, or
+     * nothing if it is `None`
+     */
+    fn set_countdown(&mut self, the_seconds: Option<u32>) -> ();
+    /**
+     * Show the frame rate of the last second in the HUD, or nothing if
+     * `the_stats` is `None`
+     */
+    fn set_fps(&mut self, the_stats: Option<FpsStats>) -> ();
+    /**
+     * Show `the_medal` as earned by the run that just ended, if it is set
+     */
+    fn set_medal(&mut self, the_medal: Option<Medal>) -> ();
 }
 
 const FRAME_TIME_FILTER_STRENGTH: f32 = 20.;
+/// Opacity of the outermost ring of the vignette, see `geometry::VIGNETTE_RINGS`
+const VIGNETTE_MAX_ALPHA: f32 = 0.6;
+/// Radial position of the slot labels in the debug overlay
+const DEBUG_LABEL_Y: f32 = 0.8;
+/// Size of a font pixel of the debug overlay in screen pixels
+const DEBUG_FONT_PIXEL: f32 = 2.;
+/// Size of the markers of the hitbox corners in the debug overlay, in
+/// screen pixels
+const DEBUG_MARKER_SIZE: f32 = 4.;
+/// Size of a font pixel of the developer console in screen pixels
+const CONSOLE_FONT_PIXEL: f32 = 2.;
+/// Size of a font pixel of the statistics screen in screen pixels
+const STATS_FONT_PIXEL: f32 = 2.;
+/// Most levels the statistics screen shows deaths for
+const STATS_MAX_LEVELS: usize = 10;
+/// Size of a cell of the death heatmap in screen pixels
+const HEATMAP_CELL: f32 = 14.;
+/// Vertical position of tutorial prompts, as a fraction of the window
+/// height from the top
+const PROMPT_Y: f32 = 0.2;
+/// Size of a font pixel of tutorial prompts in screen pixels
+const PROMPT_FONT_PIXEL: f32 = 4.;
+/// Vertical position of notices, as a fraction of the window height from
+/// the top
+const NOTICE_Y: f32 = 0.9;
+/// Size of a font pixel of notices in screen pixels
+const NOTICE_FONT_PIXEL: f32 = 2.;
+/// Vertical position of the level's title during its intro, as a fraction
+/// of the window height from the top
+const INTRO_TITLE_Y: f32 = 0.35;
+/// Size of a font pixel of the level's title in screen pixels
+const INTRO_TITLE_FONT_PIXEL: f32 = 6.;
+/// Size of a font pixel of the countdown after a pause in screen pixels
+const COUNTDOWN_FONT_PIXEL: f32 = 8.;
+/// Vertical position of the run's metrics on the game over screen, as a
+/// fraction of the window height from the top
+const GAME_OVER_STATS_Y: f32 = 0.85;
+/// Size of a font pixel of the game over screen in screen pixels
+const GAME_OVER_FONT_PIXEL: f32 = 3.;
+/// Distance of the HUD from the window's top left corner in pixels
+const HUD_MARGIN: f32 = 8.;
+/// Size of a font pixel of the HUD in screen pixels
+const HUD_FONT_PIXEL: f32 = 3.;
+/// How much of the previous frame remains visible after a frame of
+/// `constants::SPEED_REFERENCE_FRAME` when the motion trail is enabled
+const TRAIL_PERSISTENCE: f32 = 0.5;
+/// How long the post effects triggered by a death last, in seconds
+const DEATH_EFFECT_DURATION: f32 = 0.6;
+/// The `time` uniform wraps around after this many seconds so that it keeps
+/// enough precision for animations
+const SHADER_TIME_WRAP: f32 = 600.;
+
+/// Catalog key of the HUD line that shows an active `the_power_up`
+fn get_power_up_key(the_power_up: model::PowerUp) -> &'static str {
+    match the_power_up {
+        model::PowerUp::SlowTime => "power_up.slow_time",
+        model::PowerUp::ShrinkCursor => "power_up.shrink_cursor",
+        model::PowerUp::ScoreMultiplier => "power_up.score_multiplier",
+    }
+}
 
+/*
+ * Uniforms and varyings available to the fragment shader:
+ *
+ * - `varying vec3 v_color`: flat color of the shape being drawn
+ * - `uniform float alpha`: opacity, only has an effect where blending is
+ *   enabled (e.g. the vignette)
+ * - `uniform float time`: seconds since the renderer was created, wrapping
+ *   around every `SHADER_TIME_WRAP` seconds. Animated styles (shimmering
+ *   slots, pulsing obstacles) should use periods that divide it.
+ * - `varying vec2 field_pos`: position on the playfield before rotation,
+ *   x is the angle in turns [0, 1] and y the distance from the center
+ */
 const VS_SRC: &'static [u8] = b"
 #version 100
 precision mediump float;
 attribute vec4 vertex;
+attribute vec3 vertex_color;
+varying vec2 field_pos;
+varying vec3 v_color;
 uniform float rotation;
 uniform float z_value;
 uniform float zoom;
@@ -52,19 +345,63 @@ void main() {
     pos /= pos.w;
     pos.z = 0.;
     gl_Position = pos;
+    field_pos = vertex.xy;
+    v_color = vertex_color;
 }
 \0";
 
 const FS_SRC: &'static [u8] = b"
 #version 100
 precision mediump float;
-uniform vec3 color;
+// only has an effect where blending is enabled
+uniform float alpha;
+uniform float time;
+varying vec2 field_pos;
+varying vec3 v_color;
 void main() {
-    gl_FragColor = vec4(color, 1.0);
+    gl_FragColor = vec4(v_color, alpha);
 }
 \0";
 
-fn gl_check_error() -> () {
+/// Draw the vertices `the_range` of the bound buffer, given as first vertex
+/// and vertex count like the ranges of `Geometry`
+unsafe fn gl_draw_range(the_mode: GLenum, the_range: (usize, usize)) -> () {
+    gl::DrawArrays(the_mode, the_range.0 as GLint, the_range.1 as GLsizei);
+}
+
+/// Point the `vertex` and `vertex_color` attributes at the interleaved
+/// vertices of the bound buffer starting at byte `the_base`, see `Vertex`
+unsafe fn gl_bind_vertex_attribs(
+    the_vertex_loc: Option<GLint>,
+    the_vertex_color_loc: Option<GLint>,
+    the_base: usize,
+) -> () {
+    let a_stride = std::mem::size_of::<Vertex>() as GLsizei;
+    if let Some(a_loc) = the_vertex_loc {
+        gl::VertexAttribPointer(
+            a_loc as GLuint,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            a_stride,
+            the_base as *const _,
+        );
+        gl::EnableVertexAttribArray(a_loc as GLuint);
+    }
+    if let Some(a_loc) = the_vertex_color_loc {
+        gl::VertexAttribPointer(
+            a_loc as GLuint,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            a_stride,
+            (the_base + Vertex::COLOR_OFFSET) as *const _,
+        );
+        gl::EnableVertexAttribArray(a_loc as GLuint);
+    }
+}
+
+pub fn gl_check_error() -> () {
     unsafe {
         let a_error = gl::GetError();
         let a_error_msg = match a_error {
@@ -81,7 +418,68 @@ fn gl_check_error() -> () {
     }
 }
 
-fn gl_get_uniform_location(the_program: GLuint, the_name: &str) -> Option<GLint> {
+/// Compile a shader, returning the info log on failure
+pub fn gl_compile_shader(the_kind: GLenum, the_source: &[u8]) -> Result<GLuint, String> {
+    // The built-in sources are NUL-terminated, CString adds its own
+    let a_end = the_source
+        .iter()
+        .position(|the_b| *the_b == 0)
+        .unwrap_or(the_source.len());
+    let a_source = std::ffi::CString::new(&the_source[..a_end]).unwrap();
+    unsafe {
+        let a_shader = gl::CreateShader(the_kind);
+        gl::ShaderSource(a_shader, 1, &a_source.as_ptr(), std::ptr::null());
+        gl::CompileShader(a_shader);
+        let mut a_status = gl::FALSE as GLint;
+        gl::GetShaderiv(a_shader, gl::COMPILE_STATUS, &mut a_status);
+        if a_status == gl::TRUE as GLint {
+            return Ok(a_shader);
+        }
+        let mut a_len = 0;
+        gl::GetShaderiv(a_shader, gl::INFO_LOG_LENGTH, &mut a_len);
+        let mut a_log = vec![0u8; a_len.max(1) as usize];
+        gl::GetShaderInfoLog(
+            a_shader,
+            a_len,
+            std::ptr::null_mut(),
+            a_log.as_mut_ptr() as *mut _,
+        );
+        gl::DeleteShader(a_shader);
+        Err(String::from_utf8_lossy(&a_log)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+}
+
+/// Link a program from compiled shaders, returning the info log on failure
+pub fn gl_link_program(the_vs: GLuint, the_fs: GLuint) -> Result<GLuint, String> {
+    unsafe {
+        let a_program = gl::CreateProgram();
+        gl::AttachShader(a_program, the_vs);
+        gl::AttachShader(a_program, the_fs);
+        gl::LinkProgram(a_program);
+        let mut a_status = gl::FALSE as GLint;
+        gl::GetProgramiv(a_program, gl::LINK_STATUS, &mut a_status);
+        if a_status == gl::TRUE as GLint {
+            return Ok(a_program);
+        }
+        let mut a_len = 0;
+        gl::GetProgramiv(a_program, gl::INFO_LOG_LENGTH, &mut a_len);
+        let mut a_log = vec![0u8; a_len.max(1) as usize];
+        gl::GetProgramInfoLog(
+            a_program,
+            a_len,
+            std::ptr::null_mut(),
+            a_log.as_mut_ptr() as *mut _,
+        );
+        gl::DeleteProgram(a_program);
+        Err(String::from_utf8_lossy(&a_log)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+}
+
+pub fn gl_get_uniform_location(the_program: GLuint, the_name: &str) -> Option<GLint> {
     unsafe {
         let a_name_c = std::ffi::CString::new(the_name).unwrap();
         let a_pos = gl::GetUniformLocation(the_program, a_name_c.as_ptr());
@@ -92,7 +490,7 @@ fn gl_get_uniform_location(the_program: GLuint, the_name: &str) -> Option<GLint>
         }
     }
 }
-fn gl_get_attrib_location(the_program: GLuint, the_name: &str) -> Option<GLint> {
+pub fn gl_get_attrib_location(the_program: GLuint, the_name: &str) -> Option<GLint> {
     unsafe {
         let a_name_c = std::ffi::CString::new(the_name).unwrap();
         let a_pos = gl::GetAttribLocation(the_program, a_name_c.as_ptr());
@@ -172,20 +570,191 @@ impl MatrixCache {
     }
 }
 
+/// Everything the vertex data is built from. Rotation, zoom and the camera
+/// are uniforms, so a spinning but otherwise static playfield does not
+/// need new vertices.
+struct VertexSource {
+    its_slots: [model::Slot; 6],
+    its_position: f32,
+    its_cursor_scale: f32,
+    /// Cursor, cursor shadow, inner and outer hexagon
+    its_colors: [model::Color; 4],
+    its_slot_colors: Vec<model::Color>,
+    its_obstacle_colors: Vec<model::Color>,
+    its_outer_hexagon_width: f32,
+    its_outer_hexagon_dashes: u32,
+    its_background: Option<model::Background>,
+}
+
+impl VertexSource {
+    fn get_colors(the_style: &model::Style) -> [model::Color; 4] {
+        [
+            the_style.get_cursor_color().clone(),
+            the_style.get_cursor_shadow_color().clone(),
+            the_style.get_inner_hexagon_color().clone(),
+            the_style.get_outer_hexagon_color().clone(),
+        ]
+    }
+    fn new(
+        the_game: &model::GameState,
+        the_style: &model::Style,
+        the_position: f32,
+    ) -> VertexSource {
+        VertexSource {
+            its_slots: the_game.get_slots().clone(),
+            its_position: the_position,
+            its_cursor_scale: the_game.get_cursor_scale(),
+            its_colors: VertexSource::get_colors(the_style),
+            its_slot_colors: the_style.get_slot_colors().clone(),
+            its_obstacle_colors: the_style.get_obstacle_colors().clone(),
+            its_outer_hexagon_width: the_style.get_outer_hexagon_width(),
+            its_outer_hexagon_dashes: the_style.get_outer_hexagon_dashes(),
+            its_background: the_style.get_background().cloned(),
+        }
+    }
+    /// Whether vertices built from this source still show `the_game` in
+    /// `the_style`, with the cursor at `the_position`. Compares without copying anything.
+    fn matches(
+        &self,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+        the_position: f32,
+    ) -> bool {
+        self.its_position == the_position
+            && self.its_cursor_scale == the_game.get_cursor_scale()
+            && self.its_outer_hexagon_width == the_style.get_outer_hexagon_width()
+            && self.its_outer_hexagon_dashes == the_style.get_outer_hexagon_dashes()
+            && &self.its_slots == the_game.get_slots()
+            && &self.its_slot_colors == the_style.get_slot_colors()
+            && &self.its_obstacle_colors == the_style.get_obstacle_colors()
+            && self.its_colors == VertexSource::get_colors(the_style)
+            && self.its_background.as_ref() == the_style.get_background()
+    }
+}
+
+/// A level's fragment shader linked with the built-in vertex shader
+struct LevelShader {
+    its_program: GLuint,
+    its_zoom_loc: Option<GLint>,
+    its_rotation_loc: Option<GLint>,
+    its_z_loc: Option<GLint>,
+    its_proj_loc: Option<GLint>,
+    its_alpha_loc: Option<GLint>,
+    its_time_loc: Option<GLint>,
+    its_vertex_loc: Option<GLint>,
+    its_vertex_color_loc: Option<GLint>,
+}
+
+impl LevelShader {
+    pub fn compile(the_fs_source: &str) -> Result<LevelShader, String> {
+        let a_vs = gl_compile_shader(gl::VERTEX_SHADER, VS_SRC)?;
+        let a_fs = gl_compile_shader(gl::FRAGMENT_SHADER, the_fs_source.as_bytes())
+            .map_err(|the_log| format!("compiling fragment shader failed: {}", the_log));
+        let a_fs = match a_fs {
+            Ok(a_fs) => a_fs,
+            Err(a_err) => {
+                unsafe { gl::DeleteShader(a_vs) };
+                return Err(a_err);
+            }
+        };
+        let a_program = gl_link_program(a_vs, a_fs)
+            .map_err(|the_log| format!("linking fragment shader failed: {}", the_log));
+        unsafe {
+            // The program keeps what it needs
+            gl::DeleteShader(a_vs);
+            gl::DeleteShader(a_fs);
+        }
+        let a_program = a_program?;
+        Ok(LevelShader {
+            its_program: a_program,
+            its_zoom_loc: gl_get_uniform_location(a_program, "zoom"),
+            its_rotation_loc: gl_get_uniform_location(a_program, "rotation"),
+            its_z_loc: gl_get_uniform_location(a_program, "z_value"),
+            its_proj_loc: gl_get_uniform_location(a_program, "proj"),
+            its_alpha_loc: gl_get_uniform_location(a_program, "alpha"),
+            its_time_loc: gl_get_uniform_location(a_program, "time"),
+            its_vertex_loc: gl_get_attrib_location(a_program, "vertex"),
+            its_vertex_color_loc: gl_get_attrib_location(a_program, "vertex_color"),
+        })
+    }
+}
+
+impl Drop for LevelShader {
+    fn drop(&mut self) -> () {
+        unsafe { gl::DeleteProgram(self.its_program) };
+    }
+}
+
 pub struct OGLRenderer {
-    _its_program: u32,
-    its_vertex_glbuf: u32,
-    its_vertex_data: Vec<f32>,
+    its_program: u32,
+    its_vertex_stream: StreamBuffer,
+    its_geometry: Geometry,
+    /// Byte offset of this frame's vertex data within the stream buffer
+    its_vertex_base: usize,
+    /// What the latest upload was built from, `None` before the first one
+    its_vertex_source: Option<VertexSource>,
     its_aspect: f32,
     its_matrix_cache: MatrixCache,
     its_zoom_loc: Option<GLint>,
     its_rotation_loc: Option<GLint>,
     its_z_loc: Option<GLint>,
     its_proj_loc: Option<GLint>,
-    its_color_loc: GLint,
+    its_alpha_loc: Option<GLint>,
     its_vertex_loc: GLint,
+    its_vertex_color_loc: GLint,
     its_vertex_array_obj: GLuint,
     its_frame_time: f32,
+    its_start_time: std::time::Instant,
+    its_time_loc: Option<GLint>,
+    its_reduced_flash: bool,
+    its_flash_limiter: accessibility::FlashLimiter,
+    /// Style of the current frame as the flash limiter has changed it, if
+    /// it is enabled
+    its_limited_style: Option<model::Style>,
+    its_cursor_shape: Option<Shape>,
+    its_cursor_position: Option<f32>,
+    its_width: f32,
+    its_height: f32,
+    its_forced_aspect: Option<f32>,
+    /// x, y, width and height of the area the playfield is drawn into, in
+    /// pixels from the bottom left corner of the window
+    its_viewport: (GLint, GLint, GLsizei, GLsizei),
+    its_debug_overlay: bool,
+    its_debug_lines: Option<Vec<String>>,
+    its_overlay: Overlay,
+    /// Split times of the best run in seconds, see `model::STAGES`
+    its_best_splits: Vec<f32>,
+    its_input_display: Option<InputDisplay>,
+    /// Background color of the greenscreen mode, if it is enabled
+    its_chroma_key: Option<model::Color>,
+    its_catalog: i18n::Catalog,
+    its_opponent: Option<OpponentStatus>,
+    its_console: Option<Vec<String>>,
+    its_stats: Option<LifetimeStats>,
+    its_heatmap: Option<Heatmap>,
+    its_prompt: Option<String>,
+    its_notice: Option<String>,
+    /// Title of the level and how far it is shifted to the right, as a
+    /// share of the window width
+    its_intro_title: Option<(String, f32)>,
+    its_game_over_hidden: bool,
+    its_countdown: Option<u32>,
+    its_fps: Option<FpsStats>,
+    /// Medal of the run that just ended, shown on the game over screen
+    its_medal: Option<Medal>,
+    /// Render target for the motion trail and post effects, if any of them
+    /// is enabled
+    its_offscreen: Option<OffscreenTarget>,
+    /// Whether the window is being resized, see `Renderer::set_resizing`
+    its_resizing: bool,
+    its_motion_trail: bool,
+    its_proximity_shading: bool,
+    its_post_effects: PostEffects,
+    /// Remaining time of the death effects, in seconds
+    its_death_effect: f32,
+    its_was_running: bool,
+    /// Replaces the built-in program for the slots, if the level has one
+    its_level_shader: Option<LevelShader>,
 }
 
 impl OGLRenderer {
@@ -200,11 +769,6 @@ impl OGLRenderer {
             gl::Disable(gl::DEPTH_TEST);
             gl::Disable(gl::BLEND);
         }
-        let a_buf_id = unsafe {
-            let mut a_buf_id = std::mem::zeroed();
-            gl::GenBuffers(1, &mut a_buf_id);
-            a_buf_id
-        };
         let a_program = OGLRenderer::create_program();
         let a_aspect = the_width as f32 / the_height as f32;
         let a_vao = unsafe {
@@ -215,19 +779,59 @@ impl OGLRenderer {
             a_vao
         };
         let a_renderer = OGLRenderer {
-            _its_program: a_program,
-            its_vertex_glbuf: a_buf_id,
-            its_vertex_data: Vec::new(),
+            its_program: a_program,
+            its_vertex_stream: StreamBuffer::new(),
+            its_geometry: Geometry::new(),
+            its_vertex_base: 0,
+            its_vertex_source: None,
             its_aspect: a_aspect,
             its_matrix_cache: MatrixCache::new(the_game.get_style(), a_aspect),
             its_zoom_loc: gl_get_uniform_location(a_program, "zoom"),
             its_rotation_loc: gl_get_uniform_location(a_program, "rotation"),
             its_z_loc: gl_get_uniform_location(a_program, "z_value"),
             its_proj_loc: gl_get_uniform_location(a_program, "proj"),
-            its_color_loc: gl_get_uniform_location(a_program, "color").unwrap(),
+            its_alpha_loc: gl_get_uniform_location(a_program, "alpha"),
             its_vertex_loc: gl_get_attrib_location(a_program, "vertex").unwrap(),
+            its_vertex_color_loc: gl_get_attrib_location(a_program, "vertex_color").unwrap(),
             its_vertex_array_obj: a_vao,
             its_frame_time: 0.,
+            its_start_time: std::time::Instant::now(),
+            its_time_loc: gl_get_uniform_location(a_program, "time"),
+            its_reduced_flash: false,
+            its_flash_limiter: accessibility::FlashLimiter::new(),
+            its_limited_style: None,
+            its_cursor_shape: None,
+            its_cursor_position: None,
+            its_width: the_width as f32,
+            its_height: the_height as f32,
+            its_forced_aspect: None,
+            its_viewport: (0, 0, the_width as GLsizei, the_height as GLsizei),
+            its_debug_overlay: false,
+            its_debug_lines: None,
+            its_overlay: Overlay::new(),
+            its_best_splits: Vec::new(),
+            its_input_display: None,
+            its_chroma_key: None,
+            its_catalog: i18n::Catalog::default(),
+            its_opponent: None,
+            its_console: None,
+            its_stats: None,
+            its_heatmap: None,
+            its_prompt: None,
+            its_notice: None,
+            its_intro_title: None,
+            its_game_over_hidden: false,
+            its_countdown: None,
+            its_fps: None,
+            its_medal: None,
+            its_offscreen: None,
+            its_resizing: false,
+            its_motion_trail: false,
+            its_proximity_shading: false,
+            its_post_effects: PostEffects::default(),
+            its_death_effect: 0.,
+            its_was_running: true,
+            its_level_shader: None,
         };
         a_renderer
     }
@@ -266,235 +870,1009 @@ impl OGLRenderer {
             .get_matrix(the_config, self.its_aspect)
     }
 
-    fn update_vertex_buffer(&mut self, the_game: &model::GameState) -> () {
-        self.its_vertex_data.clear();
-        // create outer hexagon vertices
-        let mut push_vertex = |x: f32, y: f32| {
-            self.its_vertex_data.push(x);
-            self.its_vertex_data.push(y);
+    /// Where the cursor is drawn
+    fn get_cursor_position(&self, the_game: &model::GameState) -> f32 {
+        self.its_cursor_position
+            .unwrap_or_else(|| the_game.get_position())
+    }
+
+    /// Where the playfield point `the_x`/`the_y` ends up on the screen, in
+    /// pixels from the top left corner. Mirrors the vertex shader.
+    fn project_to_screen(
+        &mut self,
+        the_config: &model::Style,
+        the_zoom: f32,
+        the_x: f32,
+        the_y: f32,
+    ) -> (f32, f32) {
+        let a_alpha = (the_x + the_config.get_rotation()).fract() * 2. * std::f32::consts::PI;
+        let a_r = std::f32::consts::SQRT_2 * the_y * the_zoom;
+        let a_pos = glm::vec4(a_alpha.sin() * a_r, a_alpha.cos() * a_r, 0., 1.);
+        // The matrix is uploaded transposed
+        let a_pos = self.get_projection_matrix(the_config).transpose() * a_pos;
+        let a_ndc_x = a_pos.x / a_pos.w;
+        let a_ndc_y = a_pos.y / a_pos.w;
+        let (a_vp_x, a_vp_y, a_vp_w, a_vp_h) = self.its_viewport;
+        (
+            a_vp_x as f32 + (a_ndc_x + 1.) / 2. * a_vp_w as f32,
+            self.its_height - a_vp_y as f32 - (a_ndc_y + 1.) / 2. * a_vp_h as f32,
+        )
+    }
+
+    /// Fit the largest area of the forced aspect ratio into the window and
+    /// center it
+    fn update_viewport(&mut self) -> () {
+        let a_window_aspect = self.its_width / self.its_height;
+        self.its_viewport = match self.its_forced_aspect {
+            Some(a_aspect) if a_aspect > 0. && self.its_height > 0. => {
+                let (a_w, a_h) = if a_window_aspect > a_aspect {
+                    (self.its_height * a_aspect, self.its_height)
+                } else {
+                    (self.its_width, self.its_width / a_aspect)
+                };
+                (
+                    ((self.its_width - a_w) / 2.) as GLint,
+                    ((self.its_height - a_h) / 2.) as GLint,
+                    a_w as GLsizei,
+                    a_h as GLsizei,
+                )
+            }
+            _ => (0, 0, self.its_width as GLsizei, self.its_height as GLsizei),
         };
-        push_vertex(0., 0.);
-        for i in 0..the_game.get_slots().len() + 1 {
-            push_vertex((i as f32 / 6.).fract(), constants::OUTER_HEXAGON_Y);
-        }
-        // create inner hexagon vertices
-        push_vertex(0., 0.);
-        for i in 0..the_game.get_slots().len() + 1 {
-            push_vertex((i as f32 / 6.).fract(), constants::INNER_HEXAGON_Y);
-        }
-        // cursor coordinates
-        let c_left = the_game.get_position() - constants::CURSOR_W / 2.;
-        let c_right = the_game.get_position() + constants::CURSOR_W / 2.;
-        let c_top = constants::CURSOR_Y + constants::CURSOR_H;
-        // create cursorShadow vertices
-        push_vertex(c_left, constants::CURSOR_Y);
-        push_vertex(c_right, constants::CURSOR_Y);
-        push_vertex(the_game.get_position(), c_top);
-        // create cursor vertices
-        push_vertex(c_left, constants::CURSOR_Y);
-        push_vertex(c_right, constants::CURSOR_Y);
-        push_vertex(the_game.get_position(), c_top);
-        // create slot vertices
-        let slot_width_sum = the_game.get_slot_width_sum();
-        let mut x = 0.;
-        let sl = 2.;
-        for i in 0..the_game.get_slots().len() {
-            push_vertex(x, 0.);
-            push_vertex(x, sl);
-            x += the_game.get_slots()[i].get_width() as f32 / slot_width_sum;
-            push_vertex(x, 0.);
-            push_vertex(x, sl);
-        }
-        // create obstacle vertices
-        x = 0.;
-        for s in 0..the_game.get_slots().len() {
-            let slot = &the_game.get_slots()[s];
-            let slot_width = slot.get_width() / slot_width_sum;
-            for o in 0..slot.get_obstacles().len() {
-                let obstacle = &slot.get_obstacles()[o];
-                push_vertex(x, obstacle.get_distance().max(0.));
-                push_vertex(x, obstacle.get_distance() + obstacle.get_height());
-                push_vertex(x + slot_width, obstacle.get_distance().max(0.));
-                push_vertex(
-                    x + slot_width,
-                    obstacle.get_distance() + obstacle.get_height(),
-                );
+        let (_, _, a_w, a_h) = self.its_viewport;
+        self.its_aspect = a_w as f32 / a_h.max(1) as f32;
+    }
+
+    unsafe fn use_window_viewport(&self) -> () {
+        gl::Viewport(0, 0, self.its_width as GLsizei, self.its_height as GLsizei);
+    }
+
+    unsafe fn use_playfield_viewport(&self) -> () {
+        let (a_x, a_y, a_w, a_h) = self.its_viewport;
+        gl::Viewport(a_x, a_y, a_w, a_h);
+    }
+
+    /// Label every slot with its index and width
+    fn add_slot_labels(
+        &mut self,
+        the_game: &model::GameState,
+        the_config: &model::Style,
+        the_zoom: f32,
+    ) -> () {
+        let a_color = model::Color::rgba(1., 1., 0., 1.);
+        let a_width_sum = the_game.get_slot_width_sum();
+        let mut a_x = 0.;
+        for (a_idx, a_slot) in the_game.get_slots().iter().enumerate() {
+            let a_width = a_slot.get_width() / a_width_sum;
+            let (a_sx, a_sy) =
+                self.project_to_screen(the_config, the_zoom, a_x + a_width / 2., DEBUG_LABEL_Y);
+            let a_label = format!("{}:{:.2}", a_idx, a_slot.get_width());
+            self.its_overlay
+                .add_text_centered(&a_label, a_sx, a_sy, DEBUG_FONT_PIXEL, &a_color);
+            a_x += a_width;
+        }
+    }
+
+    /// Mark the corners of every obstacle and of the cursor, which is what
+    /// collisions are checked against
+    fn add_hitboxes(
+        &mut self,
+        the_game: &model::GameState,
+        the_config: &model::Style,
+        the_zoom: f32,
+    ) -> () {
+        let a_color = model::Color::rgba(1., 0., 1., 1.);
+        let a_far = the_game.get_despawn_distance();
+        let a_width_sum = the_game.get_slot_width_sum();
+        let mut a_corners = Vec::new();
+        let mut a_x = 0.;
+        for a_slot in the_game.get_slots() {
+            let a_width = a_slot.get_width() / a_width_sum;
+            for a_obstacle in a_slot.get_obstacles() {
+                let (a_left, a_right) = a_obstacle.get_span();
+                let a_near = a_obstacle.get_distance().max(0.);
+                let a_end = (a_obstacle.get_distance() + a_obstacle.get_height()).min(a_far);
+                if a_near >= a_end {
+                    continue;
+                }
+                for a_span in &[a_left, a_right] {
+                    a_corners.push((a_x + a_span * a_width, a_near));
+                    a_corners.push((a_x + a_span * a_width, a_end));
+                }
             }
-            x += slot_width;
+            a_x += a_width;
+        }
+        let a_position = self.get_cursor_position(the_game);
+        let a_half_width = constants::CURSOR_W * the_game.get_cursor_scale() / 2.;
+        let a_top = constants::CURSOR_Y + constants::CURSOR_H * the_game.get_cursor_scale();
+        a_corners.push((a_position - a_half_width, constants::CURSOR_Y));
+        a_corners.push((a_position + a_half_width, constants::CURSOR_Y));
+        a_corners.push((a_position, a_top));
+        for (a_cx, a_cy) in a_corners {
+            let (a_sx, a_sy) = self.project_to_screen(the_config, the_zoom, a_cx, a_cy);
+            self.its_overlay.add_rect(
+                a_sx - DEBUG_MARKER_SIZE / 2.,
+                a_sy - DEBUG_MARKER_SIZE / 2.,
+                DEBUG_MARKER_SIZE,
+                DEBUG_MARKER_SIZE,
+                &a_color,
+            );
         }
     }
-}
 
-impl Renderer for OGLRenderer {
-    fn resize(&mut self, the_width: u32, the_height: u32) -> () {
-        unsafe {
-            self.its_aspect = the_width as f32 / the_height as f32;
-            gl::Viewport(0, 0, the_width as GLsizei, the_height as GLsizei);
+    /// List the debug lines in the bottom left corner
+    fn add_debug_lines(&mut self) -> () {
+        let a_text = match &self.its_debug_lines {
+            Some(a_lines) if !a_lines.is_empty() => a_lines.join("\n"),
+            _ => return,
+        };
+        let (_, a_height) = self.its_overlay.measure_text(&a_text, DEBUG_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_text,
+            HUD_MARGIN,
+            self.its_height - HUD_MARGIN - a_height,
+            DEBUG_FONT_PIXEL,
+            &model::Color::rgba(1., 1., 0., 1.),
+        );
+    }
+
+    /// Show the latest split of the run and how it compares to the best run,
+    /// green if it is ahead and red if it is behind
+    fn add_split(&mut self, the_game: &model::GameState, the_config: &model::Style) -> () {
+        let a_splits = the_game.get_run().get_splits();
+        let a_stage = match a_splits.len().checked_sub(1) {
+            Some(a_stage) => a_stage,
+            None => return,
+        };
+        let a_time = a_splits[a_stage];
+        let a_stage_key = format!("stage.{}", model::STAGES[a_stage].0.to_lowercase());
+        let a_label = format!(
+            "{} {}",
+            self.its_catalog.get(&a_stage_key),
+            summary::format_time(a_time)
+        );
+        self.its_overlay.add_text(
+            &a_label,
+            HUD_MARGIN,
+            HUD_MARGIN,
+            HUD_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+        let a_best = match self.its_best_splits.get(a_stage) {
+            Some(a_best) => *a_best,
+            None => return,
+        };
+        let a_delta = a_time.as_secs_f32() - a_best;
+        let a_color = if a_delta <= 0. {
+            model::Color::rgba(0.2, 1., 0.2, 1.)
+        } else {
+            model::Color::rgba(1., 0.2, 0.2, 1.)
+        };
+        let (a_width, _) = self.its_overlay.measure_text(&a_label, HUD_FONT_PIXEL);
+        let (a_space, _) = self.its_overlay.measure_text(" ", HUD_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &format!("{:+.3}", a_delta),
+            HUD_MARGIN + a_width + a_space,
+            HUD_MARGIN,
+            HUD_FONT_PIXEL,
+            &a_color,
+        );
+    }
+
+    /// Show the run's time in the top right corner, or the time left until
+    /// the goal of a time attack
+    fn add_timer(&mut self, the_game: &model::GameState, the_config: &model::Style) -> () {
+        let a_time = the_game.get_run().get_time();
+        let a_text = match the_game.get_goal() {
+            Some(a_goal) => summary::format_time(a_goal.saturating_sub(a_time)),
+            None => summary::format_time(a_time),
+        };
+        let (a_width, _) = self.its_overlay.measure_text(&a_text, HUD_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_text,
+            self.its_width - HUD_MARGIN - a_width,
+            HUD_MARGIN,
+            HUD_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// Show the score below the timer, along with the combo multiplier once
+    /// the combo has raised it
+    fn add_score(&mut self, the_game: &model::GameState, the_config: &model::Style) -> () {
+        let a_run = the_game.get_run();
+        let mut a_text = self
+            .its_catalog
+            .format("hud.score", &[("count", &a_run.get_score())]);
+        let a_multiplier = a_run.get_combo_multiplier();
+        if a_multiplier > 1 {
+            a_text += " ";
+            a_text += &self
+                .its_catalog
+                .format("hud.combo", &[("multiplier", &a_multiplier)]);
         }
+        let (a_width, a_height) = self.its_overlay.measure_text(&a_text, HUD_FONT_PIXEL);
+        // below the opponent's time in races
+        let a_row = if self.its_opponent.is_some() { 2. } else { 1. };
+        self.its_overlay.add_text(
+            &a_text,
+            self.its_width - HUD_MARGIN - a_width,
+            HUD_MARGIN * (a_row + 1.) + a_height * a_row,
+            HUD_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
     }
-    fn render(&mut self, the_game: &model::GameState, the_delta: std::time::Duration) -> () {
-        self.its_frame_time +=
-            (the_delta.as_millis() as f32 - self.its_frame_time) / FRAME_TIME_FILTER_STRENGTH;
 
-        let a_clear_color = model::Color::rgba(0., 0., 0., 1.);
-        unsafe {
-            let config = the_game.get_style();
-            if config.get_flash_time().as_millis() > 0 {
-                gl::ClearColor(1.0, 1.0, 1.0, 1.0);
-                gl::Clear(gl::COLOR_BUFFER_BIT);
-                return;
-            }
-            gl::ClearColor(
-                a_clear_color.its_r,
-                a_clear_color.its_g,
-                a_clear_color.its_b,
-                a_clear_color.its_a,
+    /// Show the active power-ups and how long they still last below the
+    /// split
+    fn add_power_ups(&mut self, the_game: &model::GameState, the_config: &model::Style) -> () {
+        let a_lines: Vec<String> = the_game
+            .get_power_ups()
+            .iter()
+            .map(|(the_power_up, the_left)| {
+                let a_time = format!("{:.1}", the_left.as_secs_f32());
+                self.its_catalog
+                    .format(get_power_up_key(*the_power_up), &[("time", &a_time)])
+            })
+            .collect();
+        if a_lines.is_empty() {
+            return;
+        }
+        let (_, a_height) = self.its_overlay.measure_text("0", HUD_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_lines.join("\n"),
+            HUD_MARGIN,
+            HUD_MARGIN * 2. + a_height,
+            HUD_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// Show the opponent's time below the timer, dimmed once they have died
+    fn add_opponent(&mut self, the_config: &model::Style) -> () {
+        let a_status = match self.its_opponent {
+            Some(a_status) => a_status,
+            None => return,
+        };
+        let a_time = summary::format_time(a_status.time);
+        let (a_key, a_color) = if a_status.alive {
+            ("hud.opponent", the_config.get_cursor_color().clone())
+        } else {
+            ("hud.opponent_died", model::Color::rgba(0.5, 0.5, 0.5, 1.))
+        };
+        let a_text = self.its_catalog.format(a_key, &[("time", &a_time)]);
+        let (a_width, a_height) = self.its_overlay.measure_text(&a_text, HUD_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_text,
+            self.its_width - HUD_MARGIN - a_width,
+            HUD_MARGIN * 2. + a_height,
+            HUD_FONT_PIXEL,
+            &a_color,
+        );
+    }
+
+    /// Show the console's lines on a dark panel across the top of the window
+    fn add_console(&mut self) -> () {
+        let a_text = match &self.its_console {
+            Some(a_lines) => a_lines.join("\n"),
+            None => return,
+        };
+        let (_, a_height) = self.its_overlay.measure_text(&a_text, CONSOLE_FONT_PIXEL);
+        self.its_overlay.add_rect(
+            0.,
+            0.,
+            self.its_width,
+            a_height + 2. * HUD_MARGIN,
+            &model::Color::rgba(0.05, 0.05, 0.05, 1.),
+        );
+        self.its_overlay.add_text(
+            &a_text,
+            HUD_MARGIN,
+            HUD_MARGIN,
+            CONSOLE_FONT_PIXEL,
+            &model::Color::rgba(0.85, 0.85, 0.85, 1.),
+        );
+    }
+
+    /// Show the lifetime statistics on a dark panel covering the window,
+    /// with a bar per level for the deaths in the levels that took the most
+    fn add_stats(&mut self, the_config: &model::Style) -> () {
+        let a_stats = match &self.its_stats {
+            Some(a_stats) => a_stats,
+            None => return,
+        };
+        let a_play_time = summary::format_time(std::time::Duration::from_secs_f32(
+            a_stats.play_time.max(0.),
+        ));
+        let a_text = format!(
+            "{}\n{}\n{}\n{}",
+            self.its_catalog
+                .format("stats.play_time", &[("time", &a_play_time)]),
+            self.its_catalog
+                .format("stats.runs", &[("count", &a_stats.runs)]),
+            self.its_catalog.format(
+                "stats.longest_streak",
+                &[("count", &a_stats.longest_streak)]
+            ),
+            self.its_catalog.get("stats.deaths"),
+        );
+        let mut a_levels: Vec<(&String, &u32)> = a_stats.deaths.iter().collect();
+        a_levels.sort_by(|the_a, the_b| the_b.1.cmp(the_a.1));
+        a_levels.truncate(STATS_MAX_LEVELS);
+        let a_levels: Vec<(String, u32)> = a_levels
+            .into_iter()
+            .map(|(the_key, the_deaths)| (the_key.clone(), *the_deaths))
+            .collect();
+
+        let a_panel = model::Color::rgba(0.05, 0.05, 0.05, 1.);
+        let a_text_color = model::Color::rgba(0.85, 0.85, 0.85, 1.);
+        let a_bar_color = the_config.get_cursor_color().clone();
+        self.its_overlay
+            .add_rect(0., 0., self.its_width, self.its_height, &a_panel);
+        let a_title = self.its_catalog.get("stats.title").to_string();
+        let (_, a_title_height) = self.its_overlay.measure_text(&a_title, HUD_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_title,
+            HUD_MARGIN,
+            HUD_MARGIN,
+            HUD_FONT_PIXEL,
+            &a_text_color,
+        );
+        let mut a_y = 2. * HUD_MARGIN + a_title_height;
+        let (_, a_text_height) = self.its_overlay.measure_text(&a_text, STATS_FONT_PIXEL);
+        self.its_overlay
+            .add_text(&a_text, HUD_MARGIN, a_y, STATS_FONT_PIXEL, &a_text_color);
+        a_y += a_text_height + HUD_MARGIN;
+
+        // the bars start right of the longest level name
+        let a_label_width = a_levels
+            .iter()
+            .map(|(the_key, _)| self.its_overlay.measure_text(the_key, STATS_FONT_PIXEL).0)
+            .fold(0., f32::max);
+        let a_most_deaths = a_levels.first().map_or(1, |(_, the_deaths)| *the_deaths);
+        let a_bar_x = 2. * HUD_MARGIN + a_label_width;
+        let a_max_width = (self.its_width * 0.6 - a_bar_x).max(HUD_MARGIN);
+        for (a_key, a_deaths) in &a_levels {
+            let (_, a_height) = self.its_overlay.measure_text(a_key, STATS_FONT_PIXEL);
+            self.its_overlay
+                .add_text(a_key, HUD_MARGIN, a_y, STATS_FONT_PIXEL, &a_text_color);
+            let a_width = a_max_width * *a_deaths as f32 / a_most_deaths.max(1) as f32;
+            self.its_overlay
+                .add_rect(a_bar_x, a_y, a_width, a_height, &a_bar_color);
+            self.its_overlay.add_text(
+                &a_deaths.to_string(),
+                a_bar_x + a_width + HUD_MARGIN,
+                a_y,
+                STATS_FONT_PIXEL,
+                &a_text_color,
             );
-            gl::Clear(gl::COLOR_BUFFER_BIT);
+            a_y += a_height + HUD_MARGIN / 2.;
+        }
+    }
 
-            if let Some(rotation_loc) = self.its_rotation_loc {
-                gl::Uniform1f(rotation_loc, config.get_rotation());
-            }
-            // The longer dimension will see the full viewport - which is a 1x1 square.
-            // Since by default we project to have x coordinates go from -1 to 1,
-            // we only need to zoom if y is longer - i.e. aspect is less than zero
-            //const aspect = gl.canvas.width / gl.canvas.height;
-            let aspect_zoom = if self.its_aspect >= 1. {
-                self.its_aspect
-            } else {
-                1.
-            };
-            let zoom = config.get_zoom() * aspect_zoom;
-            if let Some(zoom_loc) = self.its_zoom_loc {
-                gl::Uniform1f(zoom_loc, zoom);
-            }
-            if let Some(z_loc) = self.its_z_loc {
-                assert!(z_loc != -1);
-                gl::Uniform1f(z_loc, 0.);
-            }
-            if let Some(proj_loc) = self.its_proj_loc {
-                let proj = self.get_projection_matrix(the_game.get_style());
-                gl::UniformMatrix4fv(
-                    proj_loc,
-                    1 as gl::types::GLsizei,
-                    gl::TRUE,
-                    proj.as_ptr() as *const _,
+    /// Show the heatmap in the bottom right corner, a row per stage with the
+    /// slots from left to right. The cells get redder the more deaths they
+    /// have seen.
+    fn add_heatmap(&mut self) -> () {
+        let a_heatmap = match &self.its_heatmap {
+            Some(a_heatmap) => a_heatmap.clone(),
+            None => return,
+        };
+        let a_text_color = model::Color::rgba(0.85, 0.85, 0.85, 1.);
+        let a_cold = model::Color::rgba(0.15, 0.15, 0.15, 1.);
+        let a_hot = model::Color::rgba(1., 0.1, 0.1, 1.);
+        let a_labels: Vec<String> =
+            std::iter::once(self.its_catalog.get("heatmap.start").to_string())
+                .chain(model::STAGES.iter().map(|(the_name, _)| {
+                    let a_key = format!("stage.{}", the_name.to_lowercase());
+                    self.its_catalog.get(&a_key).to_string()
+                }))
+                .collect();
+        let a_label_width = a_labels
+            .iter()
+            .map(|the_label| self.its_overlay.measure_text(the_label, STATS_FONT_PIXEL).0)
+            .fold(0., f32::max);
+        let a_grid_x = self.its_width - HUD_MARGIN - HEATMAP_SLOTS as f32 * HEATMAP_CELL;
+        let a_grid_y = self.its_height - HUD_MARGIN - HEATMAP_ROWS as f32 * HEATMAP_CELL;
+        let a_title = self.its_catalog.get("heatmap.title").to_string();
+        let (_, a_title_height) = self.its_overlay.measure_text(&a_title, STATS_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_title,
+            a_grid_x - HUD_MARGIN - a_label_width,
+            a_grid_y - HUD_MARGIN - a_title_height,
+            STATS_FONT_PIXEL,
+            &a_text_color,
+        );
+        let a_max = a_heatmap.get_max().max(1) as f32;
+        for (a_row, a_counts) in a_heatmap.counts.iter().enumerate() {
+            let a_y = a_grid_y + a_row as f32 * HEATMAP_CELL;
+            let (a_width, a_height) = self
+                .its_overlay
+                .measure_text(&a_labels[a_row], STATS_FONT_PIXEL);
+            self.its_overlay.add_text(
+                &a_labels[a_row],
+                a_grid_x - HUD_MARGIN - a_width,
+                a_y + (HEATMAP_CELL - a_height) / 2.,
+                STATS_FONT_PIXEL,
+                &a_text_color,
+            );
+            for (a_slot, a_count) in a_counts.iter().enumerate() {
+                let a_color = a_cold.mix(&a_hot, *a_count as f32 / a_max);
+                // a gap of a pixel keeps the cells apart
+                self.its_overlay.add_rect(
+                    a_grid_x + a_slot as f32 * HEATMAP_CELL,
+                    a_y,
+                    HEATMAP_CELL - 1.,
+                    HEATMAP_CELL - 1.,
+                    &a_color,
                 );
-                gl_check_error();
             }
+        }
+    }
 
-            // render slots
-            self.update_vertex_buffer(the_game);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.its_vertex_glbuf);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (self.its_vertex_data.len() * std::mem::size_of::<f32>()) as gl::types::GLsizeiptr,
-                self.its_vertex_data.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-            if gl::BindVertexArray::is_loaded() {
-                gl::BindVertexArray(self.its_vertex_array_obj);
-            }
-            gl_check_error();
-            gl::VertexAttribPointer(
-                self.its_vertex_loc as GLuint,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                0,
-                std::mem::zeroed(),
+    /// Show the held direction as a pair of arrows of which the active one
+    /// is lit, followed by the recent taps, in the bottom left corner
+    fn add_input_display(&mut self, the_config: &model::Style) -> () {
+        let a_display = match &self.its_input_display {
+            Some(a_display) => a_display,
+            None => return,
+        };
+        let a_lit = the_config.get_cursor_color().clone();
+        let a_unlit = model::Color::rgba(0.3, 0.3, 0.3, 1.);
+        let (a_width, a_height) = self.its_overlay.measure_text("<", HUD_FONT_PIXEL);
+        let a_y = self.its_height - HUD_MARGIN - a_height;
+        let a_advance = self.its_overlay.measure_text("<<", HUD_FONT_PIXEL).0 - a_width;
+        let a_arrows = [
+            ("<", a_display.direction < 0),
+            (">", a_display.direction > 0),
+        ];
+        for (a_idx, (a_arrow, a_held)) in a_arrows.iter().enumerate() {
+            let a_x = HUD_MARGIN + a_idx as f32 * a_advance;
+            let a_color = if *a_held { &a_lit } else { &a_unlit };
+            self.its_overlay
+                .add_text(a_arrow, a_x, a_y, HUD_FONT_PIXEL, a_color);
+        }
+        // newest first, right next to the arrows
+        let a_taps: String = a_display
+            .taps
+            .iter()
+            .rev()
+            .map(|the_tap| the_tap.get_symbol())
+            .collect();
+        let a_x = HUD_MARGIN + 3. * a_advance;
+        self.its_overlay
+            .add_text(&a_taps, a_x, a_y, HUD_FONT_PIXEL, &a_lit);
+    }
+
+    /// Show the prompt, e.g. the one the run is waiting on, near the top of
+    /// the window, on a dark band so that it stays readable on any colors
+    fn add_prompt(&mut self, the_config: &model::Style) -> () {
+        if let Some(a_text) = self.its_prompt.clone() {
+            self.add_banner(&a_text, PROMPT_Y, PROMPT_FONT_PIXEL, the_config);
+        }
+    }
+
+    /// Show the notice near the bottom of the window, like the prompt
+    fn add_notice(&mut self, the_config: &model::Style) -> () {
+        if let Some(a_text) = self.its_notice.clone() {
+            self.add_banner(&a_text, NOTICE_Y, NOTICE_FONT_PIXEL, the_config);
+        }
+    }
+
+    /// Show `the_text` centered at `the_y` times the window height, on a
+    /// band across the window
+    fn add_banner(
+        &mut self,
+        the_text: &str,
+        the_y: f32,
+        the_font_pixel: f32,
+        the_config: &model::Style,
+    ) -> () {
+        let (_, a_height) = self.its_overlay.measure_text(the_text, the_font_pixel);
+        let a_y = self.its_height * the_y;
+        self.its_overlay.add_rect(
+            0.,
+            a_y - a_height / 2. - HUD_MARGIN,
+            self.its_width,
+            a_height + 2. * HUD_MARGIN,
+            &model::Color::rgba(0.05, 0.05, 0.05, 1.),
+        );
+        self.its_overlay.add_text_centered(
+            the_text,
+            self.its_width / 2.,
+            a_y,
+            the_font_pixel,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// Show the level's title during its intro, as it slides across the
+    /// window
+    fn add_intro_title(&mut self, the_config: &model::Style) -> () {
+        let (a_text, a_offset) = match &self.its_intro_title {
+            Some((a_text, a_offset)) => (a_text.clone(), *a_offset),
+            None => return,
+        };
+        self.its_overlay.add_text_centered(
+            &a_text,
+            self.its_width * (0.5 + a_offset),
+            self.its_height * INTRO_TITLE_Y,
+            INTRO_TITLE_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// Show the countdown after a pause where the level's title would be
+    fn add_countdown(&mut self, the_config: &model::Style) -> () {
+        let a_seconds = match self.its_countdown {
+            Some(a_seconds) => a_seconds,
+            None => return,
+        };
+        self.its_overlay.add_text_centered(
+            &a_seconds.to_string(),
+            self.its_width / 2.,
+            self.its_height * INTRO_TITLE_Y,
+            COUNTDOWN_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// Show the frame rate at the top center, dimmed so that it does not
+    /// distract from the run
+    fn add_fps(&mut self) -> () {
+        let a_stats = match self.its_fps {
+            Some(a_stats) => a_stats,
+            None => return,
+        };
+        let a_text = self.its_catalog.format(
+            "hud.fps",
+            &[
+                ("avg", &a_stats.avg),
+                ("min", &a_stats.min),
+                ("max", &a_stats.max),
+            ],
+        );
+        let (a_width, _) = self.its_overlay.measure_text(&a_text, DEBUG_FONT_PIXEL);
+        self.its_overlay.add_text(
+            &a_text,
+            (self.its_width - a_width) / 2.,
+            HUD_MARGIN,
+            DEBUG_FONT_PIXEL,
+            &model::Color::rgba(0.7, 0.7, 0.7, 1.),
+        );
+    }
+
+    /// Show how the run went below the playfield, along with the seed if the
+    /// player chose it
+    fn add_game_over_stats(
+        &mut self,
+        the_game: &model::GameState,
+        the_config: &model::Style,
+    ) -> () {
+        let a_run = the_game.get_run();
+        let a_time = summary::format_time(a_run.get_time());
+        let mut a_text = String::new();
+        if a_run.is_completed() {
+            a_text += self.its_catalog.get("hud.completed");
+            a_text += "\n";
+        }
+        a_text += &self.its_catalog.format("hud.time", &[("time", &a_time)]);
+        a_text += "\n";
+        if let Some(a_medal) = self.its_medal {
+            let a_name = self.its_catalog.get(a_medal.get_key());
+            a_text += &self.its_catalog.format("hud.medal", &[("medal", &a_name)]);
+            a_text += "\n";
+        }
+        // a seed that has been typed in is worth sharing
+        if a_run.get_seed_text().is_some() {
+            a_text += &summary::format_seed(&self.its_catalog, a_run);
+            a_text += "\n";
+        }
+        a_text += &summary::format_run_metrics(&self.its_catalog, a_run);
+        self.its_overlay.add_text_centered(
+            a_text.trim_end(),
+            self.its_width / 2.,
+            self.its_height * GAME_OVER_STATS_Y,
+            GAME_OVER_FONT_PIXEL,
+            the_config.get_cursor_color(),
+        );
+    }
+
+    /// The offscreen target to render into, none while the window is being
+    /// resized since the target only takes on the new size afterwards. None
+    /// in chroma-key mode either, as the trail and the post effects would
+    /// tint the key color.
+    fn get_offscreen(&self) -> Option<&OffscreenTarget> {
+        self.its_offscreen
+            .as_ref()
+            .filter(|_| !self.its_resizing && self.its_chroma_key.is_none())
+    }
+
+    /// Create or drop the offscreen target depending on whether anything
+    /// needs it
+    fn update_offscreen(&mut self) -> () {
+        let a_needed = self.its_motion_trail || self.its_post_effects.any();
+        if a_needed == self.its_offscreen.is_some() {
+            return;
+        }
+        self.its_offscreen = if a_needed {
+            Some(OffscreenTarget::new(
+                self.its_width as u32,
+                self.its_height as u32,
+            ))
+        } else {
+            None
+        };
+    }
+
+    /// Switch to `the_shader` and give it the same uniforms as the built-in
+    /// program. Requires the vertex buffer to be bound.
+    unsafe fn use_level_shader(
+        &self,
+        the_shader: &LevelShader,
+        the_config: &model::Style,
+        the_zoom: f32,
+        the_alpha: f32,
+    ) -> () {
+        gl::UseProgram(the_shader.its_program);
+        if let Some(a_loc) = the_shader.its_time_loc {
+            let a_time = self.its_start_time.elapsed().as_secs_f32() % SHADER_TIME_WRAP;
+            gl::Uniform1f(a_loc, a_time);
+        }
+        if let Some(a_loc) = the_shader.its_rotation_loc {
+            gl::Uniform1f(a_loc, the_config.get_rotation());
+        }
+        if let Some(a_loc) = the_shader.its_zoom_loc {
+            gl::Uniform1f(a_loc, the_zoom);
+        }
+        if let Some(a_loc) = the_shader.its_z_loc {
+            gl::Uniform1f(a_loc, 0.);
+        }
+        if let Some(a_loc) = the_shader.its_alpha_loc {
+            gl::Uniform1f(a_loc, the_alpha);
+        }
+        if let Some(a_loc) = the_shader.its_proj_loc {
+            // The matrix has been computed for the built-in program already
+            gl::UniformMatrix4fv(
+                a_loc,
+                1,
+                gl::TRUE,
+                self.its_matrix_cache.its_matrix.as_ptr() as *const _,
             );
-            gl::EnableVertexAttribArray(self.its_vertex_loc as GLuint);
-            gl_check_error();
+        }
+        gl_bind_vertex_attribs(
+            the_shader.its_vertex_loc,
+            the_shader.its_vertex_color_loc,
+            self.its_vertex_base,
+        );
+    }
 
-            let a_color_loc = self.its_color_loc;
-            // inner hex + outer hex + cursor + cursorShadow
-            let num_hex_vertices = 8;
-            let mut offset = 2 * num_hex_vertices + 3 + 3;
-            let a_slot_colors = config.get_slot_colors();
-            for i in 0..the_game.get_slots().len() {
-                let a_slot_colr = if a_slot_colors.len() == 0 {
-                    model::Color::rgba(1., 1., 1., 1.)
-                } else {
-                    a_slot_colors[i % a_slot_colors.len()].clone()
-                };
-                gl::Uniform3f(
-                    a_color_loc,
-                    a_slot_colr.its_r,
-                    a_slot_colr.its_g,
-                    a_slot_colr.its_b,
+    /// Call `the_body` with the style the current frame is drawn in, which is
+    /// `the_style` unless the flash limiter has changed it
+    fn with_frame_style<T>(
+        &mut self,
+        the_style: &model::Style,
+        the_body: impl FnOnce(&mut OGLRenderer, &model::Style) -> T,
+    ) -> T {
+        let a_limited = self.its_limited_style.take();
+        let a_result = the_body(self, a_limited.as_ref().unwrap_or(the_style));
+        self.its_limited_style = a_limited;
+        a_result
+    }
+
+    /// The longer dimension will see the full viewport - which is a 1x1
+    /// square. Since by default we project to have x coordinates go from -1
+    /// to 1, we only need to zoom if y is longer - i.e. aspect is less than
+    /// one.
+    fn get_aspect_zoom(&self) -> f32 {
+        if self.its_aspect >= 1. {
+            self.its_aspect
+        } else {
+            1.
+        }
+    }
+
+    /// Clear the frame, set up the built-in program and upload the vertices
+    /// of `the_game`. Returns whether the frame is to be drawn, which it
+    /// is not if it is a full-screen flash and already complete.
+    unsafe fn prepare_frame(
+        &mut self,
+        the_game: &model::GameState,
+        the_config: &model::Style,
+        the_delta: std::time::Duration,
+    ) -> bool {
+        let a_clear_color = self
+            .its_chroma_key
+            .clone()
+            .unwrap_or_else(|| model::Color::rgba(0., 0., 0., 1.));
+        if let Some(a_offscreen) = self.get_offscreen() {
+            a_offscreen.bind();
+        }
+        let a_is_flashing = the_config.get_flash_time().as_millis() > 0;
+        let a_is_keyed = self.its_chroma_key.is_some();
+        if a_is_flashing && !self.its_reduced_flash && !a_is_keyed {
+            // keep the letterbox bars dark
+            let (a_x, a_y, a_w, a_h) = self.its_viewport;
+            gl::ClearColor(0., 0., 0., 1.);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(a_x, a_y, a_w, a_h);
+            gl::ClearColor(1.0, 1.0, 1.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            gl::Disable(gl::SCISSOR_TEST);
+            self.present_offscreen();
+            return false;
+        }
+        match self.get_offscreen() {
+            Some(a_trail) if self.its_motion_trail => {
+                let a_frames = constants::get_speed_scale(the_delta);
+                a_trail.fade(&a_clear_color, 1. - TRAIL_PERSISTENCE.powf(a_frames));
+            }
+            _ => {
+                gl::ClearColor(
+                    a_clear_color.its_r,
+                    a_clear_color.its_g,
+                    a_clear_color.its_b,
+                    a_clear_color.its_a,
                 );
-                gl::DrawArrays(gl::TRIANGLE_STRIP, offset, 4);
-                offset += 4;
+                gl::Clear(gl::COLOR_BUFFER_BIT);
             }
+        }
+
+        self.use_playfield_viewport();
+        gl::UseProgram(self.its_program);
+        if let Some(time_loc) = self.its_time_loc {
+            let time = self.its_start_time.elapsed().as_secs_f32() % SHADER_TIME_WRAP;
+            gl::Uniform1f(time_loc, time);
+        }
+        if let Some(rotation_loc) = self.its_rotation_loc {
+            gl::Uniform1f(rotation_loc, the_config.get_rotation());
+        }
+        let zoom = the_config.get_zoom() * self.get_aspect_zoom();
+        if let Some(zoom_loc) = self.its_zoom_loc {
+            gl::Uniform1f(zoom_loc, zoom);
+        }
+        if let Some(z_loc) = self.its_z_loc {
+            assert!(z_loc != -1);
+            gl::Uniform1f(z_loc, 0.);
+        }
+        if let Some(proj_loc) = self.its_proj_loc {
+            let proj = self.get_projection_matrix(the_config);
+            gl::UniformMatrix4fv(
+                proj_loc,
+                1 as gl::types::GLsizei,
+                gl::TRUE,
+                proj.as_ptr() as *const _,
+            );
             gl_check_error();
+        }
 
-            // render obstacles
-            let obstacle_count = the_game
-                .get_slots()
-                .iter()
-                .fold(0, |acc, slot| acc + slot.get_obstacles().len());
-            let a_obst_colr = config.get_obstacle_color();
-            gl::Uniform3f(
-                a_color_loc,
-                a_obst_colr.its_r,
-                a_obst_colr.its_g,
-                a_obst_colr.its_b,
+        // upload all vertices, unless the previous ones are still valid,
+        // e.g. while paused or on the game over screen
+        let a_position = self.get_cursor_position(the_game);
+        let a_unchanged = match &self.its_vertex_source {
+            Some(a_source) => a_source.matches(the_game, the_config, a_position),
+            None => false,
+        };
+        if a_unchanged {
+            self.its_vertex_stream.bind();
+        } else {
+            self.its_geometry.build(
+                the_game,
+                the_config,
+                a_position,
+                self.its_cursor_shape.as_ref(),
+                self.its_proximity_shading,
             );
-            for _ in 0..obstacle_count {
-                gl::DrawArrays(gl::TRIANGLE_STRIP, offset, 4);
-                offset += 4;
+            let a_bytes = geometry::as_bytes(self.its_geometry.get_data());
+            self.its_vertex_base = self.its_vertex_stream.upload(a_bytes);
+            self.its_vertex_source = Some(VertexSource::new(the_game, the_config, a_position));
+        }
+        if gl::BindVertexArray::is_loaded() {
+            gl::BindVertexArray(self.its_vertex_array_obj);
+        }
+        gl_check_error();
+        gl_bind_vertex_attribs(
+            Some(self.its_vertex_loc),
+            Some(self.its_vertex_color_loc),
+            self.its_vertex_base,
+        );
+        gl_check_error();
+        true
+    }
+
+    /// Draw the background and the slots, which the chroma color replaces.
+    /// The background shows through the slots in front of it.
+    unsafe fn render_background(&mut self, the_config: &model::Style) -> () {
+        let a_is_keyed = self.its_chroma_key.is_some();
+        let a_background = the_config.get_background().filter(|_| !a_is_keyed);
+        let a_slot_alpha = match a_background {
+            Some(a_background) => {
+                gl_draw_range(gl::TRIANGLES, self.its_geometry.get_background_range());
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                a_background.get_slot_opacity()
             }
-            offset = 0;
-            // render outer hexagon
-            let a_oh_colr = config.get_outer_hexagon_color();
-            gl::Uniform3f(
-                a_color_loc,
-                a_oh_colr.its_r,
-                a_oh_colr.its_g,
-                a_oh_colr.its_b,
-            );
-            gl::DrawArrays(gl::TRIANGLE_FAN, offset, num_hex_vertices);
-            offset += num_hex_vertices;
-            // render inner hexagon
-            let a_ih_colr = config.get_inner_hexagon_color();
-            gl::Uniform3f(
-                a_color_loc,
-                a_ih_colr.its_r,
-                a_ih_colr.its_g,
-                a_ih_colr.its_b,
+            None => 1.,
+        };
+        let a_level_shader = self.its_level_shader.as_ref().filter(|_| !a_is_keyed);
+        if let Some(a_shader) = a_level_shader {
+            let a_zoom = the_config.get_zoom() * self.get_aspect_zoom();
+            self.use_level_shader(a_shader, the_config, a_zoom, a_slot_alpha);
+        } else if let Some(a_alpha_loc) = self.its_alpha_loc {
+            gl::Uniform1f(a_alpha_loc, a_slot_alpha);
+        }
+        if !a_is_keyed {
+            gl_draw_range(gl::TRIANGLES, self.its_geometry.get_slot_range());
+        }
+        gl::Disable(gl::BLEND);
+        if a_level_shader.is_some() {
+            // back to the built-in program for everything else
+            gl::UseProgram(self.its_program);
+            gl_bind_vertex_attribs(
+                Some(self.its_vertex_loc),
+                Some(self.its_vertex_color_loc),
+                self.its_vertex_base,
             );
-            gl::DrawArrays(gl::TRIANGLE_FAN, offset, num_hex_vertices);
-            offset += num_hex_vertices;
-            // render cursor shadow
-            let a_shadow_color = config.get_cursor_shadow_color();
-            if a_shadow_color.its_a != 0. {
-                if let Some(z_loc) = self.its_z_loc {
-                    gl::Uniform1f(z_loc, -0.01);
-                    gl::Uniform3f(
-                        a_color_loc,
-                        a_shadow_color.its_r,
-                        a_shadow_color.its_g,
-                        a_shadow_color.its_b,
-                    );
-                    gl::DrawArrays(gl::TRIANGLES, offset, 3);
-                    gl::Uniform1f(z_loc, 0.);
-                }
+        }
+        gl_check_error();
+    }
+
+    /// Draw the obstacles, the outer and inner hexagon and the cursor
+    unsafe fn render_playfield(&mut self, the_config: &model::Style) -> () {
+        gl_draw_range(gl::TRIANGLES, self.its_geometry.get_shape_range());
+        // render cursor shadow
+        let a_cursor_offset = self.its_geometry.get_cursor_offset();
+        let a_cursor_vertices = self.its_geometry.get_cursor_vertices();
+        if the_config.get_cursor_shadow_color().its_a != 0. {
+            if let Some(z_loc) = self.its_z_loc {
+                gl::Uniform1f(z_loc, -0.01);
+                gl_draw_range(gl::TRIANGLES, (a_cursor_offset, a_cursor_vertices));
+                gl::Uniform1f(z_loc, 0.);
             }
-            offset += 3;
-            // render cursor
-            let a_cursor_colr = config.get_cursor_color();
-            gl::Uniform3f(
-                a_color_loc,
-                a_cursor_colr.its_r,
-                a_cursor_colr.its_g,
-                a_cursor_colr.its_b,
+        }
+        // render cursor
+        gl_draw_range(
+            gl::TRIANGLES,
+            (a_cursor_offset + a_cursor_vertices, a_cursor_vertices),
+        );
+    }
+
+    /// Darken the borders during flashes and copy the offscreen image to the
+    /// window with the post effects applied
+    unsafe fn render_post(&mut self, the_config: &model::Style) -> () {
+        let a_is_flashing = the_config.get_flash_time().as_millis() > 0;
+        if a_is_flashing && self.its_chroma_key.is_none() {
+            self.render_vignette(self.get_aspect_zoom());
+        }
+        self.its_vertex_stream.fence();
+        self.use_window_viewport();
+        self.present_offscreen();
+    }
+
+    /// Draw the HUD and everything else that is text on top of the game
+    unsafe fn render_hud(&mut self, the_game: &model::GameState, the_config: &model::Style) -> () {
+        self.its_overlay.clear();
+        if self.its_debug_overlay {
+            let a_zoom = the_config.get_zoom() * self.get_aspect_zoom();
+            self.add_slot_labels(the_game, the_config, a_zoom);
+            self.add_hitboxes(the_game, the_config, a_zoom);
+            self.add_debug_lines();
+        }
+        self.add_input_display(the_config);
+        self.add_heatmap();
+        self.add_opponent(the_config);
+        if the_game.is_running() {
+            self.add_timer(the_game, the_config);
+            self.add_score(the_game, the_config);
+            self.add_split(the_game, the_config);
+            self.add_power_ups(the_game, the_config);
+        } else if !self.its_game_over_hidden {
+            self.add_game_over_stats(the_game, the_config);
+        }
+        self.add_prompt(the_config);
+        self.add_notice(the_config);
+        self.add_intro_title(the_config);
+        self.add_countdown(the_config);
+        self.add_fps();
+        self.add_stats(the_config);
+        // last, so that it covers the HUD
+        self.add_console();
+        self.its_overlay.draw(self.its_width, self.its_height);
+    }
+
+    /// Copy the offscreen image to the window, if there is one
+    unsafe fn present_offscreen(&self) -> () {
+        if let Some(a_offscreen) = self.get_offscreen() {
+            let a_death_fade = self.its_death_effect / DEATH_EFFECT_DURATION;
+            a_offscreen.present(&self.its_post_effects, a_death_fade);
+        }
+    }
+
+    /// Darken the screen towards its borders. Requires the vertex buffer to
+    /// be bound.
+    unsafe fn render_vignette(&self, the_aspect_zoom: f32) -> () {
+        let a_alpha_loc = match self.its_alpha_loc {
+            Some(a_alpha_loc) => a_alpha_loc,
+            None => return,
+        };
+        if let Some(zoom_loc) = self.its_zoom_loc {
+            gl::Uniform1f(zoom_loc, the_aspect_zoom);
+        }
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        let a_ring_vertices = 2 * (geometry::VIGNETTE_SEGMENTS + 1);
+        let a_offset = self.its_geometry.get_vignette_offset();
+        for r in 0..geometry::VIGNETTE_RINGS {
+            let a_alpha = VIGNETTE_MAX_ALPHA * (r + 1) as f32 / geometry::VIGNETTE_RINGS as f32;
+            gl::Uniform1f(a_alpha_loc, a_alpha);
+            gl_draw_range(
+                gl::TRIANGLE_STRIP,
+                (a_offset + r * a_ring_vertices, a_ring_vertices),
             );
-            gl::DrawArrays(gl::TRIANGLES, offset, 3);
+        }
+        gl::Disable(gl::BLEND);
+    }
+}
 
-            gl::Flush();
+impl Renderer for OGLRenderer {
+    fn resize(&mut self, the_width: u32, the_height: u32) -> () {
+        self.its_width = the_width as f32;
+        self.its_height = the_height as f32;
+        self.update_viewport();
+        if !self.its_resizing {
+            if let Some(a_offscreen) = &mut self.its_offscreen {
+                a_offscreen.resize(the_width, the_height);
+            }
+        }
+        unsafe {
+            self.use_window_viewport();
         }
     }
+    fn begin_frame(
+        &mut self,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+        the_delta: std::time::Duration,
+    ) -> bool {
+        self.its_frame_time +=
+            (the_delta.as_millis() as f32 - self.its_frame_time) / FRAME_TIME_FILTER_STRENGTH;
+        self.its_limited_style = if self.its_reduced_flash {
+            Some(self.its_flash_limiter.filter(the_style, the_delta))
+        } else {
+            None
+        };
+        if self.its_was_running && !the_game.is_running() {
+            self.its_death_effect = DEATH_EFFECT_DURATION;
+        }
+        self.its_was_running = the_game.is_running();
+        self.its_death_effect = (self.its_death_effect - the_delta.as_secs_f32()).max(0.);
+        self.with_frame_style(the_style, |the_self, the_config| unsafe {
+            the_self.prepare_frame(the_game, the_config, the_delta)
+        })
+    }
+
+    fn render_pass(
+        &mut self,
+        the_pass: RenderPass,
+        the_game: &model::GameState,
+        the_style: &model::Style,
+    ) -> () {
+        self.with_frame_style(the_style, |the_self, the_config| unsafe {
+            match the_pass {
+                RenderPass::Background => the_self.render_background(the_config),
+                RenderPass::Playfield => the_self.render_playfield(the_config),
+                // nothing is drawn as particles yet
+                RenderPass::Particles => (),
+                RenderPass::Post => the_self.render_post(the_config),
+                RenderPass::Hud => the_self.render_hud(the_game, the_config),
+            }
+        })
+    }
+
+    fn end_frame(&mut self) -> () {
+        unsafe { gl::Flush() };
+    }
 
     /**
      * Get the (low-pass filtered) time between two frames in milliseconds
@@ -502,4 +1880,126 @@ impl Renderer for OGLRenderer {
     fn get_frame_time(&self) -> f32 {
         self.its_frame_time
     }
+
+    fn set_reduced_flash(&mut self, the_enabled: bool) -> () {
+        self.its_reduced_flash = the_enabled;
+    }
+
+    fn set_debug_overlay(&mut self, the_enabled: bool) -> () {
+        self.its_debug_overlay = the_enabled;
+    }
+
+    fn set_debug_lines(&mut self, the_lines: Option<Vec<String>>) -> () {
+        self.its_debug_lines = the_lines;
+    }
+
+    fn set_motion_trail(&mut self, the_enabled: bool) -> () {
+        self.its_motion_trail = the_enabled;
+        self.update_offscreen();
+    }
+
+    fn set_proximity_shading(&mut self, the_enabled: bool) -> () {
+        self.its_proximity_shading = the_enabled;
+        // the slot colors change even while the game stands still
+        self.its_vertex_source = None;
+    }
+
+    fn set_post_effects(&mut self, the_effects: PostEffects) -> () {
+        self.its_post_effects = the_effects;
+        self.update_offscreen();
+    }
+
+    fn set_level_shader(&mut self, the_source: Option<&str>) -> Result<(), String> {
+        self.its_level_shader = None;
+        if let Some(a_source) = the_source {
+            self.its_level_shader = Some(LevelShader::compile(a_source)?);
+        }
+        Ok(())
+    }
+
+    fn set_forced_aspect(&mut self, the_aspect: Option<f32>) -> () {
+        self.its_forced_aspect = the_aspect;
+        self.update_viewport();
+    }
+
+    fn set_resizing(&mut self, the_resizing: bool) -> () {
+        if self.its_resizing && !the_resizing {
+            if let Some(a_offscreen) = &mut self.its_offscreen {
+                a_offscreen.resize(self.its_width as u32, self.its_height as u32);
+            }
+        }
+        self.its_resizing = the_resizing;
+    }
+
+    fn set_best_splits(&mut self, the_splits: Vec<f32>) -> () {
+        self.its_best_splits = the_splits;
+    }
+
+    fn set_input_display(&mut self, the_display: Option<InputDisplay>) -> () {
+        self.its_input_display = the_display;
+    }
+
+    fn set_chroma_key(&mut self, the_color: Option<model::Color>) -> () {
+        self.its_chroma_key = the_color;
+    }
+
+    fn set_cursor_shape(&mut self, the_shape: Option<Shape>) -> () {
+        self.its_cursor_shape = the_shape;
+    }
+
+    fn set_cursor_position(&mut self, the_position: Option<f32>) -> () {
+        self.its_cursor_position = the_position;
+    }
+
+    fn set_font(&mut self, the_font: font::Font) -> () {
+        self.its_overlay.set_font(the_font);
+    }
+
+    fn set_console(&mut self, the_lines: Option<Vec<String>>) -> () {
+        self.its_console = the_lines;
+    }
+
+    fn set_stats(&mut self, the_stats: Option<LifetimeStats>) -> () {
+        self.its_stats = the_stats;
+    }
+
+    fn set_heatmap(&mut self, the_heatmap: Option<Heatmap>) -> () {
+        self.its_heatmap = the_heatmap;
+    }
+
+    fn set_opponent(&mut self, the_status: Option<OpponentStatus>) -> () {
+        self.its_opponent = the_status;
+    }
+
+    fn set_medal(&mut self, the_medal: Option<Medal>) -> () {
+        self.its_medal = the_medal;
+    }
+
+    fn set_prompt(&mut self, the_text: Option<String>) -> () {
+        self.its_prompt = the_text;
+    }
+
+    fn set_notice(&mut self, the_text: Option<String>) -> () {
+        self.its_notice = the_text;
+    }
+
+    fn set_intro_title(&mut self, the_title: Option<String>, the_offset: f32) -> () {
+        self.its_intro_title = the_title.map(|the_title| (the_title, the_offset));
+    }
+
+    fn set_game_over_hidden(&mut self, the_hidden: bool) -> () {
+        self.its_game_over_hidden = the_hidden;
+    }
+
+    fn set_countdown(&mut self, the_seconds: Option<u32>) -> () {
+        self.its_countdown = the_seconds;
+    }
+
+    fn set_fps(&mut self, the_stats: Option<FpsStats>) -> () {
+        self.its_fps = the_stats;
+    }
+
+    fn set_catalog(&mut self, the_catalog: i18n::Catalog) -> () {
+        self.its_catalog = the_catalog;
+    }
 }
@@ -0,0 +1,106 @@
+use super::model::SpeedCurve;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Everything needed to play a run again tick by tick: the level, the seed
+/// and where the player steered. The simulation is deterministic, so this is
+/// enough to reproduce the run, unless it was changed by console commands.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+  /// Directory of the pack the level is in, none for the built-in level
+  pub pack: Option<PathBuf>,
+  /// Name of the level within the pack
+  pub level: Option<String>,
+  pub seed: u64,
+  pub tick_rate: u32,
+  /// Whether the cursor was steered relative to the screen
+  #[serde(default)]
+  pub screen_relative: bool,
+  /// Whether the run was simulated in strict math mode, see
+  /// `model::GameState::set_strict_math`
+  #[serde(default)]
+  pub strict_math: bool,
+  /// The cursor's response the player chose over the level's, see
+  /// `model::GameState::set_speed_curve_override`
+  #[serde(default)]
+  pub speed_curve: Option<SpeedCurve>,
+  /// Tick from which on the cursor was steered in a direction, -1 for left,
+  /// 1 for right and 0 for not at all. One entry per change.
+  pub inputs: Vec<(u64, i8)>,
+}
+
+impl Replay {
+  pub fn new(
+    the_pack: Option<PathBuf>,
+    the_level: Option<String>,
+    the_seed: u64,
+    the_tick_rate: u32,
+  ) -> Replay {
+    Replay {
+      pack: the_pack,
+      level: the_level,
+      seed: the_seed,
+      tick_rate: the_tick_rate,
+      screen_relative: false,
+      strict_math: false,
+      speed_curve: None,
+      inputs: Vec::new(),
+    }
+  }
+  pub fn load(the_path: &Path) -> Result<Replay, String> {
+    let a_data = std::fs::read(the_path).map_err(|the_err| the_err.to_string())?;
+    serde_json::from_slice(&a_data).map_err(|the_err| the_err.to_string())
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  /// Note that the cursor was steered in `the_direction` during `the_tick`,
+  /// which has to come after all ticks recorded so far
+  pub fn record(&mut self, the_tick: u64, the_direction: i8) -> () {
+    if self.get_direction(the_tick) != the_direction {
+      self.inputs.push((the_tick, the_direction));
+    }
+  }
+  /// Forget everything from `the_tick` on, e.g. because the run has been
+  /// rewound to it
+  pub fn truncate(&mut self, the_tick: u64) -> () {
+    self.inputs.retain(|(the_start, _)| *the_start < the_tick);
+  }
+  /// Where the cursor was steered during `the_tick`
+  pub fn get_direction(&self, the_tick: u64) -> i8 {
+    let a_changes = self
+      .inputs
+      .partition_point(|(the_start, _)| *the_start <= the_tick);
+    match a_changes.checked_sub(1) {
+      Some(a_idx) => self.inputs[a_idx].1,
+      None => 0,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Replay;
+
+  #[test]
+  fn plays_back_what_was_recorded() {
+    let mut a_replay = Replay::new(None, None, 7, 60);
+    let a_directions = [0, 0, 1, 1, 1, -1, 0, 0, -1];
+    for (a_tick, a_direction) in a_directions.iter().enumerate() {
+      a_replay.record(a_tick as u64, *a_direction);
+    }
+    assert_eq!(a_replay.inputs, vec![(2, 1), (5, -1), (6, 0), (8, -1)]);
+    for (a_tick, a_direction) in a_directions.iter().enumerate() {
+      assert_eq!(a_replay.get_direction(a_tick as u64), *a_direction);
+    }
+    assert_eq!(a_replay.get_direction(100), -1);
+    a_replay.truncate(6);
+    assert_eq!(a_replay.get_direction(100), -1);
+    a_replay.truncate(5);
+    assert_eq!(a_replay.get_direction(100), 1);
+  }
+}
@@ -0,0 +1,49 @@
+/// Small deterministic pseudo random number generator (SplitMix64). Every
+/// source of randomness in the simulation derives from the run's seed so
+/// that runs can be reproduced.
+#[derive(Clone)]
+pub struct Rng {
+  its_state: u64,
+}
+
+impl Rng {
+  pub fn new(the_seed: u64) -> Rng {
+    Rng {
+      its_state: the_seed,
+    }
+  }
+  /// A seed that differs between program runs
+  pub fn time_seed() -> u64 {
+    match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+      Ok(a_time) => a_time.as_nanos() as u64,
+      Err(_) => 0,
+    }
+  }
+  /// A seed derived from `the_text`, the same on every platform and in
+  /// every version, so that players can share seeds as words
+  pub fn seed_from_text(the_text: &str) -> u64 {
+    // FNV-1a, which unlike the std hashers is guaranteed to stay the same
+    let a_hash = the_text
+      .bytes()
+      .fold(0xcbf29ce484222325u64, |the_hash, the_byte| {
+        (the_hash ^ the_byte as u64).wrapping_mul(0x100000001b3)
+      });
+    // spread similar texts over the whole seed range
+    Rng::new(a_hash).next_u64()
+  }
+  pub fn next_u64(&mut self) -> u64 {
+    self.its_state = self.its_state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = self.its_state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+  }
+  /// Uniformly distributed in [0, 1)
+  pub fn next_f32(&mut self) -> f32 {
+    (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+  }
+  /// Uniformly distributed in [0, the_bound)
+  pub fn next_below(&mut self, the_bound: usize) -> usize {
+    (self.next_u64() % the_bound as u64) as usize
+  }
+}
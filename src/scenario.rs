@@ -0,0 +1,196 @@
+use super::controls::{self, ControlScheme, Controls};
+use super::model::{GameState, Obstacle};
+use serde::Deserialize;
+
+/// An obstacle of a scenario, in the units of `Obstacle`
+#[derive(Deserialize)]
+pub struct ObstacleDef {
+  pub distance: f32,
+  pub height: f32,
+}
+
+#[derive(Deserialize)]
+pub struct SlotDef {
+  #[serde(default = "default_width")]
+  pub width: f32,
+  #[serde(default)]
+  pub obstacles: Vec<ObstacleDef>,
+}
+
+fn default_width() -> f32 {
+  1.
+}
+
+/// Key names that can be used in the inputs of a scenario
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Key {
+  Left,
+  Right,
+  Action,
+}
+
+impl Key {
+  fn get_scancode(self) -> u32 {
+    match self {
+      Key::Left => controls::LEFT_KEY,
+      Key::Right => controls::RIGHT_KEY,
+      Key::Action => controls::ACTION_KEY,
+    }
+  }
+}
+
+/// Keys that are held for a number of ticks. Keys that were not held in the
+/// previous step count as newly pressed in the first tick.
+#[derive(Deserialize)]
+pub struct InputStep {
+  #[serde(default)]
+  pub keys: Vec<Key>,
+  pub ticks: usize,
+}
+
+/// A hand-made situation to pin down how the simulation behaves in edge
+/// cases. Everything that is not given is left as in a new `GameState`.
+#[derive(Deserialize)]
+pub struct Scenario {
+  /// At most six slots, the remaining ones keep their defaults
+  #[serde(default)]
+  pub slots: Vec<SlotDef>,
+  #[serde(default)]
+  pub position: Option<f32>,
+  #[serde(default)]
+  pub player_speed: Option<f32>,
+  #[serde(default)]
+  pub obstacle_speed: Option<f32>,
+  #[serde(default = "default_scheme")]
+  pub control_scheme: ControlScheme,
+  #[serde(default)]
+  pub inputs: Vec<InputStep>,
+}
+
+fn default_scheme() -> ControlScheme {
+  ControlScheme::Keyboard
+}
+
+impl Scenario {
+  pub fn from_json(the_json: &[u8]) -> Result<Scenario, serde_json::Error> {
+    serde_json::from_slice(the_json)
+  }
+  /// The game state before any input has been applied
+  pub fn create_state(&self) -> GameState {
+    let mut a_game = GameState::new();
+    a_game.restart(0);
+    if let Some(a_speed) = self.player_speed {
+      a_game.set_player_speed(a_speed);
+    }
+    if let Some(a_speed) = self.obstacle_speed {
+      a_game.set_obstacle_speed(a_speed);
+    }
+    for (a_idx, a_slot) in self.slots.iter().enumerate() {
+      a_game.set_slot_width(a_idx, a_slot.width);
+      for a_obstacle in &a_slot.obstacles {
+        a_game.add_obstacle(
+          a_idx,
+          Obstacle::new_at(a_obstacle.distance, a_obstacle.height),
+        );
+      }
+    }
+    if let Some(a_position) = self.position {
+      a_game.set_position(a_position);
+    }
+    a_game.take_events();
+    a_game
+  }
+  /// Play all inputs the way the app does, i.e. controls before the game
+  /// in every tick, and return the resulting state
+  pub fn run(&self) -> GameState {
+    let mut a_game = self.create_state();
+    let mut a_controls = Controls::new();
+    a_controls.set_scheme(self.control_scheme);
+    let mut a_held: Vec<Key> = Vec::new();
+    for a_step in &self.inputs {
+      for a_key in &a_held {
+        if !a_step.keys.contains(a_key) {
+          a_controls.key_released(a_key.get_scancode());
+        }
+      }
+      for a_key in &a_step.keys {
+        if !a_held.contains(a_key) {
+          a_controls.key_pressed(a_key.get_scancode());
+        }
+      }
+      a_held = a_step.keys.clone();
+      for _ in 0..a_step.ticks {
+        let a_delta = a_game.get_tick_duration();
+        a_controls.tick(&mut a_game, a_delta);
+        a_game.tick();
+      }
+    }
+    a_game
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Scenario;
+
+  fn run(the_json: &str) -> super::GameState {
+    Scenario::from_json(the_json.as_bytes())
+      .expect("invalid scenario")
+      .run()
+  }
+
+  #[test]
+  fn wraps_around_to_the_first_slot() {
+    let a_game = run(include_str!("../scenarios/wrap_right.json"));
+    assert_eq!(a_game.get_current_slot_idx(), 0);
+    assert!(a_game.get_position() < 0.1);
+  }
+
+  #[test]
+  fn wraps_around_to_the_last_slot() {
+    let a_game = run(include_str!("../scenarios/wrap_left.json"));
+    assert_eq!(a_game.get_current_slot_idx(), 5);
+    assert!(a_game.get_position() > 0.9);
+  }
+
+  #[test]
+  fn opposite_keys_cancel_out() {
+    let a_game = run(include_str!("../scenarios/both_keys.json"));
+    assert_eq!(a_game.get_position(), 0.25);
+  }
+
+  #[test]
+  fn sideways_collision_blocks_movement() {
+    let a_game = run(include_str!("../scenarios/sideways_block.json"));
+    assert!(a_game.is_running());
+    assert_eq!(a_game.get_current_slot_idx(), 0);
+  }
+
+  #[test]
+  fn cursor_corners_block_movement() {
+    let a_game = run(include_str!("../scenarios/corner_block.json"));
+    assert!(a_game.is_running());
+    let a_border = 1. / 6. - crate::constants::CURSOR_W / 2.;
+    assert!(a_game.get_position() <= a_border);
+    assert!(a_game.get_position() > a_border - 0.005);
+  }
+
+  #[test]
+  fn zero_width_slot_is_skipped() {
+    let a_game = run(include_str!("../scenarios/zero_width.json"));
+    assert_eq!(a_game.get_current_slot_idx(), 2);
+  }
+
+  #[test]
+  fn collapsed_slot_is_never_picked() {
+    let a_game = run(include_str!("../scenarios/collapsed_last.json"));
+    assert_eq!(a_game.get_current_slot_idx(), 4);
+  }
+
+  #[test]
+  fn frontal_collision_ends_the_run() {
+    let a_game = run(include_str!("../scenarios/frontal_hit.json"));
+    assert!(!a_game.is_running());
+  }
+}
@@ -0,0 +1,138 @@
+use super::model::GameEvent;
+use serde::Serialize;
+use std::path::Path;
+
+/// Most entries kept, older ones are dropped
+const MAX_ENTRIES: usize = 100000;
+
+/// What the session log keeps of the gameplay
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LoggedEvent {
+  /// A run has begun, the ticks of the entries after it count from here
+  RunStarted {
+    level: String,
+    seed: u64,
+  },
+  /// The spawner has sent a wave, `slots` are the ones it put obstacles into
+  Spawned {
+    pattern: String,
+    slots: Vec<usize>,
+  },
+  /// Index into `STAGES`
+  StageReached {
+    stage: usize,
+  },
+  /// The cursor was in slot `slot` when it was hit
+  Died {
+    slot: usize,
+  },
+  Completed,
+}
+
+/// A gameplay event along with when it happened
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LogEntry {
+  /// Ticks since the start of the run
+  pub tick: u64,
+  #[serde(flatten)]
+  pub event: LoggedEvent,
+}
+
+/// The gameplay events of this session, oldest first, for bug reports and
+/// balancing
+#[derive(Serialize, Default)]
+pub struct SessionLog {
+  entries: Vec<LogEntry>,
+}
+
+impl SessionLog {
+  pub fn record(&mut self, the_tick: u64, the_event: LoggedEvent) -> () {
+    self.entries.push(LogEntry {
+      tick: the_tick,
+      event: the_event,
+    });
+    if self.entries.len() > MAX_ENTRIES {
+      let a_excess = self.entries.len() - MAX_ENTRIES;
+      self.entries.drain(..a_excess);
+    }
+  }
+  /// Record `the_event` if it is one the log keeps, `the_slot` being the
+  /// one the cursor is in
+  pub fn record_game_event(&mut self, the_tick: u64, the_event: &GameEvent, the_slot: usize) -> () {
+    let a_event = match the_event {
+      GameEvent::StageReached { stage } => LoggedEvent::StageReached { stage: *stage },
+      GameEvent::Died => LoggedEvent::Died { slot: the_slot },
+      GameEvent::Completed => LoggedEvent::Completed,
+      _ => return,
+    };
+    self.record(the_tick, a_event);
+  }
+  /// The last `the_count` entries, oldest first
+  pub fn get_recent(&self, the_count: usize) -> &[LogEntry] {
+    &self.entries[self.entries.len().saturating_sub(the_count)..]
+  }
+  pub fn export(&self, the_path: &Path) -> Result<(), String> {
+    let a_json = serde_json::to_vec_pretty(self).map_err(|the_err| the_err.to_string())?;
+    std::fs::write(the_path, a_json).map_err(|the_err| the_err.to_string())
+  }
+}
+
+impl std::fmt::Display for LogEntry {
+  fn fmt(&self, the_f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(the_f, "{} ", self.tick)?;
+    match &self.event {
+      LoggedEvent::RunStarted { level, seed } => write!(the_f, "run {} seed {}", level, seed),
+      LoggedEvent::Spawned { pattern, slots } => {
+        let a_slots: Vec<String> = slots.iter().map(usize::to_string).collect();
+        write!(the_f, "spawned {} [{}]", pattern, a_slots.join(" "))
+      }
+      LoggedEvent::StageReached { stage } => write!(the_f, "stage {}", stage),
+      LoggedEvent::Died { slot } => write!(the_f, "died in slot {}", slot),
+      LoggedEvent::Completed => write!(the_f, "completed"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{LoggedEvent, SessionLog};
+  use crate::model::GameEvent;
+
+  #[test]
+  fn keeps_gameplay_events_as_json() {
+    let mut a_log = SessionLog::default();
+    a_log.record(
+      0,
+      LoggedEvent::RunStarted {
+        level: "default".to_string(),
+        seed: 7,
+      },
+    );
+    a_log.record(
+      12,
+      LoggedEvent::Spawned {
+        pattern: "spiral".to_string(),
+        slots: vec![1, 4],
+      },
+    );
+    a_log.record_game_event(40, &GameEvent::WallPassed { slot: 1 }, 2);
+    a_log.record_game_event(90, &GameEvent::Died, 2);
+    assert_eq!(a_log.get_recent(10).len(), 3);
+    assert_eq!(a_log.get_recent(1)[0].to_string(), "90 died in slot 2");
+    assert_eq!(
+      a_log.get_recent(2)[0].to_string(),
+      "12 spawned spiral [1 4]"
+    );
+    assert_eq!(
+      serde_json::to_value(&a_log).unwrap(),
+      serde_json::json!({
+        "entries": [
+          { "tick": 0, "type": "run_started", "level": "default", "seed": 7 },
+          { "tick": 12, "type": "spawned", "pattern": "spiral", "slots": [1, 4] },
+          { "tick": 90, "type": "died", "slot": 2 },
+        ]
+      })
+    );
+  }
+}
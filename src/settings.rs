@@ -0,0 +1,223 @@
+use super::accessibility::PaletteMode;
+use super::constants;
+use super::controls::ControlScheme;
+use super::level::CursorDef;
+use super::model::{Color, SpeedCurve};
+use super::offscreen::PostEffects;
+use super::window::{VideoModeSetting, WindowGeometry};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const MIN_GAME_SPEED: f32 = 0.6;
+pub const MAX_GAME_SPEED: f32 = 1.;
+/// Aspect ratios the playfield can be locked to: 1:1, 4:3 and 16:9
+pub const FORCED_ASPECTS: [f32; 3] = [1., 4. / 3., 16. / 9.];
+/// Frame rate limits the hotkey steps through before going uncapped
+pub const FPS_LIMITS: [u32; 3] = [60, 120, 144];
+pub const MIN_FPS_LIMIT: u32 = 10;
+pub const MAX_FPS_LIMIT: u32 = 1000;
+
+/// User preferences that are persisted between sessions
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+  pub palette_mode: PaletteMode,
+  /// Replace the death flash and palette strobing by gentler effects
+  pub reduced_flash: bool,
+  /// Override the level's colors by a high-contrast scheme
+  pub high_contrast: bool,
+  /// Scales the whole simulation (obstacles, rotation and player alike).
+  /// Runs below 1 are flagged as assisted.
+  pub game_speed: f32,
+  /// Caps rotation, camera wobble and zoom pulses. 1 means no limit.
+  pub motion_intensity: f32,
+  pub control_scheme: ControlScheme,
+  /// Blend in the previous frames for a smoother look of fast rotations
+  pub motion_trail: bool,
+  /// Brighten the slots that obstacles are closing in on, so that danger
+  /// shows in peripheral vision
+  pub proximity_shading: bool,
+  /// Steer relative to the screen rather than the spinning playfield, the
+  /// cursor stays in place unless steered. Races are always played without.
+  pub screen_relative_steering: bool,
+  /// How the cursor speeds up while a direction is held, e.g. starting
+  /// slower for precise taps. Replaces the level's outside of daily runs
+  /// and races.
+  pub player_speed_curve: Option<SpeedCurve>,
+  /// Applied in addition to the ones requested by the level
+  pub post_effects: PostEffects,
+  /// Width / height the playfield is locked to, with bars filling the rest
+  /// of the window. Without it, wide windows show more of the playfield.
+  pub forced_aspect: Option<f32>,
+  /// Geometry of the window when the game was last closed
+  pub window: Option<WindowGeometry>,
+  /// Name of the monitor to play on, the one the window opens on if unset
+  pub monitor: Option<String>,
+  /// Video mode for fullscreen, the monitor's native one if unset or not
+  /// supported by the monitor
+  pub video_mode: Option<VideoModeSetting>,
+  /// Freeze the game while the window does not have the focus
+  pub pause_when_unfocused: bool,
+  /// Draw only a few frames per second while the window does not have the
+  /// focus
+  pub power_saver: bool,
+  /// Show the held direction and recent taps on screen
+  pub input_display: bool,
+  /// Show the lowest, average and highest frame rate of the last second
+  pub fps_overlay: bool,
+  /// Color such as `#00ff00` that the playfield is drawn over instead of
+  /// its slots, so that streamers can key the game into their scenes
+  pub chroma_key: Option<String>,
+  /// Code such as `de` of the language of all texts, the system's language
+  /// if unset
+  pub language: Option<String>,
+  /// Ticks per second the simulation runs at, e.g. 120 or 144 to match fast
+  /// monitors
+  pub tick_rate: u32,
+  /// Simulate on a fixed-point grid, so that recorded runs play back
+  /// exactly the same on other platforms. Races always do.
+  pub strict_math: bool,
+  /// Most frames drawn per second, independent of the tick rate. Any value
+  /// in between the minimum and maximum works, uncapped if unset.
+  pub fps_limit: Option<u32>,
+  /// Look of the cursor, overriding the one of the level being played
+  pub cursor: Option<CursorDef>,
+}
+
+impl Default for Settings {
+  fn default() -> Settings {
+    Settings {
+      palette_mode: PaletteMode::Normal,
+      reduced_flash: false,
+      high_contrast: false,
+      game_speed: MAX_GAME_SPEED,
+      motion_intensity: 1.,
+      control_scheme: ControlScheme::Keyboard,
+      motion_trail: false,
+      proximity_shading: false,
+      screen_relative_steering: false,
+      player_speed_curve: None,
+      post_effects: PostEffects::default(),
+      forced_aspect: None,
+      window: None,
+      monitor: None,
+      video_mode: None,
+      pause_when_unfocused: true,
+      power_saver: true,
+      input_display: false,
+      fps_overlay: false,
+      chroma_key: None,
+      language: None,
+      tick_rate: constants::DEFAULT_TICK_RATE,
+      strict_math: false,
+      fps_limit: Some(FPS_LIMITS[0]),
+      cursor: None,
+    }
+  }
+}
+
+impl Settings {
+  /// Location of the settings file, following the XDG base directory spec
+  /// where possible and falling back to the working directory
+  pub fn default_path() -> PathBuf {
+    let a_config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+      Some(a_dir) => Some(PathBuf::from(a_dir)),
+      None => std::env::var_os("HOME").map(|the_home| PathBuf::from(the_home).join(".config")),
+    };
+    match a_config_dir {
+      Some(a_dir) => a_dir.join("hexagon").join("settings.json"),
+      None => PathBuf::from("settings.json"),
+    }
+  }
+  /// Load the settings from `the_path`. Missing or broken files result in
+  /// the default settings so that the game always starts.
+  pub fn load(the_path: &Path) -> Settings {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_settings) => a_settings,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed settings file {}: {}",
+            the_path.display(),
+            a_err
+          );
+          Settings::default()
+        }
+      },
+      Err(_) => Settings::default(),
+    }
+  }
+  pub fn get_game_speed(&self) -> f32 {
+    self.game_speed.clamp(MIN_GAME_SPEED, MAX_GAME_SPEED)
+  }
+  /// Step the game speed down by 10%, wrapping around to full speed
+  pub fn cycle_game_speed(&mut self) -> () {
+    let a_speed = self.get_game_speed() - 0.1;
+    self.game_speed = if a_speed < MIN_GAME_SPEED - 0.001 {
+      MAX_GAME_SPEED
+    } else {
+      a_speed
+    };
+  }
+  pub fn get_tick_rate(&self) -> u32 {
+    self
+      .tick_rate
+      .clamp(constants::MIN_TICK_RATE, constants::MAX_TICK_RATE)
+  }
+  pub fn get_fps_limit(&self) -> Option<u32> {
+    self
+      .fps_limit
+      .map(|the_limit| the_limit.clamp(MIN_FPS_LIMIT, MAX_FPS_LIMIT))
+  }
+  /// Step through the `FPS_LIMITS`, then uncapped. A custom limit continues
+  /// with the next higher one.
+  pub fn cycle_fps_limit(&mut self) -> () {
+    self.fps_limit = match self.get_fps_limit() {
+      None => Some(FPS_LIMITS[0]),
+      Some(a_limit) => FPS_LIMITS
+        .iter()
+        .find(|the_limit| **the_limit > a_limit)
+        .cloned(),
+    };
+  }
+  pub fn get_motion_intensity(&self) -> f32 {
+    self.motion_intensity.clamp(0., 1.)
+  }
+  /// Step the motion intensity down by 25%, wrapping around to unlimited
+  pub fn cycle_motion_intensity(&mut self) -> () {
+    let a_intensity = self.get_motion_intensity() - 0.25;
+    self.motion_intensity = if a_intensity < 0.2 { 1. } else { a_intensity };
+  }
+  /// Step through the `FORCED_ASPECTS`, starting and ending with none
+  pub fn cycle_forced_aspect(&mut self) -> () {
+    let a_next = match self.forced_aspect {
+      None => 0,
+      Some(a_aspect) => match FORCED_ASPECTS
+        .iter()
+        .position(|the_aspect| (the_aspect - a_aspect).abs() < 0.001)
+      {
+        Some(a_idx) => a_idx + 1,
+        None => FORCED_ASPECTS.len(),
+      },
+    };
+    self.forced_aspect = FORCED_ASPECTS.get(a_next).cloned();
+  }
+  /// The greenscreen color, if one is set and valid
+  pub fn get_chroma_key(&self) -> Option<Color> {
+    let a_text = self.chroma_key.as_ref()?;
+    match Color::parse(a_text) {
+      Ok(a_color) => Some(a_color),
+      Err(a_err) => {
+        eprintln!("Ignoring invalid chroma key color {}: {}", a_text, a_err);
+        None
+      }
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// Most corners a shape may have, to keep collision checks cheap
+const MAX_POINTS: usize = 32;
+/// Smallest area a shape may cover, in slot-local units
+const MIN_AREA: f32 = 1e-4;
+
+/// Outline of an obstacle in slot-local coordinates: x runs across the slot
+/// from 0 to 1 and y from the obstacle's near (0) to its far edge (1). This
+/// makes shapes follow the width of their slot and the thickness of their
+/// wall. The points are shared between copies, so that spawning shaped
+/// obstacles does not allocate.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<[f32; 2]>", into = "Vec<[f32; 2]>")]
+pub struct Shape {
+  its_points: Arc<[[f32; 2]]>,
+  /// Whether x runs the other way across the slot
+  its_mirrored: bool,
+}
+
+/// Twice the signed area of the outline, positive if counter-clockwise
+fn signed_area(the_points: &[[f32; 2]]) -> f32 {
+  let mut a_sum = 0.;
+  for a_idx in 0..the_points.len() {
+    let [a_x0, a_y0] = the_points[a_idx];
+    let [a_x1, a_y1] = the_points[(a_idx + 1) % the_points.len()];
+    a_sum += a_x0 * a_y1 - a_x1 * a_y0;
+  }
+  a_sum
+}
+
+/// Positive if `the_c` lies left of the line from `the_a` to `the_b`
+fn cross(the_a: [f32; 2], the_b: [f32; 2], the_c: [f32; 2]) -> f32 {
+  (the_b[0] - the_a[0]) * (the_c[1] - the_a[1]) - (the_b[1] - the_a[1]) * (the_c[0] - the_a[0])
+}
+
+impl Shape {
+  pub fn new(the_points: Vec<[f32; 2]>) -> Result<Shape, String> {
+    if the_points.len() < 3 || the_points.len() > MAX_POINTS {
+      return Err(format!(
+        "a shape needs 3 to {} points, got {}",
+        MAX_POINTS,
+        the_points.len()
+      ));
+    }
+    let a_in_range = |the_v: f32| (0. ..=1.).contains(&the_v);
+    if !the_points
+      .iter()
+      .all(|the_point| a_in_range(the_point[0]) && a_in_range(the_point[1]))
+    {
+      return Err("shape points have to lie within [0, 1]".to_string());
+    }
+    if signed_area(&the_points).abs() / 2. < MIN_AREA {
+      return Err("shape has no area".to_string());
+    }
+    Ok(Shape {
+      its_points: the_points.into(),
+      its_mirrored: false,
+    })
+  }
+  pub fn get_point_count(&self) -> usize {
+    self.its_points.len()
+  }
+  pub fn get_point(&self, the_idx: usize) -> [f32; 2] {
+    let [a_x, a_y] = self.its_points[the_idx];
+    if self.its_mirrored {
+      [1. - a_x, a_y]
+    } else {
+      [a_x, a_y]
+    }
+  }
+  /// The shape flipped across the slot, for mirrored patterns
+  pub fn mirrored(&self) -> Shape {
+    Shape {
+      its_points: self.its_points.clone(),
+      its_mirrored: !self.its_mirrored,
+    }
+  }
+  /// Whether the point (`the_x`, `the_y`) lies inside the outline
+  pub fn contains(&self, the_x: f32, the_y: f32) -> bool {
+    let a_x = if self.its_mirrored { 1. - the_x } else { the_x };
+    let mut a_inside = false;
+    let mut a_prev = self.its_points[self.its_points.len() - 1];
+    for a_point in self.its_points.iter() {
+      if (a_point[1] > the_y) != (a_prev[1] > the_y) {
+        let a_t = (the_y - a_point[1]) / (a_prev[1] - a_point[1]);
+        if a_x < a_point[0] + a_t * (a_prev[0] - a_point[0]) {
+          a_inside = !a_inside;
+        }
+      }
+      a_prev = *a_point;
+    }
+    a_inside
+  }
+  /// Split the outline into triangles by ear clipping. Returns indices for
+  /// `get_point`. Self-intersecting outlines may come out incomplete.
+  pub fn triangulate(&self) -> Vec<[usize; 3]> {
+    let mut a_remaining: Vec<usize> = (0..self.its_points.len()).collect();
+    if signed_area(&self.its_points) < 0. {
+      a_remaining.reverse();
+    }
+    let a_point = |the_idx: usize| self.its_points[the_idx];
+    let mut a_triangles = Vec::with_capacity(self.its_points.len() - 2);
+    while a_remaining.len() > 3 {
+      let a_count = a_remaining.len();
+      let a_ear = (0..a_count).find(|the_i| {
+        let a_a = a_point(a_remaining[(the_i + a_count - 1) % a_count]);
+        let a_b = a_point(a_remaining[*the_i]);
+        let a_c = a_point(a_remaining[(the_i + 1) % a_count]);
+        if cross(a_a, a_b, a_c) <= 0. {
+          return false;
+        }
+        // no other corner may lie within the ear
+        a_remaining.iter().all(|the_other| {
+          let a_p = a_point(*the_other);
+          a_p == a_a
+            || a_p == a_b
+            || a_p == a_c
+            || cross(a_a, a_b, a_p) < 0.
+            || cross(a_b, a_c, a_p) < 0.
+            || cross(a_c, a_a, a_p) < 0.
+        })
+      });
+      let a_ear = match a_ear {
+        Some(a_ear) => a_ear,
+        None => break,
+      };
+      a_triangles.push([
+        a_remaining[(a_ear + a_count - 1) % a_count],
+        a_remaining[a_ear],
+        a_remaining[(a_ear + 1) % a_count],
+      ]);
+      a_remaining.remove(a_ear);
+    }
+    if a_remaining.len() == 3 {
+      a_triangles.push([a_remaining[0], a_remaining[1], a_remaining[2]]);
+    }
+    a_triangles
+  }
+}
+
+impl TryFrom<Vec<[f32; 2]>> for Shape {
+  type Error = String;
+  fn try_from(the_points: Vec<[f32; 2]>) -> Result<Shape, String> {
+    Shape::new(the_points)
+  }
+}
+
+impl From<Shape> for Vec<[f32; 2]> {
+  fn from(the_shape: Shape) -> Vec<[f32; 2]> {
+    (0..the_shape.get_point_count())
+      .map(|the_idx| the_shape.get_point(the_idx))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Shape;
+
+  fn chevron() -> Shape {
+    Shape::new(vec![
+      [0., 0.],
+      [0.5, 0.5],
+      [1., 0.],
+      [1., 1.],
+      [0.5, 1.],
+      [0., 1.],
+    ])
+    .unwrap()
+  }
+
+  #[test]
+  fn contains_only_the_inside() {
+    let a_shape = chevron();
+    assert!(a_shape.contains(0.5, 0.75));
+    assert!(a_shape.contains(0.1, 0.2));
+    assert!(!a_shape.contains(0.5, 0.25));
+    assert!(!a_shape.mirrored().contains(0.5, 0.25));
+  }
+
+  #[test]
+  fn triangles_cover_the_outline() {
+    let a_shape = chevron();
+    let a_triangles = a_shape.triangulate();
+    assert_eq!(a_triangles.len(), a_shape.get_point_count() - 2);
+    let a_area: f32 = a_triangles
+      .iter()
+      .map(|the_tri| {
+        let a_corners: Vec<[f32; 2]> = the_tri
+          .iter()
+          .map(|the_idx| a_shape.get_point(*the_idx))
+          .collect();
+        super::signed_area(&a_corners) / 2.
+      })
+      .sum();
+    assert!((a_area - 0.75).abs() < 1e-5);
+  }
+
+  #[test]
+  fn rejects_degenerate_outlines() {
+    assert!(Shape::new(vec![[0., 0.], [1., 1.]]).is_err());
+    assert!(Shape::new(vec![[0., 0.], [0.5, 0.5], [1., 1.]]).is_err());
+    assert!(Shape::new(vec![[0., 0.], [2., 0.], [0., 1.]]).is_err());
+  }
+}
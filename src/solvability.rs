@@ -0,0 +1,140 @@
+use super::constants;
+use super::model::{GameState, Obstacle};
+
+/// Upper bound for the number of simulated ticks, in case of extremely slow
+/// obstacles
+const MAX_TICKS: usize = 10000;
+/// Upper bound for the number of cells, in case of extremely slow players
+const MAX_CELLS: usize = 1000;
+
+/// Checks whether a player moving optimally can survive until every obstacle,
+/// including the extra ones that are about to be spawned, has passed.
+///
+/// Time is split into ticks and the circle into cells that the player can
+/// cross in one tick. A cell is reachable in a tick if it or one of its
+/// neighbours was reachable the tick before and its slot is not blocked at
+/// the cursor's tip. The playfield's rotation is irrelevant here because
+/// obstacles and cursor rotate together.
+///
+/// The buffers of a check are kept for the next one, so that spawning does
+/// not allocate once they are large enough.
+#[derive(Clone, Default)]
+pub struct Solver {
+  its_obstacles: Vec<(usize, Obstacle)>,
+  /// Slot of every cell and where the cell's center is across the slot
+  its_cell_slots: Vec<(usize, f32)>,
+  its_reachable: Vec<bool>,
+  its_next: Vec<bool>,
+  /// Indices of the obstacles that cover the current tick
+  its_active: Vec<usize>,
+}
+
+impl Solver {
+  pub fn new() -> Solver {
+    Solver::default()
+  }
+  pub fn is_solvable(&mut self, the_game: &GameState, the_extra: &[(usize, Obstacle)]) -> bool {
+    let a_speed = the_game.get_obstacle_speed();
+    let a_player_speed = the_game.get_player_speed();
+    if a_speed <= 0. || a_player_speed <= 0. {
+      return true;
+    }
+    let a_cursor_tip = constants::CURSOR_Y + constants::CURSOR_H;
+    self.its_obstacles.clear();
+    self.its_obstacles.extend_from_slice(the_extra);
+    for (a_slot_idx, a_slot) in the_game.get_slots().iter().enumerate() {
+      for a_obstacle in a_slot.get_obstacles() {
+        self.its_obstacles.push((a_slot_idx, a_obstacle.clone()));
+      }
+    }
+    // pulsing obstacles may be at their widest and thickest whenever they
+    // pass the cursor
+    for (_, a_obstacle) in self.its_obstacles.iter_mut() {
+      a_obstacle.set_swell(1.);
+    }
+    let a_ticks = self
+      .its_obstacles
+      .iter()
+      .map(|(_, the_obstacle)| {
+        let a_far_end = the_obstacle.get_distance() + the_obstacle.get_height();
+        let a_obstacle_speed = a_speed * the_game.get_speed_factor(the_obstacle);
+        // reversed obstacles are done once their near end has moved past
+        let a_way = if a_obstacle_speed < 0. {
+          a_cursor_tip - the_obstacle.get_distance()
+        } else {
+          a_far_end - a_cursor_tip
+        };
+        (a_way / a_obstacle_speed.abs()).ceil().max(0.) as usize + 1
+      })
+      .max()
+      .unwrap_or(0)
+      .min(MAX_TICKS);
+
+    let a_cell_count = ((1. / a_player_speed).ceil().max(1.) as usize).min(MAX_CELLS);
+    self.its_cell_slots.clear();
+    self
+      .its_cell_slots
+      .extend((0..a_cell_count).map(|the_cell| {
+        the_game.get_position_in_slot((the_cell as f32 + 0.5) / a_cell_count as f32)
+      }));
+    self.its_reachable.clear();
+    self.its_reachable.resize(a_cell_count, false);
+    self.its_reachable
+      [((the_game.get_position() * a_cell_count as f32) as usize).min(a_cell_count - 1)] = true;
+    self.its_next.clear();
+    self.its_next.resize(a_cell_count, false);
+
+    for a_tick in 0..a_ticks {
+      // instead of moving the obstacles in, look that much farther out,
+      // which is farther for the obstacles that move faster and closer in
+      // for the ones that move outward
+      let a_y_for = |the_obstacle: &Obstacle| {
+        a_cursor_tip + a_speed * the_game.get_speed_factor(the_obstacle) * a_tick as f32
+      };
+      let a_obstacles = &self.its_obstacles;
+      self.its_active.clear();
+      self
+        .its_active
+        .extend((0..a_obstacles.len()).filter(|the_idx| {
+          let a_obstacle = &a_obstacles[*the_idx].1;
+          a_obstacle.covers(a_y_for(a_obstacle))
+        }));
+      let mut a_any = false;
+      for a_cell in 0..a_cell_count {
+        self.its_next[a_cell] = false;
+        let (a_cell_slot, a_x) = self.its_cell_slots[a_cell];
+        let a_position = (a_cell as f32 + 0.5) / a_cell_count as f32;
+        let a_blocked = self.its_active.iter().any(|the_idx| {
+          let (a_slot, a_obstacle) = &a_obstacles[*the_idx];
+          let a_y = a_y_for(a_obstacle);
+          match a_obstacle.get_gap() {
+            Some(a_gap) => {
+              // where the gap is once the obstacle has moved that far
+              let a_distance = a_obstacle.get_distance() - (a_y - a_cursor_tip);
+              !a_gap.contains(
+                the_game.get_gap_center(*a_slot, a_gap, a_distance),
+                a_position,
+              )
+            }
+            None => *a_slot == a_cell_slot && a_obstacle.covers_at(a_x, a_y),
+          }
+        });
+        if a_blocked {
+          continue;
+        }
+        let a_left = (a_cell + a_cell_count - 1) % a_cell_count;
+        let a_right = (a_cell + 1) % a_cell_count;
+        let a_reachable = &self.its_reachable;
+        if a_reachable[a_cell] || a_reachable[a_left] || a_reachable[a_right] {
+          self.its_next[a_cell] = true;
+          a_any = true;
+        }
+      }
+      if !a_any {
+        return false;
+      }
+      std::mem::swap(&mut self.its_reachable, &mut self.its_next);
+    }
+    true
+  }
+}
@@ -0,0 +1,542 @@
+use super::constants;
+use super::level::{PatternDef, PulseDef, WallDef};
+use super::model;
+use super::rng::Rng;
+use super::solvability;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often the spawner rolls a new pattern/placement when the previous one
+/// turned out to be impossible to survive
+const MAX_SPAWN_ATTEMPTS: usize = 8;
+/// How far the walls move before a new attempt is made if no solvable
+/// placement was found
+const RETRY_DISTANCE: f32 = 0.05;
+/// How far ahead of the run `Spawner::get_upcoming` looks at most
+const MAX_LOOKAHEAD: Duration = Duration::from_secs(30);
+
+fn wall(the_slot: usize, the_offset: f32) -> WallDef {
+  WallDef {
+    slot: the_slot,
+    offset: the_offset,
+    height: None,
+    color_group: 0,
+    shape: None,
+    outer_ring: false,
+    reversed: false,
+    gap: None,
+  }
+}
+
+fn pattern(
+  the_name: &str,
+  the_difficulty: f32,
+  the_weight: f32,
+  the_walls: Vec<WallDef>,
+) -> PatternDef {
+  PatternDef {
+    name: String::from(the_name),
+    difficulty: the_difficulty,
+    weight: the_weight,
+    thickness: 0.08,
+    spacing: 0.25,
+    thickness_scaling: 0.,
+    spacing_scaling: -0.05,
+    walls: the_walls,
+    pickups: Vec::new(),
+    pulse: None,
+  }
+}
+
+/// Patterns used by levels that do not define their own
+pub fn default_patterns() -> Vec<PatternDef> {
+  vec![
+    pattern("single", 0., 1., vec![wall(0, 0.)]),
+    pattern(
+      "barrage",
+      0.,
+      2.,
+      (0..5).map(|the_slot| wall(the_slot, 0.)).collect(),
+    ),
+    pattern(
+      "alternating",
+      1.,
+      2.,
+      vec![wall(0, 0.), wall(2, 0.), wall(4, 0.)],
+    ),
+    pattern(
+      "tunnel",
+      2.,
+      1.,
+      (0..4)
+        .map(|the_i| wall(if the_i % 2 == 0 { 1 } else { 4 }, the_i as f32 * 0.2))
+        .chain(std::iter::once(WallDef {
+          slot: 0,
+          offset: 0.,
+          height: Some(0.68),
+          color_group: 1,
+          shape: None,
+          outer_ring: false,
+          reversed: false,
+          gap: None,
+        }))
+        .collect(),
+    ),
+    pattern(
+      "spiral",
+      3.,
+      1.,
+      (0..6)
+        .map(|the_slot| wall(the_slot, the_slot as f32 * 0.1))
+        .collect(),
+    ),
+  ]
+}
+
+/// How the next pattern is chosen among the unlocked ones
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum SpawnStrategy {
+  /// Independent random picks according to the pattern weights
+  #[default]
+  Weighted,
+  /// Deal every unlocked pattern once (in random order) before repeating
+  ShuffleBag,
+}
+
+/// A wave the spawner is going to spawn, see `Spawner::get_upcoming`
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpcomingWave {
+  /// Time of the run at which it spawns
+  pub time: Duration,
+  /// Name of its pattern
+  pub pattern: String,
+  /// Slots it places walls in
+  pub slots: Vec<usize>,
+}
+
+/// Places patterns at the outer end of the slots, picking harder patterns the
+/// longer the player survives
+#[derive(Clone)]
+pub struct Spawner {
+  its_patterns: Vec<PatternDef>,
+  its_strategy: SpawnStrategy,
+  /// Patterns left to deal in shuffle-bag mode
+  its_bag: Vec<usize>,
+  its_rng: Rng,
+  /// Seconds of survival it takes the difficulty to rise by one
+  its_difficulty_ramp: f32,
+  /// Radial distance the walls still have to travel before the next pattern
+  /// is spawned
+  its_distance_to_next: f32,
+  /// Color group of the next wave, alternating so that consecutive waves can
+  /// be told apart
+  its_wave_group: usize,
+  /// Pattern of the latest wave, none before the first one
+  its_last_pattern: Option<usize>,
+  /// Where the current pattern would go, kept to reuse its storage
+  its_placement: Vec<(usize, model::Obstacle)>,
+  /// Where the pickups of the current pattern would go
+  its_pickup_placement: Vec<(usize, model::Pickup)>,
+  its_solver: solvability::Solver,
+}
+
+impl Spawner {
+  /// Call `reset` with the run's seed before spawning
+  pub fn new(
+    the_patterns: Vec<PatternDef>,
+    the_difficulty_ramp: f32,
+    the_strategy: SpawnStrategy,
+  ) -> Spawner {
+    Spawner {
+      its_patterns: if the_patterns.is_empty() {
+        default_patterns()
+      } else {
+        the_patterns
+      },
+      its_strategy: the_strategy,
+      its_bag: Vec::new(),
+      its_rng: Rng::new(0),
+      its_difficulty_ramp: the_difficulty_ramp.max(0.001),
+      its_distance_to_next: 0.,
+      its_wave_group: 0,
+      its_last_pattern: None,
+      its_placement: Vec::new(),
+      its_pickup_placement: Vec::new(),
+      its_solver: solvability::Solver::new(),
+    }
+  }
+  pub fn add_pattern(&mut self, the_pattern: PatternDef) -> () {
+    self.its_patterns.push(the_pattern);
+  }
+  /// Start over for a new run. Spawning only depends on `the_seed` (and the
+  /// player's survival), so equal seeds yield equal pattern sequences.
+  pub fn reset(&mut self, the_seed: u64) -> () {
+    self.its_rng = Rng::new(the_seed);
+    self.its_bag.clear();
+    self.its_distance_to_next = 0.;
+    self.its_wave_group = 0;
+    self.its_last_pattern = None;
+  }
+  pub fn get_difficulty(&self, the_run_time: Duration) -> f32 {
+    the_run_time.as_secs_f32() / self.its_difficulty_ramp
+  }
+  /// Radial distance the walls still have to travel before the next wave
+  pub fn get_distance_to_next(&self) -> f32 {
+    self.its_distance_to_next.max(0.)
+  }
+  /// Number of waves spawned since the last reset
+  pub fn get_wave_count(&self) -> usize {
+    self.its_wave_group
+  }
+  /// Name of the pattern of the latest wave, none before the first one
+  pub fn get_last_pattern(&self) -> Option<&str> {
+    self
+      .its_last_pattern
+      .map(|the_idx| self.its_patterns[the_idx].name.as_str())
+  }
+  fn pick_pattern(&mut self, the_difficulty: f32) -> usize {
+    match self.its_strategy {
+      SpawnStrategy::Weighted => self.pick_weighted(the_difficulty),
+      SpawnStrategy::ShuffleBag => self.pick_from_bag(the_difficulty),
+    }
+  }
+  /// Draw a random pattern from the bag, refilling it with all patterns that
+  /// are unlocked at `the_difficulty` once it is empty
+  fn pick_from_bag(&mut self, the_difficulty: f32) -> usize {
+    if self.its_bag.is_empty() {
+      let a_patterns = &self.its_patterns;
+      self.its_bag.extend(
+        (0..a_patterns.len()).filter(|the_idx| a_patterns[*the_idx].difficulty <= the_difficulty),
+      );
+    }
+    if self.its_bag.is_empty() {
+      // Nothing unlocked yet, the weighted pick knows the fallback
+      return self.pick_weighted(the_difficulty);
+    }
+    let a_pos = self.its_rng.next_below(self.its_bag.len());
+    self.its_bag.swap_remove(a_pos)
+  }
+  /// Pick a pattern index among those unlocked at `the_difficulty`. Patterns
+  /// close to the current difficulty are preferred over ones that have long
+  /// been unlocked.
+  fn pick_weighted(&mut self, the_difficulty: f32) -> usize {
+    let a_weight = |the_pattern: &PatternDef| {
+      if the_pattern.difficulty > the_difficulty {
+        0.
+      } else {
+        the_pattern.weight.max(0.) / (1. + the_difficulty - the_pattern.difficulty)
+      }
+    };
+    let a_total = self.its_patterns.iter().map(a_weight).sum::<f32>();
+    if a_total <= 0. {
+      // Nothing unlocked yet, fall back to the easiest pattern
+      return (0..self.its_patterns.len())
+        .min_by(|the_a, the_b| {
+          let a_da = self.its_patterns[*the_a].difficulty;
+          let a_db = self.its_patterns[*the_b].difficulty;
+          a_da.partial_cmp(&a_db).unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or(0);
+    }
+    let mut a_pick = self.its_rng.next_f32() * a_total;
+    for (a_idx, a_pattern) in self.its_patterns.iter().enumerate() {
+      let a_weight = a_weight(a_pattern);
+      if a_pick < a_weight {
+        return a_idx;
+      }
+      a_pick -= a_weight;
+    }
+    self.its_patterns.len() - 1
+  }
+  /// Randomly rotate and mirror a pattern onto the slots, into
+  /// `its_placement`
+  fn place(&mut self, the_game: &model::GameState, the_pattern: usize, the_difficulty: f32) -> () {
+    let a_slot_count = the_game.get_slots().len();
+    let a_rotation = self.its_rng.next_below(a_slot_count);
+    let a_mirrored = self.its_rng.next_below(2) == 1;
+    let a_pattern = &self.its_patterns[the_pattern];
+    let a_wave_group = self.its_wave_group;
+    let a_to_slot = |the_slot: usize| {
+      let a_rel_slot = if a_mirrored {
+        a_slot_count - the_slot % a_slot_count
+      } else {
+        the_slot
+      };
+      (a_rel_slot + a_rotation) % a_slot_count
+    };
+    self.its_placement.clear();
+    self
+      .its_placement
+      .extend(a_pattern.walls.iter().map(|the_wall| {
+        let a_slot = a_to_slot(the_wall.slot);
+        let (a_offset, a_height) = a_pattern.get_wall_at(the_wall, the_difficulty);
+        let a_distance = if the_wall.reversed {
+          constants::REVERSE_SPAWN_DISTANCE - a_offset - a_height
+        } else if the_wall.outer_ring {
+          the_game.get_outer_ring_distance() + a_offset
+        } else {
+          the_game.get_spawn_distance() + a_offset
+        };
+        let mut a_obstacle = model::Obstacle::new_at(a_distance, a_height);
+        a_obstacle.set_outer_ring(the_wall.outer_ring);
+        a_obstacle.set_reversed(the_wall.reversed);
+        a_obstacle.set_gap(
+          the_wall
+            .gap
+            .as_ref()
+            .map(|the_gap| the_gap.to_gap(a_mirrored)),
+        );
+        a_obstacle.set_pulse(a_pattern.pulse.as_ref().map(PulseDef::to_pulse));
+        a_obstacle.set_color_group(a_wave_group.wrapping_add(the_wall.color_group));
+        a_obstacle.set_shape(the_wall.shape.as_ref().map(|the_shape| {
+          if a_mirrored {
+            the_shape.mirrored()
+          } else {
+            the_shape.clone()
+          }
+        }));
+        (a_slot, a_obstacle)
+      }));
+    self.its_pickup_placement.clear();
+    self
+      .its_pickup_placement
+      .extend(a_pattern.pickups.iter().map(|the_pickup| {
+        let a_distance = the_game.get_spawn_distance()
+          + a_pattern.get_pickup_offset_at(the_pickup, the_difficulty);
+        (
+          a_to_slot(the_pickup.slot),
+          model::Pickup::new_at(a_distance, the_pickup.power_up),
+        )
+      }));
+  }
+  /// Spawn a pattern that the player can survive. Placements that would
+  /// leave no reachable gap are rejected before they enter the game state.
+  fn spawn(&mut self, the_game: &mut model::GameState) -> () {
+    let a_difficulty = self.get_difficulty(the_game.get_run().get_time());
+    for _ in 0..MAX_SPAWN_ATTEMPTS {
+      let a_pattern = self.pick_pattern(a_difficulty);
+      self.place(the_game, a_pattern, a_difficulty);
+      if !self.its_solver.is_solvable(the_game, &self.its_placement) {
+        continue;
+      }
+      for (a_slot, a_obstacle) in self.its_placement.drain(..) {
+        the_game.add_obstacle(a_slot, a_obstacle);
+      }
+      for (a_slot, a_pickup) in self.its_pickup_placement.drain(..) {
+        the_game.add_pickup(a_slot, a_pickup);
+      }
+      self.its_wave_group += 1;
+      self.its_last_pattern = Some(a_pattern);
+      let a_def = &self.its_patterns[a_pattern];
+      self.its_distance_to_next =
+        a_def.get_depth_at(a_difficulty) + a_def.get_spacing_at(a_difficulty);
+      return;
+    }
+    self.its_distance_to_next = RETRY_DISTANCE;
+  }
+  pub fn tick(&mut self, the_game: &mut model::GameState, the_delta: Duration) -> () {
+    if !the_game.is_running() || self.its_patterns.is_empty() {
+      return;
+    }
+    self.its_distance_to_next -= the_game.get_obstacle_step(the_delta);
+    if self.its_distance_to_next <= 0. {
+      self.spawn(the_game);
+    }
+  }
+  /// The next `the_count` waves after the current state of `the_game`,
+  /// found by playing on in a copy where the player cannot die. Waves that
+  /// take longer than `MAX_LOOKAHEAD` are left out, as is anything the
+  /// level's timeline would change in the meantime.
+  pub fn get_upcoming(&self, the_game: &model::GameState, the_count: usize) -> Vec<UpcomingWave> {
+    let mut a_spawner = self.clone();
+    let mut a_game = the_game.clone();
+    a_game.set_invincible(true);
+    let a_end = a_game.get_run().get_time() + MAX_LOOKAHEAD;
+    let mut a_upcoming = Vec::new();
+    let mut a_counts = [0; 6];
+    while a_upcoming.len() < the_count && a_game.is_running() {
+      if a_game.get_run().get_time() >= a_end {
+        break;
+      }
+      for (a_count, a_slot) in a_counts.iter_mut().zip(a_game.get_slots()) {
+        *a_count = a_slot.get_obstacles().len();
+      }
+      let a_waves = a_spawner.get_wave_count();
+      let a_delta = a_game.get_tick_duration();
+      a_spawner.tick(&mut a_game, a_delta);
+      if a_spawner.get_wave_count() != a_waves {
+        let a_slots = a_game.get_slots();
+        a_upcoming.push(UpcomingWave {
+          time: a_game.get_run().get_time(),
+          pattern: a_spawner.get_last_pattern().unwrap_or_default().to_string(),
+          slots: (0..a_slots.len())
+            .filter(|the_idx| a_slots[*the_idx].get_obstacles().len() > a_counts[*the_idx])
+            .collect(),
+        });
+      }
+      a_game.tick();
+      a_game.take_events();
+    }
+    a_upcoming
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{pattern, wall, SpawnStrategy, Spawner, UpcomingWave};
+  use crate::constants;
+  use crate::level::{LevelDef, PickupDef};
+  use crate::model::{GameEvent, GameState, PowerUp};
+
+  /// Spawn and play the default patterns like the app does, carrying on
+  /// after every death
+  fn play(
+    the_spawner: &mut Spawner,
+    the_game: &mut GameState,
+    the_events: &mut Vec<GameEvent>,
+    the_ticks: usize,
+  ) -> () {
+    for _ in 0..the_ticks {
+      let a_delta = the_game.get_tick_duration();
+      the_spawner.tick(the_game, a_delta);
+      the_game.tick();
+      the_game.swap_events(the_events);
+      the_events.clear();
+      if !the_game.is_running() {
+        the_game.set_running(true);
+      }
+    }
+  }
+
+  #[test]
+  fn spawns_the_pickups_of_a_pattern() {
+    let mut a_pattern = pattern("gift", 0., 1., Vec::new());
+    a_pattern.pickups = (0..6)
+      .map(|the_slot| PickupDef {
+        slot: the_slot,
+        offset: 0.,
+        power_up: PowerUp::ShrinkCursor,
+      })
+      .collect();
+    let mut a_spawner = Spawner::new(vec![a_pattern], 10., SpawnStrategy::Weighted);
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    a_spawner.reset(7);
+    let a_delta = a_game.get_tick_duration();
+    a_spawner.tick(&mut a_game, a_delta);
+    for a_slot in a_game.get_slots() {
+      let a_pickups = a_slot.get_pickups();
+      assert_eq!(a_pickups.len(), 1);
+      assert_eq!(a_pickups[0].get_power_up(), PowerUp::ShrinkCursor);
+      assert_eq!(a_pickups[0].get_distance(), a_game.get_spawn_distance());
+    }
+  }
+
+  #[test]
+  fn spawns_reversed_walls_at_the_center() {
+    let mut a_wall = wall(0, 0.);
+    a_wall.reversed = true;
+    let mut a_spawner = Spawner::new(
+      vec![pattern("undertow", 0., 1., vec![a_wall])],
+      10.,
+      SpawnStrategy::Weighted,
+    );
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    a_spawner.reset(7);
+    let a_delta = a_game.get_tick_duration();
+    a_spawner.tick(&mut a_game, a_delta);
+    let a_obstacles: Vec<_> = a_game
+      .get_slots()
+      .iter()
+      .flat_map(|the_slot| the_slot.get_obstacles())
+      .collect();
+    assert_eq!(a_obstacles.len(), 1);
+    let a_expected = constants::REVERSE_SPAWN_DISTANCE - a_obstacles[0].get_height();
+    assert!((a_obstacles[0].get_distance() - a_expected).abs() < 1e-6);
+  }
+
+  #[test]
+  fn predicts_the_upcoming_waves() {
+    let mut a_spawner = Spawner::new(
+      vec![
+        pattern("single", 0., 1., vec![wall(0, 0.)]),
+        pattern("double", 0., 1., vec![wall(0, 0.), wall(3, 0.)]),
+      ],
+      10.,
+      SpawnStrategy::Weighted,
+    );
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    // so that the run goes on like the prediction, which cannot die
+    a_game.set_invincible(true);
+    a_spawner.reset(7);
+    let a_upcoming = a_spawner.get_upcoming(&a_game, 4);
+    assert_eq!(a_upcoming.len(), 4);
+    // the prediction plays on in a copy and leaves the run alone
+    assert_eq!(a_spawner.get_wave_count(), 0);
+    let mut a_events = Vec::new();
+    let mut a_spawned = Vec::new();
+    while a_spawned.len() < a_upcoming.len() {
+      let a_waves = a_spawner.get_wave_count();
+      // waves spawn before the tick that is played
+      let a_time = a_game.get_run().get_time();
+      play(&mut a_spawner, &mut a_game, &mut a_events, 1);
+      if a_spawner.get_wave_count() != a_waves {
+        a_spawned.push((a_time, a_spawner.get_last_pattern().unwrap().to_string()));
+      }
+    }
+    let a_predicted: Vec<_> = a_upcoming
+      .iter()
+      .map(|the_wave: &UpcomingWave| (the_wave.time, the_wave.pattern.clone()))
+      .collect();
+    assert_eq!(a_predicted, a_spawned);
+    for a_wave in &a_upcoming {
+      let a_walls = if a_wave.pattern == "single" { 1 } else { 2 };
+      assert_eq!(a_wave.slots.len(), a_walls);
+    }
+  }
+  #[test]
+  fn tells_what_it_spawned_last() {
+    let mut a_spawner = Spawner::new(
+      vec![pattern("single", 0., 1., vec![wall(0, 0.)])],
+      10.,
+      SpawnStrategy::Weighted,
+    );
+    let mut a_game = GameState::new();
+    a_game.restart(7);
+    a_spawner.reset(7);
+    assert_eq!(a_spawner.get_last_pattern(), None);
+    let a_delta = a_game.get_tick_duration();
+    a_spawner.tick(&mut a_game, a_delta);
+    assert_eq!(a_spawner.get_last_pattern(), Some("single"));
+    assert!(a_spawner.get_distance_to_next() > 0.);
+    a_spawner.reset(7);
+    assert_eq!(a_spawner.get_last_pattern(), None);
+  }
+  #[test]
+  fn levels_choose_where_obstacles_appear() {
+    let a_level = LevelDef::from_json(br#"{"name": "far", "spawn_distance": 3}"#).unwrap();
+    let mut a_game = GameState::new();
+    a_game.apply_level(&a_level);
+    a_game.restart(7);
+    // the playfield reaches as far out unless the level says otherwise
+    assert_eq!(a_game.get_despawn_distance(), 3.);
+    assert_eq!(a_game.get_outer_ring_distance(), 4.5);
+    let mut a_spawner = Spawner::new(
+      vec![pattern("single", 0., 1., vec![wall(0, 0.)])],
+      10.,
+      SpawnStrategy::Weighted,
+    );
+    a_spawner.reset(7);
+    let a_delta = a_game.get_tick_duration();
+    a_spawner.tick(&mut a_game, a_delta);
+    let a_distances: Vec<f32> = a_game
+      .get_slots()
+      .iter()
+      .flat_map(|the_slot| the_slot.get_obstacles())
+      .map(|the_obstacle| the_obstacle.get_distance())
+      .collect();
+    assert_eq!(a_distances, vec![3.]);
+  }
+}
@@ -0,0 +1,64 @@
+use super::model::Run;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Runs lasting at least this many seconds extend the streak
+pub const STREAK_TIME: f32 = 30.;
+
+/// What the player has done over all sessions. Unlike the records, every
+/// run counts, whether assisted, practiced or cheated.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LifetimeStats {
+  /// Time spent in runs, in seconds
+  pub play_time: f32,
+  pub runs: u32,
+  /// Deaths keyed by pack and level
+  pub deaths: BTreeMap<String, u32>,
+  /// Most runs of at least `STREAK_TIME` in a row
+  pub longest_streak: u32,
+  /// Runs of at least `STREAK_TIME` in a row up to the latest one
+  pub current_streak: u32,
+}
+
+impl LifetimeStats {
+  /// Load the statistics from `the_path`, starting over if there are none
+  /// or they cannot be read
+  pub fn load(the_path: &Path) -> LifetimeStats {
+    match std::fs::read(the_path) {
+      Ok(a_data) => match serde_json::from_slice(&a_data) {
+        Ok(a_stats) => a_stats,
+        Err(a_err) => {
+          eprintln!(
+            "Ignoring malformed statistics file {}: {}",
+            the_path.display(),
+            a_err
+          );
+          LifetimeStats::default()
+        }
+      },
+      Err(_) => LifetimeStats::default(),
+    }
+  }
+  pub fn save(&self, the_path: &Path) -> std::io::Result<()> {
+    if let Some(a_dir) = the_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_json = serde_json::to_vec_pretty(self)?;
+    std::fs::write(the_path, a_json)
+  }
+  /// Count `the_run`, which ended by dying in the level `the_key`
+  pub fn submit(&mut self, the_key: &str, the_run: &Run) -> () {
+    let a_time = the_run.get_time().as_secs_f32();
+    self.play_time += a_time;
+    self.runs += 1;
+    *self.deaths.entry(the_key.to_string()).or_default() += 1;
+    if a_time >= STREAK_TIME {
+      self.current_streak += 1;
+      self.longest_streak = self.longest_streak.max(self.current_streak);
+    } else {
+      self.current_streak = 0;
+    }
+  }
+}
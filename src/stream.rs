@@ -0,0 +1,163 @@
+use gl::types::*;
+
+/// Number of frames the persistently mapped buffer holds, so that the CPU can
+/// write one of them while the GPU is still reading the others
+const STREAM_SECTIONS: usize = 3;
+
+/// Vertex buffer for data that is replaced every frame. Uses a persistently
+/// mapped ring buffer where the GL version allows, and orphans the buffer's
+/// storage before every upload otherwise. Either way the CPU never has to
+/// wait for the GPU to finish reading the previous frame.
+pub struct StreamBuffer {
+    its_glbuf: GLuint,
+    /// Start of the mapped storage, null when orphaning
+    its_mapping: *mut u8,
+    /// Size of one section of the mapped storage in bytes
+    its_section_size: usize,
+    /// Section the latest upload went to
+    its_section: usize,
+    /// Signalled once the GPU is done reading the respective section
+    its_fences: [GLsync; STREAM_SECTIONS],
+    /// Set once mapping has failed, the buffer is orphaned from then on
+    its_persistent_failed: bool,
+}
+
+impl StreamBuffer {
+    pub fn new() -> StreamBuffer {
+        let a_buf_id = unsafe {
+            let mut a_buf_id = std::mem::zeroed();
+            gl::GenBuffers(1, &mut a_buf_id);
+            a_buf_id
+        };
+        StreamBuffer {
+            its_glbuf: a_buf_id,
+            its_mapping: std::ptr::null_mut(),
+            its_section_size: 0,
+            its_section: 0,
+            its_fences: [std::ptr::null(); STREAM_SECTIONS],
+            its_persistent_failed: false,
+        }
+    }
+
+    fn supports_persistent_mapping() -> bool {
+        gl::BufferStorage::is_loaded()
+            && gl::MapBufferRange::is_loaded()
+            && gl::FenceSync::is_loaded()
+            && gl::ClientWaitSync::is_loaded()
+    }
+
+    /// Replace the buffer by one whose sections hold at least `the_size`
+    /// bytes. Falls back to orphaning if it cannot be mapped.
+    unsafe fn reallocate(&mut self, the_size: usize) -> () {
+        self.release();
+        gl::GenBuffers(1, &mut self.its_glbuf);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.its_glbuf);
+        // leave room to grow a little before the next reallocation
+        let a_section_size = the_size.next_power_of_two();
+        let a_total = (a_section_size * STREAM_SECTIONS) as GLsizeiptr;
+        let a_flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+        gl::BufferStorage(gl::ARRAY_BUFFER, a_total, std::ptr::null(), a_flags);
+        self.its_mapping = gl::MapBufferRange(gl::ARRAY_BUFFER, 0, a_total, a_flags) as *mut u8;
+        if self.its_mapping.is_null() {
+            eprintln!("Mapping the vertex buffer failed, falling back to orphaning");
+            // buffer storage is immutable, so start over with a plain buffer
+            gl::DeleteBuffers(1, &self.its_glbuf);
+            gl::GenBuffers(1, &mut self.its_glbuf);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.its_glbuf);
+            self.its_section_size = 0;
+            self.its_persistent_failed = true;
+        } else {
+            self.its_section_size = a_section_size;
+        }
+        self.its_section = 0;
+    }
+
+    /// Unmap and delete the buffer along with all pending fences
+    unsafe fn release(&mut self) -> () {
+        for a_fence in self.its_fences.iter_mut() {
+            if !a_fence.is_null() {
+                gl::DeleteSync(*a_fence);
+                *a_fence = std::ptr::null();
+            }
+        }
+        if !self.its_mapping.is_null() {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.its_glbuf);
+            gl::UnmapBuffer(gl::ARRAY_BUFFER);
+            self.its_mapping = std::ptr::null_mut();
+        }
+        gl::DeleteBuffers(1, &self.its_glbuf);
+    }
+
+    /// Block until the GPU has finished reading the given section
+    unsafe fn wait_for_section(&mut self, the_section: usize) -> () {
+        let a_fence = self.its_fences[the_section];
+        if a_fence.is_null() {
+            return;
+        }
+        loop {
+            let a_result = gl::ClientWaitSync(a_fence, gl::SYNC_FLUSH_COMMANDS_BIT, 1_000_000);
+            if a_result != gl::TIMEOUT_EXPIRED {
+                break;
+            }
+        }
+        gl::DeleteSync(a_fence);
+        self.its_fences[the_section] = std::ptr::null();
+    }
+
+    /// Upload `the_data` and leave the buffer bound to `ARRAY_BUFFER`.
+    /// Returns the byte offset of the data within the buffer.
+    pub unsafe fn upload(&mut self, the_data: &[u8]) -> usize {
+        let a_size = the_data.len();
+        let a_persistent =
+            !self.its_persistent_failed && StreamBuffer::supports_persistent_mapping();
+        if a_persistent && a_size > self.its_section_size {
+            self.reallocate(a_size);
+        }
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.its_glbuf);
+        if self.its_mapping.is_null() {
+            // Let the driver hand out fresh storage instead of waiting until
+            // the old one is no longer in use
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                a_size as GLsizeiptr,
+                std::ptr::null(),
+                gl::STREAM_DRAW,
+            );
+            gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                0,
+                a_size as GLsizeiptr,
+                the_data.as_ptr() as *const _,
+            );
+            return 0;
+        }
+        self.its_section = (self.its_section + 1) % STREAM_SECTIONS;
+        self.wait_for_section(self.its_section);
+        let a_offset = self.its_section * self.its_section_size;
+        std::ptr::copy_nonoverlapping(the_data.as_ptr(), self.its_mapping.add(a_offset), a_size);
+        a_offset
+    }
+
+    /// Bind the buffer to `ARRAY_BUFFER` to draw the latest upload again
+    pub unsafe fn bind(&self) -> () {
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.its_glbuf);
+    }
+
+    /// Mark the end of the draw calls that read the latest upload
+    pub unsafe fn fence(&mut self) -> () {
+        if self.its_mapping.is_null() {
+            return;
+        }
+        let a_fence = &mut self.its_fences[self.its_section];
+        if !a_fence.is_null() {
+            gl::DeleteSync(*a_fence);
+        }
+        *a_fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+    }
+}
+
+impl Drop for StreamBuffer {
+    fn drop(&mut self) -> () {
+        unsafe { self.release() };
+    }
+}
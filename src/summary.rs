@@ -0,0 +1,120 @@
+use super::i18n::Catalog;
+use super::model::Run;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Year, month and day of `the_days` since the epoch, see
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(the_days: i64) -> (i64, i64, i64) {
+  let a_z = the_days + 719468;
+  let a_era = a_z.div_euclid(146097);
+  let a_doe = a_z - a_era * 146097;
+  let a_yoe = (a_doe - a_doe / 1460 + a_doe / 36524 - a_doe / 146096) / 365;
+  let a_doy = a_doe - (365 * a_yoe + a_yoe / 4 - a_yoe / 100);
+  let a_mp = (5 * a_doy + 2) / 153;
+  let a_day = a_doy - (153 * a_mp + 2) / 5 + 1;
+  let a_month = if a_mp < 10 { a_mp + 3 } else { a_mp - 9 };
+  let a_year = a_yoe + a_era * 400 + if a_month <= 2 { 1 } else { 0 };
+  (a_year, a_month, a_day)
+}
+
+/// `the_days` since the epoch as an ISO 8601 date
+pub fn format_day(the_days: i64) -> String {
+  let (a_year, a_month, a_day) = civil_from_days(the_days);
+  format!("{:04}-{:02}-{:02}", a_year, a_month, a_day)
+}
+
+/// `the_time` as an ISO 8601 UTC date and time
+fn format_utc(the_time: SystemTime) -> String {
+  let a_secs = the_time
+    .duration_since(UNIX_EPOCH)
+    .map(|the_duration| the_duration.as_secs())
+    .unwrap_or(0);
+  let a_secs_of_day = a_secs % 86400;
+  format!(
+    "{} {:02}:{:02} UTC",
+    format_day((a_secs / 86400) as i64),
+    a_secs_of_day / 3600,
+    a_secs_of_day % 3600 / 60
+  )
+}
+
+/// `the_time` in seconds with millisecond precision, e.g. `12.345`
+pub fn format_time(the_time: Duration) -> String {
+  let a_millis = the_time.as_millis();
+  format!("{}.{:03}", a_millis / 1000, a_millis % 1000)
+}
+
+/// How the player did in `the_run` beyond the survived time, one metric per
+/// line
+pub fn format_run_metrics(the_catalog: &Catalog, the_run: &Run) -> String {
+  let a_metrics = format!(
+    "{}\n{}\n{}\n",
+    the_catalog.format(
+      "hud.walls_dodged",
+      &[("count", &the_run.get_walls_dodged())]
+    ),
+    the_catalog.format(
+      "hud.distance",
+      &[("turns", &format!("{:.1}", the_run.get_distance()))]
+    ),
+    the_catalog.format(
+      "hud.direction_changes",
+      &[("count", &the_run.get_direction_changes())]
+    )
+  );
+  // the score only tells more than the dodged walls once a multiplier has
+  // raised it
+  if the_run.get_score() > the_run.get_walls_dodged() {
+    let a_score = the_catalog.format("hud.score", &[("count", &the_run.get_score())]);
+    return a_score + "\n" + &a_metrics;
+  }
+  a_metrics
+}
+
+/// The seed of `the_run` as the player would type it to play it again
+pub fn format_seed(the_catalog: &Catalog, the_run: &Run) -> String {
+  match the_run.get_seed_text() {
+    Some(a_text) => the_catalog.format("summary.seed", &[("seed", &a_text)]),
+    None => the_catalog.format("summary.seed", &[("seed", &the_run.get_seed())]),
+  }
+}
+
+/// A plain text description of `the_run` that is enough to reproduce it
+pub fn format_run_summary(
+  the_catalog: &Catalog,
+  the_pack: Option<&str>,
+  the_level: Option<&str>,
+  the_run: &Run,
+) -> String {
+  let mut a_summary = format!("Libre Hexagon {}\n", env!("CARGO_PKG_VERSION"));
+  if let Some(a_pack) = the_pack {
+    a_summary += &the_catalog.format("summary.pack", &[("name", &a_pack)]);
+    a_summary += "\n";
+  }
+  let a_level = the_level.unwrap_or_else(|| the_catalog.get("summary.built_in"));
+  a_summary += &the_catalog.format("summary.level", &[("name", &a_level)]);
+  a_summary += "\n";
+  a_summary += &format_seed(the_catalog, the_run);
+  a_summary += "\n";
+  let a_time = format_time(the_run.get_time());
+  a_summary += &the_catalog.format("hud.time", &[("time", &a_time)]);
+  a_summary += "\n";
+  a_summary += &format_run_metrics(the_catalog, the_run);
+  if the_run.is_assisted() {
+    let a_percent = (the_run.get_min_game_speed() * 100.).round() as u32;
+    a_summary += &the_catalog.format("summary.assisted", &[("percent", &a_percent)]);
+    a_summary += "\n";
+  }
+  if the_run.is_practice() {
+    a_summary += the_catalog.get("summary.practice");
+    a_summary += "\n";
+  }
+  if the_run.is_cheated() {
+    a_summary += the_catalog.get("summary.cheated");
+    a_summary += "\n";
+  }
+  let a_date = format_utc(SystemTime::now());
+  a_summary += &the_catalog.format("summary.date", &[("date", &a_date)]);
+  a_summary += "\n";
+  a_summary
+}
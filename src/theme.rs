@@ -0,0 +1,287 @@
+use super::i18n::Catalog;
+use super::level::StyleDef;
+use super::loader;
+use super::model::{Color, Style};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const LEFT_KEY: u32 = 105;
+const RIGHT_KEY: u32 = 106;
+const UP_KEY: u32 = 103;
+const DOWN_KEY: u32 = 108;
+const PAGE_UP_KEY: u32 = 104;
+const PAGE_DOWN_KEY: u32 = 109;
+const TAB_KEY: u32 = 15;
+/// E
+const EXPORT_KEY: u32 = 18;
+/// R
+const RELOAD_KEY: u32 = 19;
+
+/// Change of a value per press of up or down
+const FINE_STEP: f32 = 0.01;
+/// Change of a value per press of page up or page down
+const COARSE_STEP: f32 = 0.1;
+/// How often the exported file is checked for changes made by hand
+const RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A part of the style the theme editor can change
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Field {
+  CursorColor,
+  CursorShadowColor,
+  InnerHexagonColor,
+  OuterHexagonColor,
+  SlotColor(usize),
+  ObstacleColor(usize),
+  Zoom,
+  Eye,
+  LookAt,
+}
+
+impl Field {
+  /// Names of the values the field consists of
+  fn get_components(self) -> &'static [&'static str] {
+    match self {
+      Field::Zoom => &["zoom"],
+      Field::Eye | Field::LookAt => &["x", "y"],
+      _ => &["r", "g", "b", "a"],
+    }
+  }
+}
+
+fn get_channel(the_color: &Color, the_idx: usize) -> f32 {
+  match the_idx {
+    0 => the_color.its_r,
+    1 => the_color.its_g,
+    2 => the_color.its_b,
+    _ => the_color.its_a,
+  }
+}
+
+fn with_channel(the_color: &Color, the_idx: usize, the_value: f32) -> Color {
+  let mut a_color = the_color.clone();
+  let a_value = the_value.clamp(0., 1.);
+  match the_idx {
+    0 => a_color.its_r = a_value,
+    1 => a_color.its_g = a_value,
+    2 => a_color.its_b = a_value,
+    _ => a_color.its_a = a_value,
+  }
+  a_color
+}
+
+/// Frozen-time mode for tweaking the colors and camera of the current style
+/// and exporting them as a style file that levels can use as their base
+pub struct ThemeEditor {
+  its_style: Style,
+  /// Index into `get_fields` of the field being edited
+  its_field: usize,
+  /// Index into the field's components of the value being edited
+  its_component: usize,
+  /// File the theme has been exported to. Changes made to it by hand are
+  /// taken over while the editor is open.
+  its_path: Option<PathBuf>,
+  its_modified: Option<SystemTime>,
+  its_since_check: Duration,
+}
+
+impl ThemeEditor {
+  pub fn new(the_style: &Style) -> ThemeEditor {
+    ThemeEditor {
+      its_style: the_style.clone(),
+      its_field: 0,
+      its_component: 0,
+      its_path: None,
+      its_modified: None,
+      its_since_check: Duration::from_secs(0),
+    }
+  }
+  /// The style being edited, to render instead of the game's
+  pub fn get_style(&self) -> &Style {
+    &self.its_style
+  }
+  fn get_fields(&self) -> Vec<Field> {
+    let mut a_fields = vec![
+      Field::CursorColor,
+      Field::CursorShadowColor,
+      Field::InnerHexagonColor,
+      Field::OuterHexagonColor,
+    ];
+    a_fields.extend((0..self.its_style.get_slot_colors().len()).map(Field::SlotColor));
+    a_fields.extend((0..self.its_style.get_obstacle_colors().len()).map(Field::ObstacleColor));
+    a_fields.extend_from_slice(&[Field::Zoom, Field::Eye, Field::LookAt]);
+    a_fields
+  }
+  fn get_field(&self) -> Field {
+    let a_fields = self.get_fields();
+    a_fields[self.its_field % a_fields.len()]
+  }
+  fn get_value(&self, the_field: Field, the_component: usize) -> f32 {
+    let a_style = &self.its_style;
+    match the_field {
+      Field::CursorColor => get_channel(a_style.get_cursor_color(), the_component),
+      Field::CursorShadowColor => get_channel(a_style.get_cursor_shadow_color(), the_component),
+      Field::InnerHexagonColor => get_channel(a_style.get_inner_hexagon_color(), the_component),
+      Field::OuterHexagonColor => get_channel(a_style.get_outer_hexagon_color(), the_component),
+      Field::SlotColor(a_idx) => get_channel(&a_style.get_slot_colors()[a_idx], the_component),
+      Field::ObstacleColor(a_idx) => {
+        get_channel(&a_style.get_obstacle_colors()[a_idx], the_component)
+      }
+      Field::Zoom => a_style.get_zoom(),
+      Field::Eye => a_style.get_eye()[the_component],
+      Field::LookAt => a_style.get_look_at()[the_component],
+    }
+  }
+  fn set_value(&mut self, the_field: Field, the_component: usize, the_value: f32) -> () {
+    let a_style = &mut self.its_style;
+    match the_field {
+      Field::CursorColor => {
+        let a_color = with_channel(a_style.get_cursor_color(), the_component, the_value);
+        a_style.set_cursor_color(a_color);
+      }
+      Field::CursorShadowColor => {
+        let a_color = with_channel(a_style.get_cursor_shadow_color(), the_component, the_value);
+        a_style.set_cursor_shadow_color(a_color);
+      }
+      Field::InnerHexagonColor => {
+        let a_color = with_channel(a_style.get_inner_hexagon_color(), the_component, the_value);
+        a_style.set_inner_hexagon_color(a_color);
+      }
+      Field::OuterHexagonColor => {
+        let a_color = with_channel(a_style.get_outer_hexagon_color(), the_component, the_value);
+        a_style.set_outer_hexagon_color(a_color);
+      }
+      Field::SlotColor(a_idx) => {
+        let mut a_colors = a_style.get_slot_colors().clone();
+        a_colors[a_idx] = with_channel(&a_colors[a_idx], the_component, the_value);
+        a_style.set_slot_colors(a_colors);
+      }
+      Field::ObstacleColor(a_idx) => {
+        let mut a_colors = a_style.get_obstacle_colors().clone();
+        a_colors[a_idx] = with_channel(&a_colors[a_idx], the_component, the_value);
+        a_style.set_obstacle_colors(a_colors);
+      }
+      // a zoom of 0 would collapse the playfield into a point
+      Field::Zoom => a_style.set_zoom(the_value.max(COARSE_STEP)),
+      Field::Eye => {
+        let mut a_eye = *a_style.get_eye();
+        a_eye[the_component] = the_value;
+        a_style.set_eye(a_eye.x, a_eye.y);
+      }
+      Field::LookAt => {
+        let mut a_look_at = *a_style.get_look_at();
+        a_look_at[the_component] = the_value;
+        a_style.set_look_at(a_look_at.x, a_look_at.y);
+      }
+    }
+  }
+  fn adjust(&mut self, the_step: f32) -> () {
+    let a_field = self.get_field();
+    let a_value = self.get_value(a_field, self.its_component);
+    self.set_value(a_field, self.its_component, a_value + the_step);
+  }
+  /// The value being edited with its field, for the player to see
+  pub fn describe_selection(&self, the_catalog: &Catalog) -> String {
+    let a_field = self.get_field();
+    the_catalog.format(
+      "notice.theme_selection",
+      &[
+        ("field", &format!("{:?}", a_field)),
+        ("component", &a_field.get_components()[self.its_component]),
+        (
+          "value",
+          &format!("{:.2}", self.get_value(a_field, self.its_component)),
+        ),
+      ],
+    )
+  }
+  pub fn handle_keys(&mut self, the_new_keys: &BTreeSet<u32>) -> () {
+    for a_key in the_new_keys {
+      match *a_key {
+        TAB_KEY => {
+          self.its_field = (self.its_field + 1) % self.get_fields().len();
+          self.its_component = 0;
+        }
+        LEFT_KEY | RIGHT_KEY => {
+          let a_count = self.get_field().get_components().len();
+          self.its_component = if *a_key == LEFT_KEY {
+            (self.its_component + a_count - 1) % a_count
+          } else {
+            (self.its_component + 1) % a_count
+          };
+        }
+        UP_KEY => self.adjust(FINE_STEP),
+        DOWN_KEY => self.adjust(-FINE_STEP),
+        PAGE_UP_KEY => self.adjust(COARSE_STEP),
+        PAGE_DOWN_KEY => self.adjust(-COARSE_STEP),
+        RELOAD_KEY => self.reload(),
+        _ => (),
+      }
+    }
+  }
+  /// Take over changes made by hand to the exported file
+  pub fn tick(&mut self, the_delta: Duration) -> () {
+    self.its_since_check += the_delta;
+    if self.its_since_check < RELOAD_INTERVAL {
+      return;
+    }
+    self.its_since_check = Duration::from_secs(0);
+    let a_modified = match &self.its_path {
+      Some(a_path) => std::fs::metadata(a_path).and_then(|the_meta| the_meta.modified()),
+      None => return,
+    };
+    if let Ok(a_modified) = a_modified {
+      if self.its_modified != Some(a_modified) {
+        self.reload();
+      }
+    }
+  }
+  /// Apply the exported file, with its bases, on top of the current style
+  fn reload(&mut self) -> () {
+    let a_path = match &self.its_path {
+      Some(a_path) => a_path.clone(),
+      None => return,
+    };
+    self.its_modified = std::fs::metadata(&a_path)
+      .and_then(|the_meta| the_meta.modified())
+      .ok();
+    match loader::load_style(&a_path) {
+      Ok(a_theme) => {
+        a_theme.apply_to(&mut self.its_style);
+        println!("Reloaded theme from {}", a_path.display());
+      }
+      Err(a_err) => eprintln!("Failed to reload theme: {}", a_err),
+    }
+  }
+  /// Whether the export key has been pressed in `the_new_keys`
+  pub fn wants_export(&self, the_new_keys: &BTreeSet<u32>) -> bool {
+    the_new_keys.contains(&EXPORT_KEY)
+  }
+  /// Write the style to `<the_dir>/themes/<name>.json` so that it can be
+  /// used as a level's style `base`. Later exports overwrite that file.
+  pub fn export(&mut self, the_dir: &Path) -> std::io::Result<PathBuf> {
+    let a_path = match &self.its_path {
+      Some(a_path) => a_path.clone(),
+      None => {
+        let a_stamp = match SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+          Ok(a_time) => a_time.as_secs(),
+          Err(_) => 0,
+        };
+        the_dir
+          .join("themes")
+          .join(format!("theme-{}.json", a_stamp))
+      }
+    };
+    if let Some(a_dir) = a_path.parent() {
+      std::fs::create_dir_all(a_dir)?;
+    }
+    let a_theme = StyleDef::capture(&self.its_style);
+    std::fs::write(&a_path, serde_json::to_vec_pretty(&a_theme)?)?;
+    self.its_modified = std::fs::metadata(&a_path)
+      .and_then(|the_meta| the_meta.modified())
+      .ok();
+    self.its_path = Some(a_path.clone());
+    Ok(a_path)
+  }
+}
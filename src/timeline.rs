@@ -0,0 +1,214 @@
+use super::app::{Tween, TweenAPI};
+use super::constants;
+use super::level::{to_colors, EventAction, EventDef, PromptInput};
+use super::model::{Color, StyleCommand};
+use std::time::Duration;
+
+/// Blend `the_from` towards `the_to`, entries without a counterpart in
+/// `the_from` are taken from `the_to` right away
+fn mix_colors(the_from: &[Color], the_to: &[Color], the_t: f32) -> Vec<Color> {
+  the_to
+    .iter()
+    .enumerate()
+    .map(|(the_idx, the_color)| match the_from.get(the_idx) {
+      Some(a_from) => a_from.mix(the_color, the_t),
+      None => the_color.clone(),
+    })
+    .collect()
+}
+
+/// Eases a single event's change in over the tween's duration
+#[derive(Clone)]
+struct EventTween {
+  its_action: EventAction,
+  /// Value before the event started, captured on the first run
+  its_from_value: Option<f32>,
+  its_from_colors: Option<Vec<Color>>,
+}
+
+impl EventTween {
+  pub fn new(the_action: EventAction) -> EventTween {
+    EventTween {
+      its_action: the_action,
+      its_from_value: None,
+      its_from_colors: None,
+    }
+  }
+  fn lerp_value(&mut self, the_current: f32, the_target: f32, the_progress: f32) -> f32 {
+    let a_from = *self.its_from_value.get_or_insert(the_current);
+    a_from + (the_target - a_from) * the_progress
+  }
+}
+
+impl Tween for EventTween {
+  fn run(&mut self, the_progress: f32, the_api: &mut dyn TweenAPI) -> () {
+    match self.its_action.clone() {
+      EventAction::RotationSpeed { value } => {
+        let a_speed = self.lerp_value(the_api.get_rotation_speed(), value, the_progress);
+        the_api.set_rotation_speed(a_speed);
+      }
+      EventAction::ObstacleSpeed { value } => {
+        let a_game = the_api.get_game_state_mut();
+        let a_speed = self.lerp_value(a_game.get_obstacle_speed(), value, the_progress);
+        the_api.get_game_state_mut().set_obstacle_speed(a_speed);
+      }
+      EventAction::OuterHexagonWidth { value } => {
+        let a_style = the_api.get_game_state_mut().get_style_mut();
+        let a_width = self.lerp_value(a_style.get_outer_hexagon_width(), value, the_progress);
+        the_api
+          .get_game_state_mut()
+          .get_style_mut()
+          .set_outer_hexagon_width(a_width);
+      }
+      EventAction::PlayerSpeed { value } => {
+        let a_game = the_api.get_game_state_mut();
+        let a_speed = self.lerp_value(a_game.get_player_speed(), value, the_progress);
+        the_api.get_game_state_mut().set_player_speed(a_speed);
+      }
+      EventAction::SlotWidth { slot, value } => {
+        let a_game = the_api.get_game_state_mut();
+        // levels may name slots the playfield does not have
+        let a_current = match a_game.get_slots().get(slot) {
+          Some(a_slot) => a_slot.get_width(),
+          None => return,
+        };
+        let a_width = self.lerp_value(a_current, value, the_progress);
+        the_api.get_game_state_mut().set_slot_width(slot, a_width);
+      }
+      EventAction::SlotColors { colors } => {
+        let a_game = the_api.get_game_state_mut();
+        let a_from = self
+          .its_from_colors
+          .get_or_insert_with(|| a_game.get_style().get_slot_colors().clone());
+        let a_colors = mix_colors(a_from, &to_colors(&colors), the_progress);
+        a_game.submit_style_command(StyleCommand::FadePalette {
+          slot_colors: Some(a_colors),
+          obstacle_colors: None,
+        });
+      }
+      EventAction::ObstacleColors { colors } => {
+        let a_game = the_api.get_game_state_mut();
+        let a_from = self
+          .its_from_colors
+          .get_or_insert_with(|| a_game.get_style().get_obstacle_colors().clone());
+        let a_colors = mix_colors(a_from, &to_colors(&colors), the_progress);
+        a_game.submit_style_command(StyleCommand::FadePalette {
+          slot_colors: None,
+          obstacle_colors: Some(a_colors),
+        });
+      }
+      EventAction::Flash => {
+        if self.its_from_value.is_none() {
+          self.its_from_value = Some(0.);
+          the_api
+            .get_game_state_mut()
+            .get_style_mut()
+            .set_flash_time(constants::FLASH_DURATION);
+        }
+      }
+      // handed to the app by the timeline instead
+      EventAction::Prompt { .. } => (),
+    }
+  }
+}
+
+/// An event that is still easing in
+#[derive(Clone)]
+struct ActiveEvent {
+  its_tween: EventTween,
+  its_duration: Duration,
+  its_progress: Duration,
+}
+
+/// Fires a level's scripted events once the run has lasted long enough. Every
+/// event is played back as a one-shot tween. The events in flight are kept
+/// here rather than in a `TweenEngine` so that the timeline can be cloned
+/// into a savestate.
+#[derive(Clone)]
+pub struct Timeline {
+  /// Sorted by the time in seconds at which they fire
+  its_events: Vec<(f32, EventDef)>,
+  its_next: usize,
+  /// Run time in seconds that event times are counted from
+  its_start: f32,
+  its_active: Vec<ActiveEvent>,
+  /// A prompt that has fired but not been taken by the app yet
+  its_prompt: Option<(String, PromptInput)>,
+}
+
+impl Timeline {
+  pub fn new(the_events: &[EventDef], the_bpm: Option<f32>) -> Timeline {
+    let mut a_events: Vec<(f32, EventDef)> = Vec::new();
+    for a_event in the_events {
+      match a_event.get_seconds(the_bpm) {
+        Some(a_seconds) => a_events.push((a_seconds, a_event.clone())),
+        None => eprintln!("Ignoring level event without a valid time: {:?}", a_event),
+      }
+    }
+    a_events.sort_by(|the_a, the_b| {
+      the_a
+        .0
+        .partial_cmp(&the_b.0)
+        .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Timeline {
+      its_events: a_events,
+      its_next: 0,
+      its_start: 0.,
+      its_active: Vec::new(),
+      its_prompt: None,
+    }
+  }
+  /// Start over for a new run, dropping events that are still easing in
+  pub fn reset(&mut self) -> () {
+    self.reset_at(0.);
+  }
+  /// Start over with event times counted from `the_start` seconds into the
+  /// run
+  pub fn reset_at(&mut self, the_start: f32) -> () {
+    self.its_next = 0;
+    self.its_start = the_start;
+    self.its_active.clear();
+    self.its_prompt = None;
+  }
+  /// The text and the expected input of the latest prompt, which halts the
+  /// timeline until it is taken
+  pub fn take_prompt(&mut self) -> Option<(String, PromptInput)> {
+    self.its_prompt.take()
+  }
+  pub fn tick(&mut self, the_api: &mut dyn TweenAPI, the_delta: Duration) -> () {
+    let a_run_time = the_api
+      .get_game_state_mut()
+      .get_run()
+      .get_time()
+      .as_secs_f32();
+    while let Some((a_seconds, a_event)) = self.its_events.get(self.its_next) {
+      if self.its_prompt.is_some() || self.its_start + *a_seconds > a_run_time {
+        break;
+      }
+      if let EventAction::Prompt { text, input } = &a_event.action {
+        self.its_prompt = Some((text.clone(), *input));
+        self.its_next += 1;
+        continue;
+      }
+      self.its_active.push(ActiveEvent {
+        its_tween: EventTween::new(a_event.action.clone()),
+        its_duration: Duration::from_secs_f32(a_event.duration.max(0.)),
+        its_progress: Duration::from_secs(0),
+      });
+      self.its_next += 1;
+    }
+    for a_active in &mut self.its_active {
+      a_active.its_progress += the_delta;
+      let a_progress = if a_active.its_duration > Duration::from_secs(0) {
+        (a_active.its_progress.as_secs_f32() / a_active.its_duration.as_secs_f32()).min(1.)
+      } else {
+        1.
+      };
+      a_active.its_tween.run(a_progress, the_api);
+    }
+    self
+      .its_active
+      .retain(|the_active| the_active.its_progress < the_active.its_duration);
+  }
+}
@@ -0,0 +1,65 @@
+use super::level::PromptInput;
+use std::time::Duration;
+
+/// How long the player has to steer as asked to get past a prompt, so that
+/// an accidental tap does not skip it
+const HOLD_TIME: Duration = Duration::from_millis(500);
+
+/// A prompt of a scripted level, e.g. the tutorial, that the run waits on
+/// until the player has steered as asked
+pub struct Prompt {
+  its_text: String,
+  its_input: PromptInput,
+  /// How long the player has been steering as asked without a break
+  its_held: Duration,
+}
+
+impl Prompt {
+  pub fn new(the_text: String, the_input: PromptInput) -> Prompt {
+    Prompt {
+      its_text: the_text,
+      its_input: the_input,
+      its_held: Duration::from_secs(0),
+    }
+  }
+  pub fn get_text(&self) -> &str {
+    &self.its_text
+  }
+  /// Note that the player has been steering in `the_direction` for
+  /// `the_delta`. Returns whether the prompt has been followed.
+  pub fn update(&mut self, the_direction: i8, the_delta: Duration) -> bool {
+    let a_follows = match self.its_input {
+      PromptInput::Left => the_direction < 0,
+      PromptInput::Right => the_direction > 0,
+      PromptInput::Any => the_direction != 0,
+    };
+    if a_follows {
+      self.its_held += the_delta;
+    } else {
+      self.its_held = Duration::from_secs(0);
+    }
+    self.its_held >= HOLD_TIME
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Prompt, HOLD_TIME};
+  use crate::level::PromptInput;
+  use std::time::Duration;
+
+  #[test]
+  fn waits_for_the_requested_input() {
+    let a_step = HOLD_TIME / 2;
+    let mut a_prompt = Prompt::new("tutorial.move_right".to_string(), PromptInput::Right);
+    assert!(!a_prompt.update(-1, HOLD_TIME));
+    assert!(!a_prompt.update(1, a_step));
+    // letting go starts over
+    assert!(!a_prompt.update(0, a_step));
+    assert!(!a_prompt.update(1, a_step));
+    assert!(a_prompt.update(1, a_step));
+    let mut a_prompt = Prompt::new(String::new(), PromptInput::Any);
+    assert!(!a_prompt.update(0, HOLD_TIME));
+    assert!(a_prompt.update(-1, HOLD_TIME + Duration::from_millis(1)));
+  }
+}
@@ -0,0 +1,130 @@
+use super::app::App;
+use super::headless;
+use super::png;
+use super::renderer::Renderer;
+use super::replay::Replay;
+use glutin::window::Window;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// Frames rendered after the run has ended, so that the video does not stop
+/// at the moment of death
+const OUTRO_FRAMES: usize = 60;
+/// Most frames rendered, in case the replay never ends
+const MAX_FRAMES: usize = 60 * 60 * 60;
+/// Outputs with these extensions are encoded by ffmpeg
+const VIDEO_EXTENSIONS: [&str; 4] = ["mp4", "mkv", "webm", "mov"];
+
+/// Where rendered frames go
+enum FrameSink {
+  /// Numbered PNG files in a directory
+  Images(PathBuf),
+  /// Raw RGBA frames piped into ffmpeg
+  Encoder(Child),
+}
+
+impl FrameSink {
+  fn open(
+    the_out: &Path,
+    the_width: u32,
+    the_height: u32,
+    the_rate: u32,
+  ) -> Result<FrameSink, String> {
+    let a_is_video = the_out
+      .extension()
+      .and_then(|the_ext| the_ext.to_str())
+      .is_some_and(|the_ext| VIDEO_EXTENSIONS.contains(&the_ext));
+    if !a_is_video {
+      std::fs::create_dir_all(the_out).map_err(|the_err| the_err.to_string())?;
+      return Ok(FrameSink::Images(the_out.to_path_buf()));
+    }
+    let a_child = Command::new("ffmpeg")
+      .args(["-y", "-f", "rawvideo", "-pix_fmt", "rgba", "-s"])
+      .arg(format!("{}x{}", the_width, the_height))
+      .arg("-r")
+      .arg(the_rate.to_string())
+      .args(["-i", "-", "-pix_fmt", "yuv420p"])
+      .arg(the_out)
+      .stdin(Stdio::piped())
+      .spawn()
+      .map_err(|the_err| format!("cannot run ffmpeg: {}", the_err))?;
+    Ok(FrameSink::Encoder(a_child))
+  }
+
+  fn write(
+    &mut self,
+    the_index: usize,
+    the_width: u32,
+    the_height: u32,
+    the_pixels: &[u8],
+  ) -> Result<(), String> {
+    match self {
+      FrameSink::Images(a_dir) => {
+        let a_path = a_dir.join(format!("frame_{:06}.png", the_index));
+        std::fs::write(&a_path, png::encode(the_width, the_height, the_pixels))
+          .map_err(|the_err| format!("cannot write {}: {}", a_path.display(), the_err))
+      }
+      FrameSink::Encoder(a_child) => a_child
+        .stdin
+        .as_mut()
+        .ok_or("ffmpeg does not take input")?
+        .write_all(the_pixels)
+        .map_err(|the_err| format!("cannot pipe to ffmpeg: {}", the_err)),
+    }
+  }
+
+  /// Wait for the encoder, if any, to finish the video
+  fn finish(self) -> Result<(), String> {
+    if let FrameSink::Encoder(mut a_child) = self {
+      // closing its input tells ffmpeg that there are no more frames
+      drop(a_child.stdin.take());
+      let a_status = a_child.wait().map_err(|the_err| the_err.to_string())?;
+      if !a_status.success() {
+        return Err(format!("ffmpeg failed with {}", a_status));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Play `the_replay` back in `the_app` one tick per frame and write every
+/// frame to `the_out`, either a directory for PNG files or a video file
+/// that ffmpeg encodes. Since no frame is ever late, the video is perfectly
+/// smooth whatever the machine manages in real time. Returns the number of
+/// frames rendered.
+pub fn render_replay<R: Renderer>(
+  the_app: &mut App<R>,
+  the_window: &Window,
+  the_replay: Replay,
+  the_out: &Path,
+) -> Result<usize, String> {
+  let a_tick = Duration::from_nanos(1_000_000_000 / the_replay.tick_rate.max(1) as u64);
+  if let Some(a_dir) = &the_replay.pack {
+    the_app.load_pack(a_dir.clone());
+    while the_app.is_loading() {
+      the_app.tick(the_window, a_tick);
+      std::thread::sleep(Duration::from_millis(10));
+    }
+  }
+  let a_size = the_window.inner_size();
+  the_app
+    .get_renderer_mut()
+    .resize(a_size.width, a_size.height);
+  let mut a_sink = FrameSink::open(the_out, a_size.width, a_size.height, the_replay.tick_rate)?;
+  the_app.start_playback(the_replay)?;
+  let mut a_outro = 0;
+  let mut a_frames = 0;
+  while a_outro < OUTRO_FRAMES && a_frames < MAX_FRAMES {
+    the_app.tick(the_window, a_tick);
+    let a_pixels = headless::read_pixels(a_size.width, a_size.height);
+    a_sink.write(a_frames, a_size.width, a_size.height, &a_pixels)?;
+    a_frames += 1;
+    if !the_app.is_running() {
+      a_outro += 1;
+    }
+  }
+  a_sink.finish()?;
+  Ok(a_frames)
+}
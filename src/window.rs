@@ -0,0 +1,273 @@
+use glutin::dpi::{PhysicalPosition, PhysicalSize};
+use glutin::monitor::{MonitorHandle, VideoMode};
+use glutin::window::{Fullscreen, Icon, Window, WindowBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Shown in the window title and by the window manager
+pub const APP_NAME: &str = "Libre Hexagon";
+/// Width and height of the window icon in pixels
+const ICON_SIZE: u32 = 64;
+
+/// Where the window was and how it was shown when the game was last closed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WindowGeometry {
+  /// Outer position in physical pixels
+  pub x: i32,
+  pub y: i32,
+  /// Inner size in physical pixels, of the window as it is when neither
+  /// maximized nor fullscreen
+  pub width: u32,
+  pub height: u32,
+  /// Name of the monitor the window was on
+  #[serde(default)]
+  pub monitor: Option<String>,
+  #[serde(default)]
+  pub maximized: bool,
+  #[serde(default)]
+  pub fullscreen: bool,
+}
+
+/// A fullscreen video mode as chosen in the settings
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VideoModeSetting {
+  pub width: u32,
+  pub height: u32,
+  pub refresh_rate: u16,
+}
+
+impl VideoModeSetting {
+  fn from_mode(the_mode: &VideoMode) -> VideoModeSetting {
+    VideoModeSetting {
+      width: the_mode.size().width,
+      height: the_mode.size().height,
+      refresh_rate: the_mode.refresh_rate(),
+    }
+  }
+  fn matches(&self, the_mode: &VideoMode) -> bool {
+    *self == VideoModeSetting::from_mode(the_mode)
+  }
+}
+
+impl std::fmt::Display for VideoModeSetting {
+  fn fmt(&self, the_f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(
+      the_f,
+      "{}x{} @ {} Hz",
+      self.width, self.height, self.refresh_rate
+    )
+  }
+}
+
+fn contains(the_monitor: &MonitorHandle, the_point: PhysicalPosition<i32>) -> bool {
+  let a_pos = the_monitor.position();
+  let a_size = the_monitor.size();
+  the_point.x >= a_pos.x
+    && the_point.y >= a_pos.y
+    && the_point.x < a_pos.x + a_size.width as i32
+    && the_point.y < a_pos.y + a_size.height as i32
+}
+
+/// The monitor the center of the window is on
+fn find_current_monitor(the_window: &Window) -> Option<MonitorHandle> {
+  let a_pos = the_window.outer_position().ok()?;
+  let a_size = the_window.outer_size();
+  let a_center = PhysicalPosition::new(
+    a_pos.x + a_size.width as i32 / 2,
+    a_pos.y + a_size.height as i32 / 2,
+  );
+  the_window
+    .available_monitors()
+    .find(|the_monitor| contains(the_monitor, a_center))
+}
+
+pub fn find_monitor(the_window: &Window, the_name: &str) -> Option<MonitorHandle> {
+  the_window
+    .available_monitors()
+    .find(|the_monitor| the_monitor.name().as_deref() == Some(the_name))
+}
+
+/// The video mode of `the_monitor` at its native size with the highest
+/// refresh rate
+fn find_native_mode(the_monitor: &MonitorHandle) -> Option<VideoMode> {
+  let a_size = the_monitor.size();
+  the_monitor
+    .video_modes()
+    .filter(|the_mode| the_mode.size() == a_size)
+    .max_by_key(|the_mode| (the_mode.refresh_rate(), the_mode.bit_depth()))
+}
+
+/// Names of all monitors, in the order the system enumerates them
+pub fn list_monitors(the_window: &Window) -> Vec<String> {
+  the_window
+    .available_monitors()
+    .filter_map(|the_monitor| the_monitor.name())
+    .collect()
+}
+
+/// All distinct video modes of `the_monitor`, largest and fastest first
+pub fn list_video_modes(the_monitor: &MonitorHandle) -> Vec<VideoModeSetting> {
+  let mut a_modes: Vec<VideoModeSetting> = the_monitor
+    .video_modes()
+    .map(|the_mode| VideoModeSetting::from_mode(&the_mode))
+    .collect();
+  a_modes.sort_by_key(|the_mode| {
+    std::cmp::Reverse((the_mode.width * the_mode.height, the_mode.refresh_rate))
+  });
+  a_modes.dedup();
+  a_modes
+}
+
+/// The monitor called `the_name`, or the one the window is on if there is no
+/// such monitor
+pub fn select_monitor(the_window: &Window, the_name: Option<&str>) -> Option<MonitorHandle> {
+  the_name
+    .and_then(|the_name| find_monitor(the_window, the_name))
+    .or_else(|| find_current_monitor(the_window))
+}
+
+/// Center the window on `the_monitor` unless it is on it already
+pub fn move_to_monitor(the_window: &Window, the_monitor: &MonitorHandle) -> () {
+  let a_current = find_current_monitor(the_window);
+  if a_current.as_ref() == Some(the_monitor) {
+    return;
+  }
+  let a_pos = the_monitor.position();
+  let a_monitor_size = the_monitor.size();
+  let a_size = the_window.outer_size();
+  the_window.set_outer_position(PhysicalPosition::new(
+    a_pos.x + (a_monitor_size.width as i32 - a_size.width as i32).max(0) / 2,
+    a_pos.y + (a_monitor_size.height as i32 - a_size.height as i32).max(0) / 2,
+  ));
+}
+
+/// Switch the window to fullscreen, or back. Uses `the_monitor` (or the one
+/// the window is on) with the video mode `the_mode` if the monitor supports
+/// it, otherwise with its native one.
+pub fn set_fullscreen(
+  the_window: &Window,
+  the_monitor: Option<MonitorHandle>,
+  the_mode: Option<&VideoModeSetting>,
+  the_enabled: bool,
+) -> () {
+  if !the_enabled {
+    the_window.set_fullscreen(None);
+    return;
+  }
+  let a_monitor = the_monitor.or_else(|| find_current_monitor(the_window));
+  let a_chosen_mode = |the_monitor: &MonitorHandle| {
+    let a_setting = the_mode?;
+    the_monitor
+      .video_modes()
+      .find(|the_mode| a_setting.matches(the_mode))
+  };
+  let a_mode = a_monitor
+    .as_ref()
+    .and_then(|the_monitor| a_chosen_mode(the_monitor).or_else(|| find_native_mode(the_monitor)));
+  match a_mode {
+    Some(a_mode) => the_window.set_fullscreen(Some(Fullscreen::Exclusive(a_mode))),
+    None => eprintln!("Found no video mode to go fullscreen with"),
+  }
+}
+
+pub fn is_fullscreen(the_window: &Window) -> bool {
+  the_window.fullscreen().is_some()
+}
+
+/// The playfield in miniature: six slots of alternating shades around the
+/// inner hexagon, drawn here so that no image has to be shipped
+pub fn create_icon() -> Option<Icon> {
+  let a_slot_colors = [[0x1a, 0x1a, 0x66], [0x33, 0x33, 0x99]];
+  let a_center_color = [0xff, 0x99, 0x00];
+  let a_sector = std::f32::consts::PI / 3.;
+  let mut a_rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+  for a_y in 0..ICON_SIZE {
+    for a_x in 0..ICON_SIZE {
+      let a_dx = a_x as f32 + 0.5 - ICON_SIZE as f32 / 2.;
+      let a_dy = a_y as f32 + 0.5 - ICON_SIZE as f32 / 2.;
+      let a_angle = a_dx.atan2(-a_dy).rem_euclid(2. * std::f32::consts::PI);
+      // distance from the center as a share of the way to the hexagon's
+      // edge in this direction
+      let a_apothem = (a_angle % a_sector - a_sector / 2.).cos() / (a_sector / 2.).cos();
+      let a_radius = (a_dx * a_dx + a_dy * a_dy).sqrt() * a_apothem / (ICON_SIZE as f32 / 2.);
+      let a_pixel = if a_radius > 1. {
+        [0, 0, 0, 0]
+      } else {
+        let [a_r, a_g, a_b] = if a_radius < 0.3 {
+          a_center_color
+        } else {
+          a_slot_colors[(a_angle / a_sector) as usize % 2]
+        };
+        [a_r, a_g, a_b, 0xff]
+      };
+      a_rgba.extend_from_slice(&a_pixel);
+    }
+  }
+  match Icon::from_rgba(a_rgba, ICON_SIZE, ICON_SIZE) {
+    Ok(a_icon) => Some(a_icon),
+    Err(a_err) => {
+      eprintln!("Failed to create the window icon: {}", a_err);
+      None
+    }
+  }
+}
+
+impl WindowGeometry {
+  /// Record the geometry of `the_window`. While it is maximized or
+  /// fullscreen, the windowed size and position are kept from
+  /// `the_previous`.
+  pub fn capture(
+    the_window: &Window,
+    the_previous: Option<&WindowGeometry>,
+  ) -> Option<WindowGeometry> {
+    let a_monitor = find_current_monitor(the_window);
+    let a_fullscreen = is_fullscreen(the_window);
+    // winit cannot tell whether a window is maximized, a window that spans
+    // the whole width of its monitor is taken as one
+    let a_maximized = !a_fullscreen
+      && a_monitor
+        .as_ref()
+        .map(|the_monitor| the_window.outer_size().width >= the_monitor.size().width)
+        .unwrap_or(false);
+    let a_geometry = match (a_fullscreen || a_maximized, the_previous) {
+      (true, Some(a_previous)) => a_previous.clone(),
+      _ => {
+        let a_pos = the_window.outer_position().ok()?;
+        let a_size = the_window.inner_size();
+        WindowGeometry {
+          x: a_pos.x,
+          y: a_pos.y,
+          width: a_size.width,
+          height: a_size.height,
+          monitor: None,
+          maximized: false,
+          fullscreen: false,
+        }
+      }
+    };
+    Some(WindowGeometry {
+      monitor: a_monitor.and_then(|the_monitor| the_monitor.name()),
+      maximized: a_maximized,
+      fullscreen: a_fullscreen,
+      ..a_geometry
+    })
+  }
+  /// Set up the parts of the geometry that can be given before the window
+  /// exists
+  pub fn configure(&self, the_builder: WindowBuilder) -> WindowBuilder {
+    the_builder
+      .with_inner_size(PhysicalSize::new(self.width.max(1), self.height.max(1)))
+      .with_maximized(self.maximized)
+  }
+  /// Move the window back to where it was. The position is only restored
+  /// if it is still on one of the monitors. Fullscreen is left to the
+  /// caller since it depends on the selected video mode.
+  pub fn restore(&self, the_window: &Window) -> () {
+    let a_pos = PhysicalPosition::new(self.x, self.y);
+    let a_on_screen = the_window
+      .available_monitors()
+      .any(|the_monitor| contains(&the_monitor, a_pos));
+    if a_on_screen && !self.maximized {
+      the_window.set_outer_position(a_pos);
+    }
+  }
+}